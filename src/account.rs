@@ -0,0 +1,83 @@
+use crate::db::Account;
+use crate::tuning::PBKDF2_ITERATIONS;
+use clap::ArgEnum;
+use data_encoding::{BASE64, HEXLOWER};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use ring::{digest, pbkdf2};
+use std::num::NonZeroU32;
+use std::time::SystemTime;
+use tokio::task::spawn_blocking;
+
+/// Whether players must own the usernames they use
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Policy {
+    /// Any username can be used by anyone
+    Disabled,
+    /// Registered usernames are reserved to their owners
+    Optional,
+    /// Only registered users can play
+    Required,
+}
+
+fn iterations() -> NonZeroU32 {
+    NonZeroU32::new(PBKDF2_ITERATIONS).expect("PBKDF2_ITERATIONS cannot be zero")
+}
+
+/// Create an account with a freshly salted password hash
+pub(crate) async fn create(name: String, password: String) -> Result<Account, String> {
+    spawn_blocking(move || {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let mut hash = [0u8; digest::SHA256_OUTPUT_LEN];
+        let algorithm = pbkdf2::PBKDF2_HMAC_SHA256;
+        pbkdf2::derive(
+            algorithm,
+            iterations(),
+            &salt,
+            password.as_bytes(),
+            &mut hash,
+        );
+        let created = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(x) => x.as_secs(),
+            Err(_) => 0,
+        };
+        Account {
+            name,
+            salt: BASE64.encode(&salt),
+            hash: BASE64.encode(&hash),
+            created,
+        }
+    })
+    .await
+    .map_err(|x| format!("Cannot hash password: {}", x))
+}
+
+/// Check a password against the hash stored in an account
+pub(crate) async fn verify(account: Account, password: String) -> bool {
+    spawn_blocking(move || {
+        let (salt, hash) = match (
+            BASE64.decode(account.salt.as_bytes()),
+            BASE64.decode(account.hash.as_bytes()),
+        ) {
+            (Ok(x), Ok(y)) => (x, y),
+            _ => return false,
+        };
+        let algorithm = pbkdf2::PBKDF2_HMAC_SHA256;
+        pbkdf2::verify(algorithm, iterations(), &salt, password.as_bytes(), &hash).is_ok()
+    })
+    .await
+    .unwrap_or(false)
+}
+
+/// Generate a new session token
+pub(crate) fn new_token() -> String {
+    let mut token = [0u8; 32];
+    OsRng.fill_bytes(&mut token);
+    HEXLOWER.encode(&token)
+}
+
+/// Hash of a session token, which is what the database stores
+pub(crate) fn token_hash(token: &str) -> String {
+    HEXLOWER.encode(digest::digest(&digest::SHA256, token.as_bytes()).as_ref())
+}
@@ -0,0 +1,99 @@
+use crate::local::Latency;
+use crate::proto::{MatchOutcome, PlayerResult, Termination};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Quantile of the normal distribution for 95% confidence intervals
+const Z_95: f64 = 1.96;
+
+/// Results of many matches from the point of view of a single player
+#[derive(Debug, Default)]
+pub(crate) struct Tally {
+    wins: u64,
+    losses: u64,
+    draws: u64,
+    failed: u64,
+    terminations: BTreeMap<String, u64>,
+    latency: Latency,
+}
+
+/// Describe how a match ended, telling apart the faults of `name`
+/// from the ones of its opponents
+fn termination(termination: &Termination, name: &str) -> String {
+    let (reason, culprits) = match termination {
+        Termination::Normal => return "normal".to_string(),
+        Termination::Panic => return "game manager panic".to_string(),
+        Termination::Timeout(x) => ("timeout", x),
+        Termination::IllegalMove(x) => ("illegal move", x),
+        Termination::Disconnect(x) => ("disconnect", x),
+        Termination::ProtocolViolation(x) => ("protocol violation", x),
+    };
+    if culprits.iter().any(|x| x == name) {
+        reason.to_string()
+    } else {
+        format!("opponent {}", reason)
+    }
+}
+
+impl Tally {
+    pub(crate) fn record(&mut self, name: &str, outcome: &MatchOutcome, latency: &Latency) {
+        *self
+            .terminations
+            .entry(termination(&outcome.termination, name))
+            .or_default() += 1;
+        match outcome.players.get(name).map(|x| &x.result) {
+            Some(PlayerResult::Win) => self.wins += 1,
+            Some(PlayerResult::Loss) => self.losses += 1,
+            Some(PlayerResult::Draw) => self.draws += 1,
+            None => self.failed += 1,
+        }
+        self.latency.add(latency);
+    }
+
+    pub(crate) fn fail(&mut self) {
+        self.failed += 1;
+    }
+
+    pub(crate) fn played(&self) -> u64 {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Average score, counting draws as half a win,
+    /// with the half width of its 95% confidence interval
+    fn score(&self) -> (f64, f64) {
+        let n = self.played() as f64;
+        let mean = (self.wins as f64 + 0.5 * self.draws as f64) / n;
+        let squares = self.wins as f64 + 0.25 * self.draws as f64;
+        let variance = (squares / n - mean * mean).max(0.0);
+        (mean, Z_95 * (variance / n).sqrt())
+    }
+}
+
+impl fmt::Display for Tally {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let played = self.played();
+        writeln!(f, "matches: {} ({} failed)", played, self.failed)?;
+        if played == 0 {
+            return Ok(());
+        }
+        let percent = |x: u64| 100.0 * x as f64 / played as f64;
+        writeln!(f, "wins: {} ({:.1}%)", self.wins, percent(self.wins))?;
+        writeln!(f, "losses: {} ({:.1}%)", self.losses, percent(self.losses))?;
+        writeln!(f, "draws: {} ({:.1}%)", self.draws, percent(self.draws))?;
+        let (mean, error) = self.score();
+        writeln!(f, "score: {:.3} ± {:.3} (95% confidence)", mean, error)?;
+        if let Some(x) = self.latency.average() {
+            writeln!(
+                f,
+                "average move latency: {:.1}ms over {} moves",
+                x.as_secs_f64() * 1000.0,
+                self.latency.moves
+            )?;
+        }
+        writeln!(f, "terminations:")?;
+        for (reason, count) in self.terminations.iter() {
+            writeln!(f, "- {}: {}", reason, count)?;
+        }
+        Ok(())
+    }
+}
@@ -1,27 +1,48 @@
+mod bench;
+// Shared with the server, which uses most of them
+#[allow(dead_code)]
 mod db;
+#[allow(dead_code)]
+mod game;
+#[allow(dead_code)]
+mod games;
+mod local;
 mod proto;
+#[allow(dead_code)]
+mod rating;
+#[allow(dead_code)]
 mod tuning;
 
-use crate::proto::{GameParams, MatchInfo, Reply, Request};
-use async_trait::async_trait;
+use crate::bench::Tally;
+use crate::local::Latency;
+use crate::proto::{
+    GameParams, MatchInfo, MatchOutcome, Rating, Reply, Request, SeatPolicy, TimeControl,
+    TournamentData, TournamentFormat,
+};
 use clap::{ArgEnum, Parser, Subcommand};
+use futures_util::future::join_all;
 use futures_util::sink::Sink;
-use futures_util::stream::Stream;
+use futures_util::stream::{self, SplitSink, SplitStream, Stream};
 use futures_util::{SinkExt, StreamExt};
 use prettytable::format::Alignment::CENTER;
 use prettytable::{Attr, Cell, Row, Table};
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Display;
+use std::io::{self, Write};
 use std::path::PathBuf;
 use std::process::Stdio;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tokio::io::{stdin, stdout, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::fs::File;
+use tokio::io::{sink, stdin, stdout, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
 use tokio::process as proc;
 use tokio::runtime::Runtime;
 use tokio::select;
+use tokio::time::sleep;
 use tokio_tungstenite::tungstenite::protocol::Message;
 use tokio_tungstenite::tungstenite::Error as TsError;
-use tokio_tungstenite::{connect_async, MaybeTlsStream};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
 use tracing::{error, warn};
 
 #[cfg(unix)]
@@ -35,6 +56,15 @@ use {
 };
 
 const BUFFER_SIZE: usize = 1 << 16;
+const CREDENTIALS_FILE: &str = "credentials.json";
+const RESUME_ATTEMPTS: u32 = 5;
+const RESUME_DELAY: f64 = 1.0;
+
+/// Both halves of a connection to the server
+type Socket = (
+    SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
+    SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+);
 
 #[derive(Parser, Debug)]
 #[clap(version)]
@@ -60,14 +90,28 @@ enum Command {
     New(NewCommand),
     /// Play or spectate a game
     Connect(ConnectCommand),
+    /// Wait in queue for a game and play it
+    Queue(QueueCommand),
+    /// Play a game between local programs, without a server
+    Local(LocalCommand),
+    /// Play many matches and report the statistics of a program
+    Bench(BenchCommand),
     /// List all saved matches
     /// or retrive the history of a specific match
     History(HistoryCommand),
+    /// Show the best rated players of a game
+    /// or the ratings of a specific player
+    Leaderboard(LeaderboardCommand),
+    /// Create, follow or play in tournaments
+    Tournament(TournamentCommand),
+    /// Register or login to reserve a username
+    Account(AccountCommand),
 }
 
 impl Command {
     async fn run<T: Sink<Message> + Unpin, U: Stream<Item = Result<Message, TsError>> + Unpin>(
         self,
+        server: &Server,
         wsout: &mut T,
         wsin: &mut U,
     ) -> Result<(), String>
@@ -78,8 +122,21 @@ impl Command {
             Command::List(cmd) => cmd.run(wsout, wsin).await,
             Command::Lobby(cmd) => cmd.run(wsout, wsin).await,
             Command::New(cmd) => cmd.run(wsout, wsin).await,
-            Command::Connect(cmd) => cmd.run(wsout, wsin).await,
-            Command::History(cmd) => cmd.run(wsout, wsin).await,
+            Command::Connect(mut cmd) => {
+                cmd.name = cmd.name.or_else(|| server.user.clone());
+                cmd.server_url = server.url.clone();
+                cmd.run(wsout, wsin).await
+            }
+            Command::Queue(mut cmd) => {
+                cmd.name = cmd.name.or_else(|| server.user.clone());
+                cmd.run(&server.url, wsout, wsin).await
+            }
+            Command::Local(cmd) => cmd.run().await,
+            Command::Bench(cmd) => cmd.run(&server.url).await,
+            Command::History(cmd) => cmd.run(server, wsout, wsin).await,
+            Command::Leaderboard(cmd) => cmd.run(wsout, wsin).await,
+            Command::Tournament(cmd) => cmd.run(&server.url, wsout, wsin).await,
+            Command::Account(cmd) => cmd.run(server, wsout, wsin).await,
         }
     }
 }
@@ -122,6 +179,49 @@ where
     }
 }
 
+/// Expand the server bots given on the command line, where a number stands for
+/// that many default bots and anything else is the name of a bot
+fn bot_list(specs: &[String]) -> Vec<String> {
+    let mut bots = Vec::new();
+    for spec in specs {
+        match spec.parse::<usize>() {
+            Ok(n) => bots.extend(std::iter::repeat_n(String::new(), n)),
+            Err(_) => bots.push(spec.clone()),
+        }
+    }
+    bots
+}
+
+/// Parse the seat policy written on the command line: `random`, `join` or a
+/// comma separated list of `player=seat`, where seats are names or numbers
+fn parse_seating(seats: &[String], text: &str) -> Result<SeatPolicy, String> {
+    match text {
+        "random" => return Ok(SeatPolicy::Random),
+        "join" => return Ok(SeatPolicy::JoinOrder),
+        _ => {}
+    }
+    let mut mapping = BTreeMap::new();
+    for pair in text.split(',') {
+        let (name, seat) = match pair.split_once('=') {
+            Some(x) => x,
+            None => {
+                return Err(format!(
+                    "\"{}\" is not a valid seat, write it like name=seat",
+                    pair
+                ))
+            }
+        };
+        let seat = match seats.iter().position(|x| x == seat) {
+            Some(x) => x,
+            None => seat
+                .parse()
+                .map_err(|_| format!("Unknown seat \"{}\"", seat))?,
+        };
+        mapping.insert(name.to_owned(), seat);
+    }
+    Ok(SeatPolicy::Explicit(mapping))
+}
+
 #[derive(ArgEnum, Clone, Debug)]
 enum HistoryCommandDisplayEnum {
     Raw,
@@ -141,18 +241,34 @@ struct HistoryCommand {
         help = "How to display the match data"
     )]
     display: HistoryCommandDisplayEnum,
+    #[clap(
+        short('T'),
+        long,
+        help = "Show what a player exchanged with the game, yourself by default"
+    )]
+    transcript: Option<Option<String>>,
+    #[clap(short, long, help = "Password to see the transcripts of any player")]
+    verification_password: Option<String>,
 }
 
 impl HistoryCommand {
-    async fn run<T, U>(self, wsout: &mut T, wsin: &mut U) -> Result<(), String>
+    async fn run<T, U>(self, server: &Server, wsout: &mut T, wsin: &mut U) -> Result<(), String>
     where
         T: Sink<Message> + Unpin,
         <T as Sink<Message>>::Error: Display,
         U: Stream<Item = Result<Message, TsError>> + Unpin,
     {
-        let request = match self.id {
-            Some(id) => Request::HistoryMatch { id },
-            None => Request::HistoryMatchList,
+        let request = match (self.id, self.transcript) {
+            (Some(id), Some(name)) => Request::HistoryTranscript {
+                id,
+                name: name
+                    .or_else(|| server.user.clone())
+                    .ok_or_else(|| "Not logged in, specify whose transcript to show".to_string())?,
+                verification: self.verification_password,
+            },
+            (None, Some(_)) => return Err("Specify the match of the transcript".to_string()),
+            (Some(id), None) => Request::HistoryMatch { id },
+            (None, None) => Request::HistoryMatchList,
         };
 
         // Send request to server
@@ -186,6 +302,12 @@ impl HistoryCommand {
 
                 Ok(())
             }
+            Reply::HistoryTranscript { transcript } => {
+                for entry in transcript? {
+                    print!("{}", entry);
+                }
+                Ok(())
+            }
             Reply::HistoryMatchList(matches) => {
                 for value in &matches {
                     println!("- {}", value);
@@ -197,6 +319,86 @@ impl HistoryCommand {
     }
 }
 
+#[derive(Parser, Debug)]
+struct LeaderboardCommand {
+    #[clap(help = "Show the leaderboard of this game")]
+    game: Option<String>,
+    #[clap(short, long, help = "Show the ratings of this player instead")]
+    player: Option<String>,
+    #[clap(
+        short('n'),
+        long,
+        help = "Number of players to show",
+        default_value = "10"
+    )]
+    limit: usize,
+}
+
+impl LeaderboardCommand {
+    async fn run<T: Sink<Message> + Unpin, U: Stream<Item = Result<Message, TsError>> + Unpin>(
+        self,
+        wsout: &mut T,
+        wsin: &mut U,
+    ) -> Result<(), String>
+    where
+        <T as Sink<Message>>::Error: Display,
+    {
+        let request = match (self.game, self.player) {
+            (_, Some(name)) => Request::PlayerStats { name },
+            (Some(game), None) => Request::Leaderboard {
+                game,
+                limit: self.limit,
+            },
+            (None, None) => return Err("Either a game or a player must be specified".to_string()),
+        };
+        let ratings = match oneshot_request(request, wsout, wsin).await? {
+            Reply::Leaderboard { ratings } | Reply::PlayerStats { ratings } => ratings,
+            _ => return Err("Server returned the wrong reply".to_string()),
+        };
+        Self::print(ratings);
+        Ok(())
+    }
+
+    fn print(ratings: Vec<Rating>) {
+        let mut table = Table::new();
+        let mut fields = vec![
+            "#", "Game", "Name", "Rating", "Matches", "Wins", "Losses", "Draws",
+        ];
+        // Single player problems are ranked by their best score
+        let scored = ratings.iter().any(|x| x.best.is_some());
+        if scored {
+            fields.push("Best");
+        }
+        table.add_row(Row::new(
+            fields
+                .iter()
+                .map(|x| Cell::new_align(x, CENTER).with_style(Attr::Bold))
+                .collect(),
+        ));
+        for (i, rating) in ratings.iter().enumerate() {
+            let mut row = Row::new(vec![
+                Cell::new_align(&format!("{}", i + 1), CENTER),
+                Cell::new_align(&rating.game, CENTER),
+                Cell::new_align(&rating.name, CENTER),
+                Cell::new_align(&format!("{:.0}", rating.rating), CENTER),
+                Cell::new_align(&format!("{}", rating.matches), CENTER),
+                Cell::new_align(&format!("{}", rating.wins), CENTER),
+                Cell::new_align(&format!("{}", rating.losses), CENTER),
+                Cell::new_align(&format!("{}", rating.draws), CENTER),
+            ]);
+            if scored {
+                let best = match rating.best {
+                    Some(x) => format!("{:.1}", x),
+                    None => "-".to_string(),
+                };
+                row.add_cell(Cell::new_align(&best, CENTER));
+            }
+            table.add_row(row);
+        }
+        table.printstd();
+    }
+}
+
 #[derive(Parser, Debug)]
 struct ListCommand {
     #[clap(help = "Show one game with its description")]
@@ -221,12 +423,21 @@ impl ListCommand {
         };
         match oneshot_request(request, wsout, wsin).await? {
             Reply::GameList { games } => {
-                if self.usage {
-                    let usage_text = serde_json::to_string_pretty(&games).unwrap();
-                    println!("{}", usage_text);
-                } else {
-                    for game in games {
-                        println!("- {}", game.name);
+                for game in games {
+                    println!("- {}", game.name);
+                    if self.usage {
+                        let mut args: Vec<_> = game.args.iter().collect();
+                        args.sort_by(|x, y| x.0.cmp(y.0));
+                        for (name, info) in args {
+                            println!("  - {}: {}", name, info);
+                        }
+                        if !game.seats.is_empty() {
+                            println!("  - seats: {}", game.seats.join(", "));
+                        }
+                        for (i, bot) in game.bots.iter().enumerate() {
+                            let default = if i == 0 { " (default)" } else { "" };
+                            println!("  - bot {}: {}{}", bot.name, bot.description, default);
+                        }
                     }
                 }
                 Ok(())
@@ -303,11 +514,21 @@ impl LobbyCommand {
                 Cell::new_align(&game.name, CENTER),
                 Cell::new_align(&game.game, CENTER),
                 Cell::new_align(
-                    &format!("{}/{}", game.connected.len() + game.bots, game.players),
+                    &format!(
+                        "{}/{}",
+                        game.connected.len() + game.bots.len(),
+                        game.players
+                    ),
                     CENTER,
                 ),
                 Cell::new_align(&format!("{}", game.spectators), CENTER),
-                Cell::new_align(&format!("{}", game.timeout), CENTER),
+                Cell::new_align(
+                    &match game.clock {
+                        Some(clock) => format!("{}", clock),
+                        None => format!("{}", game.timeout),
+                    },
+                    CENTER,
+                ),
                 Cell::new_align(if game.password { "X" } else { "" }, CENTER),
                 Cell::new_align(
                     &if game.running {
@@ -336,10 +557,33 @@ struct NewCommand {
     verification_password: Option<String>,
     #[clap(short('n'), long, help = "Number of players")]
     players: Option<usize>,
-    #[clap(short, long, help = "Number of server bots", default_value = "0")]
-    bots: usize,
+    #[clap(
+        short,
+        long,
+        multiple = true,
+        number_of_values = 1,
+        help = "Server bots, as a number of default bots or a bot name, can be specified multiple times"
+    )]
+    bots: Vec<String>,
     #[clap(short, long, help = "Timeout for player actions")]
     timeout: Option<f64>,
+    #[clap(
+        long,
+        help = "Chess clock replacing the timeout, as seconds for the match plus increment per move, like 300+2, or delay per move, like 300d2"
+    )]
+    clock: Option<TimeControl>,
+    #[clap(
+        long,
+        help = "How players take their seats: random, join (in the order they join) or comma separated name=seat pairs"
+    )]
+    seats: Option<String>,
+    #[clap(
+        long,
+        help = "Play a best-of series of this many games, rotating seats after each one"
+    )]
+    series: Option<usize>,
+    #[clap(long, help = "Seed for the randomness of the game, to replay a match")]
+    seed: Option<u64>,
     #[clap(
         short,
         long("arg"),
@@ -367,16 +611,35 @@ impl NewCommand {
             }
             args.insert(arg[0].into(), arg[1..].join(""));
         }
+        // Check the arguments here to give better errors than the server
+        let game = self.game;
+        let games = match oneshot_request(Request::GameList {}, wsout, wsin).await? {
+            Reply::GameList { games } => games,
+            _ => return Err("Server returned the wrong reply".to_string()),
+        };
+        let usage = match games.into_iter().find(|x| x.name == game) {
+            Some(x) => x,
+            None => return Err(format!("Game \"{}\" does not exist", game)),
+        };
+        crate::game::check_args(&usage.args, args.clone())?;
+        let seating = match self.seats {
+            Some(ref x) => parse_seating(&usage.seats, x)?,
+            None => SeatPolicy::Random,
+        };
         let name = self
             .name
             .unwrap_or_else(|| format!("{}'s game", whoami::username()));
         let request = Request::GameNew {
-            game: self.game,
-            name: name,
+            game,
+            name,
             params: GameParams {
                 players: self.players,
-                bots: self.bots,
+                bots: bot_list(&self.bots),
                 timeout: self.timeout,
+                seed: self.seed,
+                clock: self.clock,
+                seating,
+                series: self.series,
             },
             args: args,
             password: self.password,
@@ -416,6 +679,12 @@ struct ConnectCommand {
     channel: CommunicationChannel,
     #[clap(help = "Command to invoke", raw = true)]
     program: Vec<String>,
+    /// Arguments of the queue to join, in which case `id` is the game to play
+    #[clap(skip)]
+    queue: Option<HashMap<String, String>>,
+    /// Server to reconnect to when the connection drops while playing
+    #[clap(skip)]
+    server_url: String,
 }
 
 #[derive(ArgEnum, Debug, Clone)]
@@ -578,6 +847,21 @@ impl ConnectCommand {
         }
     }
 
+    /// Print who sits where, once the match started
+    fn print_seats(info: &MatchInfo, shown: &mut bool) {
+        if *shown || info.seated.is_empty() {
+            return;
+        }
+        *shown = true;
+        let seats: Vec<_> = info
+            .seats
+            .iter()
+            .zip(info.seated.iter())
+            .map(|(seat, name)| format!("{}: {}", seat, name))
+            .collect();
+        println!("> Seats: {}", seats.join(", "));
+    }
+
     async fn print_update(info: &MatchInfo, last: &mut HashSet<String>) {
         if info.connected != *last {
             *last = info.connected.clone();
@@ -585,10 +869,10 @@ impl ConnectCommand {
                 "> Game has {} spectator{} and {}/{} ({} bot{}) connected player{}: {:?}",
                 info.spectators,
                 if info.spectators == 1 { "" } else { "s" },
-                info.connected.len() + info.bots,
+                info.connected.len() + info.bots.len(),
                 info.players,
-                info.bots,
-                if info.bots == 1 { "" } else { "s" },
+                info.bots.len(),
+                if info.bots.len() == 1 { "" } else { "s" },
                 if info.connected.len() == 1 { "" } else { "s" },
                 info.connected.iter().collect::<Vec<_>>()
             );
@@ -604,31 +888,58 @@ impl ConnectCommand {
         self,
         wsout: &mut T,
         wsin: &mut U,
-        pipein: X,
-        pipeout: Y,
+        mut pipein: X,
+        mut pipeout: Y,
     ) -> Result<(), String>
     where
         <T as Sink<Message>>::Error: Display,
     {
-        let request = Request::LobbyJoinMatch {
-            id: self.id.clone(),
-            name: self.name.clone().unwrap_or_else(|| whoami::username()),
-            password: self.password.clone(),
+        let name = self.name.clone().unwrap_or_else(whoami::username);
+        let request = match self.queue {
+            Some(ref args) => Request::QueueJoin {
+                game: self.id.clone(),
+                name,
+                args: args.clone(),
+            },
+            None => Request::LobbyJoinMatch {
+                id: self.id.clone(),
+                name,
+                password: self.password.clone(),
+            },
         };
-        let info = match oneshot_request(request, wsout, wsin).await {
-            Ok(Reply::LobbyJoinedMatch { info: Ok(x) }) => x,
+        let mut last_connected = HashSet::new();
+        let mut seats_shown = false;
+        let mut series = false;
+        // The connection that replaced the original one after a match was resumed
+        let mut resumed: Option<Socket> = None;
+        match oneshot_request(request, wsout, wsin).await {
+            Ok(Reply::LobbyJoinedMatch { info: Ok(info) }) => {
+                println!("> Joined \"{}\" ({})", info.name, info.game);
+                if let Some(ref x) = info.series {
+                    println!("> Best of {} series", x.length);
+                    series = true;
+                }
+                println!("> Waiting for game to start");
+                Self::print_update(&info, &mut last_connected).await;
+            }
             Ok(Reply::LobbyJoinedMatch { info: Err(x) }) => {
                 return Err(format!("Cannot join game: {}", x))
             }
+            Ok(Reply::QueueJoined { info: Ok(info) }) => {
+                println!("> Queued for {}", info.game);
+            }
+            Ok(Reply::QueueJoined { info: Err(x) }) => {
+                return Err(format!("Cannot join queue: {}", x))
+            }
             Ok(_) => return Err(format!("Server sent wrong reply")),
             Err(x) => return Err(x),
         };
-        let mut last_connected = HashSet::new();
-        println!("> Joined \"{}\" ({})", info.name, info.game);
-        println!("> Waiting for game to start");
-        Self::print_update(&info, &mut last_connected).await;
         loop {
-            let msg = match wsin.next().await {
+            let msg = match resumed {
+                Some((_, ref mut wsin)) => wsin.next().await,
+                None => wsin.next().await,
+            };
+            let msg = match msg {
                 Some(x) => x,
                 None => break Err(format!("Connection lost")),
             };
@@ -638,32 +949,147 @@ impl ConnectCommand {
                 Err(x) => break Err(format!("Connection lost: {}", x)),
             };
             match Reply::parse(&msg) {
-                Ok(Reply::MatchStarted {}) => {
+                Ok(Reply::MatchStarted { token }) => {
                     println!("> Game started");
-                    break self.ingame(wsout, wsin, pipein, pipeout).await;
+                    let socket = match resumed {
+                        Some((ref mut wsout, ref mut wsin)) => {
+                            self.ingame(wsout, wsin, &mut pipein, &mut pipeout, token)
+                                .await?
+                        }
+                        None => {
+                            self.ingame(wsout, wsin, &mut pipein, &mut pipeout, token)
+                                .await?
+                        }
+                    };
+                    if socket.is_some() {
+                        resumed = socket;
+                    }
+                    if !series {
+                        break Ok(());
+                    }
+                    // The next game of the series follows on the same program
+                    seats_shown = false;
+                    println!("> Waiting for the next game of the series");
+                }
+                Ok(Reply::SeriesEnded { series }) => {
+                    println!("> Series ended");
+                    print!("{}", series);
+                    break Ok(());
                 }
                 Ok(Reply::LobbyUpdate { info }) => {
-                    Self::print_update(&info, &mut last_connected).await
+                    Self::print_update(&info, &mut last_connected).await;
+                    Self::print_seats(&info, &mut seats_shown);
                 }
                 Ok(Reply::LobbyDelete { .. }) => break Err(format!("Game expired")),
+                Ok(Reply::QueueUpdate { info }) => {
+                    println!("> {}/{} players waiting", info.waiting, info.players)
+                }
+                Ok(Reply::QueueMatched { info }) => {
+                    println!("> Joined \"{}\" ({})", info.name, info.game);
+                    Self::print_update(&info, &mut last_connected).await
+                }
+                Ok(Reply::QueueLeaved {}) => break Err("Removed from queue".to_string()),
                 Ok(_) => break Err(format!("Received wrong message from server: {:?}", msg)),
                 Err(x) => break Err(format!("Cannot parse server reply: {}", x)),
             }
         }
     }
 
+    /// Play a match, resuming it when the connection is lost. Returns the new
+    /// connection to the server if the match was resumed, as the old one is gone
     async fn ingame<
         T: Sink<Message> + Unpin,
         U: Stream<Item = Result<Message, TsError>> + Unpin,
         X: AsyncRead + Unpin,
         Y: AsyncWrite + Unpin,
     >(
-        self,
+        &self,
         wsout: &mut T,
         wsin: &mut U,
-        mut pipein: X,
-        mut pipeout: Y,
-    ) -> Result<(), String>
+        pipein: &mut X,
+        pipeout: &mut Y,
+        token: Option<String>,
+    ) -> Result<Option<Socket>, String>
+    where
+        <T as Sink<Message>>::Error: Display,
+    {
+        let mut latency = Latency::default();
+        let mut received = 0;
+        let mut result =
+            Self::exchange(wsout, wsin, pipein, pipeout, &mut latency, &mut received).await;
+        let token = match token {
+            Some(x) => x,
+            None => return Self::ended(result.unwrap_or_else(Err)).map(|()| None),
+        };
+        let mut attempts = 0;
+        let mut socket = None;
+        loop {
+            let lost = match result {
+                Ok(x) => break Self::ended(x).map(|()| socket),
+                Err(x) if attempts < RESUME_ATTEMPTS => x,
+                Err(x) => break Err(x),
+            };
+            attempts += 1;
+            warn!("{}, resuming game ({}/{})", lost, attempts, RESUME_ATTEMPTS);
+            sleep(Duration::from_secs_f64(RESUME_DELAY)).await;
+            let (mut wsout, mut wsin) = match connect_server(&self.server_url).await {
+                Ok(x) => x.0.split(),
+                Err(x) => {
+                    result = Err(x);
+                    continue;
+                }
+            };
+            let request = Request::MatchResume {
+                token: token.clone(),
+                received,
+            };
+            result = match oneshot_request(request, &mut wsout, &mut wsin).await {
+                Ok(Reply::MatchResumed { info: Ok(_) }) => {
+                    println!("> Game resumed");
+                    attempts = 0;
+                    let (wsout, wsin) = socket.insert((wsout, wsin));
+                    Self::exchange(
+                        wsout,
+                        wsin,
+                        pipein,
+                        pipeout,
+                        &mut latency,
+                        &mut received,
+                    )
+                    .await
+                }
+                Ok(Reply::MatchResumed { info: Err(x) }) => {
+                    break Err(format!("Cannot resume game: {}", x))
+                }
+                Ok(_) => break Err("Server sent wrong reply".to_string()),
+                Err(x) => Err(x),
+            };
+        }
+    }
+
+    fn ended(result: Result<MatchOutcome, String>) -> Result<(), String> {
+        let outcome = result?;
+        println!("> Game ended");
+        print!("{}", outcome);
+        Ok(())
+    }
+
+    /// Forward game data until the match ends, counting the bytes received;
+    /// the outer error means that the connection was lost and the match may
+    /// be resumed
+    async fn exchange<
+        T: Sink<Message> + Unpin,
+        U: Stream<Item = Result<Message, TsError>> + Unpin,
+        X: AsyncRead + Unpin,
+        Y: AsyncWrite + Unpin,
+    >(
+        wsout: &mut T,
+        wsin: &mut U,
+        pipein: &mut X,
+        pipeout: &mut Y,
+        latency: &mut Latency,
+        received: &mut u64,
+    ) -> Result<Result<MatchOutcome, String>, String>
     where
         <T as Sink<Message>>::Error: Display,
     {
@@ -674,9 +1100,11 @@ impl ConnectCommand {
                     let msg = match msg {
                         Some(Ok(Message::Text(x))) => x,
                         Some(Ok(Message::Binary(x))) => {
+                            latency.received();
                             if let Err(x) = pipeout.write_all(&x).await {
-                                break Err(format!("Cannot write to stream: {}", x));
+                                break Ok(Err(format!("Cannot write to stream: {}", x)));
                             }
+                            *received += x.len() as u64;
                             if let Err(x) = pipeout.flush().await {
                                 warn!("Cannot flush stream: {}", x);
                             }
@@ -687,13 +1115,10 @@ impl ConnectCommand {
                         None => break Err(format!("Connection lost")),
                     };
                     match Reply::parse(&msg) {
-                        Ok(Reply::MatchEnded {}) => {
-                            println!("> Game ended");
-                            break Ok(());
-                        }
+                        Ok(Reply::MatchEnded { outcome }) => break Ok(Ok(outcome)),
                         Ok(Reply::LobbyUpdate { .. }) => {}
-                        Ok(_) => break Err(format!("Received wrong message from server: {:?}", msg)),
-                        Err(x) => break Err(format!("Cannot parse server reply: {}", x)),
+                        Ok(_) => break Ok(Err(format!("Received wrong message from server: {:?}", msg))),
+                        Err(x) => break Ok(Err(format!("Cannot parse server reply: {}", x))),
                     }
                 }
                 res = pipein.read(&mut buffer) => {
@@ -703,8 +1128,9 @@ impl ConnectCommand {
                             continue;
                         }
                         Ok(x) => x,
-                        Err(x) => break Err(format!("Cannot read from stream: {}", x)),
+                        Err(x) => break Ok(Err(format!("Cannot read from stream: {}", x))),
                     };
+                    latency.answered();
                     if let Err(x) = wsout.send(Message::Binary(buffer[..size].into())).await {
                         break Err(format!("Cannot send game data to server: {}", x));
                     }
@@ -736,9 +1162,11 @@ impl ConnectCommand {
             Err(x) => return Err(x),
         };
         let mut last_connected = HashSet::new();
+        let mut seats_shown = false;
         println!("> Joined spectators for \"{}\" ({})", info.name, info.game);
         println!("> Waiting for game to start");
         Self::print_update(&info, &mut last_connected).await;
+        Self::print_seats(&info, &mut seats_shown);
         loop {
             let msg = match wsin.next().await {
                 Some(x) => x,
@@ -761,12 +1189,18 @@ impl ConnectCommand {
             match Reply::parse(&msg) {
                 Ok(Reply::SpectateStarted {}) => println!("> Game started"),
                 Ok(Reply::SpectateSynced {}) => println!("> Reached current game status"),
-                Ok(Reply::SpectateEnded {}) | Ok(Reply::SpectateLeaved {}) => {
+                Ok(Reply::SpectateEnded { outcome }) => {
+                    println!("> Game ended");
+                    print!("{}", outcome);
+                    break Ok(());
+                }
+                Ok(Reply::SpectateLeaved {}) => {
                     println!("> Game ended");
                     break Ok(());
                 }
                 Ok(Reply::LobbyUpdate { info }) => {
-                    Self::print_update(&info, &mut last_connected).await
+                    Self::print_update(&info, &mut last_connected).await;
+                    Self::print_seats(&info, &mut seats_shown);
                 }
                 Ok(Reply::LobbyDelete { .. }) => break Err(format!("Game expired")),
                 Ok(_) => break Err(format!("Received wrong message from server: {:?}", msg)),
@@ -776,75 +1210,958 @@ impl ConnectCommand {
     }
 }
 
-fn init_logging() {
-    if let Err(x) = tracing_subscriber::fmt()
-        .event_format(
-            tracing_subscriber::fmt::format()
-                .without_time()
-                .with_target(false),
-        )
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .try_init()
-    {
-        println!("Cannot enable logging service: {}", x);
-    }
+#[derive(Parser, Debug)]
+struct QueueCommand {
+    #[clap(help = "Game to play")]
+    game: String,
+    #[clap(short, long, help = "Username for the game")]
+    name: Option<String>,
+    #[clap(
+        short,
+        long("arg"),
+        multiple = true,
+        number_of_values = 1,
+        help = "Additional arguments, can be specified multiple times with -a arg=val"
+    )]
+    args: Vec<String>,
+    #[clap(
+        arg_enum,
+        short,
+        long,
+        help = "Channel for program communication",
+        default_value = "stdio"
+    )]
+    channel: CommunicationChannel,
+    #[clap(help = "Command to invoke", raw = true)]
+    program: Vec<String>,
 }
 
-async fn start(args: CliArgs) -> Result<(), String> {
-    let mut ws = match connect_async(&args.server_url).await {
-        Ok(x) => x.0,
-        Err(x) => return Err(format!("Cannot connect to \"{}\": {}", args.server_url, x)),
-    };
-    let result = match ws.get_mut() {
-        &mut MaybeTlsStream::Plain(ref mut x) => x.set_nodelay(true),
-        &mut MaybeTlsStream::Rustls(ref mut x) => x.get_mut().0.set_nodelay(true),
-        &mut _ => unreachable!("Using stream other than Plain or Rustls"),
-    };
-    if let Err(x) = result {
-        warn!("Cannot set TCP_NODELAY: {}", x);
-    }
-    let (mut wsout, mut wsin) = ws.split();
-    let handshake_request = match Request::forge(&Request::Handshake {
-        magic: proto::MAGIC.to_string(),
-        version: proto::VERSION,
-    }) {
-        Ok(x) => Message::Text(x),
-        Err(x) => return Err(format!("Cannot forge handshake request: {}", x)),
-    };
-    if let Err(x) = wsout.send(handshake_request).await {
-        return Err(format!("Cannot send handshake request: {}", x));
-    };
-    let handshake_reply = loop {
-        if let Some(msg) = wsin.next().await {
-            match msg {
-                Ok(Message::Text(x)) => match Reply::parse(&x) {
-                    Ok(Reply::Handshake { magic, version }) => break (magic, version),
-                    Ok(_) => return Err(format!("Server performed a wrong handshake")),
-                    Err(x) => return Err(format!("Could not parse server handshake: {}", x)),
-                },
-                Err(x) => return Err(format!("Connection lost while performing handshake: {}", x)),
-                Ok(_) => {}
+impl QueueCommand {
+    async fn run<T: Sink<Message> + Unpin, U: Stream<Item = Result<Message, TsError>> + Unpin>(
+        self,
+        server_url: &str,
+        wsout: &mut T,
+        wsin: &mut U,
+    ) -> Result<(), String>
+    where
+        <T as Sink<Message>>::Error: Display,
+    {
+        let mut args = HashMap::new();
+        for arg in self.args {
+            let arg: Vec<_> = arg.split("=").collect();
+            if arg.len() < 2 {
+                return Err(format!("{} is not a valid argument", arg.join("")));
             }
-        } else {
-            return Err(format!("Connection lost while performing handshake"));
+            args.insert(arg[0].into(), arg[1..].join(""));
         }
-    };
-    if !(handshake_reply.0 == proto::MAGIC && handshake_reply.1 == proto::VERSION) {
-        return if handshake_reply.0 == proto::MAGIC {
-            Err(format!(
-                "Protocol version mismatch: local={}, server={}",
-                proto::VERSION,
-                handshake_reply.1
-            ))
-        } else {
-            Err(format!(
-                "\"{}\" is not a Code Colosseum server",
-                args.server_url
-            ))
+        let connect = ConnectCommand {
+            id: self.game,
+            spectate: false,
+            name: self.name,
+            password: None,
+            channel: self.channel,
+            program: self.program,
+            queue: Some(args),
+            server_url: server_url.to_string(),
         };
+        connect.run(wsout, wsin).await
     }
-    args.command.run(&mut wsout, &mut wsin).await?;
-    ws = match wsin.reunite(wsout) {
+}
+
+#[derive(Parser, Debug)]
+struct LocalCommand {
+    #[clap(help = "Game to play")]
+    game: String,
+    #[clap(short('n'), long, help = "Number of players")]
+    players: Option<usize>,
+    #[clap(
+        short,
+        long,
+        multiple = true,
+        number_of_values = 1,
+        help = "Server bots, as a number of default bots or a bot name, can be specified multiple times"
+    )]
+    bots: Vec<String>,
+    #[clap(short, long, help = "Fill empty seats with server bots")]
+    fill: bool,
+    #[clap(short, long, help = "Timeout for player actions")]
+    timeout: Option<f64>,
+    #[clap(
+        long,
+        help = "Chess clock replacing the timeout, as seconds for the match plus increment per move, like 300+2, or delay per move, like 300d2"
+    )]
+    clock: Option<TimeControl>,
+    #[clap(
+        long,
+        help = "How players take their seats: random, join (in the order they join) or comma separated name=seat pairs"
+    )]
+    seats: Option<String>,
+    #[clap(long, help = "Seed for the randomness of the game, to replay a match")]
+    seed: Option<u64>,
+    #[clap(
+        short,
+        long("arg"),
+        multiple = true,
+        number_of_values = 1,
+        help = "Additional arguments, can be specified multiple times with -a arg=val"
+    )]
+    args: Vec<String>,
+    #[clap(
+        short,
+        long,
+        help = "Save the spectator stream to a file instead of printing it"
+    )]
+    output: Option<PathBuf>,
+    #[clap(help = "Commands to invoke, separated by --", raw = true)]
+    programs: Vec<String>,
+}
+
+impl LocalCommand {
+    async fn run(self) -> Result<(), String> {
+        let mut args = HashMap::new();
+        for arg in self.args {
+            let arg: Vec<_> = arg.split("=").collect();
+            if arg.len() < 2 {
+                return Err(format!("{} is not a valid argument", arg.join("")));
+            }
+            args.insert(arg[0].into(), arg[1..].join(""));
+        }
+        let programs: Vec<Vec<String>> = self
+            .programs
+            .split(|x| x == "--")
+            .filter(|x| !x.is_empty())
+            .map(|x| x.to_vec())
+            .collect();
+        let seating = match self.seats {
+            Some(ref x) => {
+                let seats = local::builder(&self.game)?.seats().await;
+                parse_seating(&seats, x)?
+            }
+            None => SeatPolicy::Random,
+        };
+        let seed = self.seed.unwrap_or_else(rand::random);
+        println!("> Playing with seed {}", seed);
+        let params = GameParams {
+            players: self.players,
+            bots: bot_list(&self.bots),
+            timeout: self.timeout,
+            seed: Some(seed),
+            clock: self.clock,
+            seating,
+            series: None,
+        };
+        let (outcome, _) = match self.output {
+            Some(path) => {
+                let file = File::create(&path)
+                    .await
+                    .map_err(|x| format!("Cannot create \"{}\": {}", path.display(), x))?;
+                local::run(&self.game, params, args, programs, self.fill, file).await?
+            }
+            None => local::run(&self.game, params, args, programs, self.fill, stdout()).await?,
+        };
+        println!("> Game ended");
+        print!("{}", outcome);
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+struct BenchCommand {
+    #[clap(help = "Game to play")]
+    game: String,
+    #[clap(short, long, help = "Number of matches to play", default_value = "100")]
+    matches: usize,
+    #[clap(
+        short,
+        long,
+        help = "Matches played at the same time",
+        default_value = "4"
+    )]
+    jobs: usize,
+    #[clap(short, long, help = "Play locally instead of on the server")]
+    local: bool,
+    #[clap(short('n'), long, help = "Number of players")]
+    players: Option<usize>,
+    #[clap(
+        short,
+        long,
+        multiple = true,
+        number_of_values = 1,
+        help = "Server bots, as a number of default bots or a bot name, can be specified multiple times"
+    )]
+    bots: Vec<String>,
+    #[clap(short, long, help = "Timeout for player actions")]
+    timeout: Option<f64>,
+    #[clap(
+        long,
+        help = "Chess clock replacing the timeout, as seconds for the match plus increment per move, like 300+2, or delay per move, like 300d2"
+    )]
+    clock: Option<TimeControl>,
+    #[clap(short, long, help = "Password to create verified games")]
+    verification_password: Option<String>,
+    #[clap(
+        short,
+        long("arg"),
+        multiple = true,
+        number_of_values = 1,
+        help = "Additional arguments, can be specified multiple times with -a arg=val"
+    )]
+    args: Vec<String>,
+    #[clap(
+        help = "Commands to invoke, separated by --, the first one is measured",
+        raw = true
+    )]
+    programs: Vec<String>,
+}
+
+impl BenchCommand {
+    async fn run(self, server_url: &str) -> Result<(), String> {
+        let mut args = HashMap::new();
+        for arg in self.args.iter() {
+            let arg: Vec<_> = arg.split("=").collect();
+            if arg.len() < 2 {
+                return Err(format!("{} is not a valid argument", arg.join("")));
+            }
+            args.insert(arg[0].into(), arg[1..].join(""));
+        }
+        let programs: Vec<Vec<String>> = self
+            .programs
+            .split(|x| x == "--")
+            .filter(|x| !x.is_empty())
+            .map(|x| x.to_vec())
+            .collect();
+        if programs.is_empty() {
+            return Err("No program to measure".to_string());
+        }
+        let mut taken = HashSet::new();
+        let names: Vec<String> = programs
+            .iter()
+            .map(|x| {
+                let name = local::player_name(x, &taken);
+                taken.insert(name.clone());
+                name
+            })
+            .collect();
+        let mut results = stream::iter(0..self.matches)
+            .map(|i| self.play(server_url, i, &args, &programs, &names))
+            .buffer_unordered(self.jobs.max(1));
+        let mut tally = Tally::default();
+        let mut done = 0;
+        while let Some(result) = results.next().await {
+            match result {
+                Ok((outcome, latency)) => tally.record(&names[0], &outcome, &latency),
+                Err(x) => {
+                    error!("{}", x);
+                    tally.fail();
+                }
+            }
+            done += 1;
+            print!("\r> {}/{} matches played", done, self.matches);
+            drop(io::stdout().flush());
+        }
+        println!();
+        print!("{}", tally);
+        Ok(())
+    }
+
+    fn params(&self, programs: usize) -> GameParams {
+        let bots = bot_list(&self.bots);
+        GameParams {
+            players: self.players.or(Some(programs + bots.len())),
+            bots,
+            timeout: self.timeout,
+            seed: None,
+            clock: self.clock,
+            seating: SeatPolicy::Random,
+            series: None,
+        }
+    }
+
+    /// Play one match and report it from the point of view of the first program
+    async fn play(
+        &self,
+        server_url: &str,
+        i: usize,
+        args: &HashMap<String, String>,
+        programs: &[Vec<String>],
+        names: &[String],
+    ) -> Result<(MatchOutcome, Latency), String> {
+        if self.local {
+            let params = self.params(programs.len());
+            let (outcome, latencies) = local::run(
+                &self.game,
+                params,
+                args.clone(),
+                programs.to_vec(),
+                false,
+                sink(),
+            )
+            .await?;
+            return Ok((outcome, latencies[0].1));
+        }
+        let (mut wsout, mut wsin) = connect_server(server_url).await?.0.split();
+        let password = format!("{:016x}", rand::random::<u64>());
+        let request = Request::GameNew {
+            game: self.game.clone(),
+            name: format!("Bench {}", i + 1),
+            params: self.params(programs.len()),
+            args: args.clone(),
+            password: Some(password.clone()),
+            verification: self.verification_password.clone(),
+        };
+        let id = match oneshot_request(request, &mut wsout, &mut wsin).await? {
+            Reply::GameNew { id: Ok(id) } => id,
+            Reply::GameNew { id: Err(x) } => return Err(format!("Cannot create game: {}", x)),
+            _ => return Err("Server returned the wrong reply".to_string()),
+        };
+        let seats = programs
+            .iter()
+            .zip(names)
+            .map(|(program, name)| Self::seat(server_url, &id, name, &password, program));
+        let mut results = join_all(seats).await;
+        results.swap_remove(0)
+    }
+
+    /// Join a match on the server and bridge a program to it
+    async fn seat(
+        server_url: &str,
+        id: &str,
+        name: &str,
+        password: &str,
+        program: &[String],
+    ) -> Result<(MatchOutcome, Latency), String> {
+        let (mut wsout, mut wsin) = connect_server(server_url).await?.0.split();
+        let request = Request::LobbyJoinMatch {
+            id: id.to_string(),
+            name: name.to_string(),
+            password: Some(password.to_string()),
+        };
+        match oneshot_request(request, &mut wsout, &mut wsin).await? {
+            Reply::LobbyJoinedMatch { info: Ok(_) } => {}
+            Reply::LobbyJoinedMatch { info: Err(x) } => {
+                return Err(format!("Cannot join game: {}", x))
+            }
+            _ => return Err("Server sent wrong reply".to_string()),
+        }
+        let mut child = proc::Command::new(&program[0])
+            .args(&program[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|x| format!("Cannot spawn program: {}", x))?;
+        let mut stdin = child.stdin.take().expect("Cannot fail");
+        let mut stdout = child.stdout.take().expect("Cannot fail");
+        loop {
+            let msg = match wsin.next().await {
+                Some(Ok(Message::Text(x))) => x,
+                Some(Ok(_)) => continue,
+                Some(Err(x)) => return Err(format!("Connection lost: {}", x)),
+                None => return Err("Connection lost".to_string()),
+            };
+            match Reply::parse(&msg) {
+                Ok(Reply::MatchStarted { .. }) => break,
+                Ok(Reply::LobbyUpdate { .. }) => {}
+                Ok(Reply::LobbyDelete { .. }) => return Err("Game expired".to_string()),
+                Ok(_) => return Err(format!("Received wrong message from server: {:?}", msg)),
+                Err(x) => return Err(format!("Cannot parse server reply: {}", x)),
+            }
+        }
+        let mut latency = Latency::default();
+        let result = ConnectCommand::exchange(
+            &mut wsout,
+            &mut wsin,
+            &mut stdout,
+            &mut stdin,
+            &mut latency,
+            &mut 0,
+        )
+        .await;
+        drop(stdin);
+        local::wait(name, child).await;
+        Ok((result.unwrap_or_else(Err)?, latency))
+    }
+}
+
+#[derive(Parser, Debug)]
+struct TournamentCommand {
+    #[clap(subcommand)]
+    command: TournamentSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum TournamentSubcommand {
+    /// Create a new tournament
+    New(TournamentNewCommand),
+    /// List all tournaments
+    List,
+    /// Show standings and pairings of a tournament
+    Show { id: String },
+    /// Follow a running tournament
+    Watch { id: String },
+    /// Play every match of a participant in a tournament
+    Join(TournamentJoinCommand),
+}
+
+#[derive(ArgEnum, Clone, Debug)]
+enum TournamentFormatEnum {
+    RoundRobin,
+    DoubleRoundRobin,
+    Swiss,
+    SingleElimination,
+}
+
+#[derive(Parser, Debug)]
+struct TournamentNewCommand {
+    #[clap(help = "Game to play")]
+    game: String,
+    #[clap(help = "Participants, ordered by seed", required = true)]
+    participants: Vec<String>,
+    #[clap(long, help = "Name for the tournament")]
+    name: Option<String>,
+    #[clap(
+        arg_enum,
+        short,
+        long,
+        help = "Tournament format",
+        default_value = "round-robin"
+    )]
+    format: TournamentFormatEnum,
+    #[clap(short, long, help = "Number of rounds (swiss only)")]
+    rounds: Option<usize>,
+    #[clap(short, long, help = "Verification password of the server")]
+    verification_password: String,
+    #[clap(short, long, help = "Timeout for player actions")]
+    timeout: Option<f64>,
+    #[clap(
+        short,
+        long("arg"),
+        multiple = true,
+        number_of_values = 1,
+        help = "Additional arguments, can be specified multiple times with -a arg=val"
+    )]
+    args: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+struct TournamentJoinCommand {
+    #[clap(help = "Tournament ID")]
+    id: String,
+    #[clap(short, long, help = "Participant name")]
+    name: String,
+    #[clap(
+        arg_enum,
+        short,
+        long,
+        help = "Channel for program communication",
+        default_value = "stdio"
+    )]
+    channel: CommunicationChannel,
+    #[clap(help = "Command to invoke for each match", raw = true, required = true)]
+    program: Vec<String>,
+}
+
+impl TournamentCommand {
+    async fn run<T: Sink<Message> + Unpin, U: Stream<Item = Result<Message, TsError>> + Unpin>(
+        self,
+        server_url: &str,
+        wsout: &mut T,
+        wsin: &mut U,
+    ) -> Result<(), String>
+    where
+        <T as Sink<Message>>::Error: Display,
+    {
+        match self.command {
+            TournamentSubcommand::New(cmd) => cmd.run(wsout, wsin).await,
+            TournamentSubcommand::List => {
+                match oneshot_request(Request::TournamentList {}, wsout, wsin).await? {
+                    Reply::TournamentList { ids } => {
+                        for id in ids {
+                            println!("- {}", id);
+                        }
+                        Ok(())
+                    }
+                    _ => Err("Server returned the wrong reply".to_string()),
+                }
+            }
+            TournamentSubcommand::Show { id } => {
+                match oneshot_request(Request::TournamentInfo { id }, wsout, wsin).await? {
+                    Reply::TournamentInfo { info: Ok(info) } => {
+                        Self::print(&info, true);
+                        Ok(())
+                    }
+                    Reply::TournamentInfo { info: Err(x) } => {
+                        Err(format!("Cannot get tournament: {}", x))
+                    }
+                    _ => Err("Server returned the wrong reply".to_string()),
+                }
+            }
+            TournamentSubcommand::Watch { id } => {
+                let info = Self::subscribe(id, None, wsout, wsin).await?;
+                Self::print(&info, false);
+                loop {
+                    match Self::next(wsin).await? {
+                        Reply::TournamentUpdate { info } => Self::print(&info, false),
+                        Reply::TournamentEnded { info } => {
+                            Self::print(&info, true);
+                            break Ok(());
+                        }
+                        x => break Err(format!("Received wrong message from server: {:?}", x)),
+                    }
+                }
+            }
+            TournamentSubcommand::Join(cmd) => {
+                let info = Self::subscribe(cmd.id, Some(cmd.name.clone()), wsout, wsin).await?;
+                println!("> Joined \"{}\" ({}) as {}", info.name, info.game, cmd.name);
+                loop {
+                    match Self::next(wsin).await? {
+                        Reply::TournamentPairing { id, password } => {
+                            println!("> Playing match {}", id);
+                            let connect = ConnectCommand {
+                                id,
+                                spectate: false,
+                                name: Some(cmd.name.clone()),
+                                password: Some(password),
+                                channel: cmd.channel.clone(),
+                                program: cmd.program.clone(),
+                                queue: None,
+                                server_url: server_url.to_string(),
+                            };
+                            let (mut mout, mut min) = connect_server(server_url).await?.0.split();
+                            if let Err(x) = connect.run(&mut mout, &mut min).await {
+                                error!("{}", x);
+                            }
+                        }
+                        Reply::TournamentUpdate { .. } => {}
+                        Reply::TournamentEnded { info } => {
+                            Self::print(&info, true);
+                            break Ok(());
+                        }
+                        x => break Err(format!("Received wrong message from server: {:?}", x)),
+                    }
+                }
+            }
+        }
+    }
+
+    async fn subscribe<
+        T: Sink<Message> + Unpin,
+        U: Stream<Item = Result<Message, TsError>> + Unpin,
+    >(
+        id: String,
+        name: Option<String>,
+        wsout: &mut T,
+        wsin: &mut U,
+    ) -> Result<TournamentData, String>
+    where
+        <T as Sink<Message>>::Error: Display,
+    {
+        match oneshot_request(Request::TournamentSubscribe { id, name }, wsout, wsin).await? {
+            Reply::TournamentSubscribed { info: Ok(info) } => Ok(info),
+            Reply::TournamentSubscribed { info: Err(x) } => {
+                Err(format!("Cannot follow tournament: {}", x))
+            }
+            _ => Err("Server returned the wrong reply".to_string()),
+        }
+    }
+
+    async fn next<U: Stream<Item = Result<Message, TsError>> + Unpin>(
+        wsin: &mut U,
+    ) -> Result<Reply, String> {
+        loop {
+            match wsin.next().await {
+                Some(Ok(Message::Text(x))) => {
+                    break Reply::parse(&x).map_err(|x| format!("Cannot parse server reply: {}", x))
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(x)) => break Err(format!("Connection lost: {}", x)),
+                None => break Err("Connection lost".to_string()),
+            }
+        }
+    }
+
+    fn print(info: &TournamentData, pairings: bool) {
+        println!(
+            "> {} ({}, {}): {}",
+            info.name,
+            info.game,
+            info.format,
+            if info.finished {
+                format!("ended after {} rounds", info.round)
+            } else {
+                format!("round {}/{}", info.round, info.rounds)
+            }
+        );
+        let mut table = Table::new();
+        const FIELDS: &[&str] = &[
+            "#",
+            "Name",
+            "Points",
+            "Wins",
+            "Draws",
+            "Losses",
+            "Byes",
+            "Eliminated",
+        ];
+        table.add_row(Row::new(
+            FIELDS
+                .iter()
+                .map(|x| Cell::new_align(x, CENTER).with_style(Attr::Bold))
+                .collect(),
+        ));
+        for (i, s) in info.standings.iter().enumerate() {
+            table.add_row(Row::new(vec![
+                Cell::new_align(&format!("{}", i + 1), CENTER),
+                Cell::new_align(&s.name, CENTER),
+                Cell::new_align(&format!("{}", s.points), CENTER),
+                Cell::new_align(&format!("{}", s.wins), CENTER),
+                Cell::new_align(&format!("{}", s.draws), CENTER),
+                Cell::new_align(&format!("{}", s.losses), CENTER),
+                Cell::new_align(&format!("{}", s.byes), CENTER),
+                Cell::new_align(if s.eliminated { "X" } else { "" }, CENTER),
+            ]));
+        }
+        table.printstd();
+        for p in info.pairings.iter() {
+            if !pairings && p.round != info.round {
+                continue;
+            }
+            let results = match p.results {
+                Some(ref x) => x.iter().map(|x| format!("{}", x)).collect(),
+                None => vec![format!("pending"); p.players.len()],
+            };
+            let players: Vec<_> = p
+                .players
+                .iter()
+                .zip(results)
+                .map(|(name, result)| format!("{} ({})", name, result))
+                .collect();
+            match p.players.len() {
+                1 => println!("- R{}: {} bye", p.round, p.players[0]),
+                _ => println!(
+                    "- R{}: {} [{}]",
+                    p.round,
+                    players.join(" vs "),
+                    p.id.as_deref().unwrap_or("-")
+                ),
+            }
+        }
+    }
+}
+
+impl TournamentNewCommand {
+    async fn run<T: Sink<Message> + Unpin, U: Stream<Item = Result<Message, TsError>> + Unpin>(
+        self,
+        wsout: &mut T,
+        wsin: &mut U,
+    ) -> Result<(), String>
+    where
+        <T as Sink<Message>>::Error: Display,
+    {
+        let mut args = HashMap::new();
+        for arg in self.args {
+            let arg: Vec<_> = arg.split("=").collect();
+            if arg.len() < 2 {
+                return Err(format!("{} is not a valid argument", arg.join("")));
+            }
+            args.insert(arg[0].into(), arg[1..].join(""));
+        }
+        let format = match (self.format, self.rounds) {
+            (TournamentFormatEnum::Swiss, Some(rounds)) => TournamentFormat::Swiss { rounds },
+            (TournamentFormatEnum::Swiss, None) => {
+                return Err("Swiss tournaments need the number of rounds".to_string())
+            }
+            (_, Some(_)) => return Err("Rounds can be set only for swiss tournaments".to_string()),
+            (TournamentFormatEnum::RoundRobin, None) => TournamentFormat::RoundRobin,
+            (TournamentFormatEnum::DoubleRoundRobin, None) => TournamentFormat::DoubleRoundRobin,
+            (TournamentFormatEnum::SingleElimination, None) => TournamentFormat::SingleElimination,
+        };
+        let name = match self.name {
+            Some(x) => x,
+            None => format!("{} tournament", self.game),
+        };
+        let request = Request::TournamentNew {
+            name,
+            game: self.game,
+            format,
+            participants: self.participants,
+            timeout: self.timeout,
+            args,
+            verification: self.verification_password,
+        };
+        match oneshot_request(request, wsout, wsin).await? {
+            Reply::TournamentNew { id: Ok(id) } => {
+                println!("Created new tournament with id {}", id);
+                Ok(())
+            }
+            Reply::TournamentNew { id: Err(x) } => {
+                error!("Cannot create new tournament: {}", x);
+                Ok(())
+            }
+            _ => Err("Server returned the wrong reply".to_string()),
+        }
+    }
+}
+
+/// Session stored for a server, the credentials file maps server URLs to them
+#[derive(Serialize, Deserialize, Debug)]
+struct Credentials {
+    name: String,
+    token: String,
+}
+
+impl Credentials {
+    fn path() -> Result<PathBuf, String> {
+        let base = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(x) => PathBuf::from(x),
+            None => match std::env::var_os("HOME") {
+                Some(x) => PathBuf::from(x).join(".config"),
+                None => match std::env::var_os("APPDATA") {
+                    Some(x) => PathBuf::from(x),
+                    None => return Err("Cannot find configuration directory".to_string()),
+                },
+            },
+        };
+        Ok(base.join("coco").join(CREDENTIALS_FILE))
+    }
+
+    fn load() -> HashMap<String, Credentials> {
+        let data = match Self::path().and_then(|x| std::fs::read(x).map_err(|x| x.to_string())) {
+            Ok(x) => x,
+            Err(_) => return HashMap::new(),
+        };
+        match serde_json::from_slice(&data) {
+            Ok(x) => x,
+            Err(x) => {
+                warn!("Cannot parse credentials file: {}", x);
+                HashMap::new()
+            }
+        }
+    }
+
+    fn save(credentials: &HashMap<String, Credentials>) -> Result<(), String> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|x| format!("Cannot create configuration directory: {}", x))?;
+        }
+        let data = serde_json::to_vec_pretty(credentials)
+            .map_err(|x| format!("Cannot serialize credentials: {}", x))?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&path)
+            .and_then(|mut x| std::io::Write::write_all(&mut x, &data))
+            .map_err(|x| format!("Cannot write {}: {}", path.display(), x))
+    }
+}
+
+#[derive(Parser, Debug)]
+struct AccountCommand {
+    #[clap(subcommand)]
+    command: AccountSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum AccountSubcommand {
+    /// Register a new account, reserving its username
+    Register(AccountCredentials),
+    /// Login to an existing account
+    Login(AccountCredentials),
+    /// Forget the stored session for the server
+    Logout,
+    /// Show the account in use
+    Status,
+}
+
+#[derive(Parser, Debug)]
+struct AccountCredentials {
+    #[clap(help = "Username")]
+    name: String,
+    #[clap(short, long, help = "Password, asked interactively if missing")]
+    password: Option<String>,
+}
+
+impl AccountCommand {
+    async fn run<T: Sink<Message> + Unpin, U: Stream<Item = Result<Message, TsError>> + Unpin>(
+        self,
+        server: &Server,
+        wsout: &mut T,
+        wsin: &mut U,
+    ) -> Result<(), String>
+    where
+        <T as Sink<Message>>::Error: Display,
+    {
+        let (name, request) = match self.command {
+            AccountSubcommand::Register(x) => {
+                let password = x.password()?;
+                let request = Request::AccountRegister {
+                    name: x.name.clone(),
+                    password,
+                };
+                (x.name, request)
+            }
+            AccountSubcommand::Login(x) => {
+                let password = x.password()?;
+                let request = Request::AccountLogin {
+                    name: x.name.clone(),
+                    password,
+                };
+                (x.name, request)
+            }
+            AccountSubcommand::Logout => {
+                let mut credentials = Credentials::load();
+                if credentials.remove(&server.url).is_some() {
+                    Credentials::save(&credentials)?;
+                }
+                println!("Logged out");
+                return Ok(());
+            }
+            AccountSubcommand::Status => {
+                match server.user {
+                    Some(ref x) => println!("Logged in as \"{}\"", x),
+                    None => println!("Not logged in"),
+                }
+                return Ok(());
+            }
+        };
+        let token = match oneshot_request(request, wsout, wsin).await? {
+            Reply::AccountRegister { token } | Reply::AccountLogin { token } => token,
+            _ => return Err("Server returned the wrong reply".to_string()),
+        };
+        let token = match token {
+            Ok(x) => x,
+            Err(x) => return Err(format!("Authentication failed: {}", x)),
+        };
+        let mut credentials = Credentials::load();
+        credentials.insert(
+            server.url.clone(),
+            Credentials {
+                name: name.clone(),
+                token,
+            },
+        );
+        Credentials::save(&credentials)?;
+        println!("Logged in as \"{}\"", name);
+        Ok(())
+    }
+}
+
+impl AccountCredentials {
+    fn password(&self) -> Result<String, String> {
+        if let Some(ref x) = self.password {
+            return Ok(x.clone());
+        }
+        print!("Password: ");
+        std::io::Write::flush(&mut std::io::stdout())
+            .map_err(|x| format!("Cannot write to stdout: {}", x))?;
+        let mut password = String::new();
+        std::io::stdin()
+            .read_line(&mut password)
+            .map_err(|x| format!("Cannot read password: {}", x))?;
+        Ok(password.trim_end_matches(&['\r', '\n'][..]).to_owned())
+    }
+}
+
+fn init_logging() {
+    if let Err(x) = tracing_subscriber::fmt()
+        .event_format(
+            tracing_subscriber::fmt::format()
+                .without_time()
+                .with_target(false),
+        )
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init()
+    {
+        println!("Cannot enable logging service: {}", x);
+    }
+}
+
+/// Connection to a server, logged in if `user` is set
+struct Server {
+    url: String,
+    user: Option<String>,
+}
+
+async fn connect_server(
+    server_url: &str,
+) -> Result<(WebSocketStream<MaybeTlsStream<TcpStream>>, Option<String>), String> {
+    let mut ws = match connect_async(server_url).await {
+        Ok(x) => x.0,
+        Err(x) => return Err(format!("Cannot connect to \"{}\": {}", server_url, x)),
+    };
+    let result = match ws.get_mut() {
+        &mut MaybeTlsStream::Plain(ref mut x) => x.set_nodelay(true),
+        &mut MaybeTlsStream::Rustls(ref mut x) => x.get_mut().0.set_nodelay(true),
+        &mut _ => unreachable!("Using stream other than Plain or Rustls"),
+    };
+    if let Err(x) = result {
+        warn!("Cannot set TCP_NODELAY: {}", x);
+    }
+    let token = Credentials::load().remove(server_url).map(|x| x.token);
+    let handshake_request = match Request::forge(&Request::Handshake {
+        magic: proto::MAGIC.to_string(),
+        version: proto::VERSION,
+        token: token.clone(),
+    }) {
+        Ok(x) => Message::Text(x),
+        Err(x) => return Err(format!("Cannot forge handshake request: {}", x)),
+    };
+    if let Err(x) = ws.send(handshake_request).await {
+        return Err(format!("Cannot send handshake request: {}", x));
+    };
+    let handshake_reply = loop {
+        if let Some(msg) = ws.next().await {
+            match msg {
+                Ok(Message::Text(x)) => match Reply::parse(&x) {
+                    Ok(Reply::Handshake {
+                        magic,
+                        version,
+                        user,
+                    }) => break (magic, version, user),
+                    Ok(_) => return Err(format!("Server performed a wrong handshake")),
+                    Err(x) => return Err(format!("Could not parse server handshake: {}", x)),
+                },
+                Err(x) => return Err(format!("Connection lost while performing handshake: {}", x)),
+                Ok(_) => {}
+            }
+        } else {
+            return Err(format!("Connection lost while performing handshake"));
+        }
+    };
+    if !(handshake_reply.0 == proto::MAGIC && handshake_reply.1 == proto::VERSION) {
+        return if handshake_reply.0 == proto::MAGIC {
+            Err(format!(
+                "Protocol version mismatch: local={}, server={}",
+                proto::VERSION,
+                handshake_reply.1
+            ))
+        } else {
+            Err(format!("\"{}\" is not a Code Colosseum server", server_url))
+        };
+    }
+    if token.is_some() && handshake_reply.2.is_none() {
+        warn!("Stored session is not valid anymore, login again to use it");
+    }
+    Ok((ws, handshake_reply.2))
+}
+
+async fn start(args: CliArgs) -> Result<(), String> {
+    match args.command {
+        Command::Local(cmd) => return cmd.run().await,
+        Command::Bench(cmd) if cmd.local => return cmd.run(&args.server_url).await,
+        _ => {}
+    }
+    let (ws, user) = connect_server(&args.server_url).await?;
+    let server = Server {
+        url: args.server_url,
+        user,
+    };
+    let (mut wsout, mut wsin) = ws.split();
+    args.command.run(&server, &mut wsout, &mut wsin).await?;
+    let mut ws = match wsin.reunite(wsout) {
         Ok(x) => x,
         Err(x) => return Err(format!("Cannot reunite streams {}", x)),
     };
@@ -1,10 +1,16 @@
+use crate::account::{self, Policy};
 use crate::master::Services;
-use crate::proto::{self, Reply, Request};
-use crate::tuning::{CHUNK_SIZE, PING_TIMEOUT, PIPE_BUFFER, QUEUE_BUFFER};
-use crate::{db, game, lobby};
+use crate::proto::{self, Reply, Request, TranscriptEntry};
+use crate::tuning::{
+    ACCOUNT_PASSWORD_REGEX, CHUNK_SIZE, MAX_LEADERBOARD, PING_TIMEOUT, PIPE_BUFFER, QUEUE_BUFFER,
+    USERNAME_REGEX,
+};
+use crate::{db, game, lobby, tournament};
 use futures_util::sink::Sink;
 use futures_util::stream::Stream;
 use futures_util::{SinkExt, StreamExt};
+use regex::Regex;
+use std::collections::HashMap;
 use std::fmt::Display;
 use tokio::io::{split, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream};
 use tokio::select;
@@ -22,6 +28,8 @@ pub(crate) struct Client<T: AsyncRead + AsyncWrite + Unpin> {
     ws: WebSocketStream<T>,
     addr: String,
     srv: Services,
+    /// Account name of the logged in user
+    user: Option<String>,
 }
 
 macro_rules! reunite {
@@ -153,7 +161,12 @@ macro_rules! wsrecv {
 
 impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
     pub(crate) fn new(ws: WebSocketStream<T>, addr: String, srv: Services) -> Client<T> {
-        Client { ws, addr, srv }
+        Client {
+            ws,
+            addr,
+            srv,
+            user: None,
+        }
     }
 
     #[instrument(name = "client", skip(self))]
@@ -168,10 +181,22 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
             };
             match msg {
                 Ok(Message::Text(msg)) => match Request::parse(&msg) {
-                    Ok(Request::Handshake { magic, version }) => {
+                    Ok(Request::Handshake {
+                        magic,
+                        version,
+                        token,
+                    }) => {
+                        if let (Some(token), true) = (token, self.srv.accounts != Policy::Disabled)
+                        {
+                            self.user = match Self::session(&self.srv, token).await {
+                                Ok(x) => x,
+                                Err(()) => break,
+                            };
+                        }
                         match Reply::forge(&Reply::Handshake {
                             magic: proto::MAGIC.to_string(),
                             version: proto::VERSION,
+                            user: self.user.clone(),
                         }) {
                             Ok(msg) => {
                                 if let Err(x) = wsout.send(Message::Text(msg)).await {
@@ -196,6 +221,166 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
         return self.stop().await;
     }
 
+    async fn session(srv: &Services, token: String) -> Result<Option<String>, ()> {
+        let token = account::token_hash(&token);
+        let user = oneshot_reply2!(srv.db, |response| db::Command::RetrieveSession {
+            response,
+            token
+        });
+        if let Some(ref x) = user {
+            info!("Logged in as \"{}\"", x);
+        }
+        Ok(user)
+    }
+
+    /// Check that the user can play with a name
+    async fn authorize(
+        srv: &Services,
+        user: &Option<String>,
+        name: &str,
+    ) -> Result<Result<(), String>, ()> {
+        match (srv.accounts, user) {
+            (Policy::Disabled, _) => Ok(Ok(())),
+            (_, Some(x)) if x == name => Ok(Ok(())),
+            (Policy::Required, Some(x)) => Ok(Err(format!("Logged in as \"{}\"", x))),
+            (Policy::Required, None) => Ok(Err("Login required to play".to_string())),
+            (Policy::Optional, _) => {
+                let name = name.to_owned();
+                let owner = oneshot_reply2!(srv.db, |response| db::Command::RetrieveAccount {
+                    response,
+                    name
+                });
+                match owner {
+                    Some(x) => Ok(Err(format!("Username \"{}\" is reserved", x.name))),
+                    None => Ok(Ok(())),
+                }
+            }
+        }
+    }
+
+    /// Check that every participant of a tournament owns an account, since
+    /// only its owner can receive the passwords of the matches
+    async fn check_participants(
+        srv: &Services,
+        participants: &[String],
+    ) -> Result<Result<(), String>, ()> {
+        if srv.accounts == Policy::Disabled {
+            return Ok(Err(
+                "Tournaments need accounts, which are disabled on this server".to_string(),
+            ));
+        }
+        for name in participants {
+            let account = oneshot_reply2!(srv.db, |response| db::Command::RetrieveAccount {
+                response,
+                name: name.clone()
+            });
+            if account.is_none() {
+                return Ok(Err(format!("\"{}\" has no account", name)));
+            }
+        }
+        Ok(Ok(()))
+    }
+
+    /// Retrieve what a player exchanged with the game during a match
+    async fn transcript(
+        srv: &Services,
+        id: String,
+        name: &str,
+    ) -> Result<Result<Vec<TranscriptEntry>, String>, ()> {
+        let transcripts = oneshot_reply2!(srv.db, |response| db::Command::RetrieveTranscripts {
+            response,
+            id
+        });
+        match transcripts {
+            Ok(mut x) => Ok(x
+                .remove(name)
+                .ok_or_else(|| format!("\"{}\" did not play this match", name))),
+            Err(x) => Ok(Err(format!("Cannot retrieve transcripts: {:?}", x))),
+        }
+    }
+
+    async fn register(
+        srv: &Services,
+        name: String,
+        password: String,
+    ) -> Result<Result<String, String>, ()> {
+        if srv.accounts == Policy::Disabled {
+            return Ok(Err("Accounts are disabled on this server".to_string()));
+        }
+        match Regex::new(USERNAME_REGEX) {
+            Ok(x) if x.is_match(&name) => {}
+            Ok(_) => return Ok(Err(format!("\"{}\" is not a valid username", name))),
+            Err(x) => {
+                error!("Cannot compile regex: {}", x);
+                return Err(());
+            }
+        }
+        match Regex::new(ACCOUNT_PASSWORD_REGEX) {
+            Ok(x) if x.is_match(&password) => {}
+            Ok(_) => {
+                return Ok(Err(
+                    "Password must be 8 to 64 printable characters".to_string()
+                ))
+            }
+            Err(x) => {
+                error!("Cannot compile regex: {}", x);
+                return Err(());
+            }
+        }
+        let account = match account::create(name.clone(), password).await {
+            Ok(x) => x,
+            Err(x) => {
+                error!("{}", x);
+                return Err(());
+            }
+        };
+        if !oneshot_reply2!(srv.db, |response| db::Command::Register {
+            response,
+            account
+        }) {
+            return Ok(Err(format!("Username \"{}\" is already registered", name)));
+        }
+        info!("Registered \"{}\"", name);
+        Self::new_session(srv, name).await.map(Ok)
+    }
+
+    async fn login(
+        srv: &Services,
+        name: String,
+        password: String,
+    ) -> Result<Result<String, String>, ()> {
+        if srv.accounts == Policy::Disabled {
+            return Ok(Err("Accounts are disabled on this server".to_string()));
+        }
+        let lookup = name.clone();
+        let account = oneshot_reply2!(srv.db, |response| db::Command::RetrieveAccount {
+            response,
+            name: lookup
+        });
+        let valid = match account {
+            Some(x) => account::verify(x, password).await,
+            None => false,
+        };
+        if !valid {
+            return Ok(Err("Wrong username or password".to_string()));
+        }
+        info!("Logged in as \"{}\"", name);
+        Self::new_session(srv, name).await.map(Ok)
+    }
+
+    async fn new_session(srv: &Services, name: String) -> Result<String, ()> {
+        let token = account::new_token();
+        let cmd = db::Command::StoreSession {
+            name,
+            token: account::token_hash(&token),
+        };
+        if srv.db.send(cmd).await.is_err() {
+            error!("Cannot forward request to database");
+            return Err(());
+        }
+        Ok(token)
+    }
+
     async fn main(mut self) {
         let (mut wsout, mut wsin) = self.ws.split();
         loop {
@@ -235,12 +420,27 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
                     }
                 }
                 Request::LobbyJoinMatch { id, name, password } => {
+                    match Self::authorize(&self.srv, &self.user, &name).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(x)) => {
+                            send!(wsout, Reply::LobbyJoinedMatch { info: Err(x) });
+                            continue;
+                        }
+                        Err(()) => break,
+                    }
                     if let Err(()) =
                         Self::join_match(&mut wsin, &mut wsout, &self.srv, id, name, password).await
                     {
                         break;
                     }
                 }
+                Request::MatchResume { token, received } => {
+                    if let Err(()) =
+                        Self::resume_match(&mut wsin, &mut wsout, &self.srv, token, received).await
+                    {
+                        break;
+                    }
+                }
                 Request::SpectateJoin { id } => {
                     if let Err(()) =
                         Self::spectate_match(&mut wsin, &mut wsout, &self.srv, id).await
@@ -268,10 +468,113 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
                     );
                     send!(wsout, Reply::GameNew { id });
                 }
+                Request::TournamentNew {
+                    name,
+                    game,
+                    format,
+                    participants,
+                    timeout,
+                    args,
+                    verification,
+                } => {
+                    match Self::check_participants(&self.srv, &participants).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(x)) => {
+                            send!(wsout, Reply::TournamentNew { id: Err(x) });
+                            continue;
+                        }
+                        Err(()) => break,
+                    }
+                    let id = oneshot_reply!(
+                        self.srv.tournament,
+                        tournament::Command::New,
+                        name,
+                        game,
+                        format,
+                        participants,
+                        timeout,
+                        args,
+                        verification
+                    );
+                    send!(wsout, Reply::TournamentNew { id });
+                }
+                Request::TournamentList {} => {
+                    let ids = oneshot_reply!(self.srv.db, db::Command::ListTournaments);
+                    send!(wsout, Reply::TournamentList { ids });
+                }
+                Request::TournamentInfo { id } => {
+                    let info = oneshot_reply!(self.srv.tournament, tournament::Command::Info, id);
+                    send!(wsout, Reply::TournamentInfo { info });
+                }
+                Request::TournamentSubscribe { id, name } => {
+                    // The passwords of the matches go only to the owner of the account
+                    if let Some(ref name) = name {
+                        if self.user.as_ref() != Some(name) {
+                            let info = Err(format!("Log in as \"{}\" to play", name));
+                            send!(wsout, Reply::TournamentSubscribed { info });
+                            continue;
+                        }
+                    }
+                    if let Err(()) =
+                        Self::tournament(&mut wsin, &mut wsout, &self.srv, id, name).await
+                    {
+                        break;
+                    }
+                }
+                Request::QueueJoin { game, name, args } => {
+                    match Self::authorize(&self.srv, &self.user, &name).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(x)) => {
+                            send!(wsout, Reply::QueueJoined { info: Err(x) });
+                            continue;
+                        }
+                        Err(()) => break,
+                    }
+                    if let Err(()) =
+                        Self::queue(&mut wsin, &mut wsout, &self.srv, game, name, args).await
+                    {
+                        break;
+                    }
+                }
+                Request::AccountRegister { name, password } => {
+                    let token = match Self::register(&self.srv, name.clone(), password).await {
+                        Ok(x) => x,
+                        Err(()) => break,
+                    };
+                    if token.is_ok() {
+                        self.user = Some(name);
+                    }
+                    send!(wsout, Reply::AccountRegister { token });
+                }
+                Request::AccountLogin { name, password } => {
+                    let token = match Self::login(&self.srv, name.clone(), password).await {
+                        Ok(x) => x,
+                        Err(()) => break,
+                    };
+                    if token.is_ok() {
+                        self.user = Some(name);
+                    }
+                    send!(wsout, Reply::AccountLogin { token });
+                }
                 Request::HistoryMatchList => {
                     let results = oneshot_reply!(self.srv.db, db::Command::List);
                     send!(wsout, Reply::HistoryMatchList(results));
                 }
+                Request::Leaderboard { game, limit } => {
+                    let ratings =
+                        oneshot_reply!(self.srv.db, |response| db::Command::Leaderboard {
+                            response,
+                            game,
+                            limit: limit.min(MAX_LEADERBOARD),
+                        });
+                    send!(wsout, Reply::Leaderboard { ratings });
+                }
+                Request::PlayerStats { name } => {
+                    let ratings = oneshot_reply!(self.srv.db, |response| {
+                        db::Command::PlayerStats { response, name }
+                    });
+                    send!(wsout, Reply::PlayerStats { ratings });
+                }
                 Request::HistoryMatch { id } => {
                     let (tx, rx) = oneshot::channel();
                     if let Err(_) = self
@@ -301,6 +604,30 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
                         }
                     }
                 }
+                Request::HistoryTranscript {
+                    id,
+                    name,
+                    verification,
+                } => {
+                    let transcript = if verification.as_ref() == Some(&self.srv.verification_pw)
+                        || self.user.as_ref() == Some(&name)
+                    {
+                        match Self::transcript(&self.srv, id, &name).await {
+                            Ok(x) => x,
+                            Err(()) => break,
+                        }
+                    } else {
+                        Err("Transcripts are available only to their player or with the verification password".to_string())
+                    };
+                    match bincode::serialize(&Reply::HistoryTranscript { transcript }) {
+                        Err(e) => error!("Unable to serialize: {}", e),
+                        Ok(encoded) => {
+                            if let Err(e) = wsout.send(Message::Binary(encoded)).await {
+                                warn!("Cannot send reply {}", e);
+                            }
+                        }
+                    }
+                }
                 _ => {
                     warn!("Request not valid for current state: {:?}", req);
                     break;
@@ -398,10 +725,11 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
                             break;
                         }
                     }
-                    Ok(lobby::MatchEvent::Ended) => {
-                        send!(wsout, Reply::SpectateEnded {});
+                    Ok(lobby::MatchEvent::Ended(outcome)) => {
+                        send!(wsout, Reply::SpectateEnded { outcome });
                         return Ok(());
                     }
+                    Ok(lobby::MatchEvent::SeriesEnded(_)) | Ok(lobby::MatchEvent::Queued(_)) => continue,
                     Err(BrRecvError::Closed) => {
                         error!("Lobby is unreachable");
                         break;
@@ -430,8 +758,8 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
     where
         <X as Sink<Message>>::Error: Display,
     {
-        let (tx, mut rx) = mpsc::channel(QUEUE_BUFFER);
-        match oneshot_reply2!(
+        let (tx, rx) = mpsc::channel(QUEUE_BUFFER);
+        let series = match oneshot_reply2!(
             srv.lobby,
             lobby::Command::JoinMatch,
             id.clone(),
@@ -439,12 +767,66 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
             password,
             tx
         ) {
-            Ok(x) => send2!(wsout, Reply::LobbyJoinedMatch { info: Ok(x) }),
+            Ok(x) => {
+                let series = x.series.is_some();
+                send2!(wsout, Reply::LobbyJoinedMatch { info: Ok(x) });
+                series
+            }
             Err(x) => {
                 send2!(wsout, Reply::LobbyJoinedMatch { info: Err(x) });
                 return Ok(());
             }
         };
+        Self::wait_match(wsin, wsout, srv, id, name, series, rx).await
+    }
+
+    async fn resume_match<
+        X: Sink<Message> + Unpin,
+        Y: Stream<Item = Result<Message, TsError>> + Unpin,
+    >(
+        wsin: &mut Y,
+        wsout: &mut X,
+        srv: &Services,
+        token: String,
+        received: u64,
+    ) -> Result<(), ()>
+    where
+        <X as Sink<Message>>::Error: Display,
+    {
+        let (tx, mut rx) = mpsc::channel(QUEUE_BUFFER);
+        match oneshot_reply2!(srv.lobby, lobby::Command::ResumeMatch, token, received, tx) {
+            Ok((info, name, stream)) => {
+                let (id, series) = (info.id.clone(), info.series.is_some());
+                send2!(wsout, Reply::MatchResumed { info: Ok(info) });
+                Self::play(wsin, wsout, stream, &mut rx).await?;
+                if !series {
+                    return Ok(());
+                }
+                // The next games of the series follow on this connection
+                Self::wait_match(wsin, wsout, srv, id, name, series, rx).await
+            }
+            Err(x) => {
+                send2!(wsout, Reply::MatchResumed { info: Err(x) });
+                Ok(())
+            }
+        }
+    }
+
+    async fn wait_match<
+        X: Sink<Message> + Unpin,
+        Y: Stream<Item = Result<Message, TsError>> + Unpin,
+    >(
+        wsin: &mut Y,
+        wsout: &mut X,
+        srv: &Services,
+        id: String,
+        name: String,
+        series: bool,
+        mut rx: mpsc::Receiver<lobby::MatchEvent>,
+    ) -> Result<(), ()>
+    where
+        <X as Sink<Message>>::Error: Display,
+    {
         loop {
             select! {
                 msg = wsrecv!(wsin) => {
@@ -479,9 +861,16 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
                 }
                 msg = rx.recv() => { match msg {
                     Some(lobby::MatchEvent::Update(info)) => send!(wsout, Reply::LobbyUpdate { info }),
-                    Some(lobby::MatchEvent::Started(Some(stream))) => {
-                        send!(wsout, Reply::MatchStarted {});
-                        return Self::play(wsin, wsout, stream, rx).await;
+                    Some(lobby::MatchEvent::Started(Some(seat))) => {
+                        send!(wsout, Reply::MatchStarted { token: seat.token });
+                        Self::play(wsin, wsout, seat.stream, &mut rx).await?;
+                        if !series {
+                            return Ok(());
+                        }
+                    }
+                    Some(lobby::MatchEvent::SeriesEnded(series)) => {
+                        send!(wsout, Reply::SeriesEnded { series });
+                        return Ok(());
                     }
                     Some(lobby::MatchEvent::Expired) => {
                         send!(wsout, Reply::LobbyDelete { id });
@@ -501,17 +890,102 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
         Err(())
     }
 
+    async fn queue<X: Sink<Message> + Unpin, Y: Stream<Item = Result<Message, TsError>> + Unpin>(
+        wsin: &mut Y,
+        wsout: &mut X,
+        srv: &Services,
+        game: String,
+        name: String,
+        args: HashMap<String, String>,
+    ) -> Result<(), ()>
+    where
+        <X as Sink<Message>>::Error: Display,
+    {
+        let (tx, mut rx) = mpsc::channel(QUEUE_BUFFER);
+        match oneshot_reply2!(
+            srv.lobby,
+            lobby::Command::QueueJoin,
+            game.clone(),
+            name.clone(),
+            args.clone(),
+            tx
+        ) {
+            Ok(x) => send2!(wsout, Reply::QueueJoined { info: Ok(x) }),
+            Err(x) => {
+                send2!(wsout, Reply::QueueJoined { info: Err(x) });
+                return Ok(());
+            }
+        };
+        loop {
+            select! {
+                msg = wsrecv!(wsin) => {
+                    let msg = match handle_ping!(msg, wsout) {
+                        Some(Ok(Message::Text(x))) => x,
+                        Some(_) => continue,
+                        None => {
+                            if let Err(x) = oneshot_reply2!(srv.lobby, lobby::Command::QueueLeave, game.clone(), args.clone(), name.clone()) {
+                                warn!("Cannot leave queue: {}", x);
+                            }
+                            break;
+                        }
+                    };
+                    match Request::parse(&msg) {
+                        Ok(Request::QueueLeave {}) => {
+                            if let Err(x) = oneshot_reply2!(srv.lobby, lobby::Command::QueueLeave, game.clone(), args.clone(), name.clone()) {
+                                // Already matched, the match will follow
+                                warn!("Cannot leave queue: {}", x);
+                                continue;
+                            }
+                            send!(wsout, Reply::QueueLeaved {});
+                            return Ok(());
+                        }
+                        Ok(x) => {
+                            warn!("Request not valid while in queue: {:?}", x);
+                            break;
+                        }
+                        Err(x) => {
+                            warn!("Invalid request: {}", x);
+                            break;
+                        }
+                    };
+                }
+                msg = rx.recv() => { match msg {
+                    Some(lobby::MatchEvent::Queued(info)) => send!(wsout, Reply::QueueUpdate { info }),
+                    Some(lobby::MatchEvent::Update(info)) => {
+                        let id = info.id.clone();
+                        send!(wsout, Reply::QueueMatched { info });
+                        return Self::wait_match(wsin, wsout, srv, id, name, false, rx).await;
+                    }
+                    Some(lobby::MatchEvent::Expired) => {
+                        send!(wsout, Reply::QueueLeaved {});
+                        return Ok(());
+                    }
+                    Some(_) => {
+                        error!("Wrong message: {:?}", msg);
+                        break;
+                    }
+                    None => {
+                        error!("Lobby is unreachable");
+                        break;
+                    }
+                }}
+            }
+        }
+        Err(())
+    }
+
     async fn play<X: Sink<Message> + Unpin, Y: Stream<Item = Result<Message, TsError>> + Unpin>(
         wsin: &mut Y,
         wsout: &mut X,
         stream: DuplexStream,
-        mut rx: mpsc::Receiver<lobby::MatchEvent>,
+        rx: &mut mpsc::Receiver<lobby::MatchEvent>,
     ) -> Result<(), ()>
     where
         <X as Sink<Message>>::Error: Display,
     {
         let (mut ppin, mut ppout) = split(stream);
         let mut buffer = [0; PIPE_BUFFER];
+        let mut pipe_open = true;
         loop {
             select! {
                 msg = wsrecv!(wsin) => {
@@ -534,8 +1008,8 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
                 }
                 msg = rx.recv() => { match msg {
                     Some(lobby::MatchEvent::Update(info)) => send!(wsout, Reply::LobbyUpdate { info }),
-                    Some(lobby::MatchEvent::Ended) => {
-                        send!(wsout, Reply::MatchEnded {});
+                    Some(lobby::MatchEvent::Ended(outcome)) => {
+                        send!(wsout, Reply::MatchEnded { outcome });
                         return Ok(());
                     }
                     Some(_) => continue,
@@ -544,8 +1018,13 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
                         break;
                     }
                 }}
-                msg = ppin.read(&mut buffer) => {
+                msg = ppin.read(&mut buffer), if pipe_open => {
                     let size = match msg {
+                        Ok(0) => {
+                            // The game is over or another connection took the seat
+                            pipe_open = false;
+                            continue;
+                        }
                         Ok(x) => x,
                         Err(x) => {
                             error!("Game manager pipe ended prematurely: {}", x);
@@ -613,6 +1092,87 @@ impl<T: AsyncRead + AsyncWrite + Unpin> Client<T> {
         Err(())
     }
 
+    async fn tournament<
+        X: Sink<Message> + Unpin,
+        Y: Stream<Item = Result<Message, TsError>> + Unpin,
+    >(
+        wsin: &mut Y,
+        wsout: &mut X,
+        srv: &Services,
+        id: String,
+        name: Option<String>,
+    ) -> Result<(), ()>
+    where
+        <X as Sink<Message>>::Error: Display,
+    {
+        let (mut rx, pending) = match oneshot_reply2!(
+            srv.tournament,
+            tournament::Command::Subscribe,
+            id,
+            name.clone()
+        ) {
+            Ok((rx, info, pending)) => {
+                send2!(wsout, Reply::TournamentSubscribed { info: Ok(info) });
+                (rx, pending)
+            }
+            Err(x) => {
+                send2!(wsout, Reply::TournamentSubscribed { info: Err(x) });
+                return Ok(());
+            }
+        };
+        for (id, password) in pending {
+            send2!(wsout, Reply::TournamentPairing { id, password });
+        }
+        loop {
+            select! {
+                msg = wsrecv!(wsin) => {
+                    let msg = match handle_ping!(msg, wsout) {
+                        Some(Ok(Message::Text(x))) => x,
+                        Some(_) => continue,
+                        None => break,
+                    };
+                    match Request::parse(&msg) {
+                        Ok(Request::TournamentUnsubscribe {}) => {
+                            send!(wsout, Reply::TournamentUnsubscribed {});
+                            return Ok(());
+                        }
+                        Ok(x) => {
+                            warn!("Request not valid while following a tournament: {:?}", x);
+                            break;
+                        }
+                        Err(x) => {
+                            warn!("Invalid request: {}", x);
+                            break;
+                        }
+                    };
+                }
+                msg = rx.recv() => { match msg {
+                    Ok(tournament::Event::Update(info)) => send!(wsout, Reply::TournamentUpdate { info }),
+                    Ok(tournament::Event::Pairing { players, id, password }) => {
+                        if let Some(ref name) = name {
+                            if players.contains(name) {
+                                send!(wsout, Reply::TournamentPairing { id, password });
+                            }
+                        }
+                    }
+                    Ok(tournament::Event::Ended(info)) => {
+                        send!(wsout, Reply::TournamentEnded { info });
+                        return Ok(());
+                    }
+                    Err(BrRecvError::Closed) => {
+                        error!("Tournament manager is unreachable");
+                        break;
+                    }
+                    Err(BrRecvError::Lagged(x)) => {
+                        warn!("Client is {} updates behind: dropping", x);
+                        break;
+                    }
+                }}
+            };
+        }
+        Err(())
+    }
+
     async fn stop(mut self) {
         match self.ws.close(None).await {
             Ok(()) | Err(TsError::ConnectionClosed) => info!("Client disconnected"),
@@ -1,12 +1,22 @@
+use std::collections::HashMap;
 use std::{ffi::OsString, path::PathBuf};
 
-use crate::db::MatchData;
+use crate::db::{Account, MatchData};
+use crate::proto::{Rating, TournamentData};
+use crate::rating;
 
 use super::{Command, Database, DatabaseError};
 use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use tracing::{error, info, trace};
 
 const MATCH_DESCRIPTOR_FILE: &str = "descriptor.json";
+const TRANSCRIPTS_FILE: &str = "transcripts.json";
+const RATINGS_FILE: &str = "ratings.json";
+const TOURNAMENT_SUFFIX: &str = ".tournament.json";
+const ACCOUNTS_FILE: &str = "accounts.json";
+const SESSIONS_FILE: &str = "sessions.json";
 
 pub(crate) struct FileSystemArgs {
     pub(crate) root_dir: String,
@@ -14,6 +24,45 @@ pub(crate) struct FileSystemArgs {
 
 pub(crate) struct FileSystem {
     args: FileSystemArgs,
+    /// Ratings of every player, indexed by game and then by player name
+    ratings: HashMap<String, HashMap<String, Rating>>,
+    /// Registered accounts, indexed by name
+    accounts: HashMap<String, Account>,
+    /// Owner of every session, indexed by token hash
+    sessions: HashMap<String, String>,
+}
+
+impl FileSystem {
+    /// Read a JSON file from the root directory, falling back to the default value
+    fn load<T: DeserializeOwned + Default>(root_dir: &str, file: &str) -> T {
+        let path = format!("{}/{}", root_dir, file);
+        match std::fs::read(&path) {
+            Ok(data) => match serde_json::from_slice(&data) {
+                Ok(x) => x,
+                Err(e) => {
+                    error!("Unable to deserialize {}: {}", path, e);
+                    T::default()
+                }
+            },
+            Err(e) => {
+                info!("Nothing loaded from {}: {}", path, e);
+                T::default()
+            }
+        }
+    }
+
+    /// Write a JSON file in the root directory
+    async fn save<T: Serialize>(&self, file: &str, data: &T) {
+        match serde_json::to_string_pretty(data) {
+            Err(e) => error!("Unable to serialize {}: {}", file, e),
+            Ok(data_json) => {
+                let output_path = format!("{}/{}", self.args.root_dir, file);
+                if let Err(e) = tokio::fs::write(output_path, data_json).await {
+                    error!("Unable to save {}: {}", file, e);
+                }
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -21,7 +70,15 @@ impl Database for FileSystem {
     type Args = FileSystemArgs;
 
     fn create(args: Self::Args) -> Self {
-        Self { args }
+        let ratings = Self::load(&args.root_dir, RATINGS_FILE);
+        let accounts = Self::load(&args.root_dir, ACCOUNTS_FILE);
+        let sessions = Self::load(&args.root_dir, SESSIONS_FILE);
+        Self {
+            args,
+            ratings,
+            accounts,
+            sessions,
+        }
     }
 
     fn close(&mut self) {}
@@ -87,6 +144,170 @@ impl Database for FileSystem {
                     }
                 }
             }
+            // Save transcripts next to the match descriptor
+            Command::StoreTranscripts { id, transcripts } => {
+                // Only alphanumeric ids are allowed
+                if !id.chars().all(char::is_alphanumeric) {
+                    error!("Invalid game ID: {}", id);
+                    return;
+                }
+                self.save(&format!("{}/{}", id, TRANSCRIPTS_FILE), &transcripts)
+                    .await;
+            }
+            // Read transcripts from file
+            Command::RetrieveTranscripts { id, response } => {
+                let result = if !id.chars().all(char::is_alphanumeric) {
+                    error!("Invalid game ID: {}", id);
+                    Err(DatabaseError::InvalidID)
+                } else {
+                    let path = format!("{}/{}/{}", self.args.root_dir, id, TRANSCRIPTS_FILE);
+                    match tokio::fs::read(path).await {
+                        Err(e) => {
+                            error!("Unable to read transcripts: {}", e);
+                            Err(DatabaseError::FileNotFound)
+                        }
+                        Ok(data) => serde_json::from_slice(&data).map_err(|e| {
+                            error!("Unable to deserialize transcripts: {}", e);
+                            DatabaseError::UnableToDeserialize
+                        }),
+                    }
+                };
+                if let Err(e) = response.send(result) {
+                    error!("Unable to reply to retrieve transcripts command: {:?}", e);
+                }
+            }
+            // Update ratings and save them to file
+            Command::Rate { game, outcome } => {
+                rating::update(
+                    &game,
+                    self.ratings.entry(game.clone()).or_default(),
+                    &outcome,
+                );
+                self.save(RATINGS_FILE, &self.ratings).await;
+            }
+            // Return the best ratings of a game
+            Command::Leaderboard {
+                response,
+                game,
+                limit,
+            } => {
+                let mut result: Vec<Rating> = match self.ratings.get(&game) {
+                    Some(x) => x.values().cloned().collect(),
+                    None => vec![],
+                };
+                result.sort_by(rating::rank);
+                result.truncate(limit);
+                if let Err(e) = response.send(result) {
+                    error!("Unable to reply to leaderboard command: {:?}", e);
+                }
+            }
+            // Return the ratings of a player for every game
+            Command::PlayerStats { response, name } => {
+                let mut result: Vec<Rating> = self
+                    .ratings
+                    .values()
+                    .filter_map(|x| x.get(&name).cloned())
+                    .collect();
+                result.sort_by(|a, b| a.game.cmp(&b.game));
+                if let Err(e) = response.send(result) {
+                    error!("Unable to reply to player stats command: {:?}", e);
+                }
+            }
+            // Return list of all saved tournaments
+            Command::ListTournaments(response) => {
+                let mut result: Vec<String> = Vec::new();
+                match tokio::fs::read_dir(&self.args.root_dir).await {
+                    Ok(mut read_dir) => loop {
+                        match read_dir.next_entry().await {
+                            Err(e) => error!("Unable to read directory entry: {}", e),
+                            Ok(Some(file)) => {
+                                if let Ok(name) = file.file_name().into_string() {
+                                    if let Some(id) = name.strip_suffix(TOURNAMENT_SUFFIX) {
+                                        result.push(id.to_owned());
+                                    }
+                                }
+                            }
+                            Ok(None) => break,
+                        }
+                    },
+                    Err(e) => error!("Unable to read database root dir: {}", e),
+                };
+
+                if let Err(e) = response.send(result) {
+                    error!("Unable to reply to list tournaments command: {:?}", e);
+                }
+            }
+            // Save tournament data to file
+            Command::StoreTournament(data) => {
+                // Only alphanumeric ids are allowed
+                if !data.id.chars().all(char::is_alphanumeric) {
+                    error!("Invalid tournament ID: {}", data.id);
+                    return;
+                }
+                match serde_json::to_string_pretty(&data) {
+                    Err(e) => error!("Unable to serialize tournament data: {}", e),
+                    Ok(data_json) => {
+                        let output_path =
+                            format!("{}/{}{}", self.args.root_dir, data.id, TOURNAMENT_SUFFIX);
+                        if let Err(e) = tokio::fs::write(output_path, data_json).await {
+                            error!("Unable to save tournament data to file: {}", e);
+                        }
+                    }
+                }
+            }
+            // Read tournament data from file
+            Command::RetrieveTournament { id, response } => {
+                let result = if !id.chars().all(char::is_alphanumeric) {
+                    error!("Invalid tournament ID: {}", id);
+                    Err(DatabaseError::InvalidID)
+                } else {
+                    let input_path = format!("{}/{}{}", self.args.root_dir, id, TOURNAMENT_SUFFIX);
+                    match tokio::fs::read(input_path).await {
+                        Err(e) => {
+                            error!("Unable to read tournament data: {}", e);
+                            Err(DatabaseError::FileNotFound)
+                        }
+                        Ok(data) => match serde_json::from_slice::<TournamentData>(&data) {
+                            Err(e) => {
+                                error!("Unable to deserialize tournament data: {}", e);
+                                Err(DatabaseError::UnableToDeserialize)
+                            }
+                            Ok(x) => Ok(x),
+                        },
+                    }
+                };
+                if let Err(e) = response.send(result) {
+                    error!("Unable to send tournament data: {:?}", e);
+                }
+            }
+            // Create a new account if the name is available
+            Command::Register { response, account } => {
+                let created = !self.accounts.contains_key(&account.name);
+                if created {
+                    self.accounts.insert(account.name.clone(), account);
+                    self.save(ACCOUNTS_FILE, &self.accounts).await;
+                }
+                if let Err(e) = response.send(created) {
+                    error!("Unable to reply to register command: {:?}", e);
+                }
+            }
+            // Return the account owning a name
+            Command::RetrieveAccount { response, name } => {
+                if let Err(e) = response.send(self.accounts.get(&name).cloned()) {
+                    error!("Unable to reply to retrieve account command: {:?}", e);
+                }
+            }
+            // Save a new session
+            Command::StoreSession { name, token } => {
+                self.sessions.insert(token, name);
+                self.save(SESSIONS_FILE, &self.sessions).await;
+            }
+            // Return the owner of a session
+            Command::RetrieveSession { response, token } => {
+                if let Err(e) = response.send(self.sessions.get(&token).cloned()) {
+                    error!("Unable to reply to retrieve session command: {:?}", e);
+                }
+            }
             // Read match descriptor from file
             Command::Retrieve { id, response } => {
                 // Only alphanumeric ids are allowed
@@ -2,11 +2,12 @@ use std::path::PathBuf;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use tokio::sync::{mpsc, oneshot};
 use tracing::info;
 
+use crate::proto::{MatchOutcome, Rating, TournamentData, TranscriptEntry};
 use crate::tuning::QUEUE_BUFFER;
 
 pub(crate) mod filesystem;
@@ -20,6 +21,16 @@ pub(crate) struct MatchData {
     pub players: Vec<String>,
     pub bot_count: u32,
     pub history: Vec<u8>,
+    #[serde(default)]
+    pub outcome: Option<MatchOutcome>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Names the server bots played with
+    #[serde(default)]
+    pub bots: Vec<String>,
+    /// Identifier of the series the match is part of
+    #[serde(default)]
+    pub series: Option<String>,
 }
 
 impl fmt::Display for MatchData {
@@ -27,6 +38,12 @@ impl fmt::Display for MatchData {
         writeln!(f, "id: {}", self.id)?;
         writeln!(f, "game_name: {}", self.game_name)?;
         writeln!(f, "bot_count: {}", self.bot_count)?;
+        if let Some(seed) = self.seed {
+            writeln!(f, "seed: {}", seed)?;
+        }
+        if let Some(ref series) = self.series {
+            writeln!(f, "series: {}", series)?;
+        }
 
         writeln!(f, "args:")?;
         for (key, value) in self.args.iter() {
@@ -37,6 +54,16 @@ impl fmt::Display for MatchData {
         for player in &self.players {
             writeln!(f, "- {}", player)?;
         }
+        if !self.bots.is_empty() {
+            writeln!(f, "bots:")?;
+            for bot in &self.bots {
+                writeln!(f, "- {}", bot)?;
+            }
+        }
+
+        if let Some(ref outcome) = self.outcome {
+            write!(f, "{}", outcome)?;
+        }
 
         match std::str::from_utf8(&self.history) {
             Err(_) => Err(fmt::Error),
@@ -45,6 +72,15 @@ impl fmt::Display for MatchData {
     }
 }
 
+/// A registered user, owning the username
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Account {
+    pub name: String,
+    pub salt: String,
+    pub hash: String,
+    pub created: u64,
+}
+
 /// All possible database errors
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) enum DatabaseError {
@@ -64,6 +100,55 @@ pub(crate) enum Command {
     },
     /// Save a match
     Store(MatchData),
+    /// Returns the transcripts of a match, indexed by player name
+    RetrieveTranscripts {
+        response: oneshot::Sender<Result<BTreeMap<String, Vec<TranscriptEntry>>, DatabaseError>>,
+        id: String,
+    },
+    /// Save the transcripts of a match, after the match itself
+    StoreTranscripts {
+        id: String,
+        transcripts: BTreeMap<String, Vec<TranscriptEntry>>,
+    },
+    /// Update the ratings of a game with the outcome of a match
+    Rate { game: String, outcome: MatchOutcome },
+    /// Returns the best `limit` ratings of a game
+    Leaderboard {
+        response: oneshot::Sender<Vec<Rating>>,
+        game: String,
+        limit: usize,
+    },
+    /// Returns the ratings of a player for every game
+    PlayerStats {
+        response: oneshot::Sender<Vec<Rating>>,
+        name: String,
+    },
+    /// Request the list of all saved tournaments ids
+    ListTournaments(oneshot::Sender<Vec<String>>),
+    /// Returns the data of a tournament
+    RetrieveTournament {
+        response: oneshot::Sender<Result<TournamentData, DatabaseError>>,
+        id: String,
+    },
+    /// Save a tournament, overwriting its previous state
+    StoreTournament(TournamentData),
+    /// Save a new account, replies false if the name is already taken
+    Register {
+        response: oneshot::Sender<bool>,
+        account: Account,
+    },
+    /// Returns the account owning a name
+    RetrieveAccount {
+        response: oneshot::Sender<Option<Account>>,
+        name: String,
+    },
+    /// Save a session of an account, given the hash of its token
+    StoreSession { name: String, token: String },
+    /// Returns the name of the account owning a session, given the hash of its token
+    RetrieveSession {
+        response: oneshot::Sender<Option<String>>,
+        token: String,
+    },
 }
 
 #[async_trait]
@@ -1,8 +1,14 @@
 use crate::games;
-pub(crate) use crate::proto::{GameArgInfo, GameParams as Params, GameUsage};
-use crate::tuning::QUEUE_BUFFER;
+pub(crate) use crate::proto::{
+    BotInfo, GameArgInfo, GameParams as Params, GameUsage, MatchOutcome, PlayerOutcome,
+    PlayerResult, SeatPolicy, Termination, TimeControl,
+};
+use crate::tuning::{MAX_CLOCK_BANK, MAX_TIMEOUT, MIN_TIMEOUT, QUEUE_BUFFER};
 use async_trait::async_trait;
-use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use tokio::io::{DuplexStream, WriteHalf};
 use tokio::spawn;
@@ -14,24 +20,27 @@ pub(crate) trait Builder: Send + Sync + Debug {
     fn name(&self) -> &str;
     async fn description(&self) -> String;
     async fn args(&self) -> HashMap<String, GameArgInfo>;
+    /// Server bots offered by the game, the first one is the default
+    async fn bots(&self) -> Vec<BotInfo>;
+    /// Names of the seats in order, the ones past them are called by their number
+    async fn seats(&self) -> Vec<String>;
     async fn gen_instance(
         &self,
         param: &mut Params,
         args: HashMap<String, String>,
     ) -> Result<Box<dyn Instance>, String>;
-    async fn gen_bot(&self) -> Box<dyn Bot>;
+    /// Create the bot called `name`, which is one of the offered ones
+    async fn gen_bot(&self, name: &str, seed: u64) -> Box<dyn Bot>;
 }
 
 #[async_trait]
 pub(crate) trait Instance: Send + Sync + Debug {
+    /// Play the match, with `players` given in seat order, and report how it ended
     async fn start(
         &mut self,
-        players: HashMap<String, DuplexStream>,
+        players: Vec<(String, DuplexStream)>,
         spectators: WriteHalf<DuplexStream>,
-    );
-
-    /// Get arguments of this instance
-    async fn args(&self) -> HashMap<String, String>;
+    ) -> MatchOutcome;
 }
 
 #[async_trait]
@@ -39,23 +48,207 @@ pub(crate) trait Bot: Send + Sync + Debug {
     async fn start(&mut self, stream: DuplexStream);
 }
 
+/// Server bots of a match, with their names
+pub(crate) type Bots = Vec<(String, Box<dyn Bot>)>;
+
+/// A new match, with its parameters completed by the game, the names of its seats
+/// and its arguments, checked and completed with the defaults
+pub(crate) type NewMatch = (
+    Box<dyn Instance>,
+    Params,
+    Vec<String>,
+    HashMap<String, String>,
+);
+
 #[derive(Debug)]
 pub(crate) enum Command {
     GetList(oneshot::Sender<Vec<GameUsage>>),
     GetDescription(oneshot::Sender<Option<String>>, String),
     NewGame(
-        oneshot::Sender<Result<(Box<dyn Instance>, Params), String>>,
+        oneshot::Sender<Result<NewMatch, String>>,
         String,
         Params,
         HashMap<String, String>,
     ),
     GenBots(
-        oneshot::Sender<Result<Vec<Box<dyn Bot>>, String>>,
+        oneshot::Sender<Result<Bots, String>>,
         String,
-        usize,
+        Vec<String>,
+        u64,
     ),
 }
 
+/// Validate the arguments of a match against the ones accepted by its game,
+/// filling in the defaults of the missing ones
+pub(crate) fn check_args(
+    accepted: &HashMap<String, GameArgInfo>,
+    args: HashMap<String, String>,
+) -> Result<HashMap<String, String>, String> {
+    let mut checked = HashMap::new();
+    for (name, value) in args {
+        match accepted.get(&name) {
+            Some(info) => drop(checked.insert(name.clone(), info.coerce(&name, &value)?)),
+            None => {
+                let mut names: Vec<_> = accepted.keys().map(|x| x.as_str()).collect();
+                names.sort();
+                return Err(match names.len() {
+                    0 => format!("Unknown argument \"{}\", the game takes none", name),
+                    _ => format!(
+                        "Unknown argument \"{}\", the game takes {}",
+                        name,
+                        names.join(", ")
+                    ),
+                });
+            }
+        }
+    }
+    for (name, info) in accepted {
+        if !checked.contains_key(name) {
+            checked.insert(name.clone(), info.default.clone());
+        }
+    }
+    Ok(checked)
+}
+
+/// Check the server bots asked for a match against the ones offered by its game,
+/// giving the default bot to the seats where none is named
+pub(crate) fn check_bots(offered: &[BotInfo], bots: &mut [String]) -> Result<(), String> {
+    for bot in bots.iter_mut() {
+        if bot.is_empty() {
+            match offered.first() {
+                Some(x) => *bot = x.name.clone(),
+                None => return Err("The game has no server bots".to_string()),
+            }
+        } else if !offered.iter().any(|x| x.name == *bot) {
+            let names: Vec<_> = offered.iter().map(|x| x.name.as_str()).collect();
+            return Err(match names.len() {
+                0 => "The game has no server bots".to_string(),
+                _ => format!("Unknown bot \"{}\", the game has {}", bot, names.join(", ")),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Seed of the `i`-th server bot of a match played with `seed`
+pub(crate) fn bot_seed(seed: u64, i: usize) -> u64 {
+    seed.wrapping_add(i as u64 + 1)
+}
+
+/// Name in the match of the `i`-th server bot, which is a `bot`
+pub(crate) fn bot_name(bot: &str, i: usize) -> String {
+    format!("{}${}", bot, i)
+}
+
+/// Names of the seats of a match with `players` players
+pub(crate) fn seat_names(seats: &[String], players: usize) -> Vec<String> {
+    (0..players)
+        .map(|i| seats.get(i).cloned().unwrap_or_else(|| format!("{}", i)))
+        .collect()
+}
+
+/// Check that an explicit seat policy fits a match with `players` players
+pub(crate) fn check_seating(seating: &SeatPolicy, players: usize) -> Result<(), String> {
+    if let SeatPolicy::Explicit(mapping) = seating {
+        let mut taken = HashSet::new();
+        for (name, &seat) in mapping.iter() {
+            if seat >= players {
+                return Err(format!(
+                    "Seat {} of \"{}\" does not exist, the match has {} seats",
+                    seat, name, players
+                ));
+            }
+            if !taken.insert(seat) {
+                return Err(format!("Seat {} is given to more than one player", seat));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that the timeout and the chess clock of a match are in the allowed ranges
+pub(crate) fn check_timing(params: &Params) -> Result<(), String> {
+    if let Some(timeout) = params.timeout {
+        if !(MIN_TIMEOUT..=MAX_TIMEOUT).contains(&timeout) {
+            return Err(format!(
+                "Timeout {} out of allowed range [{}; {}]",
+                timeout, MIN_TIMEOUT, MAX_TIMEOUT
+            ));
+        }
+    }
+    if let Some(clock) = params.clock {
+        if !((MIN_TIMEOUT..=MAX_CLOCK_BANK).contains(&clock.bank)
+            && (0.0..=MAX_TIMEOUT).contains(&clock.increment)
+            && (0.0..=MAX_TIMEOUT).contains(&clock.delay))
+        {
+            return Err(format!(
+                "Time control {} out of allowed range: bank in [{}; {}], increment and delay in [0; {}]",
+                clock, MIN_TIMEOUT, MAX_CLOCK_BANK, MAX_TIMEOUT
+            ));
+        }
+        if clock.increment > 0.0 && clock.delay > 0.0 {
+            return Err(format!(
+                "Time control {} cannot have both an increment and a delay",
+                clock
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Assign the seats of a match played with `seed`, given the names of the
+/// players in the order they joined. Returns the names in seat order.
+pub(crate) fn seat(joined: Vec<String>, seating: &SeatPolicy, seed: u64) -> Vec<String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    match seating {
+        SeatPolicy::JoinOrder => joined,
+        SeatPolicy::Random => {
+            let mut players = joined;
+            // Sort first, so that the same seed always gives the same seats
+            players.sort();
+            players.shuffle(&mut rng);
+            players
+        }
+        SeatPolicy::Explicit(mapping) => {
+            let mut seats = vec![None; joined.len()];
+            let mut others = Vec::new();
+            for name in joined {
+                match mapping.get(&name) {
+                    Some(&i) if i < seats.len() && seats[i].is_none() => seats[i] = Some(name),
+                    _ => others.push(name),
+                }
+            }
+            others.sort();
+            others.shuffle(&mut rng);
+            let mut others = others.into_iter();
+            seats
+                .into_iter()
+                .map(|x| x.or_else(|| others.next()).expect("Cannot fail"))
+                .collect()
+        }
+    }
+}
+
+/// Check the parameters of a new match and create its instance, along with
+/// the names of its seats
+async fn new_instance(
+    game: &dyn Builder,
+    mut params: Params,
+    args: HashMap<String, String>,
+) -> Result<NewMatch, String> {
+    let args =
+        check_args(&game.args().await, args).map_err(|x| format!("Invalid arguments: {}", x))?;
+    check_bots(&game.bots().await, &mut params.bots).map_err(|x| format!("Invalid bots: {}", x))?;
+    let instance = game
+        .gen_instance(&mut params, args.clone())
+        .await
+        .map_err(|x| format!("Cannot create game: {}", x))?;
+    let players = params.players.unwrap_or_default();
+    check_seating(&params.seating, players).map_err(|x| format!("Invalid seats: {}", x))?;
+    let seats = seat_names(&game.seats().await, players);
+    Ok((instance, params, seats, args))
+}
+
 macro_rules! send {
     ($channel:expr, $data:expr) => {
         if let Err(_) = $channel.send($data) {
@@ -64,22 +257,30 @@ macro_rules! send {
     };
 }
 
-pub(crate) async fn start() -> mpsc::Sender<Command> {
+/// Start the service managing the games compiled into the server
+/// along with the `external` ones
+pub(crate) async fn start(external: Vec<Box<dyn Builder>>) -> mpsc::Sender<Command> {
     let (tx, mut rx) = mpsc::channel(QUEUE_BUFFER);
     spawn(async move {
         let mut games = HashMap::new();
-        for game in games::get() {
+        let mut names = Vec::new();
+        for game in games::get().into_iter().chain(external) {
+            names.push(String::from(game.name()));
             games.insert(String::from(game.name()), game);
         }
         while let Some(cmd) = rx.recv().await {
             match cmd {
                 Command::GetList(tx) => {
                     let mut result: Vec<GameUsage> = Vec::new();
-                    for game in games::get() {
-                        result.push(GameUsage {
-                            name: game.name().to_owned(),
-                            args: game.args().await,
-                        });
+                    for name in names.iter() {
+                        if let Some(game) = games.get(name) {
+                            result.push(GameUsage {
+                                name: name.clone(),
+                                args: game.args().await,
+                                bots: game.bots().await,
+                                seats: game.seats().await,
+                            });
+                        }
                     }
 
                     send!(tx, result);
@@ -92,12 +293,13 @@ pub(crate) async fn start() -> mpsc::Sender<Command> {
                     };
                     send!(tx, result);
                 }
-                Command::GenBots(tx, name, n_bots) => {
+                Command::GenBots(tx, name, names, seed) => {
                     let result = if let Some(game) = games.remove(&name) {
                         match spawn(async move {
                             let mut bots = Vec::new();
-                            for _ in 0..n_bots {
-                                bots.push(game.gen_bot().await);
+                            for (i, bot) in names.iter().enumerate() {
+                                let instance = game.gen_bot(bot, bot_seed(seed, i)).await;
+                                bots.push((bot_name(bot, i), instance));
                             }
                             (bots, game)
                         })
@@ -120,13 +322,10 @@ pub(crate) async fn start() -> mpsc::Sender<Command> {
                     };
                     send!(tx, result);
                 }
-                Command::NewGame(tx, name, mut params, args) => {
+                Command::NewGame(tx, name, params, args) => {
                     let result = if let Some(game) = games.remove(&name) {
                         match spawn(async move {
-                            let result = match game.gen_instance(&mut params, args).await {
-                                Ok(instance) => Ok((instance, params)),
-                                Err(x) => Err(format!("Cannot create game: {}", x)),
-                            };
+                            let result = new_instance(game.as_ref(), params, args).await;
                             (result, game)
                         })
                         .await
@@ -153,3 +352,24 @@ pub(crate) async fn start() -> mpsc::Sender<Command> {
     });
     tx
 }
+
+/// Outcome of a match that could not be played to the end because the game
+/// failed: a draw for every player, which is not rated
+pub(crate) fn aborted(players: &[String]) -> MatchOutcome {
+    let players = players
+        .iter()
+        .map(|name| {
+            let outcome = PlayerOutcome {
+                result: PlayerResult::Draw,
+                score: None,
+                time_left: None,
+            };
+            (name.clone(), outcome)
+        })
+        .collect();
+    MatchOutcome {
+        players,
+        termination: Termination::Panic,
+        details: None,
+    }
+}
@@ -0,0 +1,138 @@
+use super::super::util::LineReader;
+use super::logic::{Board, Ship};
+use crate::game;
+use crate::tuning::MAX_LINE_LENGTH;
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use tokio::io::{split, AsyncWriteExt, DuplexStream};
+use tracing::error;
+
+#[derive(Debug)]
+pub(crate) struct Bot {
+    pub(crate) seed: u64,
+    /// Fire around hits and in a checkerboard pattern, instead of at random
+    pub(crate) hunter: bool,
+}
+
+/// Cells next to `hits` not fired at yet, preferring those in line with two hits
+fn targets(size: usize, fired: &[bool], hits: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut near = Vec::new();
+    let mut line = Vec::new();
+    for &(r, c) in hits {
+        let steps = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+        for (dr, dc) in steps.iter() {
+            let (nr, nc) = (r as isize + dr, c as isize + dc);
+            if nr < 0 || nc < 0 || nr >= size as isize || nc >= size as isize {
+                continue;
+            }
+            let (nr, nc) = (nr as usize, nc as usize);
+            if fired[nr * size + nc] {
+                continue;
+            }
+            let behind = (r as isize - dr, c as isize - dc);
+            if hits.contains(&(behind.0 as usize, behind.1 as usize)) {
+                line.push((nr, nc));
+            }
+            near.push((nr, nc));
+        }
+    }
+    if line.is_empty() {
+        near
+    } else {
+        line
+    }
+}
+
+#[async_trait]
+impl game::Bot for Bot {
+    async fn start(&mut self, stream: DuplexStream) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let (input, mut output) = split(stream);
+        let mut input = LineReader::new(input, MAX_LINE_LENGTH);
+        lnin!(input); // Read first name
+        lnin!(input); // Read second name
+        let me: usize = lnin!(input).parse().expect("Cannot parse player number");
+        let size: usize = lnin!(input).parse().expect("Cannot parse board size");
+        let fleet: Vec<usize> = lnin!(input)
+            .split(' ')
+            .map(|x| x.parse().expect("Cannot parse ship length"))
+            .collect();
+        // Place the whole fleet at once, the lines are read in turn
+        let mut board = Board::new(size);
+        for &len in fleet.iter() {
+            let ship = loop {
+                let ship = Ship {
+                    row: rng.gen_range(0..size),
+                    col: rng.gen_range(0..size),
+                    vertical: rng.gen(),
+                    len,
+                };
+                if board.check(&ship).is_ok() {
+                    break ship;
+                }
+            };
+            board.place(ship);
+            lnout!(output, ship.to_string());
+        }
+        let mut fired = vec![false; size * size];
+        // Hits on ships not known to be sunk yet
+        let mut hits = Vec::new();
+        // Ships sunk in each fleet
+        let mut sunk = [0, 0];
+        let mut turn = 0;
+        while sunk.iter().all(|&x| x < fleet.len()) {
+            if turn == me {
+                let mut cells = Vec::new();
+                if self.hunter {
+                    cells = targets(size, &fired, &hits);
+                    if cells.is_empty() {
+                        cells = (0..size * size)
+                            .filter(|&x| !fired[x] && (x / size + x % size).is_multiple_of(2))
+                            .map(|x| (x / size, x % size))
+                            .collect();
+                    }
+                }
+                if cells.is_empty() {
+                    cells = (0..size * size)
+                        .filter(|&x| !fired[x])
+                        .map(|x| (x / size, x % size))
+                        .collect();
+                }
+                let (row, col) = *cells.choose(&mut rng).expect("Cannot fail");
+                fired[row * size + col] = true;
+                lnout!(output, format!("{} {}", row, col));
+            }
+            let line = lnin!(input);
+            if line == "RETIRE" {
+                break;
+            }
+            let parts: Vec<_> = line.split(' ').collect();
+            let (cell, shot) = match parts[..] {
+                [row, col, shot] => match (row.parse(), col.parse()) {
+                    (Ok(row), Ok(col)) => ((row, col), shot),
+                    _ => {
+                        error!("Server sent garbage shot");
+                        break;
+                    }
+                },
+                _ => {
+                    error!("Server sent garbage shot");
+                    break;
+                }
+            };
+            if turn == me {
+                match shot {
+                    "HIT" => hits.push(cell),
+                    "SUNK" => hits.clear(),
+                    _ => {}
+                }
+            }
+            if shot == "SUNK" {
+                sunk[1 - turn] += 1;
+            }
+            turn = 1 - turn;
+        }
+    }
+}
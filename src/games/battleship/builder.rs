@@ -0,0 +1,115 @@
+use super::bot::Bot;
+use super::instance::Instance;
+use crate::game;
+use crate::games;
+use crate::proto::{BotInfo, GameArgInfo, GameArgKind};
+use async_trait::async_trait;
+use games::util::arg;
+use rand::random;
+use std::collections::HashMap;
+use tokio::time::Duration;
+
+const DEFAULT_TIMEOUT: f64 = 30.0;
+const DEFAULT_PACE: f64 = 0.5;
+const DEFAULT_SIZE: usize = 10;
+const DEFAULT_REVEAL: &str = "end";
+
+#[derive(Debug)]
+pub(crate) struct Builder {}
+
+impl Builder {
+    pub(crate) fn new() -> Box<dyn game::Builder> {
+        Box::new(Builder {})
+    }
+}
+
+#[async_trait]
+impl game::Builder for Builder {
+    fn name(&self) -> &str {
+        "battleship"
+    }
+    async fn description(&self) -> String {
+        String::from(include_str!("description.md"))
+    }
+
+    async fn args(&self) -> HashMap<String, GameArgInfo> {
+        HashMap::from([
+            (
+                "pace".to_owned(),
+                GameArgInfo {
+                    description: "Pause between moves, to let spectators follow".to_owned(),
+                    kind: GameArgKind::Float {
+                        min: 0.0,
+                        max: 30.0,
+                    },
+                    default: DEFAULT_PACE.to_string(),
+                    unit: Some("seconds".to_owned()),
+                },
+            ),
+            (
+                "size".to_owned(),
+                GameArgInfo {
+                    description: "Side of the board of each player".to_owned(),
+                    kind: GameArgKind::Integer { min: 6, max: 20 },
+                    default: DEFAULT_SIZE.to_string(),
+                    unit: None,
+                },
+            ),
+            (
+                "reveal".to_owned(),
+                GameArgInfo {
+                    description: "When spectators see the fleets".to_owned(),
+                    kind: GameArgKind::Enum(vec!["start".to_owned(), "end".to_owned()]),
+                    default: DEFAULT_REVEAL.to_owned(),
+                    unit: None,
+                },
+            ),
+        ])
+    }
+
+    async fn gen_instance(
+        &self,
+        param: &mut game::Params,
+        args: HashMap<String, String>,
+    ) -> Result<Box<dyn game::Instance>, String> {
+        param.players = match param.players {
+            Some(2) => Some(2),
+            Some(x) => return Err(format!("Cannot create game with {} players", x)),
+            None => Some(2),
+        };
+
+        param.timeout = param.timeout.or(Some(DEFAULT_TIMEOUT));
+        let pace = arg(&args, "pace");
+        let size = arg(&args, "size");
+        let hidden = arg::<String>(&args, "reveal") == "end";
+        param.seed.get_or_insert_with(random);
+        Ok(Box::new(Instance {
+            timeout: Duration::from_secs_f64(param.timeout.expect("Cannot fail")),
+            pace: Duration::from_secs_f64(pace),
+            clock: param.clock,
+            size,
+            hidden,
+        }))
+    }
+    async fn bots(&self) -> Vec<BotInfo> {
+        vec![
+            BotInfo {
+                name: "random".to_owned(),
+                description: "Fires at random cells".to_owned(),
+            },
+            BotInfo {
+                name: "hunter".to_owned(),
+                description: "Fires around its hits until it sinks the ship".to_owned(),
+            },
+        ]
+    }
+    async fn seats(&self) -> Vec<String> {
+        vec!["first".to_owned(), "second".to_owned()]
+    }
+    async fn gen_bot(&self, name: &str, seed: u64) -> Box<dyn game::Bot> {
+        Box::new(Bot {
+            seed,
+            hunter: name == "hunter",
+        })
+    }
+}
@@ -0,0 +1,138 @@
+use super::super::util::{self, Ending, Output, Player, TurnBasedGame};
+use super::logic::{Board, Ship, FLEET};
+use crate::game::{self, MatchOutcome, Termination, TimeControl};
+use async_trait::async_trait;
+use tokio::io::{DuplexStream, WriteHalf};
+use tokio::time::Duration;
+
+#[derive(Debug)]
+pub(crate) struct Instance {
+    pub(crate) timeout: Duration,
+    pub(crate) pace: Duration,
+    pub(crate) clock: Option<TimeControl>,
+    pub(crate) size: usize,
+    pub(crate) hidden: bool,
+}
+
+/// What a player can do in its turn
+enum Action {
+    Place(Ship),
+    Fire(usize, usize),
+}
+
+/// State of a match
+struct Battleship {
+    boards: [Board; 2],
+    /// Whether spectators see the fleets only at the end
+    hidden: bool,
+    turn: usize,
+    shots: [usize; 2],
+}
+
+impl Battleship {
+    /// Show both fleets to the spectators, once the match is over
+    fn reveal(&self, out: &mut Output) {
+        if self.hidden {
+            for (i, board) in self.boards.iter().enumerate() {
+                for ship in board.ships() {
+                    out.spectators(format!("{} {}", i, ship));
+                }
+            }
+        }
+    }
+}
+
+impl TurnBasedGame for Battleship {
+    type Move = Action;
+
+    fn start(&mut self, names: &[String], out: &mut Output) {
+        // Send names in order
+        for name in names {
+            out.all(name);
+        }
+        // Send player index to players
+        out.player(0, "0");
+        out.player(1, "1");
+        // Send board size and ship lengths
+        out.all(format!("{}", self.boards[0].size()));
+        let fleet: Vec<_> = FLEET.iter().map(|x| x.to_string()).collect();
+        out.all(fleet.join(" "));
+    }
+
+    fn outcome(&mut self, out: &mut Output) -> Option<Ending> {
+        let loser = (0..2).find(|&i| self.boards[i].defeated())?;
+        self.reveal(out);
+        let mut ending = Ending::loser(2, loser);
+        ending.details = Some(format!("{} to {} shots", self.shots[0], self.shots[1]));
+        Some(ending)
+    }
+
+    fn turn(&mut self, _out: &mut Output) -> Option<usize> {
+        Some(self.turn)
+    }
+
+    fn parse(&self, player: usize, line: &str) -> Result<Action, String> {
+        let board = &self.boards[player];
+        match board.next_ship() {
+            Some(len) => {
+                let ship = Ship::parse(line, len)?;
+                board.check(&ship)?;
+                Ok(Action::Place(ship))
+            }
+            None => {
+                let (row, col) = self.boards[1 - player].parse_shot(line)?;
+                Ok(Action::Fire(row, col))
+            }
+        }
+    }
+
+    fn apply(&mut self, player: usize, action: Action, out: &mut Output) {
+        match action {
+            Action::Place(ship) => {
+                // The opponent never sees the placement
+                if !self.hidden {
+                    out.spectators(format!("{} {}", player, ship));
+                }
+                self.boards[player].place(ship);
+            }
+            Action::Fire(row, col) => {
+                self.shots[player] += 1;
+                let shot = self.boards[1 - player].fire(row, col);
+                out.all(format!("{} {} {}", row, col, shot));
+            }
+        }
+        self.turn = 1 - self.turn;
+    }
+
+    fn retire(&mut self, player: usize, _termination: &Termination, out: &mut Output) {
+        out.others(player, "RETIRE");
+        self.reveal(out);
+    }
+}
+
+#[async_trait]
+impl game::Instance for Instance {
+    async fn start(
+        &mut self,
+        players: Vec<(String, DuplexStream)>,
+        spectators: WriteHalf<DuplexStream>,
+    ) -> MatchOutcome {
+        let p = Player::from(players);
+        assert_eq!(p.len(), 2);
+        let battleship = Battleship {
+            boards: [Board::new(self.size), Board::new(self.size)],
+            hidden: self.hidden,
+            turn: 0,
+            shots: [0, 0],
+        };
+        util::play_turns(
+            battleship,
+            p,
+            spectators,
+            self.timeout,
+            self.pace,
+            self.clock,
+        )
+        .await
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt;
+
+/// Lengths of the ships of each fleet, in the order they are placed
+pub(crate) const FLEET: [usize; 5] = [5, 4, 3, 3, 2];
+
+/// A ship, covering `len` cells rightwards or downwards from its first cell
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Ship {
+    pub(crate) row: usize,
+    pub(crate) col: usize,
+    pub(crate) vertical: bool,
+    pub(crate) len: usize,
+}
+
+impl Ship {
+    /// Parse a ship of length `len` like `3 4 H`: row, column and direction
+    pub(crate) fn parse(line: &str, len: usize) -> Result<Ship, String> {
+        let parts: Vec<_> = line.split_whitespace().collect();
+        let (row, col, dir) = match parts[..] {
+            [row, col, dir] => (row, col, dir),
+            _ => return Err(format!("\"{}\" is not a ship placement", line)),
+        };
+        let vertical = match dir {
+            "H" | "h" => false,
+            "V" | "v" => true,
+            _ => return Err(format!("\"{}\" is not a direction", dir)),
+        };
+        match (row.parse(), col.parse()) {
+            (Ok(row), Ok(col)) => Ok(Ship {
+                row,
+                col,
+                vertical,
+                len,
+            }),
+            _ => Err(format!("\"{}\" is not a ship placement", line)),
+        }
+    }
+
+    pub(crate) fn cells(&self) -> Vec<(usize, usize)> {
+        (0..self.len)
+            .map(|i| match self.vertical {
+                true => (self.row + i, self.col),
+                false => (self.row, self.col + i),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Ship {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let dir = if self.vertical { "V" } else { "H" };
+        write!(f, "{} {} {}", self.row, self.col, dir)
+    }
+}
+
+/// What a shot hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Shot {
+    Miss,
+    Hit,
+    Sunk,
+}
+
+impl fmt::Display for Shot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Shot::Miss => write!(f, "MISS"),
+            Shot::Hit => write!(f, "HIT"),
+            Shot::Sunk => write!(f, "SUNK"),
+        }
+    }
+}
+
+/// The square grid of a player, with its fleet and the shots it received.
+/// Rows are numbered from the top and columns from the left, from 0.
+#[derive(Debug, Clone)]
+pub(crate) struct Board {
+    size: usize,
+    ships: Vec<Ship>,
+    /// Index of the ship covering each cell
+    cells: Vec<Option<usize>>,
+    fired: Vec<bool>,
+}
+
+impl Board {
+    pub(crate) fn new(size: usize) -> Board {
+        Board {
+            size,
+            ships: Vec::new(),
+            cells: vec![None; size * size],
+            fired: vec![false; size * size],
+        }
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    pub(crate) fn ships(&self) -> &[Ship] {
+        &self.ships
+    }
+
+    /// Length of the next ship to place, if any
+    pub(crate) fn next_ship(&self) -> Option<usize> {
+        FLEET.get(self.ships.len()).copied()
+    }
+
+    /// Check that `ship` lies within the board without overlapping others
+    pub(crate) fn check(&self, ship: &Ship) -> Result<(), String> {
+        if ship.row >= self.size || ship.col >= self.size {
+            return Err(format!("Ship {} goes out of the board", ship));
+        }
+        for (row, col) in ship.cells() {
+            if row >= self.size || col >= self.size {
+                return Err(format!("Ship {} goes out of the board", ship));
+            }
+            if self.cells[row * self.size + col].is_some() {
+                return Err(format!("Ship {} overlaps another ship", ship));
+            }
+        }
+        Ok(())
+    }
+
+    /// Add `ship`, which must have been checked, to the fleet
+    pub(crate) fn place(&mut self, ship: Ship) {
+        for (row, col) in ship.cells() {
+            self.cells[row * self.size + col] = Some(self.ships.len());
+        }
+        self.ships.push(ship);
+    }
+
+    /// Parse a shot like `3 4`, checking it is within the board and new
+    pub(crate) fn parse_shot(&self, line: &str) -> Result<(usize, usize), String> {
+        let parts: Vec<_> = line.split_whitespace().collect();
+        let (row, col): (usize, usize) = match parts[..] {
+            [row, col] => match (row.parse(), col.parse()) {
+                (Ok(row), Ok(col)) => (row, col),
+                _ => return Err(format!("\"{}\" is not a cell", line)),
+            },
+            _ => return Err(format!("\"{}\" is not a cell", line)),
+        };
+        if row >= self.size || col >= self.size {
+            return Err(format!("Cell {} {} is out of the board", row, col));
+        }
+        if self.fired[row * self.size + col] {
+            return Err(format!("Cell {} {} was already fired at", row, col));
+        }
+        Ok((row, col))
+    }
+
+    /// Receive a shot, which must have been parsed
+    pub(crate) fn fire(&mut self, row: usize, col: usize) -> Shot {
+        self.fired[row * self.size + col] = true;
+        match self.cells[row * self.size + col] {
+            None => Shot::Miss,
+            Some(i) => {
+                let sunk = self.ships[i]
+                    .cells()
+                    .iter()
+                    .all(|(r, c)| self.fired[r * self.size + c]);
+                if sunk {
+                    Shot::Sunk
+                } else {
+                    Shot::Hit
+                }
+            }
+        }
+    }
+
+    /// Whether every ship of the fleet was sunk
+    pub(crate) fn defeated(&self) -> bool {
+        self.ships.len() == FLEET.len()
+            && self
+                .ships
+                .iter()
+                .flat_map(|x| x.cells())
+                .all(|(r, c)| self.fired[r * self.size + c])
+    }
+}
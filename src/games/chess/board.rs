@@ -387,6 +387,24 @@ impl ChessBoard {
         })
     }
 
+    // Sum the values of the pieces of a given color left on the board
+    pub fn material(&self, color: Color) -> i32 {
+        self.board
+            .iter()
+            .flatten()
+            .map(|tile| match *tile {
+                Tile::Piece(piece, c) if c == color => match piece {
+                    Piece::Pawn => 1,
+                    Piece::Knight | Piece::Bishop => 3,
+                    Piece::Rook => 5,
+                    Piece::Queen => 9,
+                    Piece::King => 0,
+                },
+                _ => 0,
+            })
+            .sum()
+    }
+
     // Collect coordinates of pieces of a given color on the board
     fn collect_piece_coords(&self, color: Color) -> Vec<Point> {
         (0usize..8)
@@ -1,9 +1,14 @@
 use crate::game;
 extern crate regex;
 
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 use regex::Regex;
 use async_trait::async_trait;
-use tokio::io::{split, AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use super::super::util::LineReader;
+use crate::tuning::MAX_LINE_LENGTH;
+use tokio::io::{split, AsyncWriteExt, DuplexStream};
 use tracing::error;
 use tracing::warn;
 
@@ -15,18 +20,56 @@ use super::chess_move::MoveType;
 use super::color::Color;
 
 #[derive(Debug)] // The Bot struct is defined and derived with the Debug trait, allowing it to be printed for debugging purposes.
-pub(crate) struct Bot {}
+pub(crate) struct Bot {
+    pub(crate) seed: u64,
+    /// Pick the move that wins the most material, instead of a random one
+    pub(crate) greedy: bool,
+}
+
+/// The legal move leaving `color` with the best material balance, chosen at
+/// random among the best ones. Pawns reaching the last rank become queens.
+fn greedy_move<R: Rng>(board: &ChessBoard, color: Color, rng: &mut R) -> Option<String> {
+    let squares: Vec<String> = (0..8)
+        .flat_map(|y| (b'a'..=b'h').map(move |x| format!("{}{}", x as char, y + 1)))
+        .collect();
+    let mut best = Vec::new();
+    let mut best_score = i32::MIN;
+    for from in &squares {
+        for to in squares.iter().filter(|&to| to != from) {
+            for text in [
+                format!("{} {}", from, to),
+                format!("promote {} {} queen", from, to),
+            ] {
+                let opt = MoveType::parse(&text);
+                if !board.check_move(opt, color) {
+                    continue;
+                }
+                let after = board.apply_move_type(opt.expect("Cannot fail"));
+                let score = after.material(color) - after.material(color.other());
+                if score > best_score {
+                    best_score = score;
+                    best.clear();
+                }
+                if score == best_score {
+                    best.push(text);
+                }
+            }
+        }
+    }
+    best.choose(rng).cloned()
+}
 
 #[async_trait] // The Bot struct implements the game::Bot trait using the async_trait macro. This trait defines methods related to game bots.
 impl game::Bot for Bot {
     // This asynchronous function is the entry point for the bot's execution. It takes a DuplexStream as an argument, which provides bidirectional communication between the bot and the game server.
     async fn start(&mut self, stream: DuplexStream) {
         let mut board = ChessBoard::new();
+        let mut rng = StdRng::seed_from_u64(self.seed);
         let mut current_color = Color::White;
 
         // The DuplexStream is split into separate reader and writer halves (input and output, respectively). This allows the bot to read data from the server and send responses back.
         let (input, mut output) = split(stream);
-        let mut input = BufReader::new(input);
+        let mut input = LineReader::new(input, MAX_LINE_LENGTH);
 
         // The bot reads some initial data from the server, such as its name, the opponent's name, and its player number (me).
         lnin!(input); // Read my name
@@ -57,9 +100,15 @@ impl game::Bot for Bot {
 
             if turn == me {
 	        	//println!(">>> BOT: Calcolo la mossa");
+                if self.greedy {
+                    if let Some(x) = greedy_move(&board, current_color, &mut rng) {
+                        trimmed = x;
+                        opt = MoveType::parse(&trimmed);
+                    }
+                }
 
                 while !board.check_move(opt, current_color) {
-                    trimmed = MoveType::randomMove();
+                    trimmed = MoveType::randomMove(&mut rng);
                     opt = MoveType::parse(&trimmed);
 		        	//println!(">>> BOT: Mossa casuale {}", trimmed);
                 }
@@ -3,12 +3,10 @@ use super::bot::Bot;
 use super::instance::Instance;
 use crate::game;
 use crate::games;
-use crate::proto::GameArgInfo;
+use crate::proto::{BotInfo, GameArgInfo, GameArgKind};
 use async_trait::async_trait;
 use games::util::arg;
-use rand::rngs::{OsRng, StdRng};
-use rand::SeedableRng;
-use regex::Regex;
+use rand::random;
 use std::collections::HashMap;
 use tokio::time::Duration;
 
@@ -44,8 +42,10 @@ impl game::Builder for Builder {
         HashMap::from([(
             "pace".to_owned(),
             GameArgInfo {
-                description: "How fast the game plays (0-30)".to_owned(),
-                regex: "^(30|([12][0-9]|[0-9])(.[0-9]*)?)$".to_owned(),
+                description: "Pause between moves, to let spectators follow".to_owned(),
+                kind: GameArgKind::Float { min: 0.0, max: 30.0 },
+                default: DEFAULT_PACE.to_string(),
+                unit: Some("seconds".to_owned()),
             },
         )])
     }
@@ -65,37 +65,46 @@ impl game::Builder for Builder {
 
         // Controllo e impostazione del timeout del gioco
         param.timeout = param.timeout.or(Some(DEFAULT_TIMEOUT));
-        let constraints = self.args().await;
 
-        // Calcolo del ritmo del gioco leggendo l'argomento "pace" da args
-        let pace_reg = Regex::new(&constraints["pace"].regex).expect("Invalid Regex reading pace");
-        let pace = match arg(&args, "pace", DEFAULT_PACE) {
-            Ok(x) => {
-                if !pace_reg.is_match(&x.to_string()) {
-                    return Err(format!("Invalid pace"));
-                } else {
-                    x
-                }
-            }
-            Err(x) => return Err(format!("Invalid pace: {}", x)),
-        };
+        // Calcolo del ritmo del gioco leggendo l'argomento "pace" da args,
+        // già validato rispetto a quanto dichiarato in args()
+        let pace = arg(&args, "pace");
 
-        // Inizializzazione del generatore di numeri casuali (PRNG)
-        let rng = match StdRng::from_rng(OsRng) {
-            Ok(x) => x,
-            Err(x) => return Err(format!("Cannot initialize PRNG: {}", x)),
-        };
+        // Scelta del seed della partita, se non è stato dato
+        param.seed.get_or_insert_with(random);
 
         // Restituzione dell'istanza del gioco incapsulata in un Box
         Ok(Box::new(Instance {
             timeout: Duration::from_secs_f64(param.timeout.expect("Cannot fail")),
             pace: Duration::from_secs_f64(pace),
-            rng: rng,
+            clock: param.clock,
         }))
     }
 
+    // Metodo asincrono che restituisce i bot offerti dal gioco
+    async fn bots(&self) -> Vec<BotInfo> {
+        vec![
+            BotInfo {
+                name: "random".to_owned(),
+                description: "Plays random legal moves".to_owned(),
+            },
+            BotInfo {
+                name: "greedy".to_owned(),
+                description: "Plays the move winning the most material".to_owned(),
+            },
+        ]
+    }
+
+    // Metodo asincrono che restituisce i nomi dei posti, il Bianco muove per primo
+    async fn seats(&self) -> Vec<String> {
+        vec!["white".to_owned(), "black".to_owned()]
+    }
+
     // Metodo asincrono che genera un bot per il gioco
-    async fn gen_bot(&self) -> Box<dyn game::Bot> {
-        Box::new(Bot {})
+    async fn gen_bot(&self, name: &str, seed: u64) -> Box<dyn game::Bot> {
+        Box::new(Bot {
+            seed,
+            greedy: name == "greedy",
+        })
     }
 }
@@ -69,11 +69,10 @@ pub enum MoveType {
 
 impl MoveType {
     // Implementing a method to generate a random chess move in string format
-    pub fn randomMove() -> String {
+    pub fn randomMove<R: Rng>(rng: &mut R) -> String {
         let files = ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h']; // Array of file characters
         let ranks = ['1', '2', '3', '4', '5', '6', '7', '8']; // Array of rank characters
 
-        let mut rng = rand::thread_rng(); // Creating a random number generator instance
         let random_file_from = rng.gen_range(0..8); // Generating a random file index
         let random_rank_from = rng.gen_range(0..8); // Generating a random rank index
         let random_file_to = rng.gen_range(0..8); // Generating a random file index
@@ -1,38 +1,21 @@
 // Import necessary dependencies and modules
-use super::super::util::Player;
-use crate::game;
+use super::super::util::{self, Ending, Output, Player, TurnBasedGame};
+use crate::game::{self, MatchOutcome, Termination, TimeControl};
 use async_trait::async_trait;
-use rand::rngs::StdRng;
-use rand::Rng;
 use std::collections::HashMap;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, DuplexStream, WriteHalf};
-use tokio::time::{sleep_until, timeout, Duration, Instant};
-use tracing::warn;
-use tracing::error;
+use tokio::io::{DuplexStream, WriteHalf};
+use tokio::time::Duration;
 
 use super::board::ChessBoard;
 use super::chess_move::MoveType;
 use super::color::Color;
 
-use tokio::sync::mpsc;
-use tokio::sync::mpsc::Sender;
-use tokio::sync::mpsc::Receiver;
-
 // Define a struct named 'Instance' to hold game-related parameters
 #[derive(Debug)]
 pub(crate) struct Instance {
     pub(crate) timeout: Duration,
     pub(crate) pace: Duration,
-    pub(crate) rng: StdRng,
-}
-
-// Define a macro 'retired!' which sends a retirement message to players and spectators
-macro_rules! retired {
-    ($other:expr, $spectators:expr) => {{
-        lnout!($other, "RETIRE");
-        lnout!($spectators, "RETIRE");
-        break;
-    }};
+    pub(crate) clock: Option<TimeControl>,
 }
 
 // Function to refresh the player's turn color and print the corresponding message
@@ -47,183 +30,169 @@ pub fn refreshColor(turn: usize) -> Color {
     current_color
 }
 
-// Implement the game::Instance trait for the defined Instance struct
-#[async_trait]
-impl game::Instance for Instance {
-    // Define the 'start' method required by the trait
-    async fn start(
-        &mut self,
-        players: HashMap<String, DuplexStream>,
-        mut spectators: WriteHalf<DuplexStream>,
-    ) {
-        // Initialize the chess board and players
-        let mut board = ChessBoard::new();
-        let mut p = Player::from(players, &mut self.rng);
-        assert_eq!(p.len(), 2);
+// What a player can do in its turn
+enum Action {
+    // A valid move, with the text to send to everybody
+    Play(MoveType, String),
+    // Propose a draw to the opponent
+    Propose,
+    // Answer a draw proposal, accepting it or not
+    Answer(bool),
+    // Give up the match
+    Resign,
+}
 
+// State of a match
+struct Chess {
+    board: ChessBoard,
+    names: Vec<String>,
+    turn: usize,
+    turn_prec: usize,
+    current_color: Color,
+    // 1 when a draw has been proposed, 2 when it has been accepted
+    draw: usize,
+    resigned: bool,
+}
+
+impl Chess {
+    // Announce the winner, which is the player not on turn
+    fn winner(&self, out: &mut Output) {
+        if self.turn == 0 {
+            out.all("WINNER <BLACK>");
+        } else {
+            out.all("WINNER <WHITE>");
+        }
+    }
+}
+
+impl TurnBasedGame for Chess {
+    type Move = Action;
+
+    fn start(&mut self, names: &[String], out: &mut Output) {
         // Send player names to all participants
-        for i in 0..2 {
-            lnout!(p[0].output, &p[i].name);
-            lnout!(p[1].output, &p[i].name);
-            lnout!(spectators, &p[i].name);
+        for name in names {
+            out.all(name);
         }
         // Send player index to players
-        lnout!(p[0].output, "0");
-        lnout!(p[1].output, "1");
+        out.player(0, "0");
+        out.player(1, "1");
+        self.names = names.to_vec();
+    }
 
-        let mut turn = 0;
-        let mut turn_prec = 1;
-        let mut current_color = Color::White;
-        let mut retired = 0;
-        let mut draw = 0;
-        let mut errorCount = 0;
-        let mut hide_output = 0;
+    fn outcome(&mut self, out: &mut Output) -> Option<Ending> {
+        if self.draw == 2 {
+            return Some(Ending {
+                details: Some("draw by agreement".to_owned()),
+                ..Ending::draw(2)
+            });
+        }
+        let details = if self.resigned {
+            format!("{} resigned", self.names[self.turn])
+        } else if self.board.check_king_mate(self.current_color) {
+            "checkmate".to_owned()
+        } else {
+            return None;
+        };
+        self.winner(out);
+        Some(Ending {
+            details: Some(details),
+            ..Ending::winner(2, 1 - self.turn)
+        })
+    }
 
-        // Main game loop
-        while !board.check_king_mate(current_color) && retired == 0 && draw != 2 {
-        	//println!(">>> PLAYER: Turno {}, turno precedente {}", turn, turn_prec);
-            if turn != turn_prec {
-                board.display();
-                turn_prec = turn;
-                errorCount = 0;
-            } else {
-                errorCount = errorCount + 1;
-            };
+    fn turn(&mut self, _out: &mut Output) -> Option<usize> {
+        if self.turn != self.turn_prec {
+            self.board.display();
+            self.turn_prec = self.turn;
+        }
+        Some(self.turn)
+    }
 
-            if errorCount == 10000 {
-                retired = 1;
-                retired!(p[1 - turn].output, spectators)
+    fn parse(&self, _player: usize, line: &str) -> Result<Action, String> {
+        if line.len() == 0 {
+            return Err(format!("Empty move"));
+        }
+        // Any answer other than DRAW refuses the proposal
+        if self.draw == 1 {
+            return Ok(Action::Answer(line == "DRAW"));
+        }
+        let opt = MoveType::parse(line);
+        match opt {
+            Some(mv) if self.board.check_move(opt, self.current_color) => {
+                // Put < > around each word of the move
+                let formatted_str = line
+                    .split_whitespace()
+                    .map(|word| format!("<{}>", word))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                Ok(Action::Play(mv, formatted_str))
             }
+            _ if line == "RETIRE" => Ok(Action::Resign),
+            _ if line == "DRAW" => Ok(Action::Propose),
+            _ => Err(format!("Invalid move \"{}\"", line)),
+        }
+    }
 
-            let start = Instant::now();
-
-			//------------------------------------------------------------------------------------
-            // Read move
-            let mut buffer = String::new();
-            let mut token = "";
-        	//println!(">>> PLAYER: Attendo la mossa del giocatore {}", turn);
-            match timeout(self.timeout, p[turn].input.read_line(&mut buffer)).await {
-                // Timed out or closed connection
-                Err(_) | Ok(Err(_)) => retired!(p[1 - turn].output, spectators),
-                // Parse response
-                Ok(Ok(_)) => if buffer.trim().len() > 0{
-						        // Valid token value
-						        token = buffer.trim();
-					       	} else {
-						        // Other garbage
-						        retired = 1;
-						        hide_output = 1;
-						        retired!(p[1 - turn].output, spectators);
-						        continue;
-						    }
-            };
-            let mut trimmed = String::new();
-            trimmed = token.to_string();
-            
-            //println!(">>> PLAYER: Mossa ricevuta {}", trimmed);
-            
-            if trimmed.len() <= 0 {
-			    retired = 1;
-			    hide_output = 1;
-			    retired!(p[1 - turn].output, spectators);
-			    continue;
-            };
-
-            // Handle the draw condition
-            if draw == 1 {
-                if trimmed == "DRAW" {
-                    //lnout!(p[turn].output, "DRAW <ACCEPTED>");
-                    lnout!(p[1 - turn].output, "DRAW <ACCEPTED>");
-                    lnout!(spectators, "DRAW <ACCEPTED>");
-                    draw = draw + 1;
-                } else {
-                    draw = 0;
-                    //lnout!(p[turn].output, "DRAW <REFUSED>");
-                    lnout!(p[1 - turn].output, "DRAW <REFUSED>");
-                    lnout!(spectators, "DRAW <REFUSED>");
-                    turn = 1 - turn;
-                    current_color = refreshColor(turn);
-                };
-                continue;
-            };
-
-            // Split the input string into words
-            let words: Vec<&str> = trimmed.split_whitespace().collect();
-
-            // Create a new string with < > around each word and join them
-            let formatted_str = words
-                .iter()
-                .map(|&word| format!("<{}>", word))
-                .collect::<Vec<String>>()
-                .join(" ");
-
-            // Process the player's move
-            let opt = MoveType::parse(&trimmed);
-            if !board.check_move(opt, current_color) {
-                if trimmed == "RETIRE" {
-                    retired = 1;
-                    retired!(p[1 - turn].output, spectators)
-                } else {
-                    if trimmed == "DRAW" {
-                        draw = 1;
-                        lnout!(p[turn].output, "DRAW <PROPOSED>");
-                        lnout!(p[1 - turn].output, "DRAW <PROPOSED>");
-                        lnout!(spectators, "DRAW <PROPOSED>");
-                        turn = 1 - turn;
-                    } else {
-			            //println!(">>> PLAYER: Mossa ricevuta non valida");
-				        retired = 1;
-				        hide_output = 1;
-				        retired!(p[1 - turn].output, spectators);
-				        continue;
-                        /*lnout!(
-                            p[turn].output,
-                            "INVALID_MOVE <Impossible to recognize move>"
-                        );*/
-                    }
-                };
-                continue;
-            } else {
-	            //println!(">>> PLAYER: Mossa valida");
-            	let mut out_str = "OK ".to_owned() + &formatted_str;
-                lnout!(p[turn].output, "OK ".to_owned() + &formatted_str);
-                lnout!(p[1 - turn].output, "OK ".to_owned() + &formatted_str);
-                lnout!(spectators, "OK ".to_owned() + &formatted_str);
-
-                match opt {
-                    Some(move_type) => {
-                        board = board.apply_move_type(move_type);
-                    }
-                    None => {
-                        lnout!(p[turn].output, "INVALID_MOVE <Impossible to apply move>");
-				        retired = 1;
-				        hide_output = 1;
-				        retired!(p[1 - turn].output, spectators);
-				        continue;
-                    }
-                }
-                turn = 1 - turn;
-                current_color = refreshColor(turn);
-                sleep_until(start + self.pace).await;
-                continue;
+    fn apply(&mut self, player: usize, action: Action, out: &mut Output) {
+        match action {
+            Action::Play(move_type, formatted_str) => {
+                out.all("OK ".to_owned() + &formatted_str);
+                self.board = self.board.apply_move_type(move_type);
+                self.turn = 1 - self.turn;
+                self.current_color = refreshColor(self.turn);
+            }
+            Action::Propose => {
+                self.draw = 1;
+                out.all("DRAW <PROPOSED>");
+                self.turn = 1 - self.turn;
+            }
+            Action::Answer(true) => {
+                out.others(player, "DRAW <ACCEPTED>");
+                self.draw = 2;
+            }
+            Action::Answer(false) => {
+                self.draw = 0;
+                out.others(player, "DRAW <REFUSED>");
+                self.turn = 1 - self.turn;
+                self.current_color = refreshColor(self.turn);
+            }
+            Action::Resign => {
+                out.others(player, "RETIRE");
+                self.resigned = true;
             }
         }
+    }
 
-        // Game ending messages
-        if draw != 2 && hide_output == 0 {
-            if turn == 0 {
-                lnout!(spectators, "WINNER <BLACK>");
-            	lnout!(p[1 - turn].output, "WINNER <BLACK>");
-            	lnout!(p[turn].output, "WINNER <BLACK>");
-            } else {
-                lnout!(spectators, "WINNER <WHITE>");
-            	lnout!(p[1 - turn].output, "WINNER <WHITE>");
-            	lnout!(p[turn].output, "WINNER <WHITE>");
-            };
+    fn retire(&mut self, player: usize, termination: &Termination, out: &mut Output) {
+        out.others(player, "RETIRE");
+        // Illegal moves end the match without announcing the winner
+        if !matches!(termination, Termination::IllegalMove(_)) {
+            self.winner(out);
         }
     }
+}
 
-    async fn args(&self) -> HashMap<String, String> {
-        HashMap::from([("pace".to_owned(), format!("{:?}", self.pace))])
+// Implement the game::Instance trait for the defined Instance struct
+#[async_trait]
+impl game::Instance for Instance {
+    // Define the 'start' method required by the trait
+    async fn start(
+        &mut self,
+        players: Vec<(String, DuplexStream)>,
+        spectators: WriteHalf<DuplexStream>,
+    ) -> MatchOutcome {
+        // Initialize the chess board and players
+        let p = Player::from(players);
+        assert_eq!(p.len(), 2);
+        let chess = Chess {
+            board: ChessBoard::new(),
+            names: Vec::new(),
+            turn: 0,
+            turn_prec: 1,
+            current_color: Color::White,
+            draw: 0,
+            resigned: false,
+        };
+        util::play_turns(chess, p, spectators, self.timeout, self.pace, self.clock).await
     }
 }
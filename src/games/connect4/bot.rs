@@ -0,0 +1,108 @@
+use super::super::util::LineReader;
+use super::logic::Board;
+use crate::game;
+use crate::tuning::MAX_LINE_LENGTH;
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use tokio::io::{split, AsyncWriteExt, DuplexStream};
+use tracing::error;
+
+/// Score of a won board, far above anything the evaluation can give
+const WIN: i64 = 1 << 40;
+/// Roughly how many cells a search may look at for a single move
+const MAX_WORK: usize = 5_000_000;
+
+#[derive(Debug)]
+pub(crate) struct Bot {
+    pub(crate) seed: u64,
+    /// How many moves ahead to look, or 0 to play at random
+    pub(crate) depth: usize,
+}
+
+/// Value of `board` for `me`, who is about to move, looking `depth` moves ahead
+fn negamax(board: &Board, me: usize, depth: usize, mut alpha: i64, beta: i64) -> i64 {
+    if board.winner().is_some() {
+        // The last move won, and it was the opponent's: sooner is worse
+        return -WIN - depth as i64;
+    }
+    if board.finished() {
+        return 0;
+    }
+    if depth == 0 {
+        return board.evaluate(me);
+    }
+    let mut best = i64::MIN;
+    for col in board.valid_moves() {
+        let mut after = board.clone();
+        after.play(me, col);
+        let score = -negamax(&after, 1 - me, depth - 1, -beta, -alpha);
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+#[async_trait]
+impl game::Bot for Bot {
+    async fn start(&mut self, stream: DuplexStream) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let (input, mut output) = split(stream);
+        let mut input = LineReader::new(input, MAX_LINE_LENGTH);
+        lnin!(input); // Read first name
+        lnin!(input); // Read second name
+        let size: Vec<usize> = lnin!(input)
+            .split(' ')
+            .map(|x| x.parse().expect("Cannot parse board size"))
+            .collect();
+        let mut board = Board::new(size[0], size[1], size[2]);
+        let me: usize = lnin!(input).parse().expect("Cannot parse player number");
+        // Look less ahead on large boards, to answer in time
+        let mut depth = self.depth;
+        let cells = board.rows() * board.cols();
+        while depth > 2 && cells * board.cols().pow(depth as u32) > MAX_WORK {
+            depth -= 1;
+        }
+        let mut turn = 0;
+        while !board.finished() {
+            if turn == me {
+                let mut moves = board.valid_moves();
+                if depth > 0 {
+                    let scores: Vec<_> = moves
+                        .iter()
+                        .map(|&x| {
+                            let mut after = board.clone();
+                            after.play(me, x);
+                            -negamax(&after, 1 - me, depth - 1, -WIN * 2, WIN * 2)
+                        })
+                        .collect();
+                    let best = scores.iter().max().copied();
+                    moves = moves
+                        .into_iter()
+                        .zip(scores)
+                        .filter(|(_, x)| Some(*x) == best)
+                        .map(|(x, _)| x)
+                        .collect();
+                }
+                let x = *moves.choose(&mut rng).expect("Cannot fail");
+                board.play(me, x);
+                lnout!(output, format!("{}", x));
+            } else {
+                let col: usize = match lnin!(input).as_str() {
+                    "RETIRE" => break,
+                    x => x.parse().expect("Server sent garbage column"),
+                };
+                if !board.valid(col) {
+                    error!("Server sent invalid move");
+                    break;
+                }
+                board.play(1 - me, col);
+            }
+            turn = 1 - turn;
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use super::bot::Bot;
+use super::instance::Instance;
+use crate::game;
+use crate::games;
+use crate::proto::{BotInfo, GameArgInfo, GameArgKind};
+use async_trait::async_trait;
+use games::util::arg;
+use rand::random;
+use std::collections::HashMap;
+use tokio::time::Duration;
+
+const DEFAULT_TIMEOUT: f64 = 30.0;
+const DEFAULT_PACE: f64 = 1.0;
+const DEFAULT_ROWS: usize = 6;
+const DEFAULT_COLS: usize = 7;
+const DEFAULT_CONNECT: usize = 4;
+const MAX_SIZE: usize = 20;
+
+#[derive(Debug)]
+pub(crate) struct Builder {}
+
+impl Builder {
+    pub(crate) fn new() -> Box<dyn game::Builder> {
+        Box::new(Builder {})
+    }
+}
+
+fn size_arg(description: &str, default: usize) -> GameArgInfo {
+    GameArgInfo {
+        description: description.to_owned(),
+        kind: GameArgKind::Integer {
+            min: 2,
+            max: MAX_SIZE as i64,
+        },
+        default: default.to_string(),
+        unit: None,
+    }
+}
+
+#[async_trait]
+impl game::Builder for Builder {
+    fn name(&self) -> &str {
+        "connect4"
+    }
+    async fn description(&self) -> String {
+        String::from(include_str!("description.md"))
+    }
+
+    async fn args(&self) -> HashMap<String, GameArgInfo> {
+        HashMap::from([
+            (
+                "pace".to_owned(),
+                GameArgInfo {
+                    description: "Pause between moves, to let spectators follow".to_owned(),
+                    kind: GameArgKind::Float {
+                        min: 0.0,
+                        max: 30.0,
+                    },
+                    default: DEFAULT_PACE.to_string(),
+                    unit: Some("seconds".to_owned()),
+                },
+            ),
+            (
+                "rows".to_owned(),
+                size_arg("Number of rows of the board", DEFAULT_ROWS),
+            ),
+            (
+                "cols".to_owned(),
+                size_arg("Number of columns of the board", DEFAULT_COLS),
+            ),
+            (
+                "connect".to_owned(),
+                size_arg(
+                    "How many pieces in a row are needed to win",
+                    DEFAULT_CONNECT,
+                ),
+            ),
+        ])
+    }
+
+    async fn gen_instance(
+        &self,
+        param: &mut game::Params,
+        args: HashMap<String, String>,
+    ) -> Result<Box<dyn game::Instance>, String> {
+        param.players = match param.players {
+            Some(2) => Some(2),
+            Some(x) => return Err(format!("Cannot create game with {} players", x)),
+            None => Some(2),
+        };
+
+        param.timeout = param.timeout.or(Some(DEFAULT_TIMEOUT));
+        let pace = arg(&args, "pace");
+        let rows: usize = arg(&args, "rows");
+        let cols = arg(&args, "cols");
+        let connect = arg(&args, "connect");
+        if connect > rows.max(cols) {
+            return Err(format!(
+                "Cannot connect {} in a {}x{} board",
+                connect, rows, cols
+            ));
+        }
+        param.seed.get_or_insert_with(random);
+        Ok(Box::new(Instance {
+            timeout: Duration::from_secs_f64(param.timeout.expect("Cannot fail")),
+            pace: Duration::from_secs_f64(pace),
+            clock: param.clock,
+            rows,
+            cols,
+            connect,
+        }))
+    }
+    async fn bots(&self) -> Vec<BotInfo> {
+        vec![
+            BotInfo {
+                name: "random".to_owned(),
+                description: "Drops a piece in a random column".to_owned(),
+            },
+            BotInfo {
+                name: "greedy".to_owned(),
+                description: "Wins or blocks when it can, otherwise builds lines".to_owned(),
+            },
+            BotInfo {
+                name: "minimax".to_owned(),
+                description: "Searches several moves ahead".to_owned(),
+            },
+        ]
+    }
+    async fn seats(&self) -> Vec<String> {
+        vec!["first".to_owned(), "second".to_owned()]
+    }
+    async fn gen_bot(&self, name: &str, seed: u64) -> Box<dyn game::Bot> {
+        let depth = match name {
+            "greedy" => 2,
+            "minimax" => 6,
+            _ => 0,
+        };
+        Box::new(Bot { seed, depth })
+    }
+}
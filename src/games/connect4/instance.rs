@@ -0,0 +1,88 @@
+use super::super::util::{self, Ending, Output, Player, TurnBasedGame};
+use super::logic::Board;
+use crate::game::{self, MatchOutcome, TimeControl};
+use async_trait::async_trait;
+use tokio::io::{DuplexStream, WriteHalf};
+use tokio::time::Duration;
+
+#[derive(Debug)]
+pub(crate) struct Instance {
+    pub(crate) timeout: Duration,
+    pub(crate) pace: Duration,
+    pub(crate) clock: Option<TimeControl>,
+    pub(crate) rows: usize,
+    pub(crate) cols: usize,
+    pub(crate) connect: usize,
+}
+
+/// State of a match
+struct Connect4 {
+    board: Board,
+    turn: usize,
+}
+
+impl TurnBasedGame for Connect4 {
+    type Move = usize;
+
+    fn start(&mut self, names: &[String], out: &mut Output) {
+        // Send names in order
+        for name in names {
+            out.all(name);
+        }
+        // Send board size, so that spectators can replay the match
+        out.all(format!(
+            "{} {} {}",
+            self.board.rows(),
+            self.board.cols(),
+            self.board.connect()
+        ));
+        // Send player index to players
+        out.player(0, "0");
+        out.player(1, "1");
+    }
+
+    fn outcome(&mut self, _out: &mut Output) -> Option<Ending> {
+        if let Some(winner) = self.board.winner() {
+            Some(Ending::winner(2, winner))
+        } else if self.board.finished() {
+            Some(Ending::draw(2))
+        } else {
+            None
+        }
+    }
+
+    fn turn(&mut self, _out: &mut Output) -> Option<usize> {
+        Some(self.turn)
+    }
+
+    fn parse(&self, _player: usize, line: &str) -> Result<usize, String> {
+        match line.parse::<usize>() {
+            Ok(x) if self.board.valid(x) => Ok(x),
+            Ok(x) if x < self.board.cols() => Err(format!("Column {} is full", x)),
+            _ => Err(format!("\"{}\" is not a column", line)),
+        }
+    }
+
+    fn apply(&mut self, player: usize, col: usize, out: &mut Output) {
+        self.board.play(player, col);
+        out.others(player, format!("{}", col));
+        self.turn = 1 - self.turn;
+    }
+}
+
+#[async_trait]
+impl game::Instance for Instance {
+    async fn start(
+        &mut self,
+        players: Vec<(String, DuplexStream)>,
+        spectators: WriteHalf<DuplexStream>,
+    ) -> MatchOutcome {
+        let p = Player::from(players);
+        assert_eq!(p.len(), 2);
+        let connect4 = Connect4 {
+            board: Board::new(self.rows, self.cols, self.connect),
+            turn: 0,
+        };
+        util::play_turns(connect4, p, spectators, self.timeout, self.pace, self.clock).await
+    }
+}
@@ -0,0 +1,203 @@
+/// The four directions a line can take: vertical, horizontal and the two diagonals
+const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// A board where pieces fall to the lowest free row of their column.
+/// Rows are numbered from the bottom, columns from the left.
+#[derive(Debug, Clone)]
+pub(crate) struct Board {
+    rows: usize,
+    cols: usize,
+    connect: usize,
+    cells: Vec<Option<usize>>,
+    heights: Vec<usize>,
+    winner: Option<usize>,
+}
+
+impl Board {
+    pub(crate) fn new(rows: usize, cols: usize, connect: usize) -> Board {
+        Board {
+            rows,
+            cols,
+            connect,
+            cells: vec![None; rows * cols],
+            heights: vec![0; cols],
+            winner: None,
+        }
+    }
+
+    pub(crate) fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub(crate) fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub(crate) fn connect(&self) -> usize {
+        self.connect
+    }
+
+    fn get(&self, row: isize, col: isize) -> Option<usize> {
+        if row < 0 || col < 0 || row as usize >= self.rows || col as usize >= self.cols {
+            return None;
+        }
+        self.cells[row as usize * self.cols + col as usize]
+    }
+
+    /// Whether a piece can be dropped in `col`
+    pub(crate) fn valid(&self, col: usize) -> bool {
+        col < self.cols && self.heights[col] < self.rows
+    }
+
+    /// Columns where a piece can be dropped, from the center outwards
+    pub(crate) fn valid_moves(&self) -> Vec<usize> {
+        let mut moves: Vec<usize> = (0..self.cols).filter(|&x| self.valid(x)).collect();
+        let center = (self.cols as isize - 1) / 2;
+        moves.sort_by_key(|&x| (x as isize - center).abs());
+        moves
+    }
+
+    /// Drop a piece of `player` in `col`, which must be valid
+    pub(crate) fn play(&mut self, player: usize, col: usize) {
+        let row = self.heights[col];
+        self.cells[row * self.cols + col] = Some(player);
+        self.heights[col] += 1;
+        let (row, col) = (row as isize, col as isize);
+        for (dr, dc) in DIRECTIONS.iter() {
+            // Count the pieces of the line through the new one, on both sides
+            let mut count = 1;
+            for sign in [-1, 1] {
+                let mut i = 1;
+                while self.get(row + sign * i * dr, col + sign * i * dc) == Some(player) {
+                    count += 1;
+                    i += 1;
+                }
+            }
+            if count >= self.connect {
+                self.winner = Some(player);
+            }
+        }
+    }
+
+    pub(crate) fn winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    /// Whether the match is over, with a winner or a full board
+    pub(crate) fn finished(&self) -> bool {
+        self.winner.is_some() || self.heights.iter().all(|&x| x == self.rows)
+    }
+
+    /// How promising the board looks for `player`: every stretch of `connect`
+    /// cells that only one player occupies counts for it, more the fuller it is
+    pub(crate) fn evaluate(&self, player: usize) -> i64 {
+        let mut score = 0;
+        let k = self.connect as isize;
+        for row in 0..self.rows as isize {
+            for col in 0..self.cols as isize {
+                for (dr, dc) in DIRECTIONS.iter() {
+                    let end = (row + (k - 1) * dr, col + (k - 1) * dc);
+                    if end.0 < 0
+                        || end.1 < 0
+                        || end.0 >= self.rows as isize
+                        || end.1 >= self.cols as isize
+                    {
+                        continue;
+                    }
+                    let mut count = [0i64; 2];
+                    for i in 0..k {
+                        if let Some(x) = self.get(row + i * dr, col + i * dc) {
+                            count[x] += 1;
+                        }
+                    }
+                    if count[1 - player] == 0 {
+                        score += count[player] * count[player];
+                    } else if count[player] == 0 {
+                        score -= count[1 - player] * count[1 - player];
+                    }
+                }
+            }
+        }
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Play alternately the given columns, starting with player 0
+    fn board(rows: usize, cols: usize, connect: usize, moves: &[usize]) -> Board {
+        let mut b = Board::new(rows, cols, connect);
+        for (i, &col) in moves.iter().enumerate() {
+            assert!(b.valid(col) && !b.finished(), "move {} in {}", i, col);
+            b.play(i % 2, col);
+        }
+        b
+    }
+
+    #[test]
+    fn vertical() {
+        let b = board(6, 7, 4, &[0, 1, 0, 1, 0, 1]);
+        assert_eq!(b.winner(), None);
+        let b = board(6, 7, 4, &[0, 1, 0, 1, 0, 1, 0]);
+        assert_eq!(b.winner(), Some(0));
+    }
+
+    #[test]
+    fn horizontal_on_a_wide_board() {
+        // Five in a row on a 4x9 board, completed in the middle
+        let b = board(4, 9, 5, &[3, 3, 4, 4, 6, 6, 7, 7]);
+        assert_eq!(b.winner(), None);
+        let b = board(4, 9, 5, &[3, 3, 4, 4, 6, 6, 7, 7, 5]);
+        assert_eq!(b.winner(), Some(0));
+    }
+
+    #[test]
+    fn diagonals() {
+        // Three in a row up to the right on a 3x3 board
+        let b = board(3, 3, 3, &[0, 1, 1, 2, 2, 0, 2]);
+        assert_eq!(b.winner(), Some(0));
+        // And up to the left
+        let b = board(3, 3, 3, &[2, 1, 1, 0, 0, 2, 0]);
+        assert_eq!(b.winner(), Some(0));
+        // Away from the corners, for player 1 on a 5x5 board
+        let b = board(5, 5, 3, &[4, 2, 3, 3, 1, 4, 0, 4]);
+        assert_eq!(b.winner(), Some(1));
+    }
+
+    #[test]
+    fn edges() {
+        // Lines do not wrap around the sides of the board
+        let b = board(4, 5, 3, &[3, 0, 4, 1, 0, 1]);
+        assert_eq!(b.winner(), None);
+        // Connecting two is enough on a small board
+        let b = board(2, 2, 2, &[0, 0, 1]);
+        assert_eq!(b.winner(), Some(0));
+    }
+
+    #[test]
+    fn full_board() {
+        let b = board(2, 3, 3, &[0, 1, 2, 0, 1, 2]);
+        assert_eq!(b.winner(), None);
+        assert!(b.finished());
+        assert!(b.valid_moves().is_empty());
+    }
+
+    #[test]
+    fn valid_moves() {
+        let b = board(2, 7, 4, &[3, 3]);
+        assert!(!b.valid(3));
+        assert!(!b.valid(7));
+        assert_eq!(b.valid_moves(), vec![2, 4, 1, 5, 0, 6]);
+    }
+
+    #[test]
+    fn evaluate() {
+        let b = Board::new(6, 7, 4);
+        assert_eq!(b.evaluate(0), 0);
+        let b = board(6, 7, 4, &[3]);
+        assert!(b.evaluate(0) > 0);
+        assert_eq!(b.evaluate(0), -b.evaluate(1));
+    }
+}
@@ -1,19 +1,27 @@
+use super::super::util::LineReader;
 use super::logic;
 use crate::game;
+use crate::tuning::MAX_LINE_LENGTH;
 use async_trait::async_trait;
 use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
-use tokio::io::{split, AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::io::{split, AsyncWriteExt, DuplexStream};
 use tracing::error;
 
 #[derive(Debug)]
-pub(crate) struct Bot {}
+pub(crate) struct Bot {
+    pub(crate) seed: u64,
+    /// Sceglie la mossa che cattura di più invece di una a caso
+    pub(crate) goloso: bool,
+}
 
 #[async_trait]
 impl game::Bot for Bot {
     async fn start(&mut self, stream: DuplexStream) {
+        let mut rng = SmallRng::seed_from_u64(self.seed);
         let (input, mut output) = split(stream);
-        let mut input = BufReader::new(input);
+        let mut input = LineReader::new(input, MAX_LINE_LENGTH);
         lnin!(input); // Leggo il mio nome
         lnin!(input); // Leggo il nome dell'avversario
 
@@ -34,16 +42,30 @@ impl game::Bot for Bot {
 
         while !fine_partita {
             match lnin!(input).as_str() {
+                "Turno bianco!" if self.goloso => {
+                    // Faccio muovere le pedine bianche al bot goloso
+                    (mossa_scelta, damiera) = bot_goloso(damiera.clone(), true, &mut rng);
+
+                    // Invio la mossa scelta
+                    lnout!(output, mossa_scelta);
+                }
+                "Turno nero!" if self.goloso => {
+                    // Faccio muovere le pedine nere al bot goloso
+                    (mossa_scelta, damiera) = bot_goloso(damiera.clone(), false, &mut rng);
+
+                    // Invio la mossa scelta
+                    lnout!(output, mossa_scelta);
+                }
                 "Turno bianco!" => {
                     // Faccio muovere le pedine bianche al bot
-                    (mossa_scelta, damiera) = bot_bianco(damiera.clone()).await;
+                    (mossa_scelta, damiera) = bot_bianco(damiera.clone(), &mut rng).await;
 
                     // Invio la mossa scelta
                     lnout!(output, mossa_scelta);
                 }
                 "Turno nero!" => {
                     // Faccio muovere le pedine nere al bot
-                    (mossa_scelta, damiera) = bot_nero(damiera.clone()).await;
+                    (mossa_scelta, damiera) = bot_nero(damiera.clone(), &mut rng).await;
 
                     // Invio la mossa scelta
                     lnout!(output, mossa_scelta);
@@ -82,12 +104,11 @@ impl game::Bot for Bot {
 
                         // Converto le mosse da alfanumeriche a numeriche
                         for i in 0..mossa_avversario.len() {
-                            mossa_avversario[i] =
-                                logic::conv_mossa_in_num(&mossa_avversario[i]).await;
+                            mossa_avversario[i] = logic::conv_mossa_in_num(&mossa_avversario[i]);
                         }
 
                         // Aggiorno la damiera
-                        damiera = logic::aggionra_damiera(mossa_avversario, damiera.clone()).await;
+                        damiera = logic::aggionra_damiera(mossa_avversario, damiera.clone());
                     }
 
                     continue;
@@ -97,7 +118,86 @@ impl game::Bot for Bot {
     }
 }
 
-pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec<Vec<&str>>) {
+/// Sceglie tra le mosse del bianco, o del nero, quella che cattura il pezzo di
+/// valore maggiore, poi quella che fa dama, a caso tra le migliori
+pub(crate) fn bot_goloso<'a>(
+    mut damiera: Vec<Vec<&'a str>>,
+    bianco: bool,
+    rng: &mut SmallRng,
+) -> (String, Vec<Vec<&'a str>>) {
+    let (pedina, dama, avversari) = match bianco {
+        true => ("b", "B", ["n", "N"]),
+        false => ("n", "N", ["b", "B"]),
+    };
+
+    // Raccolgo le mosse con il loro valore, la cattura e la promozione
+    let mut mosse = Vec::new();
+    for r in 0..8 {
+        for c in 0..8 {
+            let pezzo = damiera[r][c];
+            // Le pedine vanno solo avanti, le dame in ogni direzione
+            let righe: &[isize] = match (pezzo == dama, bianco) {
+                _ if pezzo != pedina && pezzo != dama => &[],
+                (true, _) => &[-1, 1],
+                (false, true) => &[-1],
+                (false, false) => &[1],
+            };
+            for &dr in righe {
+                for dc in [-1, 1] {
+                    let casella = |k: isize| {
+                        let (r2, c2) = (r as isize + dr * k, c as isize + dc * k);
+                        match (0..8).contains(&r2) && (0..8).contains(&c2) {
+                            true => Some((r2 as usize, c2 as usize)),
+                            false => None,
+                        }
+                    };
+                    match (casella(1), casella(2)) {
+                        (Some((r1, c1)), _) if damiera[r1][c1] == " " => {
+                            let promossa = logic::dama(pezzo, r1) != pezzo;
+                            mosse.push(((0, promossa), (r, c), (r1, c1), None));
+                        }
+                        // Le pedine non possono mangiare le dame
+                        (Some((r1, c1)), Some((r2, c2)))
+                            if damiera[r2][c2] == " "
+                                && (damiera[r1][c1] == avversari[0]
+                                    || pezzo == dama && damiera[r1][c1] == avversari[1]) =>
+                        {
+                            let valore = if damiera[r1][c1] == avversari[1] {
+                                3
+                            } else {
+                                1
+                            };
+                            let promossa = logic::dama(pezzo, r2) != pezzo;
+                            mosse.push(((valore, promossa), (r, c), (r2, c2), Some((r1, c1))));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    // Tengo solo le mosse migliori e ne scelgo una
+    let migliore = mosse.iter().map(|x| x.0).max();
+    mosse.retain(|x| Some(x.0) == migliore);
+    match mosse.choose(rng) {
+        Some(&(_, (r, c), (r2, c2), mangiata)) => {
+            damiera[r2][c2] = logic::dama(damiera[r][c], r2); // Setto la nuova posizione della pedina e controllo se ho fatto dama
+            damiera[r][c] = " "; // Cancello la posizione iniziale
+            if let Some((r1, c1)) = mangiata {
+                damiera[r1][c1] = " "; // Cancello la pedina avversaria mangiata
+            }
+            let mossa = logic::stampa_mossa(r, c) + " " + &logic::stampa_mossa(r2, c2);
+            (mossa, damiera)
+        }
+        None => (String::new(), damiera),
+    }
+}
+
+pub(crate) async fn bot_bianco<'a>(
+    mut damiera: Vec<Vec<&'a str>>,
+    rng: &mut SmallRng,
+) -> (String, Vec<Vec<&'a str>>) {
     let mut dame = Vec::new();
     let mut pedine = Vec::new();
     let mut mossa_scelta: String = String::new();
@@ -131,9 +231,8 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
                 damiera[row - 1][col - 1] = " "; // Cancello la pedina avversaria mangiata
                 damiera[row - 2][col - 2] = "B"; // Setto la nuova posizione della pedina
 
-                mossa_scelta = logic::stampa_mossa(row, col).await
-                    + " "
-                    + &logic::stampa_mossa(row - 2, col - 2).await;
+                mossa_scelta =
+                    logic::stampa_mossa(row, col) + " " + &logic::stampa_mossa(row - 2, col - 2);
 
                 cattura = true;
                 break; // Ho mangiato ed esco dal for
@@ -145,9 +244,8 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
                 damiera[row - 1][col + 1] = " "; // Cancello la pedina avversaria mangiata
                 damiera[row - 2][col + 2] = "B"; // Setto la nuova posizione della pedina
 
-                mossa_scelta = logic::stampa_mossa(row, col).await
-                    + " "
-                    + &logic::stampa_mossa(row - 2, col + 2).await;
+                mossa_scelta =
+                    logic::stampa_mossa(row, col) + " " + &logic::stampa_mossa(row - 2, col + 2);
 
                 cattura = true;
                 break; // Ho mangiato ed esco dal for
@@ -159,9 +257,8 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
                 damiera[row + 1][col + 1] = " "; // Cancello la pedina avversaria mangiata
                 damiera[row + 2][col + 2] = "B"; // Setto la nuova posizione della pedina
 
-                mossa_scelta = logic::stampa_mossa(row, col).await
-                    + " "
-                    + &logic::stampa_mossa(row + 2, col + 2).await;
+                mossa_scelta =
+                    logic::stampa_mossa(row, col) + " " + &logic::stampa_mossa(row + 2, col + 2);
 
                 cattura = true;
                 break; // Ho mangiato ed esco dal for
@@ -173,9 +270,8 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
                 damiera[row + 1][col - 1] = " "; // Cancello la pedina avversaria mangiata
                 damiera[row + 2][col - 2] = "B"; // Setto la nuova posizione della pedina
 
-                mossa_scelta = logic::stampa_mossa(row, col).await
-                    + " "
-                    + &logic::stampa_mossa(row + 2, col - 2).await;
+                mossa_scelta =
+                    logic::stampa_mossa(row, col) + " " + &logic::stampa_mossa(row + 2, col - 2);
 
                 cattura = true;
                 break; // Ho mangiato ed esco dal for
@@ -195,11 +291,10 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
             {
                 damiera[row][col] = " "; // Cancello la posizione iniziale
                 damiera[row - 1][col - 1] = " "; // Cancello la pedina avversaria mangiata
-                damiera[row - 2][col - 2] = logic::dama("b", row - 2).await; // Setto la nuova posizione della pedina e controllo se ho fatto dama
+                damiera[row - 2][col - 2] = logic::dama("b", row - 2); // Setto la nuova posizione della pedina e controllo se ho fatto dama
 
-                mossa_scelta = logic::stampa_mossa(row, col).await
-                    + " "
-                    + &logic::stampa_mossa(row - 2, col - 2).await;
+                mossa_scelta =
+                    logic::stampa_mossa(row, col) + " " + &logic::stampa_mossa(row - 2, col - 2);
 
                 cattura = true;
                 break; // Ho mangiato ed esco dal for
@@ -209,11 +304,10 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
             {
                 damiera[row][col] = " "; // Cancello la posizione iniziale
                 damiera[row - 1][col + 1] = " "; // Cancello la pedina avversaria mangiata
-                damiera[row - 2][col + 2] = logic::dama("b", row - 2).await; // Setto la nuova posizione della pedina e controllo se ho fatto dama
+                damiera[row - 2][col + 2] = logic::dama("b", row - 2); // Setto la nuova posizione della pedina e controllo se ho fatto dama
 
-                mossa_scelta = logic::stampa_mossa(row, col).await
-                    + " "
-                    + &logic::stampa_mossa(row - 2, col + 2).await;
+                mossa_scelta =
+                    logic::stampa_mossa(row, col) + " " + &logic::stampa_mossa(row - 2, col + 2);
 
                 cattura = true;
                 break; // Ho mangiato ed esco dal for
@@ -224,7 +318,6 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
     // Se non ho mangiato faccio una mossa in maniera casuale
     if cattura == false {
         let n_pedine = pedine.len() + dame.len();
-        let mut rng = SmallRng::from_entropy();
         let mut scelta = rng.gen_range(0..n_pedine);
         let mut mossa = false;
         let mut continua = true;
@@ -240,9 +333,9 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
                     damiera[row][col] = " "; // Cancello la posizione iniziale
                     damiera[row - 1][col - 1] = "B"; // Setto la nuova posizione della pedina
 
-                    mossa_scelta = logic::stampa_mossa(row, col).await
+                    mossa_scelta = logic::stampa_mossa(row, col)
                         + " "
-                        + &logic::stampa_mossa(row - 1, col - 1).await;
+                        + &logic::stampa_mossa(row - 1, col - 1);
 
                     mossa = true;
                     break; // Ho fatto una mossa ed esco dal for
@@ -252,9 +345,9 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
                     damiera[row][col] = " "; // Cancello la posizione iniziale
                     damiera[row - 1][col + 1] = "B"; // Setto la nuova posizione della pedina
 
-                    mossa_scelta = logic::stampa_mossa(row, col).await
+                    mossa_scelta = logic::stampa_mossa(row, col)
                         + " "
-                        + &logic::stampa_mossa(row - 1, col + 1).await;
+                        + &logic::stampa_mossa(row - 1, col + 1);
 
                     mossa = true;
                     break; // Ho fatto una mossa ed esco dal for
@@ -264,9 +357,9 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
                     damiera[row][col] = " "; // Cancello la posizione iniziale
                     damiera[row + 1][col + 1] = "B"; // Setto la nuova posizione della pedina
 
-                    mossa_scelta = logic::stampa_mossa(row, col).await
+                    mossa_scelta = logic::stampa_mossa(row, col)
                         + " "
-                        + &logic::stampa_mossa(row + 1, col + 1).await;
+                        + &logic::stampa_mossa(row + 1, col + 1);
 
                     mossa = true;
                     break; // Ho fatto una mossa ed esco dal for
@@ -276,9 +369,9 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
                     damiera[row][col] = " "; // Cancello la posizione iniziale
                     damiera[row + 1][col - 1] = "B"; // Setto la nuova posizione della pedina
 
-                    mossa_scelta = logic::stampa_mossa(row, col).await
+                    mossa_scelta = logic::stampa_mossa(row, col)
                         + " "
-                        + &logic::stampa_mossa(row + 1, col - 1).await;
+                        + &logic::stampa_mossa(row + 1, col - 1);
 
                     mossa = true;
                     break; // Ho fatto una mossa ed esco dal for
@@ -307,22 +400,22 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
                     && damiera[row - 1][col - 1] == " "
                 {
                     damiera[row][col] = " "; // Cancello la posizione iniziale
-                    damiera[row - 1][col - 1] = logic::dama("b", row - 1).await; // Setto la nuova posizione della pedina e controllo se ho fatto dama
+                    damiera[row - 1][col - 1] = logic::dama("b", row - 1); // Setto la nuova posizione della pedina e controllo se ho fatto dama
 
-                    mossa_scelta = logic::stampa_mossa(row, col).await
+                    mossa_scelta = logic::stampa_mossa(row, col)
                         + " "
-                        + &logic::stampa_mossa(row - 1, col - 1).await;
+                        + &logic::stampa_mossa(row - 1, col - 1);
 
                     break; // Ho fatto una mossa ed esco dal for
                 } else if ((row as i32) - 1 >= 0 && (col as i32) + 1 <= 7)
                     && damiera[row - 1][col + 1] == " "
                 {
                     damiera[row][col] = " "; // Cancello la posizione iniziale
-                    damiera[row - 1][col + 1] = logic::dama("b", row - 1).await; // Setto la nuova posizione della pedina e controllo se ho fatto dama
+                    damiera[row - 1][col + 1] = logic::dama("b", row - 1); // Setto la nuova posizione della pedina e controllo se ho fatto dama
 
-                    mossa_scelta = logic::stampa_mossa(row, col).await
+                    mossa_scelta = logic::stampa_mossa(row, col)
                         + " "
-                        + &logic::stampa_mossa(row - 1, col + 1).await;
+                        + &logic::stampa_mossa(row - 1, col + 1);
 
                     break; // Ho fatto una mossa ed esco dal for
                 } else {
@@ -341,7 +434,10 @@ pub(crate) async fn bot_bianco<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, V
     (mossa_scelta, damiera)
 }
 
-pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec<Vec<&str>>) {
+pub(crate) async fn bot_nero<'a>(
+    mut damiera: Vec<Vec<&'a str>>,
+    rng: &mut SmallRng,
+) -> (String, Vec<Vec<&'a str>>) {
     let mut dame = Vec::new();
     let mut pedine = Vec::new();
     let mut mossa_scelta: String = String::new();
@@ -375,9 +471,8 @@ pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec
                 damiera[row - 1][col - 1] = " "; // Cancello la pedina avversaria mangiata
                 damiera[row - 2][col - 2] = "N"; // Setto la nuova posizione della pedina
 
-                mossa_scelta = logic::stampa_mossa(row, col).await
-                    + " "
-                    + &logic::stampa_mossa(row - 2, col - 2).await;
+                mossa_scelta =
+                    logic::stampa_mossa(row, col) + " " + &logic::stampa_mossa(row - 2, col - 2);
 
                 cattura = true;
                 break; // Ho mangiato ed esco dal for
@@ -389,9 +484,8 @@ pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec
                 damiera[row - 1][col + 1] = " "; // Cancello la pedina avversaria mangiata
                 damiera[row - 2][col + 2] = "N"; // Setto la nuova posizione della pedina
 
-                mossa_scelta = logic::stampa_mossa(row, col).await
-                    + " "
-                    + &logic::stampa_mossa(row - 2, col + 2).await;
+                mossa_scelta =
+                    logic::stampa_mossa(row, col) + " " + &logic::stampa_mossa(row - 2, col + 2);
 
                 cattura = true;
                 break; // Ho mangiato ed esco dal for
@@ -403,9 +497,8 @@ pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec
                 damiera[row + 1][col + 1] = " "; // Cancello la pedina avversaria mangiata
                 damiera[row + 2][col + 2] = "N"; // Setto la nuova posizione della pedina
 
-                mossa_scelta = logic::stampa_mossa(row, col).await
-                    + " "
-                    + &logic::stampa_mossa(row + 2, col + 2).await;
+                mossa_scelta =
+                    logic::stampa_mossa(row, col) + " " + &logic::stampa_mossa(row + 2, col + 2);
 
                 cattura = true;
                 break; // Ho mangiato ed esco dal for
@@ -417,9 +510,8 @@ pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec
                 damiera[row + 1][col - 1] = " "; // Cancello la pedina avversaria mangiata
                 damiera[row + 2][col - 2] = "N"; // Setto la nuova posizione della pedina
 
-                mossa_scelta = logic::stampa_mossa(row, col).await
-                    + " "
-                    + &logic::stampa_mossa(row + 2, col - 2).await;
+                mossa_scelta =
+                    logic::stampa_mossa(row, col) + " " + &logic::stampa_mossa(row + 2, col - 2);
 
                 cattura = true;
                 break; // Ho mangiato ed esco dal for
@@ -439,11 +531,10 @@ pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec
             {
                 damiera[row][col] = " "; // Cancello la posizione iniziale
                 damiera[row + 1][col + 1] = " "; // Cancello la pedina avversaria mangiata
-                damiera[row + 2][col + 2] = logic::dama("n", row + 2).await; // Setto la nuova posizione della pedina e controllo se ho fatto dama
+                damiera[row + 2][col + 2] = logic::dama("n", row + 2); // Setto la nuova posizione della pedina e controllo se ho fatto dama
 
-                mossa_scelta = logic::stampa_mossa(row, col).await
-                    + " "
-                    + &logic::stampa_mossa(row + 2, col + 2).await;
+                mossa_scelta =
+                    logic::stampa_mossa(row, col) + " " + &logic::stampa_mossa(row + 2, col + 2);
 
                 cattura = true;
                 break; // Ho mangiato ed esco dal for
@@ -453,11 +544,10 @@ pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec
             {
                 damiera[row][col] = " "; // Cancello la posizione iniziale
                 damiera[row + 1][col - 1] = " "; // Cancello la pedina avversaria mangiata
-                damiera[row + 2][col - 2] = logic::dama("n", row + 2).await; // Setto la nuova posizione della pedina e controllo se ho fatto dama
+                damiera[row + 2][col - 2] = logic::dama("n", row + 2); // Setto la nuova posizione della pedina e controllo se ho fatto dama
 
-                mossa_scelta = logic::stampa_mossa(row, col).await
-                    + " "
-                    + &logic::stampa_mossa(row + 2, col - 2).await;
+                mossa_scelta =
+                    logic::stampa_mossa(row, col) + " " + &logic::stampa_mossa(row + 2, col - 2);
 
                 cattura = true;
                 break; // Ho mangiato ed esco dal for
@@ -468,7 +558,6 @@ pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec
     // Se non ho mangiato faccio una mossa in maniera casuale
     if cattura == false {
         let n_pedine = pedine.len() + dame.len();
-        let mut rng = SmallRng::from_entropy();
         let mut scelta = rng.gen_range(0..n_pedine);
         let mut mossa = false;
         let mut continua = true;
@@ -484,9 +573,9 @@ pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec
                     damiera[row][col] = " "; // Cancello la posizione iniziale
                     damiera[row - 1][col - 1] = "N"; // Setto la nuova posizione della pedina
 
-                    mossa_scelta = logic::stampa_mossa(row, col).await
+                    mossa_scelta = logic::stampa_mossa(row, col)
                         + " "
-                        + &logic::stampa_mossa(row - 1, col - 1).await;
+                        + &logic::stampa_mossa(row - 1, col - 1);
 
                     mossa = true;
                     break; // Ho fatto una mossa ed esco dal for
@@ -496,9 +585,9 @@ pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec
                     damiera[row][col] = " "; // Cancello la posizione iniziale
                     damiera[row - 1][col + 1] = "N"; // Setto la nuova posizione della pedina
 
-                    mossa_scelta = logic::stampa_mossa(row, col).await
+                    mossa_scelta = logic::stampa_mossa(row, col)
                         + " "
-                        + &logic::stampa_mossa(row - 1, col + 1).await;
+                        + &logic::stampa_mossa(row - 1, col + 1);
 
                     mossa = true;
                     break; // Ho fatto una mossa ed esco dal for
@@ -508,9 +597,9 @@ pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec
                     damiera[row][col] = " "; // Cancello la posizione iniziale
                     damiera[row + 1][col + 1] = "N"; // Setto la nuova posizione della pedina
 
-                    mossa_scelta = logic::stampa_mossa(row, col).await
+                    mossa_scelta = logic::stampa_mossa(row, col)
                         + " "
-                        + &logic::stampa_mossa(row + 1, col + 1).await;
+                        + &logic::stampa_mossa(row + 1, col + 1);
 
                     mossa = true;
                     break; // Ho fatto una mossa ed esco dal for
@@ -520,9 +609,9 @@ pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec
                     damiera[row][col] = " "; // Cancello la posizione iniziale
                     damiera[row + 1][col - 1] = "N"; // Setto la nuova posizione della pedina
 
-                    mossa_scelta = logic::stampa_mossa(row, col).await
+                    mossa_scelta = logic::stampa_mossa(row, col)
                         + " "
-                        + &logic::stampa_mossa(row + 1, col - 1).await;
+                        + &logic::stampa_mossa(row + 1, col - 1);
 
                     mossa = true;
                     break; // Ho fatto una mossa ed esco dal for
@@ -551,22 +640,22 @@ pub(crate) async fn bot_nero<'a>(mut damiera: Vec<Vec<&'a str>>) -> (String, Vec
                     && damiera[row + 1][col + 1] == " "
                 {
                     damiera[row][col] = " "; // Cancello la posizione iniziale
-                    damiera[row + 1][col + 1] = logic::dama("n", row + 1).await; // Setto la nuova posizione della pedina e controllo se ho fatto dama
+                    damiera[row + 1][col + 1] = logic::dama("n", row + 1); // Setto la nuova posizione della pedina e controllo se ho fatto dama
 
-                    mossa_scelta = logic::stampa_mossa(row, col).await
+                    mossa_scelta = logic::stampa_mossa(row, col)
                         + " "
-                        + &logic::stampa_mossa(row + 1, col + 1).await;
+                        + &logic::stampa_mossa(row + 1, col + 1);
 
                     break; // Ho fatto una mossa ed esco dal for
                 } else if ((row as i32) + 1 <= 7 && (col as i32) - 1 >= 0)
                     && damiera[row + 1][col - 1] == " "
                 {
                     damiera[row][col] = " "; // Cancello la posizione iniziale
-                    damiera[row + 1][col - 1] = logic::dama("n", row + 1).await; // Setto la nuova posizione della pedina e controllo se ho fatto dama
+                    damiera[row + 1][col - 1] = logic::dama("n", row + 1); // Setto la nuova posizione della pedina e controllo se ho fatto dama
 
-                    mossa_scelta = logic::stampa_mossa(row, col).await
+                    mossa_scelta = logic::stampa_mossa(row, col)
                         + " "
-                        + &logic::stampa_mossa(row + 1, col - 1).await;
+                        + &logic::stampa_mossa(row + 1, col - 1);
 
                     break; // Ho fatto una mossa ed esco dal for
                 } else {
@@ -2,11 +2,10 @@ use super::bot::Bot;
 use super::instance::Instance;
 use crate::game;
 use crate::games;
-use crate::proto::GameArgInfo;
+use crate::proto::{BotInfo, GameArgInfo, GameArgKind};
 use async_trait::async_trait;
 use games::util::arg;
-use rand::rngs::{OsRng, StdRng};
-use rand::SeedableRng;
+use rand::random;
 use std::collections::HashMap;
 use tokio::time::Duration;
 
@@ -35,8 +34,13 @@ impl game::Builder for Builder {
         HashMap::from([(
             "pace".to_owned(),
             GameArgInfo {
-                description: "How fast the game plays (0-30)".to_owned(),
-                regex: "^(30|([12][0-9]|[0-9])(.[0-9]*)?)$".to_owned(),
+                description: "Pause between moves, to let spectators follow".to_owned(),
+                kind: GameArgKind::Float {
+                    min: 0.0,
+                    max: 30.0,
+                },
+                default: DEFAULT_PACE.to_string(),
+                unit: Some("seconds".to_owned()),
             },
         )])
     }
@@ -52,22 +56,33 @@ impl game::Builder for Builder {
             None => Some(2),
         };
         param.timeout = param.timeout.or(Some(DEFAULT_TIMEOUT));
-        let pace = match arg(&args, "pace", DEFAULT_PACE) {
-            Ok(x) if x < 0.0 || x > 30.0 => return Err(format!("Invalid pace")),
-            Ok(x) => x,
-            Err(x) => return Err(format!("Invalid pace: {}", x)),
-        };
-        let rng = match StdRng::from_rng(OsRng) {
-            Ok(x) => x,
-            Err(x) => return Err(format!("Cannot initialize PRNG: {}", x)),
-        };
+        let pace = arg(&args, "pace");
+        param.seed.get_or_insert_with(random);
         Ok(Box::new(Instance {
             timeout: Duration::from_secs_f64(param.timeout.expect("Cannot fail")),
             pace: Duration::from_secs_f64(pace),
-            rng: rng,
+            clock: param.clock,
         }))
     }
-    async fn gen_bot(&self) -> Box<dyn game::Bot> {
-        Box::new(Bot {})
+    async fn bots(&self) -> Vec<BotInfo> {
+        vec![
+            BotInfo {
+                name: "random".to_owned(),
+                description: "Plays random legal moves".to_owned(),
+            },
+            BotInfo {
+                name: "greedy".to_owned(),
+                description: "Captures the most valuable piece it can, then promotes".to_owned(),
+            },
+        ]
+    }
+    async fn seats(&self) -> Vec<String> {
+        vec!["white".to_owned(), "black".to_owned()]
+    }
+    async fn gen_bot(&self, name: &str, seed: u64) -> Box<dyn game::Bot> {
+        Box::new(Bot {
+            seed,
+            goloso: name == "greedy",
+        })
     }
 }
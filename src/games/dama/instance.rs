@@ -1,50 +1,145 @@
-use super::super::util::Player;
+use super::super::util::{self, Ending, Output, Player, TurnBasedGame};
 use super::logic;
-use crate::game;
+use crate::game::{self, MatchOutcome, Termination, TimeControl};
 use async_trait::async_trait;
-use rand::rngs::StdRng;
-use std::collections::HashMap;
-use tokio::io::{AsyncWriteExt, DuplexStream, WriteHalf};
-use tokio::time::{sleep_until, Duration, Instant};
-use tracing::warn;
+use tokio::io::{DuplexStream, WriteHalf};
+use tokio::time::Duration;
 
 #[derive(Debug)]
 pub(crate) struct Instance {
     pub(crate) timeout: Duration,
     pub(crate) pace: Duration,
-    pub(crate) rng: StdRng,
+    pub(crate) clock: Option<TimeControl>,
+}
+
+// Richiesta di mossa inviata al giocatore di turno
+const RICHIESTA: &str = "Inserisci la pedina che vuoi muovere e poi le mosse che vuoi fare\nEs > 6A 5B oppure 6A 4C 2A oppure 6A 4C 2A ...";
+
+// Stato di una partita
+struct Dama {
+    damiera: Vec<Vec<&'static str>>,
+    turno_bianco: bool,
+}
+
+impl TurnBasedGame for Dama {
+    // Il percorso della pedina, in coordinate numeriche
+    type Move = Vec<String>;
+
+    fn start(&mut self, names: &[String], out: &mut Output) {
+        // Invio i nomi dei giocatori
+        for name in names {
+            out.all(name);
+        }
+
+        out.all("Avvio la partita di dama...");
+
+        // Stampo la damiera
+        logic::stampa_damiera(self.damiera.clone(), out);
+
+        // Inizia sempre la partita il secondo giocatore che si connette
+        out.player(0, "Sei i Bianchi");
+        out.player(1, "Sei i Neri");
+        out.spectators(
+            "Il giocatore ".to_owned()
+                + &names[0]
+                + " è i bianchi e il giocatore "
+                + &names[1]
+                + " è i neri.",
+        );
+    }
+
+    fn outcome(&mut self, out: &mut Output) -> Option<Ending> {
+        let vincitore = logic::partita_in_corso(self.damiera.clone(), out, self.turno_bianco)?;
+        out.all("Game Over ;)\n");
+        Some(Ending::winner(2, vincitore))
+    }
+
+    fn turn(&mut self, out: &mut Output) -> Option<usize> {
+        // Dico al giocatore cosa deve muovere
+        let turno = if self.turno_bianco {
+            out.player(0, "Turno bianco!");
+            out.player(1, "Attendi il tuo turno!");
+            out.spectators("Turno bianco!");
+            0
+        } else {
+            out.player(1, "Turno nero!");
+            out.player(0, "Attendi il tuo turno!");
+            out.spectators("Turno nero!");
+            1
+        };
+        out.player(turno, RICHIESTA);
+        Some(turno)
+    }
+
+    fn parse(&self, _player: usize, line: &str) -> Result<Vec<String>, String> {
+        // Verifico la validità del percorso dato dall'utente
+        let percorso = logic::percorso(line)?;
+        if self.turno_bianco {
+            logic::verifica_percorso_bianco(self.damiera.clone(), &percorso)?;
+        } else {
+            logic::verifica_percorso_nero(self.damiera.clone(), &percorso)?;
+        }
+        Ok(percorso)
+    }
+
+    fn apply(&mut self, player: usize, percorso: Vec<String>, out: &mut Output) {
+        // Converto le mosse da numeriche a alfanumeriche
+        let mut mossa = String::new();
+        for posizione in percorso.iter() {
+            mossa += &(logic::conv_mossa_in_alfanum(posizione) + " ");
+        }
+
+        // Invio la mossa fatta all'avversario
+        out.player(1 - player, mossa);
+
+        // Aggiorno e stampo la damiera
+        self.damiera = logic::aggionra_damiera(percorso, self.damiera.clone());
+        logic::stampa_damiera(self.damiera.clone(), out);
+
+        // Cambio il turno di gioco
+        self.turno_bianco = !self.turno_bianco;
+    }
+
+    fn rejected(&mut self, player: usize, errore: &str, out: &mut Output) -> bool {
+        // Il giocatore può riprovare finché non scade il tempo
+        out.player(player, errore);
+        out.player(player, RICHIESTA);
+        true
+    }
+
+    fn retire(&mut self, player: usize, termination: &Termination, out: &mut Output) {
+        if let Termination::Timeout(_) = termination {
+            out.player(
+                player,
+                "\nTempo scaduto, hai esaurito il tempo per fare una mossa!",
+            );
+        }
+        if player == 0 {
+            out.player(
+                1,
+                "\nI bianchi hanno abbandonato la partita.\nI neri vincono la partita!\n",
+            );
+        } else {
+            out.player(
+                0,
+                "\nI neri hanno abbandonato la partita.\nI bianchi vincono la partita!\n",
+            );
+        }
+        out.all("Game Over ;)\n");
+    }
 }
 
 #[async_trait]
 impl game::Instance for Instance {
     async fn start(
         &mut self,
-        players: HashMap<String, DuplexStream>,
-        mut spectators: WriteHalf<DuplexStream>,
-    ) {
-        let mut giocatore = Player::from(players, &mut self.rng);
+        players: Vec<(String, DuplexStream)>,
+        spectators: WriteHalf<DuplexStream>,
+    ) -> MatchOutcome {
+        let giocatore = Player::from(players);
         assert_eq!(giocatore.len(), 2);
 
-        // Invio i nomi dei giocatori
-        for i in 0..2 {
-            lnout2!(giocatore[0].output, &giocatore[i].name);
-            lnout2!(giocatore[1].output, &giocatore[i].name);
-            lnout2!(spectators, &giocatore[i].name);
-        }
-
-        _ = giocatore[0]
-            .output
-            .write(("Avvio la partita di dama...\n").as_bytes())
-            .await;
-        _ = giocatore[1]
-            .output
-            .write(("Avvio la partita di dama...\n").as_bytes())
-            .await;
-        _ = spectators
-            .write(("Avvio la partita di dama...\n").as_bytes())
-            .await;
-
-        let mut damiera: Vec<Vec<&str>> = vec![
+        let damiera: Vec<Vec<&str>> = vec![
             vec![" ", "n", " ", "n", " ", "n", " ", "n"],
             vec!["n", " ", "n", " ", "n", " ", "n", " "],
             vec![" ", "n", " ", "n", " ", "n", " ", "n"],
@@ -54,142 +149,18 @@ impl game::Instance for Instance {
             vec![" ", "b", " ", "b", " ", "b", " ", "b"],
             vec!["b", " ", "b", " ", "b", " ", "b", " "],
         ];
-
-        // Stampo la damiera
-        logic::stampa_damiera(damiera.clone(), &mut giocatore, &mut spectators).await;
-
-        // Setto chi inizia a giocare
-        let mut turno_bianco: bool = true;
-
-        // Creo il vettore che conterrà il percorso valido
-        let mut percorso_valido: Vec<String> = Vec::new();
-
-        // Creo la stringa che conterrà la mossa da inviare all'avversario
-        let mut mossa: String = String::new();
-
-        // Inizia sempre la partita il secondo giocatore che si connette
-        _ = giocatore[0]
-            .output
-            .write(("Sei i Bianchi\n").as_bytes())
-            .await;
-        _ = giocatore[1].output.write(("Sei i Neri\n").as_bytes()).await;
-        _ = spectators
-            .write(
-                ("Il giocatore ".to_owned()
-                    + &giocatore[0].name
-                    + " è i bianchi e il giocatore "
-                    + &giocatore[1].name
-                    + " è i neri.\n")
-                    .as_bytes(),
-            )
-            .await;
-
-        // Avvio il gioco
-        while !logic::partita_in_corso(
-            damiera.clone(),
-            &mut giocatore,
-            &mut spectators,
-            turno_bianco,
+        let dama = Dama {
+            damiera,
+            turno_bianco: true,
+        };
+        util::play_turns(
+            dama,
+            giocatore,
+            spectators,
+            self.timeout,
+            self.pace,
+            self.clock,
         )
         .await
-        {
-            let start = Instant::now();
-
-            if turno_bianco {
-                // Bianchi
-
-                // Dico al giocatore cosa deve muovere
-                lnout2!(giocatore[0].output, "Turno bianco!");
-                _ = giocatore[1]
-                    .output
-                    .write(("Attendi il tuo turno!\n").as_bytes())
-                    .await;
-                _ = spectators.write(("Turno bianco!\n").as_bytes()).await;
-
-                // Verifico la validità del percorso dato dall'utente
-                percorso_valido = logic::verifica_percorso_bianco(
-                    damiera.clone(),
-                    &mut giocatore[0],
-                    self.timeout,
-                )
-                .await;
-
-                // Controllo se c'è stato un abbandono della partita
-                if percorso_valido[0] == "Err" {
-                    _ = giocatore[1].output.write(("\nI bianchi hanno abbandonato la partita.\nI neri vincono la partita!\n\n").as_bytes()).await;
-                    break;
-                }
-
-                // Converto le mosse da numeriche a alfanumeriche
-                for i in 0..percorso_valido.len() {
-                    mossa += &(logic::conv_mossa_in_alfanum(&percorso_valido[i]).await + " ")
-                }
-
-                // Invio la mossa fatta all'avversario
-                lnout2!(giocatore[1].output, mossa.clone());
-
-                // Cambio il turno di gioco
-                turno_bianco = false;
-            } else {
-                // Neri
-
-                // Dico al giocatore cosa deve muovere
-                lnout2!(giocatore[1].output, "Turno nero!");
-                _ = giocatore[0]
-                    .output
-                    .write(("Attendi il tuo turno!\n").as_bytes())
-                    .await;
-                _ = spectators.write(("Turno nero!\n").as_bytes()).await;
-
-                // Verifico la validità del percorso dato dall'utente
-                percorso_valido =
-                    logic::verifica_percorso_nero(damiera.clone(), &mut giocatore[1], self.timeout)
-                        .await;
-
-                // Controllo se c'è stato un abbandono della partita
-                if percorso_valido[0] == "Err" {
-                    _ = giocatore[0].output.write(("\nI neri hanno abbandonato la partita.\nI bianchi vincono la partita!\n\n").as_bytes()).await;
-                    break;
-                }
-
-                // Converto le mosse da numeriche a alfanumeriche
-                for i in 0..percorso_valido.len() {
-                    mossa += &(logic::conv_mossa_in_alfanum(&percorso_valido[i]).await + " ");
-                }
-
-                // Invio la mossa fatta all'avversario
-                lnout2!(giocatore[0].output, mossa.clone());
-
-                // Cambio il turno di gioco
-                turno_bianco = true;
-            }
-
-            // Pulisco la variabile usata per passare la mossa all'avversario
-            mossa.clear();
-
-            // Aggiorno la damiera
-            damiera = logic::aggionra_damiera(percorso_valido.clone(), damiera.clone()).await;
-
-            // Stampo la damiera aggiornata
-            logic::stampa_damiera(damiera.clone(), &mut giocatore, &mut spectators).await;
-
-            // Faccio una pausa
-            sleep_until(start + self.pace).await;
-        }
-
-        // Fine del gioco
-        _ = giocatore[0]
-            .output
-            .write(("Game Over ;)\n\n").as_bytes())
-            .await;
-        _ = giocatore[1]
-            .output
-            .write(("Game Over ;)\n\n").as_bytes())
-            .await;
-        _ = spectators.write(("Game Over ;)\n\n").as_bytes()).await;
-    }
-
-    async fn args(&self) -> HashMap<String, String> {
-        HashMap::from([("pace".to_owned(), format!("{:?}", self.pace))])
     }
 }
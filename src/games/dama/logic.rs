@@ -1,14 +1,11 @@
-use super::super::util::Player;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, DuplexStream, WriteHalf};
-use tokio::time::{timeout, Duration};
+use super::super::util::Output;
 
-pub(crate) async fn partita_in_corso(
+pub(crate) fn partita_in_corso(
     damiera: Vec<Vec<&str>>,
-    giocatore: &mut Vec<Player>,
-    spectators: &mut WriteHalf<DuplexStream>,
+    out: &mut Output,
     turno_bianco: bool,
-) -> bool {
-    let mut fine_partita: bool = false;
+) -> Option<usize> {
+    let mut vincitore: Option<usize> = None;
     let mut n_pedine_bianche: usize = 0;
     let mut n_pedine_nere: usize = 0;
 
@@ -25,88 +22,60 @@ pub(crate) async fn partita_in_corso(
 
     // Se un giocatore esaurisce le pedine o le mossa fermo il gioco
     if n_pedine_bianche == 0 {
-        _ = giocatore[0]
-            .output
-            .write(("Non hai più pedine disponibili.\nI neri vincono la partita!\n\n").as_bytes())
-            .await;
-        _ = giocatore[1]
-            .output
-            .write(("I neri vincono la partita!\n\n").as_bytes())
-            .await;
-        _ = spectators
-            .write(("I neri vincono la partita!\n\n").as_bytes())
-            .await;
-
-        fine_partita = true;
+        out.player(
+            0,
+            "Non hai più pedine disponibili.\nI neri vincono la partita!\n",
+        );
+        out.player(1, "I neri vincono la partita!\n");
+        out.spectators("I neri vincono la partita!\n");
+
+        vincitore = Some(1);
     } else if n_pedine_nere == 0 {
-        _ = giocatore[0]
-            .output
-            .write(("I bianchi vincono la partita!\n\n").as_bytes())
-            .await;
-        _ = giocatore[1]
-            .output
-            .write(
-                ("Non hai più pedine disponibili.\nI bianchi vincono la partita!\n\n").as_bytes(),
-            )
-            .await;
-        _ = spectators
-            .write(("I bianchi vincono la partita!\n\n").as_bytes())
-            .await;
-
-        fine_partita = true;
-    } else if mosse_possibili(damiera.clone(), turno_bianco).await == false
+        out.player(0, "I bianchi vincono la partita!\n");
+        out.player(
+            1,
+            "Non hai più pedine disponibili.\nI bianchi vincono la partita!\n",
+        );
+        out.spectators("I bianchi vincono la partita!\n");
+
+        vincitore = Some(0);
+    } else if !mosse_possibili(damiera.clone(), turno_bianco)
     // Controllo se è possibile per il prossimo giocatore fare uno spostamento o una cattura
     {
         if turno_bianco == true {
-            _ = giocatore[0]
-                .output
-                .write(
-                    ("Non hai più mosse disponibili.\nI neri vincono la partita!\n\n").as_bytes(),
-                )
-                .await;
-            _ = giocatore[1]
-                .output
-                .write(
-                    ("I bianchi non hanno più mosse disponibili.\nI neri vincono la partita!\n\n")
-                        .as_bytes(),
-                )
-                .await;
-            _ = spectators
-                .write(
-                    ("I bianchi non hanno più mosse disponibili.\nI neri vincono la partita!\n\n")
-                        .as_bytes(),
-                )
-                .await;
+            vincitore = Some(1);
+            out.player(
+                0,
+                "Non hai più mosse disponibili.\nI neri vincono la partita!\n",
+            );
+            out.player(
+                1,
+                "I bianchi non hanno più mosse disponibili.\nI neri vincono la partita!\n",
+            );
+            out.spectators(
+                "I bianchi non hanno più mosse disponibili.\nI neri vincono la partita!\n",
+            );
         } else {
-            _ = giocatore[0]
-                .output
-                .write(
-                    ("I neri non hanno più mosse disponibili.\nI bianchi vincono la partita!\n\n")
-                        .as_bytes(),
-                )
-                .await;
-            _ = giocatore[1]
-                .output
-                .write(
-                    ("Non hai più mosse disponibili.\nI bianchi vincono la partita!\n\n")
-                        .as_bytes(),
-                )
-                .await;
-            _ = spectators
-                .write(
-                    ("I neri non hanno più mosse disponibili.\nI bianchi vincono la partita!\n\n")
-                        .as_bytes(),
-                )
-                .await;
+            vincitore = Some(0);
+            out.player(
+                0,
+                "I neri non hanno più mosse disponibili.\nI bianchi vincono la partita!\n",
+            );
+            out.player(
+                1,
+                "Non hai più mosse disponibili.\nI bianchi vincono la partita!\n",
+            );
+            out.spectators(
+                "I neri non hanno più mosse disponibili.\nI bianchi vincono la partita!\n",
+            );
         }
-
-        fine_partita = true;
     }
 
-    fine_partita
+    // Restituisco l'indice del giocatore vincitore, se la partita è finita
+    vincitore
 }
 
-async fn mosse_possibili(damiera: Vec<Vec<&str>>, turno_bianco: bool) -> bool {
+fn mosse_possibili(damiera: Vec<Vec<&str>>, turno_bianco: bool) -> bool {
     let mut mossa: bool = false; // Setto se è possibile o meno fare una mossa che sia uno spostamento o una cattura
     let mut pedine: Vec<Vec<usize>> = Vec::new(); // Setto il vettore che contiene le pedine del giocatore
     let mut dame: Vec<Vec<usize>> = Vec::new(); // Setto il vettore che contiene le dame del giocatore
@@ -331,737 +300,671 @@ async fn mosse_possibili(damiera: Vec<Vec<&str>>, turno_bianco: bool) -> bool {
     mossa
 }
 
-pub(crate) async fn stampa_damiera(
-    damiera: Vec<Vec<&str>>,
-    giocatore: &mut Vec<Player>,
-    spectators: &mut WriteHalf<DuplexStream>,
-) {
+pub(crate) fn stampa_damiera(damiera: Vec<Vec<&str>>, out: &mut Output) {
     let mut stampa = String::new();
 
     for r in 0..damiera.len() {
         // Stampo la prima riga di lettere
         if r == 0 {
-            _ = giocatore[0]
-                .output
-                .write(("\n   A  B  C  D  E  F  G  H\n").as_bytes())
-                .await;
-            _ = giocatore[1]
-                .output
-                .write(("\n   A  B  C  D  E  F  G  H\n").as_bytes())
-                .await;
-            _ = spectators
-                .write(("\n   A  B  C  D  E  F  G  H\n").as_bytes())
-                .await;
+            out.all("\n   A  B  C  D  E  F  G  H");
         }
 
         // Stampo i numeri a sx
         stampa.clear();
         stampa = (r + 1).to_string() + " ";
-        _ = giocatore[0].output.write(stampa.as_bytes()).await;
-        _ = giocatore[1].output.write(stampa.as_bytes()).await;
-        _ = spectators.write(stampa.as_bytes()).await;
 
         for c in 0..damiera[r].len() {
             // Stampo la Damiera
-            stampa.clear();
-            stampa = "[".to_owned() + damiera[r][c] + "]";
-            _ = giocatore[0].output.write(stampa.as_bytes()).await;
-            _ = giocatore[1].output.write(stampa.as_bytes()).await;
-            _ = spectators.write(stampa.as_bytes()).await;
+            stampa += &("[".to_owned() + damiera[r][c] + "]");
         }
 
         // Stampo i numeri a dx
-        stampa.clear();
-        stampa = " ".to_owned() + &(r + 1).to_string() + "\n";
-        _ = giocatore[0].output.write(stampa.as_bytes()).await;
-        _ = giocatore[1].output.write(stampa.as_bytes()).await;
-        _ = spectators.write(stampa.as_bytes()).await;
+        stampa += &(" ".to_owned() + &(r + 1).to_string());
+        out.all(&stampa);
 
         // Stampo l'ultima riga di lettere
         if r == 7 {
-            _ = giocatore[0]
-                .output
-                .write(("   A  B  C  D  E  F  G  H\n\n").as_bytes())
-                .await;
-            _ = giocatore[1]
-                .output
-                .write(("   A  B  C  D  E  F  G  H\n\n").as_bytes())
-                .await;
-            _ = spectators
-                .write(("   A  B  C  D  E  F  G  H\n\n").as_bytes())
-                .await;
+            out.all("   A  B  C  D  E  F  G  H\n");
         }
     }
 }
 
-pub(crate) async fn verifica_percorso_bianco(
+pub(crate) fn verifica_percorso_bianco(
     damiera: Vec<Vec<&str>>,
-    giocatore: &mut Player,
-    timer: Duration,
-) -> Vec<String> {
+    mosse: &[String],
+) -> Result<(), String> {
     let mut err_mossa: bool = true;
-    let mut mosse: Vec<String> = Vec::new();
     let mut stampa = String::new();
+    let mut errore = String::new();
 
-    while err_mossa {
-        // Chiedo all'utente che mosse vuole fare
-        mosse = percorso(giocatore, timer).await;
+    // Setto se sto muovendo una pedina o una dama
+    let mut dama: bool = false;
 
-        // Verifico che il giocatore non abbia abbandonato
-        if mosse[0] == "Err" {
-            return mosse;
-        }
+    // Setto la posizione iniziale della pedina
+    let mut pedina_r: usize = mosse[0]
+        .chars()
+        .next()
+        .expect("Error to 'pedina_r' in verifica_percorso_bianco")
+        as usize
+        - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
+    let mut pedina_c: usize = mosse[0]
+        .chars()
+        .nth(1)
+        .expect("Error to 'pedina_c' in verifica_percorso_bianco")
+        as usize
+        - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
+    let mut mossa_r: usize;
+    let mut mossa_c: usize;
 
-        // Setto se sto muovendo una pedina o una dama
-        let mut dama: bool = false;
-
-        // Setto la posizione iniziale della pedina
-        let mut pedina_r: usize = mosse[0]
-            .chars()
-            .nth(0)
-            .expect("Error to 'pedina_r' in verifica_percorso_bianco")
-            as usize
-            - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
-        let mut pedina_c: usize = mosse[0]
-            .chars()
-            .nth(1)
-            .expect("Error to 'pedina_c' in verifica_percorso_bianco")
-            as usize
-            - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
-        let mut mossa_r: usize;
-        let mut mossa_c: usize;
-
-        for (n, m) in mosse.iter().enumerate() {
-            if n == 0 {
-                // Controllo se ho selezionato la mia pedina
-                if damiera[pedina_r][pedina_c] == "b" {
-                    dama = false;
-                } else if damiera[pedina_r][pedina_c] == "B" {
-                    dama = true;
-                } else {
-                    _ = giocatore
-                        .output
-                        .write(
-                            ("\nNon hai selezionato una tua pedina!\nRicorda che sei i bianchi.\n")
-                                .as_bytes(),
-                        )
-                        .await;
-                    break; // Esco dal for
-                }
+    for (n, m) in mosse.iter().enumerate() {
+        if n == 0 {
+            // Controllo se ho selezionato la mia pedina
+            if damiera[pedina_r][pedina_c] == "b" {
+                dama = false;
+            } else if damiera[pedina_r][pedina_c] == "B" {
+                dama = true;
             } else {
-                // Setto la mossa successiva
-                mossa_r = m
-                    .chars()
-                    .nth(0)
-                    .expect("Error to 'mossa_r' in verifica_percorso_bianco")
-                    as usize
-                    - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
-                mossa_c = m
-                    .chars()
-                    .nth(1)
-                    .expect("Error to 'mossa_c' in verifica_percorso_bianco")
-                    as usize
-                    - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
-
-                // Verifico le mosse e le cattura
-                if dama == false {
-                    // Pedina
-
-                    // Controllo se si fa una mossa o una cattura
-                    if pedina_r - 1 == mossa_r && mosse.len() == 2 {
-                        // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
-                        if ((pedina_c == 0 && pedina_c + 1 == mossa_c)
-                            || (pedina_c == 7 && pedina_c - 1 == mossa_c)
-                            || ((pedina_c != 0 && pedina_c != 7)
-                                && (pedina_c + 1 == mossa_c || pedina_c - 1 == mossa_c)))
-                            && damiera[mossa_r][mossa_c] == " "
-                        {
-                            // Spiegazione controlli
-                            // 1) mossa a DX se si è sulla colonna 0
-                            // 2) mossa a SX se si è sulla colonna 7
-                            // 3) mossa normale se si è al centro
-                            err_mossa = false;
-                        } else {
-                            // La colonna è sbagliata
-                            if damiera[mossa_r][mossa_c] != " " {
-                                stampa.clear();
-                                stampa = "\nSpostamento ".to_owned()
-                                    + &stampa_mossa(pedina_r, pedina_c).await
-                                    + " -> "
-                                    + &stampa_mossa(mossa_r, mossa_c).await
-                                    + " non valido!\nLa posizione scelta è gia occupata.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            } else {
-                                stampa.clear();
-                                stampa = "\nSpostamento ".to_owned() + &stampa_mossa(pedina_r, pedina_c).await + " -> " + &stampa_mossa(mossa_r, mossa_c).await +" non valido!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            }
-
-                            break; // Esco dal for
-                        }
-                    } else if pedina_r - 2 == mossa_r {
-                        // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
-                        if (((pedina_c == 0 || pedina_c == 1)
-                            && pedina_c + 2 == mossa_c
-                            && damiera[pedina_r - 1][pedina_c + 1] == "n")
-                            || ((pedina_c == 7 || pedina_c == 6)
-                                && pedina_c - 2 == mossa_c
-                                && damiera[pedina_r - 1][pedina_c - 1] == "n")
-                            || ((pedina_c != 0 && pedina_c != 1 && pedina_c != 7 && pedina_c != 6)
-                                && (pedina_c + 2 == mossa_c || pedina_c - 2 == mossa_c)
-                                && ((mossa_c > pedina_c
-                                    && damiera[pedina_r - 1][pedina_c + 1] == "n")
-                                    || (mossa_c < pedina_c
-                                        && damiera[pedina_r - 1][pedina_c - 1] == "n"))))
-                            && damiera[mossa_r][mossa_c] == " "
-                        {
-                            // Spiegazione controlli
-                            // 1) cattura a DX se si è sulla colonna 0 o 1
-                            // 2) cattura a SX se si è sulla colonna 7 0 6
-                            // 3) cattura normale se si è al centro
-
-                            // La cattura è valida
-                            err_mossa = false;
+                errore = "\nNon hai selezionato una tua pedina!\nRicorda che sei i bianchi.\n"
+                    .to_owned();
+                break; // Esco dal for
+            }
+        } else {
+            // Setto la mossa successiva
+            mossa_r = m
+                .chars()
+                .next()
+                .expect("Error to 'mossa_r' in verifica_percorso_bianco")
+                as usize
+                - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
+            mossa_c = m
+                .chars()
+                .nth(1)
+                .expect("Error to 'mossa_c' in verifica_percorso_bianco")
+                as usize
+                - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
+
+            // Verifico le mosse e le cattura
+            if !dama {
+                // Pedina
+
+                // Controllo se si fa una mossa o una cattura
+                if pedina_r - 1 == mossa_r && mosse.len() == 2 {
+                    // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
+                    if ((pedina_c == 0 && pedina_c + 1 == mossa_c)
+                        || (pedina_c == 7 && pedina_c - 1 == mossa_c)
+                        || ((pedina_c != 0 && pedina_c != 7)
+                            && (pedina_c + 1 == mossa_c || pedina_c - 1 == mossa_c)))
+                        && damiera[mossa_r][mossa_c] == " "
+                    {
+                        // Spiegazione controlli
+                        // 1) mossa a DX se si è sulla colonna 0
+                        // 2) mossa a SX se si è sulla colonna 7
+                        // 3) mossa normale se si è al centro
+                        err_mossa = false;
+                    } else {
+                        // La colonna è sbagliata
+                        if damiera[mossa_r][mossa_c] != " " {
+                            stampa.clear();
+                            stampa = "\nSpostamento ".to_owned()
+                                + &stampa_mossa(pedina_r, pedina_c)
+                                + " -> "
+                                + &stampa_mossa(mossa_r, mossa_c)
+                                + " non valido!\nLa posizione scelta è gia occupata.\n";
+                            errore = stampa.clone();
                         } else {
-                            // La colonna è sbagliata
-                            if damiera[mossa_r][mossa_c] != " " {
-                                stampa.clear();
-                                stampa = "\nCattura ".to_owned()
-                                    + &stampa_mossa(pedina_r, pedina_c).await
-                                    + " -> "
-                                    + &stampa_mossa(mossa_r, mossa_c).await
-                                    + " non valida!\nLa posizione scelta è gia occupata.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            } else {
-                                stampa.clear();
-                                stampa = "\nCattura ".to_owned() + &stampa_mossa(pedina_r, pedina_c).await + " -> " + &stampa_mossa(mossa_r, mossa_c).await +" non valida!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            }
-
-                            err_mossa = true;
-                            break; // Esco dal for
+                            stampa.clear();
+                            stampa = "\nSpostamento ".to_owned() + &stampa_mossa(pedina_r, pedina_c) + " -> " + &stampa_mossa(mossa_r, mossa_c) +" non valido!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
+                            errore = stampa.clone();
                         }
+
+                        break; // Esco dal for
+                    }
+                } else if pedina_r - 2 == mossa_r {
+                    // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
+                    if (((pedina_c == 0 || pedina_c == 1)
+                        && pedina_c + 2 == mossa_c
+                        && damiera[pedina_r - 1][pedina_c + 1] == "n")
+                        || ((pedina_c == 7 || pedina_c == 6)
+                            && pedina_c - 2 == mossa_c
+                            && damiera[pedina_r - 1][pedina_c - 1] == "n")
+                        || ((pedina_c != 0 && pedina_c != 1 && pedina_c != 7 && pedina_c != 6)
+                            && (pedina_c + 2 == mossa_c || pedina_c - 2 == mossa_c)
+                            && ((mossa_c > pedina_c
+                                && damiera[pedina_r - 1][pedina_c + 1] == "n")
+                                || (mossa_c < pedina_c
+                                    && damiera[pedina_r - 1][pedina_c - 1] == "n"))))
+                        && damiera[mossa_r][mossa_c] == " "
+                    {
+                        // Spiegazione controlli
+                        // 1) cattura a DX se si è sulla colonna 0 o 1
+                        // 2) cattura a SX se si è sulla colonna 7 0 6
+                        // 3) cattura normale se si è al centro
+
+                        // La cattura è valida
+                        err_mossa = false;
                     } else {
-                        // La riga è sbagliata
-                        if pedina_r == mossa_r {
+                        // La colonna è sbagliata
+                        if damiera[mossa_r][mossa_c] != " " {
                             stampa.clear();
-                            stampa = "\nSpostamento ".to_owned()
-                                + &stampa_mossa(pedina_r, pedina_c).await
+                            stampa = "\nCattura ".to_owned()
+                                + &stampa_mossa(pedina_r, pedina_c)
                                 + " -> "
-                                + &stampa_mossa(mossa_r, mossa_c).await
-                                + " non valido!\nPuoi muoverti solo in diagonale.\n";
-                            _ = giocatore.output.write(stampa.as_bytes()).await;
+                                + &stampa_mossa(mossa_r, mossa_c)
+                                + " non valida!\nLa posizione scelta è gia occupata.\n";
+                            errore = stampa.clone();
                         } else {
                             stampa.clear();
-                            stampa = "\nSpostamento ".to_owned() + &stampa_mossa(pedina_r, pedina_c).await + " -> " + &stampa_mossa(mossa_r, mossa_c).await +" non valido!\nLe righe sono troppo distanti o ti stai muovendo nel verso sbagliato.\n";
-                            _ = giocatore.output.write(stampa.as_bytes()).await;
+                            stampa = "\nCattura ".to_owned() + &stampa_mossa(pedina_r, pedina_c) + " -> " + &stampa_mossa(mossa_r, mossa_c) +" non valida!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
+                            errore = stampa.clone();
                         }
 
                         err_mossa = true;
                         break; // Esco dal for
                     }
                 } else {
-                    // Dama
-
-                    // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se si fa una mossa o una cattura
-                    if ((pedina_r == 0 && pedina_r + 1 == mossa_r)
-                        || (pedina_r == 7 && pedina_r - 1 == mossa_r)
-                        || ((pedina_r != 0 && pedina_r != 7)
-                            && (pedina_r - 1 == mossa_r || pedina_r + 1 == mossa_r)))
-                        && mosse.len() == 2
+                    // La riga è sbagliata
+                    if pedina_r == mossa_r {
+                        stampa.clear();
+                        stampa = "\nSpostamento ".to_owned()
+                            + &stampa_mossa(pedina_r, pedina_c)
+                            + " -> "
+                            + &stampa_mossa(mossa_r, mossa_c)
+                            + " non valido!\nPuoi muoverti solo in diagonale.\n";
+                        errore = stampa.clone();
+                    } else {
+                        stampa.clear();
+                        stampa = "\nSpostamento ".to_owned() + &stampa_mossa(pedina_r, pedina_c) + " -> " + &stampa_mossa(mossa_r, mossa_c) +" non valido!\nLe righe sono troppo distanti o ti stai muovendo nel verso sbagliato.\n";
+                        errore = stampa.clone();
+                    }
+
+                    err_mossa = true;
+                    break; // Esco dal for
+                }
+            } else {
+                // Dama
+
+                // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se si fa una mossa o una cattura
+                if ((pedina_r == 0 && pedina_r + 1 == mossa_r)
+                    || (pedina_r == 7 && pedina_r - 1 == mossa_r)
+                    || ((pedina_r != 0 && pedina_r != 7)
+                        && (pedina_r - 1 == mossa_r || pedina_r + 1 == mossa_r)))
+                    && mosse.len() == 2
+                {
+                    // Spiegazione controlli
+                    // 1) mossa GIU' se si è sulla riga 0
+                    // 2) mossa SU se si è sulla riga 7
+                    // 3) mossa normale se si è al centro
+
+                    // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
+                    if ((pedina_c == 0 && pedina_c + 1 == mossa_c)
+                        || (pedina_c == 7 && pedina_c - 1 == mossa_c)
+                        || ((pedina_c != 0 && pedina_c != 7)
+                            && (pedina_c + 1 == mossa_c || pedina_c - 1 == mossa_c)))
+                        && damiera[mossa_r][mossa_c] == " "
                     {
                         // Spiegazione controlli
-                        // 1) mossa GIU' se si è sulla riga 0
-                        // 2) mossa SU se si è sulla riga 7
+                        // 1) mossa a DX se si è sulla colonna 0
+                        // 2) mossa a SX se si è sulla colonna 7
                         // 3) mossa normale se si è al centro
-
-                        // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
-                        if ((pedina_c == 0 && pedina_c + 1 == mossa_c)
-                            || (pedina_c == 7 && pedina_c - 1 == mossa_c)
-                            || ((pedina_c != 0 && pedina_c != 7)
-                                && (pedina_c + 1 == mossa_c || pedina_c - 1 == mossa_c)))
-                            && damiera[mossa_r][mossa_c] == " "
-                        {
-                            // Spiegazione controlli
-                            // 1) mossa a DX se si è sulla colonna 0
-                            // 2) mossa a SX se si è sulla colonna 7
-                            // 3) mossa normale se si è al centro
-                            err_mossa = false;
+                        err_mossa = false;
+                    } else {
+                        // La colonna è sbagliata
+                        if damiera[mossa_r][mossa_c] != " " {
+                            stampa.clear();
+                            stampa = "\nSpostamento ".to_owned()
+                                + &stampa_mossa(pedina_r, pedina_c)
+                                + " -> "
+                                + &stampa_mossa(mossa_r, mossa_c)
+                                + " non valido!\nLa posizione scelta è gia occupata.\n";
+                            errore = stampa.clone();
                         } else {
-                            // La colonna è sbagliata
-                            if damiera[mossa_r][mossa_c] != " " {
-                                stampa.clear();
-                                stampa = "\nSpostamento ".to_owned()
-                                    + &stampa_mossa(pedina_r, pedina_c).await
-                                    + " -> "
-                                    + &stampa_mossa(mossa_r, mossa_c).await
-                                    + " non valido!\nLa posizione scelta è gia occupata.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            } else {
-                                stampa.clear();
-                                stampa = "\nSpostamento ".to_owned() + &stampa_mossa(pedina_r, pedina_c).await + " -> " + &stampa_mossa(mossa_r, mossa_c).await +" non valido!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            }
-
-                            break; // Esco dal for
+                            stampa.clear();
+                            stampa = "\nSpostamento ".to_owned() + &stampa_mossa(pedina_r, pedina_c) + " -> " + &stampa_mossa(mossa_r, mossa_c) +" non valido!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
+                            errore = stampa.clone();
                         }
-                    } else if ((pedina_r == 0 || pedina_r == 1) && pedina_r + 2 == mossa_r)
-                        || ((pedina_r == 7 || pedina_r == 6) && pedina_r - 2 == mossa_r)
-                        || ((pedina_r != 0 && pedina_r != 1 && pedina_r != 7 && pedina_r != 6)
-                            && (pedina_r - 2 == mossa_r || pedina_r + 2 == mossa_r))
-                    {
-                        // Spiegazione controlli
-                        // 1) cattura GIU' se si è sulla riga 0 o 1
-                        // 2) cattura SU se si è sulla riga 7 o 6
-                        // 3) cattura normale se si è al centro
 
-                        // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
-                        if (((pedina_c == 0 || pedina_c == 1)
-                            && pedina_c + 2 == mossa_c
+                        break; // Esco dal for
+                    }
+                } else if ((pedina_r == 0 || pedina_r == 1) && pedina_r + 2 == mossa_r)
+                    || ((pedina_r == 7 || pedina_r == 6) && pedina_r - 2 == mossa_r)
+                    || ((pedina_r != 0 && pedina_r != 1 && pedina_r != 7 && pedina_r != 6)
+                        && (pedina_r - 2 == mossa_r || pedina_r + 2 == mossa_r))
+                {
+                    // Spiegazione controlli
+                    // 1) cattura GIU' se si è sulla riga 0 o 1
+                    // 2) cattura SU se si è sulla riga 7 o 6
+                    // 3) cattura normale se si è al centro
+
+                    // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
+                    if (((pedina_c == 0 || pedina_c == 1)
+                        && pedina_c + 2 == mossa_c
+                        && ((mossa_r > pedina_r && damiera[pedina_r + 1][pedina_c + 1] == "n")
+                            || (mossa_r > pedina_r && damiera[pedina_r + 1][pedina_c + 1] == "N")
+                            || (mossa_r < pedina_r && damiera[pedina_r - 1][pedina_c + 1] == "n")
+                            || (mossa_r < pedina_r && damiera[pedina_r - 1][pedina_c + 1] == "N")))
+                        || ((pedina_c == 7 || pedina_c == 6)
+                            && pedina_c - 2 == mossa_c
                             && ((mossa_r > pedina_r
+                                && damiera[pedina_r + 1][pedina_c - 1] == "n")
+                                || (mossa_r > pedina_r
+                                    && damiera[pedina_r + 1][pedina_c - 1] == "N")
+                                || (mossa_r < pedina_r
+                                    && damiera[pedina_r - 1][pedina_c - 1] == "n")
+                                || (mossa_r < pedina_r
+                                    && damiera[pedina_r - 1][pedina_c - 1] == "N")))
+                        || ((pedina_c != 0 && pedina_c != 1 && pedina_c != 7 && pedina_c != 6)
+                            && (pedina_c + 2 == mossa_c || pedina_c - 2 == mossa_c)
+                            && ((mossa_r > pedina_r
+                                && mossa_c > pedina_c
                                 && damiera[pedina_r + 1][pedina_c + 1] == "n")
                                 || (mossa_r > pedina_r
+                                    && mossa_c > pedina_c
                                     && damiera[pedina_r + 1][pedina_c + 1] == "N")
                                 || (mossa_r < pedina_r
+                                    && mossa_c > pedina_c
                                     && damiera[pedina_r - 1][pedina_c + 1] == "n")
                                 || (mossa_r < pedina_r
-                                    && damiera[pedina_r - 1][pedina_c + 1] == "N")))
-                            || ((pedina_c == 7 || pedina_c == 6)
-                                && pedina_c - 2 == mossa_c
-                                && ((mossa_r > pedina_r
-                                    && damiera[pedina_r + 1][pedina_c - 1] == "n")
-                                    || (mossa_r > pedina_r
-                                        && damiera[pedina_r + 1][pedina_c - 1] == "N")
-                                    || (mossa_r < pedina_r
-                                        && damiera[pedina_r - 1][pedina_c - 1] == "n")
-                                    || (mossa_r < pedina_r
-                                        && damiera[pedina_r - 1][pedina_c - 1] == "N")))
-                            || ((pedina_c != 0 && pedina_c != 1 && pedina_c != 7 && pedina_c != 6)
-                                && (pedina_c + 2 == mossa_c || pedina_c - 2 == mossa_c)
-                                && ((mossa_r > pedina_r
                                     && mossa_c > pedina_c
-                                    && damiera[pedina_r + 1][pedina_c + 1] == "n")
-                                    || (mossa_r > pedina_r
-                                        && mossa_c > pedina_c
-                                        && damiera[pedina_r + 1][pedina_c + 1] == "N")
-                                    || (mossa_r < pedina_r
-                                        && mossa_c > pedina_c
-                                        && damiera[pedina_r - 1][pedina_c + 1] == "n")
-                                    || (mossa_r < pedina_r
-                                        && mossa_c > pedina_c
-                                        && damiera[pedina_r - 1][pedina_c + 1] == "N")
-                                    || (mossa_r > pedina_r
-                                        && mossa_c < pedina_c
-                                        && damiera[pedina_r + 1][pedina_c - 1] == "n")
-                                    || (mossa_r > pedina_r
-                                        && mossa_c < pedina_c
-                                        && damiera[pedina_r + 1][pedina_c - 1] == "N")
-                                    || (mossa_r < pedina_r
-                                        && mossa_c < pedina_c
-                                        && damiera[pedina_r - 1][pedina_c - 1] == "n")
-                                    || (mossa_r < pedina_r
-                                        && mossa_c < pedina_c
-                                        && damiera[pedina_r - 1][pedina_c - 1] == "N"))))
-                            && damiera[mossa_r][mossa_c] == " "
-                        {
-                            // Spiegazione controlli
-                            // 1) cattura DX se si è sulla colonna 0 o 1
-                            // 2) cattura SX se si è sulla colonna 7 o 6
-                            // 3) cattura normale se si è al centro
-
-                            // La cattura è valida
-                            err_mossa = false;
-                        } else {
-                            // La colonna è sbagliata
-                            if damiera[mossa_r][mossa_c] != " " {
-                                stampa.clear();
-                                stampa = "\nCattura ".to_owned()
-                                    + &stampa_mossa(pedina_r, pedina_c).await
-                                    + " -> "
-                                    + &stampa_mossa(mossa_r, mossa_c).await
-                                    + " non valida!\nLa posizione scelta è gia occupata.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            } else {
-                                stampa.clear();
-                                stampa = "\nCattura ".to_owned() + &stampa_mossa(pedina_r, pedina_c).await + " -> " + &stampa_mossa(mossa_r, mossa_c).await +" non valida!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            }
-
-                            err_mossa = true;
-                            break; // Esco dal for
-                        }
+                                    && damiera[pedina_r - 1][pedina_c + 1] == "N")
+                                || (mossa_r > pedina_r
+                                    && mossa_c < pedina_c
+                                    && damiera[pedina_r + 1][pedina_c - 1] == "n")
+                                || (mossa_r > pedina_r
+                                    && mossa_c < pedina_c
+                                    && damiera[pedina_r + 1][pedina_c - 1] == "N")
+                                || (mossa_r < pedina_r
+                                    && mossa_c < pedina_c
+                                    && damiera[pedina_r - 1][pedina_c - 1] == "n")
+                                || (mossa_r < pedina_r
+                                    && mossa_c < pedina_c
+                                    && damiera[pedina_r - 1][pedina_c - 1] == "N"))))
+                        && damiera[mossa_r][mossa_c] == " "
+                    {
+                        // Spiegazione controlli
+                        // 1) cattura DX se si è sulla colonna 0 o 1
+                        // 2) cattura SX se si è sulla colonna 7 o 6
+                        // 3) cattura normale se si è al centro
+
+                        // La cattura è valida
+                        err_mossa = false;
                     } else {
-                        // La riga è sbagliata
-                        if pedina_r == mossa_r {
+                        // La colonna è sbagliata
+                        if damiera[mossa_r][mossa_c] != " " {
                             stampa.clear();
-                            stampa = "\nSpostamento ".to_owned()
-                                + &stampa_mossa(pedina_r, pedina_c).await
+                            stampa = "\nCattura ".to_owned()
+                                + &stampa_mossa(pedina_r, pedina_c)
                                 + " -> "
-                                + &stampa_mossa(mossa_r, mossa_c).await
-                                + " non valido!\nPuoi muoverti solo in diagonale.\n";
-                            _ = giocatore.output.write(stampa.as_bytes()).await;
+                                + &stampa_mossa(mossa_r, mossa_c)
+                                + " non valida!\nLa posizione scelta è gia occupata.\n";
+                            errore = stampa.clone();
                         } else {
                             stampa.clear();
-                            stampa = "\nSpostamento ".to_owned()
-                                + &stampa_mossa(pedina_r, pedina_c).await
-                                + " -> "
-                                + &stampa_mossa(mossa_r, mossa_c).await
-                                + " non valido!\nLe righe sono troppo distanti.\n";
-                            _ = giocatore.output.write(stampa.as_bytes()).await;
+                            stampa = "\nCattura ".to_owned() + &stampa_mossa(pedina_r, pedina_c) + " -> " + &stampa_mossa(mossa_r, mossa_c) +" non valida!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
+                            errore = stampa.clone();
                         }
 
                         err_mossa = true;
                         break; // Esco dal for
                     }
-                }
+                } else {
+                    // La riga è sbagliata
+                    if pedina_r == mossa_r {
+                        stampa.clear();
+                        stampa = "\nSpostamento ".to_owned()
+                            + &stampa_mossa(pedina_r, pedina_c)
+                            + " -> "
+                            + &stampa_mossa(mossa_r, mossa_c)
+                            + " non valido!\nPuoi muoverti solo in diagonale.\n";
+                        errore = stampa.clone();
+                    } else {
+                        stampa.clear();
+                        stampa = "\nSpostamento ".to_owned()
+                            + &stampa_mossa(pedina_r, pedina_c)
+                            + " -> "
+                            + &stampa_mossa(mossa_r, mossa_c)
+                            + " non valido!\nLe righe sono troppo distanti.\n";
+                        errore = stampa.clone();
+                    }
 
-                // Setto la nuova posizione della pedina
-                pedina_r = mossa_r;
-                pedina_c = mossa_c;
+                    err_mossa = true;
+                    break; // Esco dal for
+                }
             }
+
+            // Setto la nuova posizione della pedina
+            pedina_r = mossa_r;
+            pedina_c = mossa_c;
         }
     }
 
-    // Restituisco le mossa controllate
-    mosse
+    // Restituisco l'errore da mostrare al giocatore, se il percorso non è valido
+    if err_mossa {
+        Err(errore.trim_end().to_owned())
+    } else {
+        Ok(())
+    }
 }
 
-pub(crate) async fn verifica_percorso_nero(
+pub(crate) fn verifica_percorso_nero(
     damiera: Vec<Vec<&str>>,
-    giocatore: &mut Player,
-    timer: Duration,
-) -> Vec<String> {
+    mosse: &[String],
+) -> Result<(), String> {
     let mut err_mossa: bool = true;
-    let mut mosse: Vec<String> = Vec::new();
-    let mut stampa: String = String::new();
+    let mut stampa = String::new();
+    let mut errore = String::new();
 
-    while err_mossa {
-        // Chiedo all'utente che mosse vuole fare
-        mosse = percorso(giocatore, timer).await;
+    // Setto se sto muovendo una pedina o una dama
+    let mut dama: bool = false;
 
-        // Verifico che il giocatore non abbia abbandonato
-        if mosse[0] == "Err" {
-            return mosse;
-        }
+    // Setto la posizione iniziale della pedina
+    let mut pedina_r: usize = mosse[0]
+        .chars()
+        .next()
+        .expect("Error to 'pedina_r' in verifica_percorso_nero")
+        as usize
+        - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
+    let mut pedina_c: usize = mosse[0]
+        .chars()
+        .nth(1)
+        .expect("Error to 'pedina_c' in verifica_percorso_nero")
+        as usize
+        - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
+    let mut mossa_r: usize;
+    let mut mossa_c: usize;
 
-        // Setto se sto muovendo una pedina o una dama
-        let mut dama: bool = false;
-
-        // Setto la posizione iniziale della pedina
-        let mut pedina_r: usize = mosse[0]
-            .chars()
-            .nth(0)
-            .expect("Error to 'pedina_r' in verifica_percorso_nero")
-            as usize
-            - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
-        let mut pedina_c: usize = mosse[0]
-            .chars()
-            .nth(1)
-            .expect("Error to 'pedina_c' in verifica_percorso_nero")
-            as usize
-            - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
-        let mut mossa_r: usize;
-        let mut mossa_c: usize;
-
-        for (n, m) in mosse.iter().enumerate() {
-            if n == 0 {
-                // Controllo se ho selezionato la mia pedina
-                if damiera[pedina_r][pedina_c] == "n" {
-                    dama = false;
-                } else if damiera[pedina_r][pedina_c] == "N" {
-                    dama = true;
-                } else {
-                    _ = giocatore
-                        .output
-                        .write(
-                            ("\nNon hai selezionato una tua pedina!\nRicorda che sei i neri.\n")
-                                .as_bytes(),
-                        )
-                        .await;
-                    break; // Esco dal for
-                }
+    for (n, m) in mosse.iter().enumerate() {
+        if n == 0 {
+            // Controllo se ho selezionato la mia pedina
+            if damiera[pedina_r][pedina_c] == "n" {
+                dama = false;
+            } else if damiera[pedina_r][pedina_c] == "N" {
+                dama = true;
             } else {
-                // Setto la mossa successiva
-                mossa_r = m
-                    .chars()
-                    .nth(0)
-                    .expect("Error to 'mossa_r' in verifica_percorso_nero")
-                    as usize
-                    - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
-                mossa_c = m
-                    .chars()
-                    .nth(1)
-                    .expect("Error to 'mossa_c' in verifica_percorso_nero")
-                    as usize
-                    - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
-
-                // Verifico le mosse e le cattura
-                if dama == false {
-                    // Pedina
-
-                    // Controllo se si fa una mossa o una cattura
-                    if pedina_r + 1 == mossa_r && mosse.len() == 2 {
-                        // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
-                        if ((pedina_c == 0 && pedina_c + 1 == mossa_c)
-                            || (pedina_c == 7 && pedina_c - 1 == mossa_c)
-                            || ((pedina_c != 0 && pedina_c != 7)
-                                && (pedina_c + 1 == mossa_c || pedina_c - 1 == mossa_c)))
-                            && damiera[mossa_r][mossa_c] == " "
-                        {
-                            // Spiegazione controlli
-                            // 1) mossa a DX se si è sulla colonna 0
-                            // 2) mossa a SX se si è sulla colonna 7
-                            // 3) mossa normale se si è al centro
-                            err_mossa = false;
-                        } else {
-                            // La colonna è sbagliata
-                            if damiera[mossa_r][mossa_c] != " " {
-                                stampa.clear();
-                                stampa = "\nSpostamento ".to_owned()
-                                    + &stampa_mossa(pedina_r, pedina_c).await
-                                    + " -> "
-                                    + &stampa_mossa(mossa_r, mossa_c).await
-                                    + " non valido!\nLa posizione scelta è gia occupata.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            } else {
-                                stampa.clear();
-                                stampa = "\nSpostamento ".to_owned() + &stampa_mossa(pedina_r, pedina_c).await + " -> " + &stampa_mossa(mossa_r, mossa_c).await +" non valido!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            }
-
-                            break; // Esco dal for
-                        }
-                    } else if pedina_r + 2 == mossa_r {
-                        // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
-                        if (((pedina_c == 0 || pedina_c == 1)
-                            && pedina_c + 2 == mossa_c
-                            && damiera[pedina_r + 1][pedina_c + 1] == "b")
-                            || ((pedina_c == 7 || pedina_c == 6)
-                                && pedina_c - 2 == mossa_c
-                                && damiera[pedina_r + 1][pedina_c - 1] == "b")
-                            || ((pedina_c != 0 && pedina_c != 1 && pedina_c != 7 && pedina_c != 6)
-                                && (pedina_c + 2 == mossa_c || pedina_c - 2 == mossa_c)
-                                && ((mossa_c > pedina_c
-                                    && damiera[pedina_r + 1][pedina_c + 1] == "b")
-                                    || (mossa_c < pedina_c
-                                        && damiera[pedina_r + 1][pedina_c - 1] == "b"))))
-                            && damiera[mossa_r][mossa_c] == " "
-                        {
-                            // Spiegazione controlli
-                            // 1) cattura a DX se si è sulla colonna 0 o 1
-                            // 2) cattura a SX se si è sulla colonna 7 0 6
-                            // 3) cattura normale se si è al centro
-
-                            // La cattura è valida
-                            err_mossa = false;
+                errore =
+                    "\nNon hai selezionato una tua pedina!\nRicorda che sei i neri.\n".to_owned();
+                break; // Esco dal for
+            }
+        } else {
+            // Setto la mossa successiva
+            mossa_r = m
+                .chars()
+                .next()
+                .expect("Error to 'mossa_r' in verifica_percorso_nero")
+                as usize
+                - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
+            mossa_c = m
+                .chars()
+                .nth(1)
+                .expect("Error to 'mossa_c' in verifica_percorso_nero")
+                as usize
+                - 0x30; // 0x30 = 0 nella tabella ASCII (Altrimenti non converte bene)
+
+            // Verifico le mosse e le cattura
+            if !dama {
+                // Pedina
+
+                // Controllo se si fa una mossa o una cattura
+                if pedina_r + 1 == mossa_r && mosse.len() == 2 {
+                    // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
+                    if ((pedina_c == 0 && pedina_c + 1 == mossa_c)
+                        || (pedina_c == 7 && pedina_c - 1 == mossa_c)
+                        || ((pedina_c != 0 && pedina_c != 7)
+                            && (pedina_c + 1 == mossa_c || pedina_c - 1 == mossa_c)))
+                        && damiera[mossa_r][mossa_c] == " "
+                    {
+                        // Spiegazione controlli
+                        // 1) mossa a DX se si è sulla colonna 0
+                        // 2) mossa a SX se si è sulla colonna 7
+                        // 3) mossa normale se si è al centro
+                        err_mossa = false;
+                    } else {
+                        // La colonna è sbagliata
+                        if damiera[mossa_r][mossa_c] != " " {
+                            stampa.clear();
+                            stampa = "\nSpostamento ".to_owned()
+                                + &stampa_mossa(pedina_r, pedina_c)
+                                + " -> "
+                                + &stampa_mossa(mossa_r, mossa_c)
+                                + " non valido!\nLa posizione scelta è gia occupata.\n";
+                            errore = stampa.clone();
                         } else {
-                            // La colonna è sbagliata
-                            if damiera[mossa_r][mossa_c] != " " {
-                                stampa.clear();
-                                stampa = "\nCattura ".to_owned()
-                                    + &stampa_mossa(pedina_r, pedina_c).await
-                                    + " -> "
-                                    + &stampa_mossa(mossa_r, mossa_c).await
-                                    + " non valida!\nLa posizione scelta è gia occupata.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            } else {
-                                stampa.clear();
-                                stampa = "\nCattura ".to_owned() + &stampa_mossa(pedina_r, pedina_c).await + " -> " + &stampa_mossa(mossa_r, mossa_c).await +" non valida!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            }
-
-                            err_mossa = true;
-                            break; // Esco dal for
+                            stampa.clear();
+                            stampa = "\nSpostamento ".to_owned() + &stampa_mossa(pedina_r, pedina_c) + " -> " + &stampa_mossa(mossa_r, mossa_c) +" non valido!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
+                            errore = stampa.clone();
                         }
+
+                        break; // Esco dal for
+                    }
+                } else if pedina_r + 2 == mossa_r {
+                    // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
+                    if (((pedina_c == 0 || pedina_c == 1)
+                        && pedina_c + 2 == mossa_c
+                        && damiera[pedina_r + 1][pedina_c + 1] == "b")
+                        || ((pedina_c == 7 || pedina_c == 6)
+                            && pedina_c - 2 == mossa_c
+                            && damiera[pedina_r + 1][pedina_c - 1] == "b")
+                        || ((pedina_c != 0 && pedina_c != 1 && pedina_c != 7 && pedina_c != 6)
+                            && (pedina_c + 2 == mossa_c || pedina_c - 2 == mossa_c)
+                            && ((mossa_c > pedina_c
+                                && damiera[pedina_r + 1][pedina_c + 1] == "b")
+                                || (mossa_c < pedina_c
+                                    && damiera[pedina_r + 1][pedina_c - 1] == "b"))))
+                        && damiera[mossa_r][mossa_c] == " "
+                    {
+                        // Spiegazione controlli
+                        // 1) cattura a DX se si è sulla colonna 0 o 1
+                        // 2) cattura a SX se si è sulla colonna 7 0 6
+                        // 3) cattura normale se si è al centro
+
+                        // La cattura è valida
+                        err_mossa = false;
                     } else {
-                        // La riga è sbagliata
-                        if pedina_r == mossa_r {
+                        // La colonna è sbagliata
+                        if damiera[mossa_r][mossa_c] != " " {
                             stampa.clear();
-                            stampa = "\nSpostamento ".to_owned()
-                                + &stampa_mossa(pedina_r, pedina_c).await
+                            stampa = "\nCattura ".to_owned()
+                                + &stampa_mossa(pedina_r, pedina_c)
                                 + " -> "
-                                + &stampa_mossa(mossa_r, mossa_c).await
-                                + " non valido!\nPuoi muoverti solo in diagonale.\n";
-                            _ = giocatore.output.write(stampa.as_bytes()).await;
+                                + &stampa_mossa(mossa_r, mossa_c)
+                                + " non valida!\nLa posizione scelta è gia occupata.\n";
+                            errore = stampa.clone();
                         } else {
                             stampa.clear();
-                            stampa = "\nSpostamento ".to_owned() + &stampa_mossa(pedina_r, pedina_c).await + " -> " + &stampa_mossa(mossa_r, mossa_c).await +" non valido!\nLe righe sono troppo distanti o ti stai muovendo nel verso sbagliato.\n";
-                            _ = giocatore.output.write(stampa.as_bytes()).await;
+                            stampa = "\nCattura ".to_owned() + &stampa_mossa(pedina_r, pedina_c) + " -> " + &stampa_mossa(mossa_r, mossa_c) +" non valida!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
+                            errore = stampa.clone();
                         }
 
                         err_mossa = true;
                         break; // Esco dal for
                     }
                 } else {
-                    // Dama
-
-                    // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se si fa una mossa o una cattura
-                    if ((pedina_r == 0 && pedina_r + 1 == mossa_r)
-                        || (pedina_r == 7 && pedina_r - 1 == mossa_r)
-                        || ((pedina_r != 0 && pedina_r != 7)
-                            && (pedina_r - 1 == mossa_r || pedina_r + 1 == mossa_r)))
-                        && mosse.len() == 2
+                    // La riga è sbagliata
+                    if pedina_r == mossa_r {
+                        stampa.clear();
+                        stampa = "\nSpostamento ".to_owned()
+                            + &stampa_mossa(pedina_r, pedina_c)
+                            + " -> "
+                            + &stampa_mossa(mossa_r, mossa_c)
+                            + " non valido!\nPuoi muoverti solo in diagonale.\n";
+                        errore = stampa.clone();
+                    } else {
+                        stampa.clear();
+                        stampa = "\nSpostamento ".to_owned() + &stampa_mossa(pedina_r, pedina_c) + " -> " + &stampa_mossa(mossa_r, mossa_c) +" non valido!\nLe righe sono troppo distanti o ti stai muovendo nel verso sbagliato.\n";
+                        errore = stampa.clone();
+                    }
+
+                    err_mossa = true;
+                    break; // Esco dal for
+                }
+            } else {
+                // Dama
+
+                // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se si fa una mossa o una cattura
+                if ((pedina_r == 0 && pedina_r + 1 == mossa_r)
+                    || (pedina_r == 7 && pedina_r - 1 == mossa_r)
+                    || ((pedina_r != 0 && pedina_r != 7)
+                        && (pedina_r - 1 == mossa_r || pedina_r + 1 == mossa_r)))
+                    && mosse.len() == 2
+                {
+                    // Spiegazione controlli
+                    // 1) mossa GIU' se si è sulla riga 0
+                    // 2) mossa SU se si è sulla riga 7
+                    // 3) mossa normale se si è al centro
+
+                    // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
+                    if ((pedina_c == 0 && pedina_c + 1 == mossa_c)
+                        || (pedina_c == 7 && pedina_c - 1 == mossa_c)
+                        || ((pedina_c != 0 && pedina_c != 7)
+                            && (pedina_c + 1 == mossa_c || pedina_c - 1 == mossa_c)))
+                        && damiera[mossa_r][mossa_c] == " "
                     {
                         // Spiegazione controlli
-                        // 1) mossa GIU' se si è sulla riga 0
-                        // 2) mossa SU se si è sulla riga 7
+                        // 1) mossa a DX se si è sulla colonna 0
+                        // 2) mossa a SX se si è sulla colonna 7
                         // 3) mossa normale se si è al centro
-
-                        // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
-                        if ((pedina_c == 0 && pedina_c + 1 == mossa_c)
-                            || (pedina_c == 7 && pedina_c - 1 == mossa_c)
-                            || ((pedina_c != 0 && pedina_c != 7)
-                                && (pedina_c + 1 == mossa_c || pedina_c - 1 == mossa_c)))
-                            && damiera[mossa_r][mossa_c] == " "
-                        {
-                            // Spiegazione controlli
-                            // 1) mossa a DX se si è sulla colonna 0
-                            // 2) mossa a SX se si è sulla colonna 7
-                            // 3) mossa normale se si è al centro
-                            err_mossa = false;
+                        err_mossa = false;
+                    } else {
+                        // La colonna è sbagliata
+                        if damiera[mossa_r][mossa_c] != " " {
+                            stampa.clear();
+                            stampa = "\nSpostamento ".to_owned()
+                                + &stampa_mossa(pedina_r, pedina_c)
+                                + " -> "
+                                + &stampa_mossa(mossa_r, mossa_c)
+                                + " non valido!\nLa posizione scelta è gia occupata.\n";
+                            errore = stampa.clone();
                         } else {
-                            // La colonna è sbagliata
-                            if damiera[mossa_r][mossa_c] != " " {
-                                stampa.clear();
-                                stampa = "\nSpostamento ".to_owned()
-                                    + &stampa_mossa(pedina_r, pedina_c).await
-                                    + " -> "
-                                    + &stampa_mossa(mossa_r, mossa_c).await
-                                    + " non valido!\nLa posizione scelta è gia occupata.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            } else {
-                                stampa.clear();
-                                stampa = "\nSpostamento ".to_owned() + &stampa_mossa(pedina_r, pedina_c).await + " -> " + &stampa_mossa(mossa_r, mossa_c).await +" non valido!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            }
-
-                            break; // Esco dal for
+                            stampa.clear();
+                            stampa = "\nSpostamento ".to_owned() + &stampa_mossa(pedina_r, pedina_c) + " -> " + &stampa_mossa(mossa_r, mossa_c) +" non valido!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
+                            errore = stampa.clone();
                         }
-                    } else if ((pedina_r == 0 || pedina_r == 1) && pedina_r + 2 == mossa_r)
-                        || ((pedina_r == 7 || pedina_r == 6) && pedina_r - 2 == mossa_r)
-                        || ((pedina_r != 0 && pedina_r != 1 && pedina_r != 7 && pedina_r != 6)
-                            && (pedina_r - 2 == mossa_r || pedina_r + 2 == mossa_r))
-                    {
-                        // Spiegazione controlli
-                        // 1) cattura GIU' se si è sulla riga 0 o 1
-                        // 2) cattura SU se si è sulla riga 7 o 6
-                        // 3) cattura normale se si è al centro
 
-                        // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
-                        if (((pedina_c == 0 || pedina_c == 1)
-                            && pedina_c + 2 == mossa_c
+                        break; // Esco dal for
+                    }
+                } else if ((pedina_r == 0 || pedina_r == 1) && pedina_r + 2 == mossa_r)
+                    || ((pedina_r == 7 || pedina_r == 6) && pedina_r - 2 == mossa_r)
+                    || ((pedina_r != 0 && pedina_r != 1 && pedina_r != 7 && pedina_r != 6)
+                        && (pedina_r - 2 == mossa_r || pedina_r + 2 == mossa_r))
+                {
+                    // Spiegazione controlli
+                    // 1) cattura GIU' se si è sulla riga 0 o 1
+                    // 2) cattura SU se si è sulla riga 7 o 6
+                    // 3) cattura normale se si è al centro
+
+                    // Controllo se la mossa è in diagonale, di non uscire dalla damiera e se la casella di arrivo è vuota
+                    if (((pedina_c == 0 || pedina_c == 1)
+                        && pedina_c + 2 == mossa_c
+                        && ((mossa_r > pedina_r && damiera[pedina_r + 1][pedina_c + 1] == "b")
+                            || (mossa_r > pedina_r && damiera[pedina_r + 1][pedina_c + 1] == "B")
+                            || (mossa_r < pedina_r && damiera[pedina_r - 1][pedina_c + 1] == "b")
+                            || (mossa_r < pedina_r && damiera[pedina_r - 1][pedina_c + 1] == "B")))
+                        || ((pedina_c == 7 || pedina_c == 6)
+                            && pedina_c - 2 == mossa_c
+                            && ((mossa_r > pedina_r
+                                && damiera[pedina_r + 1][pedina_c - 1] == "b")
+                                || (mossa_r > pedina_r
+                                    && damiera[pedina_r + 1][pedina_c - 1] == "B")
+                                || (mossa_r < pedina_r
+                                    && damiera[pedina_r - 1][pedina_c - 1] == "b")
+                                || (mossa_r < pedina_r
+                                    && damiera[pedina_r - 1][pedina_c - 1] == "B")))
+                        || ((pedina_c != 0 && pedina_c != 1 && pedina_c != 7 && pedina_c != 6)
+                            && (pedina_c + 2 == mossa_c || pedina_c - 2 == mossa_c)
                             && ((mossa_r > pedina_r
+                                && mossa_c > pedina_c
                                 && damiera[pedina_r + 1][pedina_c + 1] == "b")
                                 || (mossa_r > pedina_r
+                                    && mossa_c > pedina_c
                                     && damiera[pedina_r + 1][pedina_c + 1] == "B")
                                 || (mossa_r < pedina_r
+                                    && mossa_c > pedina_c
                                     && damiera[pedina_r - 1][pedina_c + 1] == "b")
                                 || (mossa_r < pedina_r
-                                    && damiera[pedina_r - 1][pedina_c + 1] == "B")))
-                            || ((pedina_c == 7 || pedina_c == 6)
-                                && pedina_c - 2 == mossa_c
-                                && ((mossa_r > pedina_r
-                                    && damiera[pedina_r + 1][pedina_c - 1] == "b")
-                                    || (mossa_r > pedina_r
-                                        && damiera[pedina_r + 1][pedina_c - 1] == "B")
-                                    || (mossa_r < pedina_r
-                                        && damiera[pedina_r - 1][pedina_c - 1] == "b")
-                                    || (mossa_r < pedina_r
-                                        && damiera[pedina_r - 1][pedina_c - 1] == "B")))
-                            || ((pedina_c != 0 && pedina_c != 1 && pedina_c != 7 && pedina_c != 6)
-                                && (pedina_c + 2 == mossa_c || pedina_c - 2 == mossa_c)
-                                && ((mossa_r > pedina_r
                                     && mossa_c > pedina_c
-                                    && damiera[pedina_r + 1][pedina_c + 1] == "b")
-                                    || (mossa_r > pedina_r
-                                        && mossa_c > pedina_c
-                                        && damiera[pedina_r + 1][pedina_c + 1] == "B")
-                                    || (mossa_r < pedina_r
-                                        && mossa_c > pedina_c
-                                        && damiera[pedina_r - 1][pedina_c + 1] == "b")
-                                    || (mossa_r < pedina_r
-                                        && mossa_c > pedina_c
-                                        && damiera[pedina_r - 1][pedina_c + 1] == "B")
-                                    || (mossa_r > pedina_r
-                                        && mossa_c < pedina_c
-                                        && damiera[pedina_r + 1][pedina_c - 1] == "b")
-                                    || (mossa_r > pedina_r
-                                        && mossa_c < pedina_c
-                                        && damiera[pedina_r + 1][pedina_c - 1] == "B")
-                                    || (mossa_r < pedina_r
-                                        && mossa_c < pedina_c
-                                        && damiera[pedina_r - 1][pedina_c - 1] == "b")
-                                    || (mossa_r < pedina_r
-                                        && mossa_c < pedina_c
-                                        && damiera[pedina_r - 1][pedina_c - 1] == "B"))))
-                            && damiera[mossa_r][mossa_c] == " "
-                        {
-                            // Spiegazione controlli
-                            // 1) cattura DX se si è sulla colonna 0 o 1
-                            // 2) cattura SX se si è sulla colonna 7 o 6
-                            // 3) cattura normale se si è al centro
-
-                            // La cattura è valida
-                            err_mossa = false;
-                        } else {
-                            // La colonna è sbagliata
-                            if damiera[mossa_r][mossa_c] != " " {
-                                stampa.clear();
-                                stampa = "\nCattura ".to_owned()
-                                    + &stampa_mossa(pedina_r, pedina_c).await
-                                    + " -> "
-                                    + &stampa_mossa(mossa_r, mossa_c).await
-                                    + " non valida!\nLa posizione scelta è gia occupata.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            } else {
-                                stampa.clear();
-                                stampa = "\nCattura ".to_owned() + &stampa_mossa(pedina_r, pedina_c).await + " -> " + &stampa_mossa(mossa_r, mossa_c).await +" non valida!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
-                                _ = giocatore.output.write(stampa.as_bytes()).await;
-                            }
-
-                            err_mossa = true;
-                            break; // Esco dal for
-                        }
+                                    && damiera[pedina_r - 1][pedina_c + 1] == "B")
+                                || (mossa_r > pedina_r
+                                    && mossa_c < pedina_c
+                                    && damiera[pedina_r + 1][pedina_c - 1] == "b")
+                                || (mossa_r > pedina_r
+                                    && mossa_c < pedina_c
+                                    && damiera[pedina_r + 1][pedina_c - 1] == "B")
+                                || (mossa_r < pedina_r
+                                    && mossa_c < pedina_c
+                                    && damiera[pedina_r - 1][pedina_c - 1] == "b")
+                                || (mossa_r < pedina_r
+                                    && mossa_c < pedina_c
+                                    && damiera[pedina_r - 1][pedina_c - 1] == "B"))))
+                        && damiera[mossa_r][mossa_c] == " "
+                    {
+                        // Spiegazione controlli
+                        // 1) cattura DX se si è sulla colonna 0 o 1
+                        // 2) cattura SX se si è sulla colonna 7 o 6
+                        // 3) cattura normale se si è al centro
+
+                        // La cattura è valida
+                        err_mossa = false;
                     } else {
-                        // La riga è sbagliata
-                        if pedina_r == mossa_r {
+                        // La colonna è sbagliata
+                        if damiera[mossa_r][mossa_c] != " " {
                             stampa.clear();
-                            stampa = "\nSpostamento ".to_owned()
-                                + &stampa_mossa(pedina_r, pedina_c).await
+                            stampa = "\nCattura ".to_owned()
+                                + &stampa_mossa(pedina_r, pedina_c)
                                 + " -> "
-                                + &stampa_mossa(mossa_r, mossa_c).await
-                                + " non valido!\nPuoi muoverti solo in diagonale.\n";
-                            _ = giocatore.output.write(stampa.as_bytes()).await;
+                                + &stampa_mossa(mossa_r, mossa_c)
+                                + " non valida!\nLa posizione scelta è gia occupata.\n";
+                            errore = stampa.clone();
                         } else {
                             stampa.clear();
-                            stampa = "\nSpostamento ".to_owned()
-                                + &stampa_mossa(pedina_r, pedina_c).await
-                                + " -> "
-                                + &stampa_mossa(mossa_r, mossa_c).await
-                                + " non valido!\nLe righe sono troppo distanti.\n";
-                            _ = giocatore.output.write(stampa.as_bytes()).await;
+                            stampa = "\nCattura ".to_owned() + &stampa_mossa(pedina_r, pedina_c) + " -> " + &stampa_mossa(mossa_r, mossa_c) +" non valida!\nNon ti stai spostando in diagonale o ti sei mosso di troppe colonne.\n";
+                            errore = stampa.clone();
                         }
 
                         err_mossa = true;
                         break; // Esco dal for
                     }
-                }
+                } else {
+                    // La riga è sbagliata
+                    if pedina_r == mossa_r {
+                        stampa.clear();
+                        stampa = "\nSpostamento ".to_owned()
+                            + &stampa_mossa(pedina_r, pedina_c)
+                            + " -> "
+                            + &stampa_mossa(mossa_r, mossa_c)
+                            + " non valido!\nPuoi muoverti solo in diagonale.\n";
+                        errore = stampa.clone();
+                    } else {
+                        stampa.clear();
+                        stampa = "\nSpostamento ".to_owned()
+                            + &stampa_mossa(pedina_r, pedina_c)
+                            + " -> "
+                            + &stampa_mossa(mossa_r, mossa_c)
+                            + " non valido!\nLe righe sono troppo distanti.\n";
+                        errore = stampa.clone();
+                    }
 
-                // Setto la nuova posizione della pedina
-                pedina_r = mossa_r;
-                pedina_c = mossa_c;
+                    err_mossa = true;
+                    break; // Esco dal for
+                }
             }
+
+            // Setto la nuova posizione della pedina
+            pedina_r = mossa_r;
+            pedina_c = mossa_c;
         }
     }
 
-    // Restituisco le mossa controllate
-    mosse
+    // Restituisco l'errore da mostrare al giocatore, se il percorso non è valido
+    if err_mossa {
+        Err(errore.trim_end().to_owned())
+    } else {
+        Ok(())
+    }
 }
 
-pub(crate) async fn aggionra_damiera<'a>(
+pub(crate) fn aggionra_damiera(
     percorso_valido: Vec<String>,
-    mut damiera: Vec<Vec<&'a str>>,
-) -> Vec<Vec<&'a str>> {
+    mut damiera: Vec<Vec<&str>>,
+) -> Vec<Vec<&str>> {
     // Setto la posizione iniziale della pedina
     let mut pedina_r: usize = percorso_valido[0]
         .chars()
@@ -1105,7 +1008,7 @@ pub(crate) async fn aggionra_damiera<'a>(
             {
                 // Setto la nuova posizione della pedina
                 // e controllo se ho fatto dama
-                damiera[mossa_r][mossa_c] = dama(pedina, mossa_r).await;
+                damiera[mossa_r][mossa_c] = dama(pedina, mossa_r);
             } else {
                 // Cancello le pedine mangiate
                 if mossa_r < pedina_r && mossa_c < pedina_c {
@@ -1123,7 +1026,7 @@ pub(crate) async fn aggionra_damiera<'a>(
                 // Se mi trovo all'ultima mossa setto la nuova posizione della pedina
                 // e controllo se ho fatto dama
                 if n + 1 == percorso_valido.len() {
-                    damiera[mossa_r][mossa_c] = dama(pedina, mossa_r).await;
+                    damiera[mossa_r][mossa_c] = dama(pedina, mossa_r);
                 }
             }
 
@@ -1137,7 +1040,7 @@ pub(crate) async fn aggionra_damiera<'a>(
     damiera
 }
 
-pub(crate) async fn dama(mut pedina: &str, mossa_r: usize) -> &str {
+pub(crate) fn dama(mut pedina: &str, mossa_r: usize) -> &str {
     // Verifico se ho fatto dama
     if pedina == "b" && mossa_r == 0 {
         pedina = "B";
@@ -1150,86 +1053,50 @@ pub(crate) async fn dama(mut pedina: &str, mossa_r: usize) -> &str {
     }
 }
 
-async fn percorso(giocatore: &mut Player, timer: Duration) -> Vec<String> {
-    // Inizializzo le variabili
-    let mut percorso: String = String::new();
-    let mut mosse: Vec<String> = Vec::new();
-    let mut err_mosse: bool = true;
+pub(crate) fn percorso(linea: &str) -> Result<Vec<String>, String> {
+    // Elimino tutti gli elementi non necessari dalla stringa
+    let percorso = linea.replace(&['\n', '\r', '\t'][..], "");
+    // Inserisco gli elementi in un vettore
+    let mut mosse: Vec<String> = percorso.split(" ").map(|x| x.into()).collect();
+    // Elimino lo spazio finale dal vettore se presente
+    mosse.retain(|x| !x.is_empty());
 
     // Controllo le mosse siano all'interno della damiera
-    while err_mosse {
-        _ = giocatore.output.write(("Inserisci la pedina che vuoi muovere e poi le mosse che vuoi fare\nEs > 6A 5B oppure 6A 4C 2A oppure 6A 4C 2A ...\n").as_bytes()).await;
-
-        // Pulisco la variabili altrimenti si portano dietro tutti i valori precedenti
-        percorso.clear();
-        mosse.clear();
-
-        // Controllo che il giocatore non abbia abbandonato la partita
-        match timeout(timer, giocatore.input.read_line(&mut percorso)).await {
-            Err(_) | Ok(Err(_)) => {
-                _ = giocatore
-                    .output
-                    .write(
-                        ("\nTempo scaduto, hai esaurito il tempo per fare una mossa!\n").as_bytes(),
-                    )
-                    .await;
-                mosse.insert(0, "Err".to_string());
-                return mosse;
-            }
-            Ok(Ok(x)) => {
-                // Se viene ritornato 0 vuol dire che il giocatore ha abbandonato
-                if x == 0 {
-                    mosse.insert(0, "Err".to_string());
-                    return mosse;
-                }
-            }
-        };
-
-        // Elimino tutti gli elementi non necessari dalla stringa
-        percorso = percorso.replace(&['\n', '\r', '\t'][..], "");
-        // Inserisco gli elementi in un vettore
-        mosse = percorso.split(" ").map(|x| x.into()).collect();
-        // Elimino lo spazio finale dal vettore se presente
-        mosse.retain(|x| x != "");
-
-        for i in 0..mosse.len() {
-            let m = &mosse[i];
+    let mut err_mosse: bool = true;
+    for i in 0..mosse.len() {
+        let m = &mosse[i];
 
-            // Verifico che ogni mossa abbia 2 caratteri
-            if m.len() != 2 {
-                break;
-            }
+        // Verifico che ogni mossa abbia 2 caratteri
+        if m.len() != 2 {
+            break;
+        }
 
-            let pos = conv_mossa_in_num(&m).await;
+        let pos = conv_mossa_in_num(m);
 
-            // Verifico che i valori inseriti siano all'interno della damiera
-            if pos.len() != 2 {
-                break;
-            }
+        // Verifico che i valori inseriti siano all'interno della damiera
+        if pos.len() != 2 {
+            break;
+        }
 
-            // Cavo l'elemento dato dall'utente e lo rimpiazzo con quello convertito
-            mosse.remove(i);
-            mosse.insert(i, pos);
+        // Cavo l'elemento dato dall'utente e lo rimpiazzo con quello convertito
+        mosse.remove(i);
+        mosse.insert(i, pos);
 
-            // Controllo se tutte le mosse sono state convertite
-            if i + 1 == mosse.len() && mosse.len() > 1 {
-                err_mosse = false;
-            }
+        // Controllo se tutte le mosse sono state convertite
+        if i + 1 == mosse.len() && mosse.len() > 1 {
+            err_mosse = false;
         }
+    }
 
-        if err_mosse {
-            _ = giocatore
-                .output
-                .write(("\nMossa non valida riprova!\n").as_bytes())
-                .await;
-        }
+    if err_mosse {
+        return Err("\nMossa non valida riprova!".to_owned());
     }
 
     // Restituisco le mosse convertire
-    mosse
+    Ok(mosse)
 }
 
-pub(crate) async fn conv_mossa_in_num(posizione: &str) -> String {
+pub(crate) fn conv_mossa_in_num(posizione: &str) -> String {
     // Convertitore di riga
     let row: &str = match posizione.chars().nth(0) {
         Some('1') => "0",
@@ -1260,7 +1127,7 @@ pub(crate) async fn conv_mossa_in_num(posizione: &str) -> String {
     row.to_owned() + col
 }
 
-pub(crate) async fn conv_mossa_in_alfanum(posizione: &str) -> String {
+pub(crate) fn conv_mossa_in_alfanum(posizione: &str) -> String {
     // Convertitore di riga
     let row: &str = match posizione.chars().nth(0) {
         Some('0') => "1",
@@ -1291,7 +1158,7 @@ pub(crate) async fn conv_mossa_in_alfanum(posizione: &str) -> String {
     row.to_owned() + col
 }
 
-pub(crate) async fn stampa_mossa(row: usize, col: usize) -> String {
+pub(crate) fn stampa_mossa(row: usize, col: usize) -> String {
     // Convertitore di riga
     let r: &str = match row {
         0 => "1",
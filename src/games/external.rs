@@ -0,0 +1,466 @@
+use super::util::{LineError, LineReader, Player};
+use crate::game::{self, MatchOutcome, PlayerResult, Termination};
+use crate::proto::{BotInfo, GameArgInfo};
+use crate::tuning::MAX_LINE_LENGTH;
+use async_trait::async_trait;
+use rand::random;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{copy, split, AsyncWriteExt, DuplexStream, WriteHalf};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::mpsc;
+use tokio::time::{sleep_until, timeout, Duration, Instant};
+use tokio::{join, select, spawn};
+use tracing::{error, warn};
+
+const DEFAULT_TIMEOUT: f64 = 90.0;
+
+/// How long the referee is given to exit on its own after the match ended
+const EXIT_GRACE_PERIOD: f64 = 1.0;
+
+/// How long the referee can stay silent past the timeout of a player action
+/// before the match is aborted, as it failed to enforce the timeout
+const SILENCE_GRACE_PERIOD: f64 = 5.0;
+
+/// A game whose referee is an external program, as written in the
+/// configuration file of the server
+#[derive(Deserialize, Debug)]
+struct Config {
+    name: String,
+    /// Command line of the referee
+    command: Vec<String>,
+    /// Markdown file with the description of the game
+    description: String,
+    #[serde(default)]
+    args: HashMap<String, GameArgInfo>,
+    min_players: usize,
+    max_players: usize,
+    /// Seconds players have for each move, when the match does not say
+    timeout: Option<f64>,
+    /// Server bots of the game, the first one is the default
+    #[serde(default)]
+    bots: Vec<BotConfig>,
+    /// Names of the seats, the ones past them are called by their number
+    #[serde(default)]
+    seats: Vec<String>,
+}
+
+/// A server bot of an external game
+#[derive(Deserialize, Debug)]
+struct BotConfig {
+    name: String,
+    description: String,
+    /// Command line of the bot
+    command: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Builder {
+    config: Config,
+    description: String,
+    /// Directory the programs of the game are run from
+    dir: PathBuf,
+}
+
+/// Load the external games listed in the configuration file at `path`.
+/// Relative paths in it are resolved against the directory of the file.
+pub(crate) fn load(path: &str) -> Result<Vec<Box<dyn game::Builder>>, String> {
+    let text =
+        std::fs::read_to_string(path).map_err(|x| format!("Cannot read \"{}\": {}", path, x))?;
+    let configs: Vec<Config> =
+        serde_json::from_str(&text).map_err(|x| format!("Cannot parse \"{}\": {}", path, x))?;
+    let dir = match Path::new(path).parent() {
+        Some(x) if x != Path::new("") => x.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let mut names: HashSet<String> = super::get().iter().map(|x| x.name().to_owned()).collect();
+    let mut builders: Vec<Box<dyn game::Builder>> = Vec::new();
+    for config in configs {
+        if !names.insert(config.name.clone()) {
+            return Err(format!("Game \"{}\" is defined twice", config.name));
+        }
+        if config.command.is_empty() || config.bots.iter().any(|x| x.command.is_empty()) {
+            return Err(format!("Empty command line for game \"{}\"", config.name));
+        }
+        let mut bots = HashSet::new();
+        if let Some(x) = config.bots.iter().find(|x| !bots.insert(&x.name)) {
+            return Err(format!(
+                "Bot \"{}\" of game \"{}\" is defined twice",
+                x.name, config.name
+            ));
+        }
+        if config.min_players == 0 || config.min_players > config.max_players {
+            return Err(format!(
+                "Invalid number of players for game \"{}\": from {} to {}",
+                config.name, config.min_players, config.max_players
+            ));
+        }
+        for (name, info) in config.args.iter() {
+            if let Err(x) = info.coerce(name, &info.default) {
+                return Err(format!(
+                    "Invalid default for game \"{}\": {}",
+                    config.name, x
+                ));
+            }
+        }
+        let file = dir.join(&config.description);
+        let description = std::fs::read_to_string(&file)
+            .map_err(|x| format!("Cannot read \"{}\": {}", file.display(), x))?;
+        builders.push(Box::new(Builder {
+            config,
+            description,
+            dir: dir.clone(),
+        }));
+    }
+    Ok(builders)
+}
+
+#[async_trait]
+impl game::Builder for Builder {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    async fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    async fn args(&self) -> HashMap<String, GameArgInfo> {
+        self.config.args.clone()
+    }
+
+    async fn bots(&self) -> Vec<BotInfo> {
+        self.config
+            .bots
+            .iter()
+            .map(|x| BotInfo {
+                name: x.name.clone(),
+                description: x.description.clone(),
+            })
+            .collect()
+    }
+
+    async fn seats(&self) -> Vec<String> {
+        self.config.seats.clone()
+    }
+
+    async fn gen_instance(
+        &self,
+        param: &mut game::Params,
+        args: HashMap<String, String>,
+    ) -> Result<Box<dyn game::Instance>, String> {
+        let (min, max) = (self.config.min_players, self.config.max_players);
+        param.players = match param.players {
+            Some(x) if x >= min && x <= max => Some(x),
+            Some(x) => return Err(format!("Cannot create game with {} players", x)),
+            None => Some(min),
+        };
+        if param.clock.is_some() {
+            return Err("The game does not support time controls".to_string());
+        }
+        param.timeout = param
+            .timeout
+            .or(Some(self.config.timeout.unwrap_or(DEFAULT_TIMEOUT)));
+        let seed = *param.seed.get_or_insert_with(random);
+        Ok(Box::new(Instance {
+            command: self.config.command.clone(),
+            dir: self.dir.clone(),
+            args,
+            timeout: param.timeout.expect("Cannot fail"),
+            seed,
+        }))
+    }
+
+    async fn gen_bot(&self, name: &str, seed: u64) -> Box<dyn game::Bot> {
+        let bot = self.config.bots.iter().find(|x| x.name == name);
+        Box::new(Bot {
+            command: bot.map(|x| x.command.clone()).unwrap_or_default(),
+            dir: self.dir.clone(),
+            seed,
+        })
+    }
+}
+
+/// Spawn a program of an external game, with its standard input and output piped
+fn spawn_program(command: &[String], dir: &Path, seed: u64) -> Result<Child, String> {
+    if command.is_empty() {
+        return Err("Empty command line".to_string());
+    }
+    Command::new(&command[0])
+        .args(&command[1..])
+        .current_dir(dir)
+        .env("COCO_SEED", seed.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|x| format!("Cannot spawn \"{}\": {}", command[0], x))
+}
+
+#[derive(Debug)]
+pub(crate) struct Bot {
+    command: Vec<String>,
+    dir: PathBuf,
+    seed: u64,
+}
+
+#[async_trait]
+impl game::Bot for Bot {
+    async fn start(&mut self, stream: DuplexStream) {
+        let mut child = match spawn_program(&self.command, &self.dir, self.seed) {
+            Ok(x) => x,
+            Err(x) => {
+                error!("{}", x);
+                return;
+            }
+        };
+        let mut stdin = child.stdin.take().expect("Cannot fail");
+        let mut stdout = child.stdout.take().expect("Cannot fail");
+        let (mut input, mut output) = split(stream);
+        let to_bot = async move {
+            let result = copy(&mut input, &mut stdin).await;
+            // Let the bot know the match is over
+            drop(stdin);
+            result
+        };
+        let (r0, r1) = join!(to_bot, copy(&mut stdout, &mut output));
+        if let Err(x) = r0.and(r1) {
+            warn!("External bot stopped abruptly: {}", x);
+        }
+        drop(child.wait().await);
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Instance {
+    command: Vec<String>,
+    dir: PathBuf,
+    args: HashMap<String, String>,
+    timeout: f64,
+    seed: u64,
+}
+
+/// What the referee sends over its standard output
+enum Message {
+    Player(usize, String),
+    Spectators(String),
+    Result(usize, PlayerResult, Option<f64>),
+    Details(String),
+    End(Termination),
+}
+
+/// Parse a line written by the referee of a match between `names`
+fn parse(line: &str, names: &[String]) -> Result<Message, String> {
+    let seat = |x: &str| match x.parse::<usize>() {
+        Ok(i) if i < names.len() => Ok(i),
+        _ => Err(format!("Invalid seat \"{}\"", x)),
+    };
+    let culprits = |rest: std::str::Split<char>| -> Result<Vec<String>, String> {
+        rest.map(|x| seat(x).map(|i| names[i].clone())).collect()
+    };
+    let (head, rest) = line.split_once(' ').unwrap_or((line, ""));
+    Ok(match head {
+        "*" => Message::Spectators(rest.to_owned()),
+        "RESULT" => {
+            let mut fields = rest.split(' ');
+            let i = seat(fields.next().unwrap_or_default())?;
+            let result = match fields.next() {
+                Some("WIN") => PlayerResult::Win,
+                Some("LOSS") => PlayerResult::Loss,
+                Some("DRAW") => PlayerResult::Draw,
+                _ => return Err(format!("Invalid result in \"{}\"", line)),
+            };
+            let score = match fields.next().map(|x| x.parse::<f64>()) {
+                Some(Ok(x)) => Some(x),
+                Some(Err(_)) => return Err(format!("Invalid score in \"{}\"", line)),
+                None => None,
+            };
+            Message::Result(i, result, score)
+        }
+        "DETAILS" => Message::Details(rest.to_owned()),
+        "END" => {
+            let mut fields = rest.split(' ');
+            Message::End(match fields.next() {
+                Some("NORMAL") => Termination::Normal,
+                Some("TIMEOUT") => Termination::Timeout(culprits(fields)?),
+                Some("ILLEGAL") => Termination::IllegalMove(culprits(fields)?),
+                Some("DISCONNECT") => Termination::Disconnect(culprits(fields)?),
+                _ => return Err(format!("Invalid termination in \"{}\"", line)),
+            })
+        }
+        x => Message::Player(seat(x)?, rest.to_owned()),
+    })
+}
+
+/// Send a line to the referee
+async fn tell(referee: &mut ChildStdin, line: String) -> Result<(), String> {
+    referee
+        .write_all((line + "\n").as_bytes())
+        .await
+        .map_err(|x| format!("Cannot write to the referee: {}", x))
+}
+
+impl Instance {
+    /// Relay lines between players and the referee until it ends the match
+    async fn referee(
+        &mut self,
+        child: &mut Child,
+        players: Vec<Player>,
+        spectators: &mut WriteHalf<DuplexStream>,
+    ) -> Result<MatchOutcome, String> {
+        let mut stdin = child.stdin.take().expect("Cannot fail");
+        let mut stdout =
+            LineReader::new(child.stdout.take().expect("Cannot fail"), MAX_LINE_LENGTH);
+        let names: Vec<_> = players.iter().map(|x| x.name.clone()).collect();
+
+        tell(&mut stdin, format!("SEED {}", self.seed)).await?;
+        tell(&mut stdin, format!("TIMEOUT {}", self.timeout)).await?;
+        let args: BTreeMap<_, _> = self.args.iter().collect();
+        for (name, value) in args {
+            tell(&mut stdin, format!("ARG {} {}", name, value)).await?;
+        }
+        for (i, name) in names.iter().enumerate() {
+            tell(&mut stdin, format!("PLAYER {} {}", i, name)).await?;
+        }
+        tell(&mut stdin, "START".to_string()).await?;
+
+        // Every player is read by its own task, so that none can hold up the others
+        let (tx, mut rx) = mpsc::channel(names.len());
+        let mut outputs = Vec::new();
+        let mut readers = Vec::new();
+        for (i, player) in players.into_iter().enumerate() {
+            let tx = tx.clone();
+            let mut input = player.input;
+            outputs.push(player.output);
+            readers.push(spawn(async move {
+                loop {
+                    let line = input.next().await;
+                    let end = line.is_err();
+                    if tx.send((i, line)).await.is_err() || end {
+                        break;
+                    }
+                }
+            }));
+        }
+
+        let mut results = vec![None; names.len()];
+        let mut details = None;
+        let silence = Duration::from_secs_f64(self.timeout + SILENCE_GRACE_PERIOD);
+        let mut deadline = Instant::now() + silence;
+        let result = loop {
+            select! {
+                line = stdout.next() => {
+                    deadline = Instant::now() + silence;
+                    let line = match line {
+                        Ok(x) => x,
+                        Err(LineError::Closed) => break Err("The referee exited before the end of the match".to_string()),
+                        Err(LineError::Violation(x)) => break Err(format!("Cannot read from the referee: {}", x)),
+                    };
+                    match parse(&line, &names) {
+                        Ok(Message::Player(i, text)) => {
+                            if let Err(x) = outputs[i].write_all((text + "\n").as_bytes()).await {
+                                warn!("Cannot write to \"{}\": {}", names[i], x);
+                            }
+                        }
+                        Ok(Message::Spectators(text)) => {
+                            if let Err(x) = spectators.write_all((text + "\n").as_bytes()).await {
+                                warn!("Cannot write to spectators: {}", x);
+                            }
+                        }
+                        Ok(Message::Result(i, result, score)) => results[i] = Some((result, score)),
+                        Ok(Message::Details(text)) => details = Some(text),
+                        Ok(Message::End(termination)) => break Ok(termination),
+                        Err(x) => break Err(format!("Invalid line from the referee: {}", x)),
+                    }
+                }
+                Some((i, line)) = rx.recv() => {
+                    let line = match line {
+                        Ok(x) => format!("{} {}", i, x.trim_end()),
+                        Err(LineError::Closed) => format!("CLOSED {}", i),
+                        Err(LineError::Violation(x)) => {
+                            // The server ends the match, as the referee could not tell
+                            warn!("Protocol violation of \"{}\": {}", names[i], x);
+                            for (j, result) in results.iter_mut().enumerate() {
+                                *result = match i == j {
+                                    true => Some((PlayerResult::Loss, None)),
+                                    false => Some((PlayerResult::Win, None)),
+                                };
+                            }
+                            details = Some(x);
+                            break Ok(Termination::ProtocolViolation(vec![names[i].clone()]));
+                        }
+                    };
+                    if let Err(x) = tell(&mut stdin, line).await {
+                        break Err(x);
+                    }
+                }
+                _ = sleep_until(deadline) => {
+                    break Err(format!("The referee was silent for more than {:?}", silence));
+                }
+            }
+        };
+        for reader in readers {
+            reader.abort();
+        }
+        let termination = result?;
+        let mut players = BTreeMap::new();
+        for (name, result) in names.into_iter().zip(results) {
+            match result {
+                Some((result, score)) => drop(players.insert(
+                    name,
+                    game::PlayerOutcome {
+                        result,
+                        score,
+                        time_left: None,
+                    },
+                )),
+                None => return Err(format!("The referee gave no result to \"{}\"", name)),
+            }
+        }
+        Ok(MatchOutcome {
+            players,
+            termination,
+            details,
+        })
+    }
+}
+
+#[async_trait]
+impl game::Instance for Instance {
+    async fn start(
+        &mut self,
+        players: Vec<(String, DuplexStream)>,
+        mut spectators: WriteHalf<DuplexStream>,
+    ) -> MatchOutcome {
+        let names: Vec<_> = players.iter().map(|(name, _)| name.clone()).collect();
+        let p = Player::from(players);
+        let result = match spawn_program(&self.command, &self.dir, self.seed) {
+            Ok(mut child) => {
+                let result = self.referee(&mut child, p, &mut spectators).await;
+                match timeout(Duration::from_secs_f64(EXIT_GRACE_PERIOD), child.wait()).await {
+                    Ok(Ok(x)) if x.success() => {}
+                    Ok(Ok(x)) => warn!("Referee exited with non-zero code: {}", x),
+                    Ok(Err(x)) => warn!("Referee exited abruptly: {}", x),
+                    Err(_) => {
+                        warn!("Referee did not exit, killing it");
+                        drop(child.kill().await);
+                    }
+                }
+                result
+            }
+            Err(x) => Err(x),
+        };
+        match result {
+            Ok(outcome) => outcome,
+            Err(x) => {
+                error!("External game failed: {}", x);
+                MatchOutcome {
+                    details: Some(x),
+                    ..game::aborted(&names)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use super::super::util::LineReader;
+use super::logic::{Board, Move, Position};
+use crate::game;
+use crate::tuning::MAX_LINE_LENGTH;
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use tokio::io::{split, AsyncWriteExt, DuplexStream};
+use tracing::error;
+
+#[derive(Debug)]
+pub(crate) struct Bot {
+    pub(crate) seed: u64,
+    /// Pick the move that looks best right now, instead of a random one
+    pub(crate) greedy: bool,
+}
+
+/// How good placing a stone on `point` looks for `me`: capturing is better
+/// than saving a group in atari, and putting a group in atari is bad
+fn score(board: &Board, after: &Position, me: usize, point: usize) -> i64 {
+    let captured = board.position().count(1 - me) - after.count(1 - me);
+    let liberties = after.group(point).1;
+    let saved = board
+        .position()
+        .neighbors(point)
+        .into_iter()
+        .filter(|&x| board.position().get(x) == Some(me) && board.position().group(x).1 == 1)
+        .count();
+    if captured > 0 {
+        2 * captured as i64 + 1
+    } else if saved > 0 && liberties > 1 {
+        1
+    } else if liberties == 1 {
+        -1
+    } else {
+        0
+    }
+}
+
+#[async_trait]
+impl game::Bot for Bot {
+    async fn start(&mut self, stream: DuplexStream) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let (input, mut output) = split(stream);
+        let mut input = LineReader::new(input, MAX_LINE_LENGTH);
+        lnin!(input); // Read first name
+        lnin!(input); // Read second name
+        let me: usize = lnin!(input).parse().expect("Cannot parse player number");
+        let size: usize = lnin!(input)
+            .split(' ')
+            .next()
+            .and_then(|x| x.parse().ok())
+            .expect("Cannot parse board size");
+        let mut board = Board::new(size);
+        let mut turn = 0;
+        while !board.finished() {
+            if turn == me {
+                // Never fill own eyes, so that the match can end
+                let mut moves: Vec<_> = (0..size * size)
+                    .filter(|&x| !board.position().eye(me, x))
+                    .filter_map(|x| board.after(me, x).ok().map(|after| (x, after)))
+                    .collect();
+                if self.greedy {
+                    let best = moves
+                        .iter()
+                        .map(|(x, after)| score(&board, after, me, *x))
+                        .max();
+                    moves.retain(|(x, after)| Some(score(&board, after, me, *x)) == best);
+                }
+                let m = match moves.choose(&mut rng) {
+                    Some((x, _)) => Move::Play(*x),
+                    None => Move::Pass,
+                };
+                board.play(me, m);
+                lnout!(output, board.name(m));
+            } else {
+                let line = lnin!(input);
+                if line == "RETIRE" {
+                    break;
+                }
+                match board.parse(&line) {
+                    Some(m) if board.valid(1 - me, m).is_ok() => board.play(1 - me, m),
+                    _ => {
+                        error!("Server sent invalid move");
+                        break;
+                    }
+                }
+            }
+            turn = 1 - turn;
+        }
+    }
+}
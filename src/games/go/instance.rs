@@ -0,0 +1,162 @@
+use super::super::util::{self, Ending, Output, Player, TurnBasedGame};
+use super::logic::{Board, Move};
+use crate::game::{self, MatchOutcome, PlayerResult, TimeControl};
+use async_trait::async_trait;
+use tokio::io::{DuplexStream, WriteHalf};
+use tokio::time::Duration;
+
+#[derive(Debug)]
+pub(crate) struct Instance {
+    pub(crate) timeout: Duration,
+    pub(crate) pace: Duration,
+    pub(crate) clock: Option<TimeControl>,
+    pub(crate) size: usize,
+    pub(crate) komi: f64,
+}
+
+/// State of a match
+struct Go {
+    board: Board,
+    komi: f64,
+    turn: usize,
+}
+
+impl TurnBasedGame for Go {
+    type Move = Move;
+
+    fn start(&mut self, names: &[String], out: &mut Output) {
+        // Send names in order
+        for name in names {
+            out.all(name);
+        }
+        // Send player index to players
+        out.player(0, "0");
+        out.player(1, "1");
+        // Send board size and komi
+        out.all(format!("{} {}", self.board.size(), self.komi));
+    }
+
+    fn outcome(&mut self, _out: &mut Output) -> Option<Ending> {
+        if !self.board.finished() {
+            return None;
+        }
+        let position = self.board.position();
+        let scores = [position.area(0) as f64, position.area(1) as f64 + self.komi];
+        let results = (0..2)
+            .map(|i| {
+                let result = if scores[i] > scores[1 - i] {
+                    PlayerResult::Win
+                } else if scores[i] < scores[1 - i] {
+                    PlayerResult::Loss
+                } else {
+                    PlayerResult::Draw
+                };
+                (result, Some(scores[i]))
+            })
+            .collect();
+        let details = if scores[0] > scores[1] {
+            format!("B+{}", scores[0] - scores[1])
+        } else if scores[0] < scores[1] {
+            format!("W+{}", scores[1] - scores[0])
+        } else {
+            "Draw".to_owned()
+        };
+        Some(Ending {
+            results,
+            details: Some(details),
+        })
+    }
+
+    fn turn(&mut self, _out: &mut Output) -> Option<usize> {
+        Some(self.turn)
+    }
+
+    fn parse(&self, player: usize, line: &str) -> Result<Move, String> {
+        match self.board.parse(line) {
+            Some(x) => self.board.valid(player, x).map(|_| x),
+            None => Err(format!("\"{}\" is not a move", line)),
+        }
+    }
+
+    fn apply(&mut self, player: usize, m: Move, out: &mut Output) {
+        self.board.play(player, m);
+        out.others(player, self.board.name(m));
+        self.turn = 1 - self.turn;
+    }
+}
+
+#[async_trait]
+impl game::Instance for Instance {
+    async fn start(
+        &mut self,
+        players: Vec<(String, DuplexStream)>,
+        spectators: WriteHalf<DuplexStream>,
+    ) -> MatchOutcome {
+        let p = Player::from(players);
+        assert_eq!(p.len(), 2);
+        let go = Go {
+            board: Board::new(self.size),
+            komi: self.komi,
+            turn: 0,
+        };
+        util::play_turns(go, p, spectators, self.timeout, self.pace, self.clock).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Play the given moves alternately from black, returning the ending
+    fn ending(size: usize, komi: f64, moves: &[&str]) -> Ending {
+        let mut game = Go {
+            board: Board::new(size),
+            komi,
+            turn: 0,
+        };
+        let mut out = Output::new(2);
+        for text in moves {
+            assert!(game.outcome(&mut out).is_none());
+            let player = game.turn(&mut out).unwrap();
+            let m = game.parse(player, text).unwrap();
+            game.apply(player, m, &mut out);
+        }
+        game.outcome(&mut out).unwrap()
+    }
+
+    const WALLS: [&str; 12] = [
+        "B1", "D1", "B2", "D2", "B3", "D3", "B4", "D4", "B5", "D5", "pass", "pass",
+    ];
+
+    #[test]
+    fn komi() {
+        let e = ending(5, 6.5, &WALLS);
+        assert_eq!(e.details.as_deref(), Some("W+6.5"));
+        assert_eq!(e.results[0], (PlayerResult::Loss, Some(10.0)));
+        assert_eq!(e.results[1], (PlayerResult::Win, Some(16.5)));
+    }
+
+    #[test]
+    fn scoring() {
+        let mut moves = WALLS[..10].to_vec();
+        moves.extend(["C3", "pass", "pass"].iter());
+        let e = ending(5, 0.5, &moves);
+        // The stone on C3 counts, the empty points next to it are still neutral
+        assert_eq!(e.details.as_deref(), Some("B+0.5"));
+        assert_eq!(e.results[0].1, Some(11.0));
+        let e = ending(5, 0.0, &WALLS);
+        assert_eq!(e.details.as_deref(), Some("Draw"));
+        assert!(e.results.iter().all(|x| x.0 == PlayerResult::Draw));
+    }
+
+    #[test]
+    fn illegal_moves() {
+        let game = Go {
+            board: Board::new(5),
+            komi: 0.0,
+            turn: 0,
+        };
+        assert_eq!(game.parse(0, "Z1").unwrap_err(), "\"Z1\" is not a move");
+        assert!(game.parse(0, "c3").is_ok());
+    }
+}
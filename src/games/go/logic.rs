@@ -0,0 +1,360 @@
+use std::collections::HashSet;
+
+/// Column letters, skipping `I` like most Go software does
+const LETTERS: &str = "ABCDEFGHJKLMNOPQRST";
+
+/// The stones on a square board, with rows numbered from the top and columns
+/// from the left. Player 0 plays black and player 1 plays white.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Position {
+    size: usize,
+    cells: Vec<Option<usize>>,
+}
+
+impl Position {
+    pub(crate) fn get(&self, point: usize) -> Option<usize> {
+        self.cells[point]
+    }
+
+    pub(crate) fn neighbors(&self, point: usize) -> Vec<usize> {
+        let (row, col) = (point / self.size, point % self.size);
+        let mut neighbors = Vec::with_capacity(4);
+        if row > 0 {
+            neighbors.push(point - self.size);
+        }
+        if row + 1 < self.size {
+            neighbors.push(point + self.size);
+        }
+        if col > 0 {
+            neighbors.push(point - 1);
+        }
+        if col + 1 < self.size {
+            neighbors.push(point + 1);
+        }
+        neighbors
+    }
+
+    /// Points connected to `point` having the same content, and the points
+    /// of other content they touch
+    fn region(&self, point: usize) -> (Vec<usize>, Vec<usize>) {
+        let mut seen = vec![false; self.cells.len()];
+        let mut region = vec![point];
+        let mut border = Vec::new();
+        seen[point] = true;
+        let mut i = 0;
+        while i < region.len() {
+            for x in self.neighbors(region[i]) {
+                if seen[x] {
+                    continue;
+                }
+                seen[x] = true;
+                if self.cells[x] == self.cells[point] {
+                    region.push(x);
+                } else {
+                    border.push(x);
+                }
+            }
+            i += 1;
+        }
+        (region, border)
+    }
+
+    /// Stones of the group including `point`, and how many liberties it has
+    pub(crate) fn group(&self, point: usize) -> (Vec<usize>, usize) {
+        let (stones, border) = self.region(point);
+        let liberties = border.iter().filter(|&&x| self.cells[x].is_none()).count();
+        (stones, liberties)
+    }
+
+    /// Number of stones of `player`
+    pub(crate) fn count(&self, player: usize) -> usize {
+        self.cells.iter().filter(|&&x| x == Some(player)).count()
+    }
+
+    /// Whether `point` is empty and surrounded only by stones of `player`
+    pub(crate) fn eye(&self, player: usize, point: usize) -> bool {
+        self.cells[point].is_none()
+            && self
+                .neighbors(point)
+                .iter()
+                .all(|&x| self.cells[x] == Some(player))
+    }
+
+    /// Area of `player`: its stones plus the empty points that only reach
+    /// its stones. Every stone on the board counts as alive.
+    pub(crate) fn area(&self, player: usize) -> usize {
+        let mut seen = vec![false; self.cells.len()];
+        let mut area = self.count(player);
+        for point in 0..self.cells.len() {
+            if seen[point] || self.cells[point].is_some() {
+                continue;
+            }
+            let (region, border) = self.region(point);
+            for &x in region.iter() {
+                seen[x] = true;
+            }
+            if border.iter().all(|&x| self.cells[x] == Some(player)) && !border.is_empty() {
+                area += region.len();
+            }
+        }
+        area
+    }
+}
+
+/// What a player can do in its turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Move {
+    Pass,
+    Play(usize),
+}
+
+/// A match in progress: the current position, every position seen so far
+/// and how many passes in a row were made
+#[derive(Debug, Clone)]
+pub(crate) struct Board {
+    position: Position,
+    history: HashSet<Position>,
+    passes: usize,
+}
+
+impl Board {
+    pub(crate) fn new(size: usize) -> Board {
+        let position = Position {
+            size,
+            cells: vec![None; size * size],
+        };
+        Board {
+            history: HashSet::from([position.clone()]),
+            position,
+            passes: 0,
+        }
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.position.size
+    }
+
+    pub(crate) fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// Parse a move: `pass`, or a column letter and a row number counted
+    /// from the bottom, like `D4`, ignoring case
+    pub(crate) fn parse(&self, text: &str) -> Option<Move> {
+        if text.eq_ignore_ascii_case("pass") {
+            return Some(Move::Pass);
+        }
+        let size = self.size();
+        let mut chars = text.chars();
+        let letter = chars.next()?.to_ascii_uppercase();
+        let col = LETTERS.find(letter).filter(|&x| x < size)?;
+        let row: usize = chars.as_str().parse().ok()?;
+        if row < 1 || row > size {
+            return None;
+        }
+        Some(Move::Play((size - row) * size + col))
+    }
+
+    /// Name of a move, in the format accepted by `parse`
+    pub(crate) fn name(&self, m: Move) -> String {
+        let size = self.size();
+        match m {
+            Move::Pass => "PASS".to_owned(),
+            Move::Play(x) => {
+                let letter = LETTERS.as_bytes()[x % size] as char;
+                format!("{}{}", letter, size - x / size)
+            }
+        }
+    }
+
+    /// The position after `player` places a stone on `point` and captures,
+    /// or why it cannot: the point is taken, the stone would have no
+    /// liberties, or the position would repeat an earlier one
+    pub(crate) fn after(&self, player: usize, point: usize) -> Result<Position, String> {
+        let name = self.name(Move::Play(point));
+        if self.position.cells[point].is_some() {
+            return Err(format!("{} is not empty", name));
+        }
+        let mut next = self.position.clone();
+        next.cells[point] = Some(player);
+        for x in next.neighbors(point) {
+            if next.cells[x] == Some(1 - player) {
+                let (stones, liberties) = next.group(x);
+                if liberties == 0 {
+                    for s in stones {
+                        next.cells[s] = None;
+                    }
+                }
+            }
+        }
+        if next.group(point).1 == 0 {
+            return Err(format!("{} is suicide", name));
+        }
+        if self.history.contains(&next) {
+            return Err(format!("{} repeats an earlier position", name));
+        }
+        Ok(next)
+    }
+
+    /// Whether `player` can make move `m`
+    pub(crate) fn valid(&self, player: usize, m: Move) -> Result<(), String> {
+        match m {
+            Move::Pass => Ok(()),
+            Move::Play(x) => self.after(player, x).map(|_| ()),
+        }
+    }
+
+    /// Make move `m` for `player`, which must be valid
+    pub(crate) fn play(&mut self, player: usize, m: Move) {
+        match m {
+            Move::Pass => self.passes += 1,
+            Move::Play(x) => {
+                self.position = self.after(player, x).expect("Invalid move");
+                self.history.insert(self.position.clone());
+                self.passes = 0;
+            }
+        }
+    }
+
+    /// Whether both players passed in a row
+    pub(crate) fn finished(&self) -> bool {
+        self.passes >= 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Play the given moves alternately, starting with black
+    fn play(board: &mut Board, first: usize, moves: &[&str]) {
+        for (i, text) in moves.iter().enumerate() {
+            let player = (first + i) % 2;
+            let m = board.parse(text).unwrap();
+            board.valid(player, m).unwrap();
+            board.play(player, m);
+        }
+    }
+
+    fn point(board: &Board, text: &str) -> usize {
+        match board.parse(text) {
+            Some(Move::Play(x)) => x,
+            x => panic!("{} is {:?}", text, x),
+        }
+    }
+
+    #[test]
+    fn coordinates() {
+        let b = Board::new(9);
+        assert_eq!(b.parse("A9"), Some(Move::Play(0)));
+        assert_eq!(b.parse("j1"), Some(Move::Play(80)));
+        assert_eq!(b.parse("H1"), Some(Move::Play(79)));
+        assert_eq!(b.parse("Pass"), Some(Move::Pass));
+        for text in ["I5", "K5", "A0", "A10", "", "5A"] {
+            assert_eq!(b.parse(text), None, "{}", text);
+        }
+        for x in 0..81 {
+            assert_eq!(b.parse(&b.name(Move::Play(x))), Some(Move::Play(x)));
+        }
+        assert_eq!(b.name(Move::Pass), "PASS");
+        assert_eq!(Board::new(19).parse("T19"), Some(Move::Play(18)));
+    }
+
+    #[test]
+    fn capture() {
+        let mut b = Board::new(5);
+        play(&mut b, 0, &["A2", "A1", "B2"]);
+        assert_eq!(b.position().get(point(&b, "A1")), Some(1));
+        play(&mut b, 1, &["E5", "B1"]);
+        assert_eq!(b.position().get(point(&b, "A1")), None);
+        assert_eq!(b.position().count(1), 1);
+        let (stones, liberties) = b.position().group(point(&b, "A2"));
+        assert_eq!((stones.len(), liberties), (3, 5));
+    }
+
+    #[test]
+    fn suicide() {
+        let mut b = Board::new(5);
+        play(&mut b, 0, &["A2", "E5", "B1"]);
+        let err = b.valid(1, Move::Play(point(&b, "A1"))).unwrap_err();
+        assert_eq!(err, "A1 is suicide");
+        // Unless it captures
+        let mut b = Board::new(5);
+        play(&mut b, 0, &["A2", "B2", "B1", "C1", "E5", "A3"]);
+        assert!(b.valid(1, Move::Play(point(&b, "A1"))).is_ok());
+        assert_eq!(
+            b.valid(1, Move::Play(point(&b, "B1"))).unwrap_err(),
+            "B1 is not empty"
+        );
+    }
+
+    #[test]
+    fn ko() {
+        let mut b = Board::new(5);
+        play(
+            &mut b,
+            0,
+            &["B4", "C4", "A3", "D3", "B2", "C2", "E1", "B3", "C3"],
+        );
+        // Black captured in the ko, white cannot take back right away
+        assert_eq!(b.position().get(point(&b, "B3")), None);
+        let retake = Move::Play(point(&b, "B3"));
+        let err = b.valid(1, retake).unwrap_err();
+        assert_eq!(err, "B3 repeats an earlier position");
+        // Passing does not change the position, so it does not help
+        play(&mut b, 1, &["pass"]);
+        assert!(b.valid(1, retake).is_err());
+        // A move elsewhere does
+        play(&mut b, 0, &["E5", "pass"]);
+        assert!(b.valid(1, retake).is_ok());
+    }
+
+    #[test]
+    fn superko() {
+        // Two kos, where each player took one of them in turn
+        let mut b = Board::new(9);
+        let setup = [
+            "B9", "C9", "A8", "B8", "B7", "D8", "C3", "C7", "D2", "B3", "C1", "A2", "B2", "B1",
+        ];
+        play(&mut b, 0, &setup);
+        play(&mut b, 0, &["C8", "C2", "pass", "B8"]);
+        // Taking back the other ko would not repeat the last position, but
+        // one from four moves before
+        let err = b.valid(0, Move::Play(point(&b, "B2"))).unwrap_err();
+        assert_eq!(err, "B2 repeats an earlier position");
+    }
+
+    #[test]
+    fn end() {
+        let mut b = Board::new(5);
+        play(&mut b, 0, &["pass", "C3", "pass"]);
+        assert!(!b.finished());
+        play(&mut b, 1, &["pass"]);
+        assert!(b.finished());
+    }
+
+    #[test]
+    fn eye() {
+        let mut b = Board::new(5);
+        play(&mut b, 0, &["A2", "E5", "B1"]);
+        assert!(b.position().eye(0, point(&b, "A1")));
+        assert!(!b.position().eye(1, point(&b, "A1")));
+        assert!(!b.position().eye(0, point(&b, "B2")));
+    }
+
+    #[test]
+    fn area() {
+        let mut b = Board::new(5);
+        assert_eq!((b.position().area(0), b.position().area(1)), (0, 0));
+        play(
+            &mut b,
+            0,
+            &["B1", "D1", "B2", "D2", "B3", "D3", "B4", "D4", "B5", "D5"],
+        );
+        // Column C touches both players and counts for neither
+        assert_eq!((b.position().area(0), b.position().area(1)), (10, 10));
+        // Every stone counts as alive, even when it would be captured
+        play(&mut b, 0, &["E3"]);
+        assert_eq!((b.position().area(0), b.position().area(1)), (11, 5));
+    }
+}
@@ -0,0 +1,97 @@
+use super::problem::{self, Problem};
+use super::util::{arg, Ending, Output, TurnBasedGame};
+use crate::game::Builder;
+use crate::proto::{GameArgInfo, GameArgKind};
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::HashMap;
+
+const DEFAULT_MAX: u64 = 1000;
+
+/// State of a match: the number to guess and the queries asked so far
+#[derive(Debug, Clone)]
+struct Guess {
+    max: u64,
+    secret: u64,
+    queries: usize,
+    solved: bool,
+}
+
+impl Guess {
+    /// Queries needed by a binary search in the worst case
+    fn target(&self) -> usize {
+        (64 - self.max.leading_zeros()) as usize
+    }
+}
+
+impl TurnBasedGame for Guess {
+    type Move = u64;
+
+    fn start(&mut self, names: &[String], out: &mut Output) {
+        out.all(&names[0]);
+        out.all(format!("{} {}", self.max, problem::limit(self.target())));
+    }
+
+    fn outcome(&mut self, _out: &mut Output) -> Option<Ending> {
+        problem::ending(self.solved, self.queries, self.target(), self.secret)
+    }
+
+    fn turn(&mut self, _out: &mut Output) -> Option<usize> {
+        Some(0)
+    }
+
+    fn parse(&self, _player: usize, line: &str) -> Result<u64, String> {
+        match line.parse() {
+            Ok(x) if 1 <= x && x <= self.max => Ok(x),
+            _ => Err(format!("Invalid guess \"{}\"", line)),
+        }
+    }
+
+    fn apply(&mut self, _player: usize, guess: u64, out: &mut Output) {
+        self.queries += 1;
+        out.spectators(format!("{}", guess));
+        let answer = if guess < self.secret {
+            "HIGHER"
+        } else if guess > self.secret {
+            "LOWER"
+        } else {
+            self.solved = true;
+            "CORRECT"
+        };
+        out.all(answer);
+    }
+}
+
+impl Problem for Guess {
+    const NAME: &'static str = "guess";
+    const DESCRIPTION: &'static str = include_str!("description.md");
+
+    fn args() -> HashMap<String, GameArgInfo> {
+        HashMap::from([(
+            "max".to_owned(),
+            GameArgInfo {
+                description: "Largest number that can be the secret".to_owned(),
+                kind: GameArgKind::Integer {
+                    min: 1,
+                    max: 1_000_000_000,
+                },
+                default: DEFAULT_MAX.to_string(),
+                unit: None,
+            },
+        )])
+    }
+
+    fn new(args: &HashMap<String, String>, rng: &mut StdRng) -> Result<Guess, String> {
+        let max = arg(args, "max");
+        Ok(Guess {
+            max,
+            secret: rng.gen_range(1..=max),
+            queries: 0,
+            solved: false,
+        })
+    }
+}
+
+pub(crate) fn get() -> Box<dyn Builder> {
+    problem::builder::<Guess>()
+}
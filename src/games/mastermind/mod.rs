@@ -0,0 +1,138 @@
+use super::problem::{self, Problem};
+use super::util::{arg, Ending, Output, TurnBasedGame};
+use crate::game::Builder;
+use crate::proto::{GameArgInfo, GameArgKind};
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::HashMap;
+
+const DEFAULT_COLORS: usize = 6;
+const DEFAULT_PEGS: usize = 4;
+
+/// State of a match: the secret code and the queries asked so far
+#[derive(Debug, Clone)]
+struct Mastermind {
+    colors: usize,
+    secret: Vec<usize>,
+    queries: usize,
+    solved: bool,
+}
+
+impl Mastermind {
+    /// Queries to aim for, one more than the pegs like the five guesses
+    /// needed by Knuth's strategy with the classic six colors and four pegs
+    fn target(&self) -> usize {
+        self.secret.len() + 1
+    }
+
+    /// Pegs of the right color in the right place, and of the right color
+    /// in the wrong place
+    fn answer(&self, guess: &[usize]) -> (usize, usize) {
+        let exact = guess
+            .iter()
+            .zip(self.secret.iter())
+            .filter(|(x, y)| x == y)
+            .count();
+        let common: usize = (0..self.colors)
+            .map(|c| {
+                let a = guess.iter().filter(|&&x| x == c).count();
+                let b = self.secret.iter().filter(|&&x| x == c).count();
+                a.min(b)
+            })
+            .sum();
+        (exact, common - exact)
+    }
+}
+
+impl TurnBasedGame for Mastermind {
+    type Move = Vec<usize>;
+
+    fn start(&mut self, names: &[String], out: &mut Output) {
+        out.all(&names[0]);
+        out.all(format!(
+            "{} {} {}",
+            self.colors,
+            self.secret.len(),
+            problem::limit(self.target())
+        ));
+    }
+
+    fn outcome(&mut self, _out: &mut Output) -> Option<Ending> {
+        let secret: String = self.secret.iter().map(|x| x.to_string()).collect();
+        problem::ending(self.solved, self.queries, self.target(), secret)
+    }
+
+    fn turn(&mut self, _out: &mut Output) -> Option<usize> {
+        Some(0)
+    }
+
+    fn parse(&self, _player: usize, line: &str) -> Result<Vec<usize>, String> {
+        let guess: Vec<usize> = line
+            .chars()
+            .map(|x| match x.to_digit(10) {
+                Some(d) if (d as usize) < self.colors => Ok(d as usize),
+                _ => Err(format!("Invalid color \"{}\"", x)),
+            })
+            .collect::<Result<_, _>>()?;
+        if guess.len() != self.secret.len() {
+            return Err(format!(
+                "Wrong number of pegs: {} instead of {}",
+                guess.len(),
+                self.secret.len()
+            ));
+        }
+        Ok(guess)
+    }
+
+    fn apply(&mut self, _player: usize, guess: Vec<usize>, out: &mut Output) {
+        self.queries += 1;
+        let (exact, misplaced) = self.answer(&guess);
+        self.solved = exact == self.secret.len();
+        let guess: String = guess.iter().map(|x| x.to_string()).collect();
+        out.spectators(guess);
+        out.all(format!("{} {}", exact, misplaced));
+    }
+}
+
+impl Problem for Mastermind {
+    const NAME: &'static str = "mastermind";
+    const DESCRIPTION: &'static str = include_str!("description.md");
+
+    fn args() -> HashMap<String, GameArgInfo> {
+        HashMap::from([
+            (
+                "colors".to_owned(),
+                GameArgInfo {
+                    description: "How many colors the pegs can have".to_owned(),
+                    kind: GameArgKind::Integer { min: 2, max: 10 },
+                    default: DEFAULT_COLORS.to_string(),
+                    unit: None,
+                },
+            ),
+            (
+                "pegs".to_owned(),
+                GameArgInfo {
+                    description: "How many pegs the secret code has".to_owned(),
+                    kind: GameArgKind::Integer { min: 1, max: 10 },
+                    default: DEFAULT_PEGS.to_string(),
+                    unit: None,
+                },
+            ),
+        ])
+    }
+
+    fn new(args: &HashMap<String, String>, rng: &mut StdRng) -> Result<Mastermind, String> {
+        let colors = arg(args, "colors");
+        let pegs = arg(args, "pegs");
+        Ok(Mastermind {
+            colors,
+            secret: (0..pegs).map(|_| rng.gen_range(0..colors)).collect(),
+            queries: 0,
+            solved: false,
+        })
+    }
+}
+
+pub(crate) fn get() -> Box<dyn Builder> {
+    problem::builder::<Mastermind>()
+}
@@ -1,13 +1,61 @@
 #[macro_use]
 mod util;
 
+mod battleship;
 mod chess;
+mod connect4;
 mod dama;
+pub(crate) mod external;
+mod go;
+mod guess;
+mod mastermind;
+mod permutation;
+mod poker;
+mod problem;
+mod reversi;
 mod roshambo;
 mod royalur;
 
 use crate::game::Builder;
 
 pub(crate) fn get() -> Vec<Box<dyn Builder>> {
-    vec![roshambo::get(), royalur::get(), chess::get(), dama::get()]
+    vec![
+        roshambo::get(),
+        royalur::get(),
+        chess::get(),
+        connect4::get(),
+        reversi::get(),
+        go::get(),
+        battleship::get(),
+        poker::get(),
+        dama::get(),
+        guess::get(),
+        mastermind::get(),
+        permutation::get(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{self, Params, SeatPolicy};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn defaults() {
+        for builder in get() {
+            let args = game::check_args(&builder.args().await, HashMap::new()).unwrap();
+            let mut params = Params {
+                players: None,
+                bots: Vec::new(),
+                timeout: None,
+                seed: None,
+                clock: None,
+                seating: SeatPolicy::Random,
+                series: None,
+            };
+            let created = builder.gen_instance(&mut params, args).await;
+            assert!(created.is_ok(), "{}", builder.name());
+        }
+    }
 }
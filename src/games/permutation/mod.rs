@@ -0,0 +1,114 @@
+use super::problem::{self, Problem};
+use super::util::{arg, Ending, Output, TurnBasedGame};
+use crate::game::Builder;
+use crate::proto::{GameArgInfo, GameArgKind};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+
+const DEFAULT_SIZE: usize = 8;
+
+/// State of a match: the hidden permutation and the queries asked so far
+#[derive(Debug, Clone)]
+struct Permutation {
+    secret: Vec<usize>,
+    queries: usize,
+    solved: bool,
+}
+
+impl Permutation {
+    /// Queries to aim for, enough to place every number with a binary search
+    fn target(&self) -> usize {
+        let n = self.secret.len();
+        (n * (usize::BITS - (n - 1).leading_zeros()) as usize).max(1)
+    }
+}
+
+fn join(numbers: &[usize]) -> String {
+    let numbers: Vec<_> = numbers.iter().map(|x| x.to_string()).collect();
+    numbers.join(" ")
+}
+
+impl TurnBasedGame for Permutation {
+    type Move = Vec<usize>;
+
+    fn start(&mut self, names: &[String], out: &mut Output) {
+        out.all(&names[0]);
+        out.all(format!(
+            "{} {}",
+            self.secret.len(),
+            problem::limit(self.target())
+        ));
+    }
+
+    fn outcome(&mut self, _out: &mut Output) -> Option<Ending> {
+        problem::ending(self.solved, self.queries, self.target(), join(&self.secret))
+    }
+
+    fn turn(&mut self, _out: &mut Output) -> Option<usize> {
+        Some(0)
+    }
+
+    fn parse(&self, _player: usize, line: &str) -> Result<Vec<usize>, String> {
+        let n = self.secret.len();
+        let query: Vec<usize> = line
+            .split_whitespace()
+            .map(|x| match x.parse() {
+                Ok(x) if 1 <= x && x <= n => Ok(x),
+                _ => Err(format!("Invalid number \"{}\"", x)),
+            })
+            .collect::<Result<_, _>>()?;
+        if query.len() != n {
+            return Err(format!(
+                "Wrong length of the query: {} instead of {}",
+                query.len(),
+                n
+            ));
+        }
+        Ok(query)
+    }
+
+    fn apply(&mut self, _player: usize, query: Vec<usize>, out: &mut Output) {
+        self.queries += 1;
+        let matching = query
+            .iter()
+            .zip(self.secret.iter())
+            .filter(|(x, y)| x == y)
+            .count();
+        self.solved = matching == self.secret.len();
+        out.spectators(join(&query));
+        out.all(format!("{}", matching));
+    }
+}
+
+impl Problem for Permutation {
+    const NAME: &'static str = "permutation";
+    const DESCRIPTION: &'static str = include_str!("description.md");
+
+    fn args() -> HashMap<String, GameArgInfo> {
+        HashMap::from([(
+            "size".to_owned(),
+            GameArgInfo {
+                description: "How many numbers the permutation has".to_owned(),
+                kind: GameArgKind::Integer { min: 1, max: 1000 },
+                default: DEFAULT_SIZE.to_string(),
+                unit: None,
+            },
+        )])
+    }
+
+    fn new(args: &HashMap<String, String>, rng: &mut StdRng) -> Result<Permutation, String> {
+        let size = arg(args, "size");
+        let mut secret: Vec<usize> = (1..=size).collect();
+        secret.shuffle(rng);
+        Ok(Permutation {
+            secret,
+            queries: 0,
+            solved: false,
+        })
+    }
+}
+
+pub(crate) fn get() -> Box<dyn Builder> {
+    problem::builder::<Permutation>()
+}
@@ -0,0 +1,101 @@
+use super::super::util::LineReader;
+use super::logic::{self, Card};
+use crate::game;
+use crate::tuning::MAX_LINE_LENGTH;
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::io::{split, AsyncWriteExt, DuplexStream};
+use tracing::error;
+
+#[derive(Debug)]
+pub(crate) struct Bot {
+    pub(crate) seed: u64,
+    /// Act on the strength of the cards, instead of at random
+    pub(crate) tight: bool,
+}
+
+/// How much to bet with `hole` and `board`: 0 to give up when asked to pay,
+/// 1 to call, 2 to raise
+fn strength(hole: &[Card], board: &[Card]) -> usize {
+    if board.is_empty() {
+        let (high, low) = match hole[0].rank >= hole[1].rank {
+            true => (hole[0].rank, hole[1].rank),
+            false => (hole[1].rank, hole[0].rank),
+        };
+        let suited = hole[0].suit == hole[1].suit;
+        if high == low || low >= 11 {
+            2
+        } else if high == 14 || low >= 9 || (suited && high - low == 1) {
+            1
+        } else {
+            0
+        }
+    } else {
+        let cards: Vec<_> = hole.iter().chain(board.iter()).copied().collect();
+        match logic::best(&cards)[0] {
+            logic::HIGH_CARD => 0,
+            logic::PAIR => 1,
+            _ => 2,
+        }
+    }
+}
+
+fn cards(parts: &[&str]) -> Vec<Card> {
+    parts
+        .iter()
+        .map(|x| x.parse().expect("Server sent garbage card"))
+        .collect()
+}
+
+#[async_trait]
+impl game::Bot for Bot {
+    async fn start(&mut self, stream: DuplexStream) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let (input, mut output) = split(stream);
+        let mut input = LineReader::new(input, MAX_LINE_LENGTH);
+        let players: usize = lnin!(input)
+            .parse()
+            .expect("Cannot parse number of players");
+        for _ in 0..players {
+            lnin!(input); // Read names
+        }
+        let me = lnin!(input);
+        lnin!(input); // Read chips, blinds and hands
+        let mut hole = Vec::new();
+        let mut board = Vec::new();
+        loop {
+            let line = lnin!(input);
+            let parts: Vec<_> = line.split(' ').collect();
+            match parts[..] {
+                ["RETIRE", seat] if seat == me => break,
+                ["HAND", ..] => board.clear(),
+                ["CARDS", seat, ..] if seat == me => hole = cards(&parts[2..]),
+                ["FLOP", ..] | ["TURN", ..] | ["RIVER", ..] => board.extend(cards(&parts[1..])),
+                ["ACT", seat, call, min, max] if seat == me => {
+                    let parse = |x: &str| x.parse::<u64>().expect("Cannot parse amount");
+                    let (call, min, max) = (parse(call), parse(min), parse(max));
+                    let choice = match self.tight {
+                        true => strength(&hole, &board),
+                        false => match rng.gen_range(0..10) {
+                            0 | 1 => 0,
+                            9 => 2,
+                            _ => 1,
+                        },
+                    };
+                    let action = match choice {
+                        2 if min > 0 => format!("RAISE {}", rng.gen_range(min..=max.min(2 * min))),
+                        0 if call > 0 => "FOLD".to_owned(),
+                        _ => "CALL".to_owned(),
+                    };
+                    lnout!(output, action);
+                }
+                [] => {
+                    error!("Server sent empty line");
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use super::bot::Bot;
+use super::instance::Instance;
+use crate::game;
+use crate::games;
+use crate::proto::{BotInfo, GameArgInfo, GameArgKind};
+use async_trait::async_trait;
+use games::util::arg;
+use rand::rngs::StdRng;
+use rand::{random, SeedableRng};
+use std::collections::HashMap;
+use tokio::time::Duration;
+
+const DEFAULT_TIMEOUT: f64 = 30.0;
+const DEFAULT_PACE: f64 = 0.5;
+const DEFAULT_CHIPS: u64 = 1000;
+const DEFAULT_BLIND: u64 = 10;
+const DEFAULT_HANDS: usize = 100;
+const DEFAULT_DELAY: bool = true;
+const MAX_PLAYERS: usize = 9;
+
+#[derive(Debug)]
+pub(crate) struct Builder {}
+
+impl Builder {
+    pub(crate) fn new() -> Box<dyn game::Builder> {
+        Box::new(Builder {})
+    }
+}
+
+#[async_trait]
+impl game::Builder for Builder {
+    fn name(&self) -> &str {
+        "poker"
+    }
+    async fn description(&self) -> String {
+        String::from(include_str!("description.md"))
+    }
+
+    async fn args(&self) -> HashMap<String, GameArgInfo> {
+        HashMap::from([
+            (
+                "pace".to_owned(),
+                GameArgInfo {
+                    description: "Pause between moves, to let spectators follow".to_owned(),
+                    kind: GameArgKind::Float {
+                        min: 0.0,
+                        max: 30.0,
+                    },
+                    default: DEFAULT_PACE.to_string(),
+                    unit: Some("seconds".to_owned()),
+                },
+            ),
+            (
+                "chips".to_owned(),
+                GameArgInfo {
+                    description: "Chips of each player at the start".to_owned(),
+                    kind: GameArgKind::Integer {
+                        min: 1,
+                        max: 1_000_000_000,
+                    },
+                    default: DEFAULT_CHIPS.to_string(),
+                    unit: None,
+                },
+            ),
+            (
+                "blind".to_owned(),
+                GameArgInfo {
+                    description: "Small blind, the big blind is twice as much".to_owned(),
+                    kind: GameArgKind::Integer {
+                        min: 1,
+                        max: 1_000_000_000,
+                    },
+                    default: DEFAULT_BLIND.to_string(),
+                    unit: None,
+                },
+            ),
+            (
+                "hands".to_owned(),
+                GameArgInfo {
+                    description: "Most hands to play".to_owned(),
+                    kind: GameArgKind::Integer { min: 1, max: 10000 },
+                    default: DEFAULT_HANDS.to_string(),
+                    unit: None,
+                },
+            ),
+            (
+                "delay".to_owned(),
+                GameArgInfo {
+                    description: "Show each hand to spectators only once it is over".to_owned(),
+                    kind: GameArgKind::Bool,
+                    default: DEFAULT_DELAY.to_string(),
+                    unit: None,
+                },
+            ),
+        ])
+    }
+
+    async fn gen_instance(
+        &self,
+        param: &mut game::Params,
+        args: HashMap<String, String>,
+    ) -> Result<Box<dyn game::Instance>, String> {
+        param.players = match param.players {
+            Some(x) if (2..=MAX_PLAYERS).contains(&x) => Some(x),
+            Some(x) => return Err(format!("Cannot create game with {} players", x)),
+            None => Some(2),
+        };
+
+        param.timeout = param.timeout.or(Some(DEFAULT_TIMEOUT));
+        let pace = arg(&args, "pace");
+        let chips = arg(&args, "chips");
+        let blind = arg(&args, "blind");
+        let hands = arg(&args, "hands");
+        let delay = arg(&args, "delay");
+        let rng = StdRng::seed_from_u64(*param.seed.get_or_insert_with(random));
+        Ok(Box::new(Instance {
+            timeout: Duration::from_secs_f64(param.timeout.expect("Cannot fail")),
+            pace: Duration::from_secs_f64(pace),
+            clock: param.clock,
+            rng,
+            chips,
+            blind,
+            hands,
+            delay,
+        }))
+    }
+    async fn bots(&self) -> Vec<BotInfo> {
+        vec![
+            BotInfo {
+                name: "random".to_owned(),
+                description: "Mostly calls, sometimes folds or raises at random".to_owned(),
+            },
+            BotInfo {
+                name: "tight".to_owned(),
+                description: "Plays only good starting cards, and bets on strong hands".to_owned(),
+            },
+        ]
+    }
+    async fn seats(&self) -> Vec<String> {
+        Vec::new()
+    }
+    async fn gen_bot(&self, name: &str, seed: u64) -> Box<dyn game::Bot> {
+        Box::new(Bot {
+            seed,
+            tight: name == "tight",
+        })
+    }
+}
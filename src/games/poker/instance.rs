@@ -0,0 +1,544 @@
+use super::super::util::{self, Ending, Output, Player, TurnBasedGame};
+use super::logic::{self, Card};
+use crate::game::{self, MatchOutcome, PlayerResult, TimeControl};
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use tokio::io::{DuplexStream, WriteHalf};
+use tokio::time::Duration;
+
+#[derive(Debug)]
+pub(crate) struct Instance {
+    pub(crate) timeout: Duration,
+    pub(crate) pace: Duration,
+    pub(crate) clock: Option<TimeControl>,
+    pub(crate) rng: StdRng,
+    pub(crate) chips: u64,
+    pub(crate) blind: u64,
+    pub(crate) hands: usize,
+    pub(crate) delay: bool,
+}
+
+/// What a player can do when it has to act
+enum Action {
+    Fold,
+    Call,
+    /// Raise the bet of the round to the given total
+    Raise(u64),
+}
+
+/// State of a match
+struct Poker {
+    rng: StdRng,
+    /// The small blind, the big one is twice as much
+    blind: u64,
+    /// Most hands to play
+    hands: usize,
+    /// Whether spectators see each hand only once it is over
+    delay: bool,
+    held: Vec<String>,
+    /// Number of the current hand, from 1, and whether it is being played
+    hand: usize,
+    playing: bool,
+    dealer: usize,
+    deck: Vec<Card>,
+    board: Vec<Card>,
+    /// Chips of each player not bet yet
+    stacks: Vec<u64>,
+    /// Cards of each player, empty for those out of the hand
+    hole: Vec<Vec<Card>>,
+    folded: Vec<bool>,
+    /// Chips bet by each player in the current betting round, and in the whole hand
+    bets: Vec<u64>,
+    committed: Vec<u64>,
+    /// Whether each player acted since the last raise, and whether it can still raise
+    acted: Vec<bool>,
+    can_raise: Vec<bool>,
+    /// Highest bet of the round, and the smallest raise allowed over it
+    bet: u64,
+    min_raise: u64,
+    /// Seat from which to look for the next player to act
+    next: usize,
+}
+
+impl Poker {
+    /// Send a line to every player and the spectators
+    fn public(&mut self, out: &mut Output, line: String) {
+        for i in 0..self.stacks.len() {
+            out.player(i, &line);
+        }
+        self.spectate(out, line);
+    }
+
+    /// Send a line to the spectators, holding it back until the end of
+    /// the hand if they are delayed
+    fn spectate(&mut self, out: &mut Output, line: String) {
+        match self.delay {
+            true => self.held.push(line),
+            false => out.spectators(line),
+        }
+    }
+
+    /// Send the spectators the lines held back so far
+    fn release(&mut self, out: &mut Output) {
+        for line in self.held.drain(..) {
+            out.spectators(line);
+        }
+    }
+
+    /// First seat after `seat` of a player that still has chips
+    fn following(&self, seat: usize) -> usize {
+        let n = self.stacks.len();
+        (1..=n)
+            .map(|k| (seat + k) % n)
+            .find(|&i| self.stacks[i] > 0)
+            .expect("Cannot fail")
+    }
+
+    /// Players in the hand that did not fold
+    fn live(&self) -> Vec<usize> {
+        (0..self.stacks.len())
+            .filter(|&i| !self.hole[i].is_empty() && !self.folded[i])
+            .collect()
+    }
+
+    /// Move up to `amount` chips of `seat` to its bet, returning how many
+    fn put(&mut self, seat: usize, amount: u64) -> u64 {
+        let amount = amount.min(self.stacks[seat]);
+        self.stacks[seat] -= amount;
+        self.bets[seat] += amount;
+        self.committed[seat] += amount;
+        amount
+    }
+
+    /// Smallest and largest total `seat` can raise the bet to, or zeros
+    /// if it cannot raise
+    fn raises(&self, seat: usize) -> (u64, u64) {
+        let max = self.bets[seat] + self.stacks[seat];
+        if !self.can_raise[seat] || max <= self.bet {
+            return (0, 0);
+        }
+        ((self.bet + self.min_raise).min(max), max)
+    }
+
+    /// Start a betting round, where no one bet yet
+    fn round(&mut self, bet: u64, next: usize) {
+        for i in 0..self.stacks.len() {
+            self.bets[i] = 0;
+            self.acted[i] = false;
+            self.can_raise[i] = true;
+        }
+        self.bet = bet;
+        self.min_raise = 2 * self.blind;
+        self.next = next;
+    }
+
+    /// Start a new hand: move the button, deal the cards and post the blinds
+    fn deal(&mut self, out: &mut Output) {
+        let n = self.stacks.len();
+        self.hand += 1;
+        self.playing = true;
+        self.dealer = match self.hand {
+            1 => self.following(n - 1),
+            _ => self.following(self.dealer),
+        };
+        self.public(out, format!("HAND {} {}", self.hand, self.dealer));
+        let stacks: Vec<_> = self.stacks.iter().map(|x| x.to_string()).collect();
+        self.public(out, format!("STACKS {}", stacks.join(" ")));
+
+        // Heads up the dealer posts the small blind
+        let seated = self.stacks.iter().filter(|&&x| x > 0).count();
+        let small = match seated {
+            2 => self.dealer,
+            _ => self.following(self.dealer),
+        };
+        let big = self.following(small);
+
+        self.deck = Card::deck();
+        self.deck.shuffle(&mut self.rng);
+        self.board.clear();
+        for i in 0..n {
+            self.folded[i] = false;
+            self.committed[i] = 0;
+            self.hole[i] = match self.stacks[i] {
+                0 => Vec::new(),
+                _ => self.deck.split_off(self.deck.len() - 2),
+            };
+        }
+        self.round(2 * self.blind, (big + 1) % n);
+        for (seat, blind) in [(small, self.blind), (big, 2 * self.blind)] {
+            let amount = self.put(seat, blind);
+            self.public(out, format!("BLIND {} {}", seat, amount));
+        }
+        // Players only see their own cards, spectators see all of them
+        for i in 0..n {
+            if !self.hole[i].is_empty() {
+                let line = format!("CARDS {} {}", i, logic::join(&self.hole[i]));
+                out.player(i, &line);
+                self.spectate(out, line);
+            }
+        }
+    }
+
+    /// Next player that has to act in the betting round, if any
+    fn to_act(&self) -> Option<usize> {
+        let n = self.stacks.len();
+        let live = self.live();
+        if live.len() < 2 {
+            return None;
+        }
+        // Nobody is left to answer a raise of the only player with chips
+        let able: Vec<_> = live.iter().filter(|&&i| self.stacks[i] > 0).collect();
+        if able.len() == 1 && self.bets[*able[0]] >= self.bet {
+            return None;
+        }
+        (0..n).map(|k| (self.next + k) % n).find(|&i| {
+            live.contains(&i) && self.stacks[i] > 0 && (!self.acted[i] || self.bets[i] < self.bet)
+        })
+    }
+
+    /// End the hand, showing the cards if needed and splitting every pot
+    /// among the best hands of the players that contributed to it
+    fn showdown(&mut self, out: &mut Output) {
+        let n = self.stacks.len();
+        let live = self.live();
+        let mut values = vec![Vec::new(); n];
+        if live.len() > 1 {
+            for &i in live.iter() {
+                let line = format!("SHOW {} {}", i, logic::join(&self.hole[i]));
+                self.public(out, line);
+                let cards: Vec<_> = self.hole[i]
+                    .iter()
+                    .chain(self.board.iter())
+                    .copied()
+                    .collect();
+                values[i] = logic::best(&cards);
+            }
+        }
+        // Each pot takes the same amount from everybody, up to the smallest
+        // contribution left of the players still in the hand
+        let mut left = self.committed.clone();
+        loop {
+            let eligible: Vec<_> = live.iter().copied().filter(|&i| left[i] > 0).collect();
+            let level = match eligible.iter().map(|&i| left[i]).min() {
+                Some(x) => x,
+                None => break,
+            };
+            let mut pot = 0;
+            for x in left.iter_mut() {
+                let y = (*x).min(level);
+                pot += y;
+                *x -= y;
+            }
+            let best = eligible
+                .iter()
+                .map(|&i| &values[i])
+                .max()
+                .expect("Cannot fail");
+            let mut winners: Vec<_> = eligible
+                .iter()
+                .copied()
+                .filter(|&i| &values[i] == best)
+                .collect();
+            // Odd chips go to the first winners after the dealer
+            winners.sort_by_key(|&i| (i + n - self.dealer - 1) % n);
+            let share = pot / winners.len() as u64;
+            let odd = (pot % winners.len() as u64) as usize;
+            for (k, &i) in winners.iter().enumerate() {
+                let won = share + (k < odd) as u64;
+                self.stacks[i] += won;
+                self.public(out, format!("WIN {} {}", i, won));
+            }
+        }
+        self.playing = false;
+        self.release(out);
+    }
+}
+
+impl TurnBasedGame for Poker {
+    type Move = Action;
+
+    fn start(&mut self, names: &[String], out: &mut Output) {
+        // Send names in order, after how many they are
+        out.all(format!("{}", names.len()));
+        for name in names {
+            out.all(name);
+        }
+        // Send player index to players
+        for i in 0..names.len() {
+            out.player(i, format!("{}", i));
+        }
+        out.all(format!(
+            "{} {} {} {}",
+            self.stacks[0],
+            self.blind,
+            2 * self.blind,
+            self.hands
+        ));
+    }
+
+    fn outcome(&mut self, _out: &mut Output) -> Option<Ending> {
+        let remaining = self.stacks.iter().filter(|&&x| x > 0).count();
+        if self.playing || (remaining > 1 && self.hand < self.hands) {
+            return None;
+        }
+        let top = *self.stacks.iter().max().expect("Cannot fail");
+        let tied = self.stacks.iter().filter(|&&x| x == top).count() > 1;
+        let results = self
+            .stacks
+            .iter()
+            .map(|&x| {
+                let result = match (x == top, tied) {
+                    (true, false) => PlayerResult::Win,
+                    (true, true) => PlayerResult::Draw,
+                    (false, _) => PlayerResult::Loss,
+                };
+                (result, Some(x as f64))
+            })
+            .collect();
+        Some(Ending {
+            results,
+            details: Some(format!("{} hands", self.hand)),
+        })
+    }
+
+    fn turn(&mut self, out: &mut Output) -> Option<usize> {
+        if !self.playing {
+            self.deal(out);
+        }
+        if let Some(i) = self.to_act() {
+            let call = (self.bet - self.bets[i]).min(self.stacks[i]);
+            let (min, max) = self.raises(i);
+            self.public(out, format!("ACT {} {} {} {}", i, call, min, max));
+            return Some(i);
+        }
+        // The betting round is over: deal the next cards or end the hand
+        if self.live().len() > 1 && self.board.len() < 5 {
+            let (street, count) = match self.board.len() {
+                0 => ("FLOP", 3),
+                3 => ("TURN", 1),
+                _ => ("RIVER", 1),
+            };
+            let cards = self.deck.split_off(self.deck.len() - count);
+            self.board.extend(cards.iter());
+            self.public(out, format!("{} {}", street, logic::join(&cards)));
+            let next = (self.dealer + 1) % self.stacks.len();
+            self.round(0, next);
+        } else {
+            self.showdown(out);
+        }
+        None
+    }
+
+    fn parse(&self, player: usize, line: &str) -> Result<Action, String> {
+        let parts: Vec<_> = line.split_whitespace().collect();
+        match parts[..] {
+            ["FOLD"] => Ok(Action::Fold),
+            ["CALL"] => Ok(Action::Call),
+            ["RAISE", x] => {
+                let x: u64 = x
+                    .parse()
+                    .map_err(|_| format!("\"{}\" is not an amount", x))?;
+                match self.raises(player) {
+                    (0, _) => Err("Cannot raise now".to_string()),
+                    (min, max) if x < min || x > max => Err(format!(
+                        "Cannot raise to {}, only from {} to {}",
+                        x, min, max
+                    )),
+                    _ => Ok(Action::Raise(x)),
+                }
+            }
+            _ => Err(format!("\"{}\" is not an action", line)),
+        }
+    }
+
+    fn apply(&mut self, player: usize, action: Action, out: &mut Output) {
+        let line = match action {
+            Action::Fold => {
+                self.folded[player] = true;
+                format!("{} FOLD", player)
+            }
+            Action::Call => {
+                let amount = self.put(player, self.bet - self.bets[player]);
+                format!("{} CALL {}", player, amount)
+            }
+            Action::Raise(x) => {
+                // A raise smaller than the previous one, only possible when
+                // going all in, does not let who already acted raise again
+                let full = x - self.bet >= self.min_raise;
+                for i in 0..self.stacks.len() {
+                    if full {
+                        self.acted[i] = false;
+                        self.can_raise[i] = true;
+                    } else if self.acted[i] {
+                        self.acted[i] = false;
+                        self.can_raise[i] = false;
+                    }
+                }
+                if full {
+                    self.min_raise = x - self.bet;
+                }
+                self.bet = x;
+                self.put(player, x - self.bets[player]);
+                format!("{} RAISE {}", player, x)
+            }
+        };
+        self.acted[player] = true;
+        self.next = (player + 1) % self.stacks.len();
+        self.public(out, line);
+    }
+
+    /// A player that fails to act folds and leaves the table, losing the
+    /// chips it did not bet, while the others keep playing
+    fn eliminate(&mut self, player: usize, out: &mut Output) -> bool {
+        self.folded[player] = true;
+        self.stacks[player] = 0;
+        self.public(out, format!("RETIRE {}", player));
+        true
+    }
+}
+
+#[async_trait]
+impl game::Instance for Instance {
+    async fn start(
+        &mut self,
+        players: Vec<(String, DuplexStream)>,
+        spectators: WriteHalf<DuplexStream>,
+    ) -> MatchOutcome {
+        let p = Player::from(players);
+        let n = p.len();
+        let poker = Poker {
+            rng: self.rng.clone(),
+            blind: self.blind,
+            hands: self.hands,
+            delay: self.delay,
+            held: Vec::new(),
+            hand: 0,
+            playing: false,
+            dealer: 0,
+            deck: Vec::new(),
+            board: Vec::new(),
+            stacks: vec![self.chips; n],
+            hole: vec![Vec::new(); n],
+            folded: vec![false; n],
+            bets: vec![0; n],
+            committed: vec![0; n],
+            acted: vec![false; n],
+            can_raise: vec![true; n],
+            bet: 0,
+            min_raise: 0,
+            next: 0,
+        };
+        util::play_turns(poker, p, spectators, self.timeout, self.pace, self.clock).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    /// A table in the middle of a hand, where `dealer` is on the button
+    /// and the board and hole cards are already dealt
+    fn table(stacks: &[u64], dealer: usize, board: &str, hole: &[&str]) -> Poker {
+        let n = stacks.len();
+        let cards =
+            |x: &str| -> Vec<Card> { x.split_whitespace().map(|x| x.parse().unwrap()).collect() };
+        Poker {
+            rng: StdRng::seed_from_u64(0),
+            blind: 5,
+            hands: 10,
+            delay: false,
+            held: Vec::new(),
+            hand: 1,
+            playing: true,
+            dealer,
+            deck: Vec::new(),
+            board: cards(board),
+            stacks: stacks.to_vec(),
+            hole: hole.iter().map(|&x| cards(x)).collect(),
+            folded: vec![false; n],
+            bets: vec![0; n],
+            committed: vec![0; n],
+            acted: vec![false; n],
+            can_raise: vec![true; n],
+            bet: 0,
+            min_raise: 10,
+            next: 0,
+        }
+    }
+
+    const BOARD: &str = "2c 7d 9h Js Kc";
+    /// From the best: a pair of kings, a pair of jacks, a pair of nines
+    const KINGS: &str = "Kd 3s";
+    const JACKS: &str = "Jd 4s";
+    const NINES: &str = "9d 5s";
+
+    #[test]
+    fn side_pots() {
+        let mut p = table(&[0, 0, 500], 0, BOARD, &[KINGS, JACKS, NINES]);
+        p.committed = vec![100, 300, 300];
+        p.showdown(&mut Output::new(3));
+        assert_eq!(p.stacks, vec![300, 400, 500]);
+        assert!(!p.playing);
+    }
+
+    #[test]
+    fn side_pot_to_the_short_stack() {
+        let mut p = table(&[0, 0, 0], 0, BOARD, &[NINES, KINGS, JACKS]);
+        p.committed = vec![400, 200, 100];
+        p.showdown(&mut Output::new(3));
+        // Kings take the main pot and the side pot, while the nines get
+        // back the chips nobody matched
+        assert_eq!(p.stacks, vec![200, 500, 0]);
+    }
+
+    #[test]
+    fn folded_chips() {
+        let mut p = table(&[0, 0, 0], 0, BOARD, &[KINGS, JACKS, NINES]);
+        p.committed = vec![50, 200, 200];
+        p.folded[0] = true;
+        p.showdown(&mut Output::new(3));
+        // A player that folds gives its chips to the pots without being
+        // eligible to win them
+        assert_eq!(p.stacks, vec![0, 450, 0]);
+    }
+
+    #[test]
+    fn uncontested() {
+        let mut p = table(&[100, 100, 100], 0, "", &[KINGS, JACKS, NINES]);
+        p.committed = vec![10, 20, 5];
+        p.folded = vec![true, false, true];
+        let mut out = Output::new(3);
+        p.showdown(&mut out);
+        assert_eq!(p.stacks, vec![100, 135, 100]);
+    }
+
+    #[test]
+    fn split_pot_odd_chips() {
+        // Both play the board, the odd chip goes to the first after the dealer
+        let board = "Ac Ad Kh Ks Qc";
+        for (dealer, stacks) in [
+            (0, vec![0, 26, 25]),
+            (1, vec![0, 25, 26]),
+            (2, vec![0, 26, 25]),
+        ] {
+            let mut p = table(&[0, 0, 0], dealer, board, &["2d 3s", "2h 3h", "2s 3c"]);
+            p.committed = vec![17, 17, 17];
+            p.folded[0] = true;
+            p.showdown(&mut Output::new(3));
+            assert_eq!(p.stacks, stacks, "dealer {}", dealer);
+        }
+    }
+
+    #[test]
+    fn retired_player_forfeits_its_stack() {
+        let mut p = table(&[400, 300, 300], 0, BOARD, &[KINGS, JACKS, NINES]);
+        p.committed = vec![100, 100, 100];
+        let mut out = Output::new(3);
+        assert!(p.eliminate(0, &mut out));
+        p.showdown(&mut out);
+        assert_eq!(p.stacks, vec![0, 600, 300]);
+        assert_eq!(p.following(2), 1);
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+use std::str::FromStr;
+
+const RANKS: &str = "23456789TJQKA";
+const SUITS: &str = "cdhs";
+
+/// A playing card, with rank from 2 to 14 (ace) and suit from 0 to 3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Card {
+    pub(crate) rank: u8,
+    pub(crate) suit: u8,
+}
+
+impl Card {
+    /// The 52 cards of a deck, in order
+    pub(crate) fn deck() -> Vec<Card> {
+        (0..4)
+            .flat_map(|suit| (2..=14).map(move |rank| Card { rank, suit }))
+            .collect()
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rank = RANKS.as_bytes()[self.rank as usize - 2] as char;
+        let suit = SUITS.as_bytes()[self.suit as usize] as char;
+        write!(f, "{}{}", rank, suit)
+    }
+}
+
+impl FromStr for Card {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Card, String> {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(r), Some(s), None) => match (RANKS.find(r), SUITS.find(s)) {
+                (Some(rank), Some(suit)) => Ok(Card {
+                    rank: rank as u8 + 2,
+                    suit: suit as u8,
+                }),
+                _ => Err(format!("\"{}\" is not a card", text)),
+            },
+            _ => Err(format!("\"{}\" is not a card", text)),
+        }
+    }
+}
+
+/// Join `cards` with spaces
+pub(crate) fn join(cards: &[Card]) -> String {
+    let cards: Vec<_> = cards.iter().map(|x| x.to_string()).collect();
+    cards.join(" ")
+}
+
+/// Categories of poker hands, from the weakest
+pub(crate) const HIGH_CARD: u8 = 0;
+pub(crate) const PAIR: u8 = 1;
+pub(crate) const TWO_PAIR: u8 = 2;
+pub(crate) const THREE_OF_A_KIND: u8 = 3;
+pub(crate) const STRAIGHT: u8 = 4;
+pub(crate) const FLUSH: u8 = 5;
+pub(crate) const FULL_HOUSE: u8 = 6;
+pub(crate) const FOUR_OF_A_KIND: u8 = 7;
+pub(crate) const STRAIGHT_FLUSH: u8 = 8;
+
+/// Value of five cards: the category followed by the ranks deciding ties,
+/// so that values compare like the hands they come from
+fn value(cards: &[Card]) -> Vec<u8> {
+    let mut ranks: Vec<u8> = cards.iter().map(|x| x.rank).collect();
+    ranks.sort_unstable_by(|a, b| b.cmp(a));
+    let flush = cards.iter().all(|x| x.suit == cards[0].suit);
+    let mut distinct = ranks.clone();
+    distinct.dedup();
+    let straight = match distinct[..] {
+        [14, 5, 4, 3, 2] => Some(5),
+        [a, .., b] if distinct.len() == 5 && a - b == 4 => Some(a),
+        _ => None,
+    };
+    // Ranks grouped by how many times they appear, most frequent and highest first
+    let mut groups: Vec<(usize, u8)> = distinct
+        .iter()
+        .map(|&r| (ranks.iter().filter(|&&x| x == r).count(), r))
+        .collect();
+    groups.sort_unstable_by(|a, b| b.cmp(a));
+    let category = match (straight, flush, groups[0].0, groups.get(1).map(|x| x.0)) {
+        (Some(high), true, _, _) => return vec![STRAIGHT_FLUSH, high],
+        (_, _, 4, _) => FOUR_OF_A_KIND,
+        (_, _, 3, Some(2)) => FULL_HOUSE,
+        (_, true, _, _) => FLUSH,
+        (Some(high), _, _, _) => return vec![STRAIGHT, high],
+        (_, _, 3, _) => THREE_OF_A_KIND,
+        (_, _, 2, Some(2)) => TWO_PAIR,
+        (_, _, 2, _) => PAIR,
+        _ => HIGH_CARD,
+    };
+    let mut value = vec![category];
+    value.extend(groups.iter().map(|x| x.1));
+    value
+}
+
+/// Value of the best five cards among `cards`, which are at least five
+pub(crate) fn best(cards: &[Card]) -> Vec<u8> {
+    let n = cards.len();
+    let mut best = Vec::new();
+    // Try every way to leave out all but five cards
+    for skip in 0..(1u32 << n) {
+        if skip.count_ones() as usize != n - 5 {
+            continue;
+        }
+        let hand: Vec<_> = (0..n)
+            .filter(|&i| skip & (1 << i) == 0)
+            .map(|i| cards[i])
+            .collect();
+        best = best.max(value(&hand));
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(text: &str) -> Vec<Card> {
+        text.split_whitespace()
+            .map(|x| x.parse().unwrap())
+            .collect()
+    }
+
+    fn v(text: &str) -> Vec<u8> {
+        value(&cards(text))
+    }
+
+    #[test]
+    fn cards_roundtrip() {
+        let deck = Card::deck();
+        assert_eq!(deck.len(), 52);
+        for card in deck {
+            assert_eq!(card.to_string().parse::<Card>(), Ok(card));
+        }
+        assert_eq!(join(&cards("Ah Tc 2d")), "Ah Tc 2d");
+        assert!("1h".parse::<Card>().is_err());
+        assert!("Ahh".parse::<Card>().is_err());
+        assert!("A".parse::<Card>().is_err());
+    }
+
+    #[test]
+    fn categories() {
+        assert_eq!(v("Ah Kd 9c 5s 3h"), vec![HIGH_CARD, 14, 13, 9, 5, 3]);
+        assert_eq!(v("9h 9d Ac 5s 3h"), vec![PAIR, 9, 14, 5, 3]);
+        assert_eq!(v("9h 9d 5c 5s Ah"), vec![TWO_PAIR, 9, 5, 14]);
+        assert_eq!(v("9h 9d 9c 5s Ah"), vec![THREE_OF_A_KIND, 9, 14, 5]);
+        assert_eq!(v("9h Td Jc Qs Kh"), vec![STRAIGHT, 13]);
+        assert_eq!(v("2h 9h Jh Qh Kh"), vec![FLUSH, 13, 12, 11, 9, 2]);
+        assert_eq!(v("5h 5d 9c 9s 9h"), vec![FULL_HOUSE, 9, 5]);
+        assert_eq!(v("5h 9d 9c 9s 9h"), vec![FOUR_OF_A_KIND, 9, 5]);
+        assert_eq!(v("Th Jh Qh Kh Ah"), vec![STRAIGHT_FLUSH, 14]);
+    }
+
+    #[test]
+    fn wheel() {
+        assert_eq!(v("Ah 2d 3c 4s 5h"), vec![STRAIGHT, 5]);
+        assert_eq!(v("Ah 2h 3h 4h 5h"), vec![STRAIGHT_FLUSH, 5]);
+        assert!(v("Ah 2d 3c 4s 5h") < v("2h 3d 4c 5s 6h"));
+        // Ranks do not wrap around the ace
+        assert_eq!(v("Qh Kd Ac 2s 3h")[0], HIGH_CARD);
+    }
+
+    #[test]
+    fn kickers() {
+        assert!(v("9h 9d Ac 5s 3h") > v("9c 9s Kc Qs Jh"));
+        assert!(v("9h 9d Ac 5s 4h") > v("9c 9s Ad 5c 3d"));
+        assert!(v("9h 9d 5c 5s 3h") > v("8c 8s 7c 7s Ad"));
+        assert!(v("9h 9d 5c 5s 4h") > v("9c 9s 5d 5h 3d"));
+        assert!(v("5h 5d 9c 9s 9h") > v("Ah Ad 8c 8s 8h"));
+        assert!(v("Ad Qd Jd 9d 7d") > v("2h 9h Jh Qh Kh"));
+        assert_eq!(v("Ah Kd 9c 5s 3h"), v("As Kc 9d 5h 3c"));
+    }
+
+    #[test]
+    fn best_of_seven() {
+        // The board plays, ignoring the pair in the hand
+        assert_eq!(
+            best(&cards("2h 2d 9c Tc Jc Qc Kc")),
+            vec![STRAIGHT_FLUSH, 13]
+        );
+        // A higher straight beats the wheel that is also there
+        assert_eq!(best(&cards("Ah 2d 3c 4s 5h 6d Kc")), vec![STRAIGHT, 6]);
+        // Two trips make a full house with the highest as the set
+        assert_eq!(best(&cards("4h 4d 4c 8s 8h 8d Ac")), vec![FULL_HOUSE, 8, 4]);
+        // Three pairs keep the best kicker among the rest
+        assert_eq!(
+            best(&cards("4h 4d 8s 8h Kc Kd 7c")),
+            vec![TWO_PAIR, 13, 8, 7]
+        );
+        assert_eq!(best(&cards("Ah Kd 9c 5s 3h")), v("Ah Kd 9c 5s 3h"));
+    }
+}
@@ -0,0 +1,145 @@
+use super::util::{self, Ending, Player, TurnBasedGame};
+use crate::game::{self, MatchOutcome, PlayerResult};
+use crate::proto::{BotInfo, GameArgInfo};
+use async_trait::async_trait;
+use rand::random;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::marker::PhantomData;
+use tokio::io::{DuplexStream, WriteHalf};
+use tokio::time::Duration;
+
+const DEFAULT_TIMEOUT: f64 = 10.0;
+/// Players asking this many times the target number of queries fail the problem
+const LIMIT_FACTOR: usize = 10;
+
+/// An interactive problem, where a single player asks queries to the server
+/// until it finds a secret, and is scored by how many queries it needed.
+/// The rules are those of a turn based game with only one seat.
+pub(crate) trait Problem: TurnBasedGame + Clone + Debug + Sync + 'static {
+    const NAME: &'static str;
+    const DESCRIPTION: &'static str;
+
+    fn args() -> HashMap<String, GameArgInfo>;
+
+    /// Pick the secret of a match from its arguments, using the randomness of the match
+    fn new(args: &HashMap<String, String>, rng: &mut StdRng) -> Result<Self, String>;
+}
+
+/// Most queries a player can ask, for a problem that can be solved with `target`
+pub(crate) fn limit(target: usize) -> usize {
+    LIMIT_FACTOR * target
+}
+
+/// Score of a player that solved a problem with `queries` queries: a hundred
+/// points up to `target` queries, and proportionally less past it
+fn score(queries: usize, target: usize) -> f64 {
+    if queries <= target {
+        100.0
+    } else {
+        100.0 * target as f64 / queries as f64
+    }
+}
+
+/// End the match once the player solved the problem or ran out of queries,
+/// revealing the `secret`
+pub(crate) fn ending(
+    solved: bool,
+    queries: usize,
+    target: usize,
+    secret: impl Display,
+) -> Option<Ending> {
+    let (result, score) = if solved {
+        (PlayerResult::Win, score(queries, target))
+    } else if queries >= limit(target) {
+        (PlayerResult::Loss, 0.0)
+    } else {
+        return None;
+    };
+    Some(Ending {
+        results: vec![(result, Some(score))],
+        details: Some(format!("{} queries, secret {}", queries, secret)),
+    })
+}
+
+#[derive(Debug)]
+pub(crate) struct Builder<P> {
+    problem: PhantomData<fn() -> P>,
+}
+
+pub(crate) fn builder<P: Problem>() -> Box<dyn game::Builder> {
+    Box::new(Builder::<P> {
+        problem: PhantomData,
+    })
+}
+
+#[derive(Debug)]
+struct Instance<P> {
+    problem: P,
+    timeout: f64,
+    clock: Option<game::TimeControl>,
+}
+
+#[async_trait]
+impl<P: Problem> game::Builder for Builder<P> {
+    fn name(&self) -> &str {
+        P::NAME
+    }
+    async fn description(&self) -> String {
+        String::from(P::DESCRIPTION)
+    }
+    async fn args(&self) -> HashMap<String, GameArgInfo> {
+        P::args()
+    }
+    async fn gen_instance(
+        &self,
+        param: &mut game::Params,
+        args: HashMap<String, String>,
+    ) -> Result<Box<dyn game::Instance>, String> {
+        param.players = match param.players {
+            Some(1) | None => Some(1),
+            Some(x) => return Err(format!("Cannot create problem with {} players", x)),
+        };
+        param.timeout = param.timeout.or(Some(DEFAULT_TIMEOUT));
+        let seed = *param.seed.get_or_insert_with(random);
+        let problem = P::new(&args, &mut StdRng::seed_from_u64(seed))?;
+        Ok(Box::new(Instance {
+            problem,
+            timeout: param.timeout.expect("Cannot fail"),
+            clock: param.clock,
+        }))
+    }
+    async fn bots(&self) -> Vec<BotInfo> {
+        Vec::new()
+    }
+    async fn seats(&self) -> Vec<String> {
+        vec!["player".to_owned()]
+    }
+    async fn gen_bot(&self, _name: &str, _seed: u64) -> Box<dyn game::Bot> {
+        unreachable!("Problems have no server bots")
+    }
+}
+
+#[async_trait]
+impl<P: Problem> game::Instance for Instance<P> {
+    async fn start(
+        &mut self,
+        players: Vec<(String, DuplexStream)>,
+        spectators: WriteHalf<DuplexStream>,
+    ) -> MatchOutcome {
+        let p = Player::from(players);
+        assert_eq!(p.len(), 1);
+        let timeout = Duration::from_secs_f64(self.timeout);
+        util::play_turns(
+            self.problem.clone(),
+            p,
+            spectators,
+            timeout,
+            Duration::ZERO,
+            self.clock,
+        )
+        .await
+    }
+}
@@ -0,0 +1,80 @@
+use super::super::util::LineReader;
+use super::logic::Board;
+use crate::game;
+use crate::tuning::MAX_LINE_LENGTH;
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use tokio::io::{split, AsyncWriteExt, DuplexStream};
+use tracing::error;
+
+#[derive(Debug)]
+pub(crate) struct Bot {
+    pub(crate) seed: u64,
+    /// Pick the move leaving the best discs by position, instead of a random one
+    pub(crate) positional: bool,
+}
+
+/// How valuable a disc in `row` and `col` is on a board of side `size`:
+/// corners cannot be flipped, while the squares next to them give them away
+fn weight(size: usize, row: usize, col: usize) -> i64 {
+    let edge = |x| x == 0 || x == size - 1;
+    let near = |x| x == 1 || x == size - 2;
+    match (edge(row), edge(col), near(row), near(col)) {
+        (true, true, _, _) => 100,
+        (_, _, true, true) => -50,
+        (true, _, _, true) | (_, true, true, _) => -20,
+        (true, _, _, _) | (_, true, _, _) => 10,
+        (_, _, true, _) | (_, _, _, true) => -5,
+        _ => 1,
+    }
+}
+
+#[async_trait]
+impl game::Bot for Bot {
+    async fn start(&mut self, stream: DuplexStream) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let (input, mut output) = split(stream);
+        let mut input = LineReader::new(input, MAX_LINE_LENGTH);
+        lnin!(input); // Read first name
+        lnin!(input); // Read second name
+        let me: usize = lnin!(input).parse().expect("Cannot parse player number");
+        let size: usize = lnin!(input).parse().expect("Cannot parse board size");
+        let mut board = Board::new(size);
+        let mut turn = 0;
+        while !board.finished() {
+            if board.valid_moves(turn).is_empty() {
+                // The server announces the pass
+                lnin!(input);
+            } else if turn == me {
+                let mut moves = board.valid_moves(me);
+                if self.positional {
+                    let score = |x: usize| {
+                        let mut after = board.clone();
+                        after.play(me, x);
+                        after.weigh(me, |r, c| weight(size, r, c))
+                    };
+                    let best = moves.iter().map(|&x| score(x)).max();
+                    moves.retain(|&x| Some(score(x)) == best);
+                }
+                let x = *moves.choose(&mut rng).expect("Cannot fail");
+                board.play(me, x);
+                lnout!(output, board.name(x));
+            } else {
+                let line = lnin!(input);
+                if line == "RETIRE" {
+                    break;
+                }
+                match board.parse(&line) {
+                    Some(x) if board.valid(1 - me, x) => board.play(1 - me, x),
+                    _ => {
+                        error!("Server sent invalid move");
+                        break;
+                    }
+                }
+            }
+            turn = 1 - turn;
+        }
+    }
+}
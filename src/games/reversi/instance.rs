@@ -0,0 +1,134 @@
+use super::super::util::{self, Ending, Output, Player, TurnBasedGame};
+use super::logic::Board;
+use crate::game::{self, MatchOutcome, PlayerResult, TimeControl};
+use async_trait::async_trait;
+use tokio::io::{DuplexStream, WriteHalf};
+use tokio::time::Duration;
+
+#[derive(Debug)]
+pub(crate) struct Instance {
+    pub(crate) timeout: Duration,
+    pub(crate) pace: Duration,
+    pub(crate) clock: Option<TimeControl>,
+    pub(crate) size: usize,
+}
+
+/// State of a match
+struct Reversi {
+    board: Board,
+    turn: usize,
+}
+
+impl TurnBasedGame for Reversi {
+    type Move = usize;
+
+    fn start(&mut self, names: &[String], out: &mut Output) {
+        // Send names in order
+        for name in names {
+            out.all(name);
+        }
+        // Send player index to players
+        out.player(0, "0");
+        out.player(1, "1");
+        // Send board size
+        out.all(format!("{}", self.board.size()));
+    }
+
+    fn outcome(&mut self, _out: &mut Output) -> Option<Ending> {
+        if !self.board.finished() {
+            return None;
+        }
+        let discs = [self.board.count(0), self.board.count(1)];
+        let results = (0..2)
+            .map(|i| {
+                let result = if discs[i] > discs[1 - i] {
+                    PlayerResult::Win
+                } else if discs[i] < discs[1 - i] {
+                    PlayerResult::Loss
+                } else {
+                    PlayerResult::Draw
+                };
+                (result, Some(discs[i] as f64))
+            })
+            .collect();
+        Some(Ending {
+            results,
+            details: Some(format!("{} to {} discs", discs[0], discs[1])),
+        })
+    }
+
+    fn turn(&mut self, out: &mut Output) -> Option<usize> {
+        if self.board.valid_moves(self.turn).is_empty() {
+            // The current player has no valid moves, the other one has some
+            out.all("PASS");
+            self.turn = 1 - self.turn;
+            return None;
+        }
+        Some(self.turn)
+    }
+
+    fn parse(&self, player: usize, line: &str) -> Result<usize, String> {
+        match self.board.parse(line) {
+            Some(x) if self.board.valid(player, x) => Ok(x),
+            Some(_) => Err(format!("Cannot play in {}", line)),
+            None => Err(format!("\"{}\" is not a square", line)),
+        }
+    }
+
+    fn apply(&mut self, player: usize, square: usize, out: &mut Output) {
+        self.board.play(player, square);
+        out.others(player, self.board.name(square));
+        self.turn = 1 - self.turn;
+    }
+}
+
+#[async_trait]
+impl game::Instance for Instance {
+    async fn start(
+        &mut self,
+        players: Vec<(String, DuplexStream)>,
+        spectators: WriteHalf<DuplexStream>,
+    ) -> MatchOutcome {
+        let p = Player::from(players);
+        assert_eq!(p.len(), 2);
+        let reversi = Reversi {
+            board: Board::new(self.size),
+            turn: 0,
+        };
+        util::play_turns(reversi, p, spectators, self.timeout, self.pace, self.clock).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forced_pass() {
+        let mut game = Reversi {
+            board: Board::from(&["WB..", "....", "....", "...."]),
+            turn: 0,
+        };
+        let mut out = Output::new(2);
+        assert!(game.outcome(&mut out).is_none());
+        assert_eq!(game.turn(&mut out), None);
+        assert_eq!(game.turn(&mut out), Some(1));
+        assert!(game.parse(1, "d1").is_err());
+        let m = game.parse(1, "c1").unwrap();
+        game.apply(1, m, &mut out);
+        let ending = game.outcome(&mut out).unwrap();
+        assert_eq!(ending.details.as_deref(), Some("0 to 3 discs"));
+        assert_eq!(ending.results[0].0, PlayerResult::Loss);
+        assert_eq!(ending.results[1], (PlayerResult::Win, Some(3.0)));
+    }
+
+    #[test]
+    fn draw() {
+        let mut game = Reversi {
+            board: Board::from(&["BW", "WB"]),
+            turn: 0,
+        };
+        let ending = game.outcome(&mut Output::new(2)).unwrap();
+        assert!(ending.results.iter().all(|x| x.0 == PlayerResult::Draw));
+    }
+}
@@ -0,0 +1,225 @@
+/// The eight directions discs can be flipped along, as row and column steps
+const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// A square board, with rows numbered from the top and columns from the left.
+/// Player 0 plays black and player 1 plays white.
+#[derive(Debug, Clone)]
+pub(crate) struct Board {
+    size: usize,
+    cells: Vec<Option<usize>>,
+}
+
+impl Board {
+    /// The starting position, with two discs per player in the center
+    pub(crate) fn new(size: usize) -> Board {
+        let mut cells = vec![None; size * size];
+        let (a, b) = (size / 2 - 1, size / 2);
+        cells[a * size + a] = Some(1);
+        cells[b * size + b] = Some(1);
+        cells[a * size + b] = Some(0);
+        cells[b * size + a] = Some(0);
+        Board { size, cells }
+    }
+
+    /// A position drawn with a row of `B`, `W` or `.` per line, from the top
+    #[cfg(test)]
+    pub(crate) fn from(rows: &[&str]) -> Board {
+        let cells = rows
+            .iter()
+            .flat_map(|x| x.chars())
+            .map(|x| match x {
+                'B' => Some(0),
+                'W' => Some(1),
+                _ => None,
+            })
+            .collect();
+        Board {
+            size: rows.len(),
+            cells,
+        }
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Parse a square like `d3`: column letter from `a`, row number from `1`
+    pub(crate) fn parse(&self, text: &str) -> Option<usize> {
+        let mut chars = text.chars();
+        let col = chars.next()? as usize;
+        let row: usize = chars.as_str().parse().ok()?;
+        if col < 'a' as usize || col - ('a' as usize) >= self.size || row < 1 || row > self.size {
+            return None;
+        }
+        Some((row - 1) * self.size + col - 'a' as usize)
+    }
+
+    /// Name of `square`, in the format accepted by `parse`
+    pub(crate) fn name(&self, square: usize) -> String {
+        let col = (b'a' + (square % self.size) as u8) as char;
+        format!("{}{}", col, square / self.size + 1)
+    }
+
+    /// Discs of the opponent that `player` would flip by playing in `square`
+    fn flips(&self, player: usize, square: usize) -> Vec<usize> {
+        let mut flips = Vec::new();
+        if self.cells[square].is_some() {
+            return flips;
+        }
+        let n = self.size as isize;
+        let (row, col) = ((square / self.size) as isize, (square % self.size) as isize);
+        for (dr, dc) in DIRECTIONS.iter() {
+            let mut line = Vec::new();
+            let (mut r, mut c) = (row + dr, col + dc);
+            while 0 <= r && r < n && 0 <= c && c < n {
+                let i = (r * n + c) as usize;
+                match self.cells[i] {
+                    Some(x) if x != player => line.push(i),
+                    Some(_) => {
+                        flips.extend(line);
+                        break;
+                    }
+                    None => break,
+                }
+                r += dr;
+                c += dc;
+            }
+        }
+        flips
+    }
+
+    /// Whether `player` can play in `square`, flipping at least one disc
+    pub(crate) fn valid(&self, player: usize, square: usize) -> bool {
+        square < self.cells.len() && !self.flips(player, square).is_empty()
+    }
+
+    /// Squares where `player` can play
+    pub(crate) fn valid_moves(&self, player: usize) -> Vec<usize> {
+        (0..self.cells.len())
+            .filter(|&x| self.valid(player, x))
+            .collect()
+    }
+
+    /// Place a disc of `player` in `square`, which must be valid, flipping
+    /// the opponent's discs it encloses
+    pub(crate) fn play(&mut self, player: usize, square: usize) {
+        for i in self.flips(player, square) {
+            self.cells[i] = Some(player);
+        }
+        self.cells[square] = Some(player);
+    }
+
+    /// Whether neither player can move
+    pub(crate) fn finished(&self) -> bool {
+        self.valid_moves(0).is_empty() && self.valid_moves(1).is_empty()
+    }
+
+    /// Number of discs of `player`
+    pub(crate) fn count(&self, player: usize) -> usize {
+        self.cells.iter().filter(|&&x| x == Some(player)).count()
+    }
+
+    /// Sum of `weight` over the discs of `player`, minus that of the opponent
+    pub(crate) fn weigh(&self, player: usize, weight: impl Fn(usize, usize) -> i64) -> i64 {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, x)| match x {
+                Some(x) if *x == player => weight(i / self.size, i % self.size),
+                Some(_) => -weight(i / self.size, i % self.size),
+                None => 0,
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(b: &Board, squares: Vec<usize>) -> Vec<String> {
+        squares.into_iter().map(|x| b.name(x)).collect()
+    }
+
+    #[test]
+    fn squares() {
+        let b = Board::new(8);
+        assert_eq!(b.parse("a1"), Some(0));
+        assert_eq!(b.parse("h8"), Some(63));
+        assert_eq!(b.parse("c2"), Some(10));
+        assert_eq!(b.name(10), "c2");
+        for text in ["i1", "a0", "a9", "A1", "a", "", "1a", "a-1"] {
+            assert_eq!(b.parse(text), None, "{}", text);
+        }
+        assert_eq!(Board::new(10).parse("j10"), Some(99));
+    }
+
+    #[test]
+    fn opening() {
+        let b = Board::new(8);
+        assert_eq!((b.count(0), b.count(1)), (2, 2));
+        assert_eq!(names(&b, b.valid_moves(0)), vec!["d3", "c4", "f5", "e6"]);
+        assert_eq!(names(&b, b.valid_moves(1)), vec!["e3", "f4", "c5", "d6"]);
+        let b = Board::new(4);
+        assert_eq!(names(&b, b.valid_moves(0)), vec!["b1", "a2", "d3", "c4"]);
+    }
+
+    #[test]
+    fn flips_in_every_direction() {
+        let mut b = Board::from(&["B.B..", ".WW..", "BW...", ".....", "....."]);
+        let c3 = b.parse("c3").unwrap();
+        assert!(b.valid(0, c3));
+        b.play(0, c3);
+        let expected = Board::from(&["B.B..", ".BB..", "BBB..", ".....", "....."]);
+        assert_eq!(b.cells, expected.cells);
+    }
+
+    #[test]
+    fn flips_whole_lines_only() {
+        // Nothing to flip across an empty square or up to the edge
+        let b = Board::from(&["B.W.", "WWW.", "....", "...."]);
+        assert!(!b.valid(0, b.parse("d1").unwrap()));
+        assert!(!b.valid(0, b.parse("d2").unwrap()));
+        // Every disc up to the first of the player
+        let mut b = Board::from(&["BWWW.B", "......", "......", "......", "......", "......"]);
+        b.play(0, b.parse("e1").unwrap());
+        assert_eq!((b.count(0), b.count(1)), (6, 0));
+        // Lines do not wrap around the sides of the board
+        let b = Board::from(&["..BW", "W...", "....", "...."]);
+        assert!(!b.valid(0, b.parse("b2").unwrap()));
+    }
+
+    #[test]
+    fn forced_pass() {
+        let b = Board::from(&["WB..", "....", "....", "...."]);
+        assert!(b.valid_moves(0).is_empty());
+        assert_eq!(names(&b, b.valid_moves(1)), vec!["c1"]);
+        assert!(!b.finished());
+    }
+
+    #[test]
+    fn finished() {
+        let mut b = Board::from(&["WB..", "....", "....", "...."]);
+        b.play(1, b.parse("c1").unwrap());
+        assert!(b.finished());
+        assert_eq!((b.count(0), b.count(1)), (0, 3));
+        // A full board
+        assert!(Board::from(&["BW", "WB"]).finished());
+    }
+
+    #[test]
+    fn weigh() {
+        let b = Board::from(&["BW", "B."]);
+        assert_eq!(b.weigh(0, |_, _| 1), 1);
+        assert_eq!(b.weigh(1, |row, _| row as i64 + 1), 1 - 3);
+    }
+}
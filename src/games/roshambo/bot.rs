@@ -1,29 +1,49 @@
+use super::super::util::LineReader;
 use crate::game;
+use crate::tuning::MAX_LINE_LENGTH;
 use async_trait::async_trait;
-use rand::rngs::{OsRng, StdRng};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use rand::SeedableRng;
-use tokio::io::{split, AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::io::{split, AsyncWriteExt, DuplexStream};
 use tracing::error;
 
 #[derive(Debug)]
-pub(crate) struct Bot {}
+pub(crate) struct Bot {
+    pub(crate) seed: u64,
+    /// Answer the move the opponent played most, instead of playing at random
+    pub(crate) counter: bool,
+}
 
 #[async_trait]
 impl game::Bot for Bot {
     async fn start(&mut self, stream: DuplexStream) {
         const MOVES: [&str; 3] = ["ROCK", "PAPER", "SCISSORS"];
-        let mut rng = StdRng::from_rng(OsRng).expect("Cannot initialize PRNG");
+        let mut rng = StdRng::seed_from_u64(self.seed);
         let (input, mut output) = split(stream);
-        let mut input = BufReader::new(input);
+        let mut input = LineReader::new(input, MAX_LINE_LENGTH);
         lnin!(input); // Read my name
         lnin!(input); // Read opponent name
         let rounds: usize = lnin!(input).parse().expect("Cannot parse number of rounds");
+        // How many times the opponent played each move
+        let mut seen = [0usize; 3];
         for _ in 0..rounds {
-            lnout!(output, *MOVES.choose(&mut rng).expect("Cannot fail"));
-            if lnin!(input) == "RETIRE" {
+            let max = *seen.iter().max().expect("Cannot fail");
+            if self.counter && max > 0 {
+                // Each move is beaten by the next one
+                let likely: Vec<_> = (0..3).filter(|&x| seen[x] == max).collect();
+                let x = likely.choose(&mut rng).expect("Cannot fail");
+                lnout!(output, MOVES[(x + 1) % 3]);
+            } else {
+                lnout!(output, *MOVES.choose(&mut rng).expect("Cannot fail"));
+            }
+            let answer = lnin!(input);
+            if answer == "RETIRE" {
                 break;
             }
+            if let Some(x) = MOVES.iter().position(|x| *x == answer) {
+                seen[x] += 1;
+            }
         }
     }
 }
@@ -2,12 +2,10 @@ use super::bot::Bot;
 use super::instance::Instance;
 use crate::game;
 use crate::games;
-use crate::proto::GameArgInfo;
+use crate::proto::{BotInfo, GameArgInfo, GameArgKind};
 use async_trait::async_trait;
 use games::util::arg;
-use rand::rngs::{OsRng, StdRng};
-use rand::SeedableRng;
-use regex::Regex;
+use rand::random;
 use std::collections::HashMap;
 
 const DEFAULT_TIMEOUT: f64 = 30.0;
@@ -36,15 +34,22 @@ impl game::Builder for Builder {
             (
                 "rounds".to_owned(),
                 GameArgInfo {
-                    description: "How many rounds (1-9999)".to_owned(),
-                    regex: "^([1-9][0-9]{0,3})$".to_owned(),
+                    description: "How many rounds".to_owned(),
+                    kind: GameArgKind::Integer { min: 1, max: 9999 },
+                    default: DEFAULT_ROUNDS.to_string(),
+                    unit: None,
                 },
             ),
             (
                 "pace".to_owned(),
                 GameArgInfo {
-                    description: "How fast the game plays (0-30)".to_owned(),
-                    regex: "^(30|([12][0-9]|[0-9])(.[0-9]*)?)$".to_owned(),
+                    description: "Pause between rounds, to let spectators follow".to_owned(),
+                    kind: GameArgKind::Float {
+                        min: 0.0,
+                        max: 30.0,
+                    },
+                    default: DEFAULT_PACE.to_string(),
+                    unit: Some("seconds".to_owned()),
                 },
             ),
         ])
@@ -59,44 +64,38 @@ impl game::Builder for Builder {
             Some(x) => return Err(format!("Cannot create game with {} players", x)),
             None => Some(2),
         };
+        if param.clock.is_some() {
+            return Err("The game does not support time controls".to_string());
+        }
         param.timeout = param.timeout.or(Some(DEFAULT_TIMEOUT));
-        let constraints = self.args().await;
-
-        let rounds_reg = Regex::new(&constraints["rounds"].regex).unwrap();
-        let rounds = match arg(&args, "rounds", DEFAULT_ROUNDS) {
-            Ok(x) => {
-                if !rounds_reg.is_match(&x.to_string()) {
-                    return Err(format!("Invalid number of rounds"));
-                } else {
-                    x
-                }
-            }
-            Err(x) => return Err(format!("Invaid number of rounds: {}", x)),
-        };
-
-        let pace_reg = Regex::new(&constraints["pace"].regex).unwrap();
-        let pace = match arg(&args, "pace", DEFAULT_PACE) {
-            Ok(x) => {
-                if !pace_reg.is_match(&x.to_string()) {
-                    return Err(format!("Invalid pace"));
-                } else {
-                    x
-                }
-            }
-            Err(x) => return Err(format!("Invaid pace: {}", x)),
-        };
-        let rng = match StdRng::from_rng(OsRng) {
-            Ok(x) => x,
-            Err(x) => return Err(format!("Cannot initialize PRNG: {}", x)),
-        };
+        let rounds = arg(&args, "rounds");
+        let pace = arg(&args, "pace");
+        param.seed.get_or_insert_with(random);
         Ok(Box::new(Instance {
             rounds: rounds,
             timeout: param.timeout.expect("Cannot fail"),
             pace: pace,
-            rng: rng,
         }))
     }
-    async fn gen_bot(&self) -> Box<dyn game::Bot> {
-        Box::new(Bot {})
+    async fn bots(&self) -> Vec<BotInfo> {
+        vec![
+            BotInfo {
+                name: "random".to_owned(),
+                description: "Plays random moves".to_owned(),
+            },
+            BotInfo {
+                name: "counter".to_owned(),
+                description: "Beats the move the opponent played most so far".to_owned(),
+            },
+        ]
+    }
+    async fn seats(&self) -> Vec<String> {
+        vec!["first".to_owned(), "second".to_owned()]
+    }
+    async fn gen_bot(&self, name: &str, seed: u64) -> Box<dyn game::Bot> {
+        Box::new(Bot {
+            seed,
+            counter: name == "counter",
+        })
     }
 }
@@ -1,99 +1,126 @@
-use super::super::util::Player;
-use crate::game;
+use super::super::util::{self, Ending, Output, Player, Policy, SimultaneousGame};
+use crate::game::{self, MatchOutcome, PlayerResult};
 use async_trait::async_trait;
-use rand::rngs::StdRng;
-use std::collections::HashMap;
 use std::time::Duration;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, DuplexStream, WriteHalf};
-use tokio::join;
-use tokio::time::{sleep_until, timeout, Instant};
-use tracing::warn;
+use tokio::io::{DuplexStream, WriteHalf};
 
 #[derive(Debug)]
 pub(crate) struct Instance {
     pub(crate) rounds: usize,
     pub(crate) timeout: f64,
     pub(crate) pace: f64,
-    pub(crate) rng: StdRng,
 }
 
-macro_rules! process {
-    ($result:expr, $line:expr) => {{
-        match $result {
-            Err(_) | Ok(Err(_)) => None,
-            Ok(Ok(_)) => match $line.trim() {
-                "ROCK" => Some("ROCK"),
-                "PAPER" => Some("PAPER"),
-                "SCISSORS" => Some("SCISSORS"),
-                _ => None,
-            },
+fn beats(x: &str, y: &str) -> bool {
+    matches!(
+        (x, y),
+        ("ROCK", "SCISSORS") | ("PAPER", "ROCK") | ("SCISSORS", "PAPER")
+    )
+}
+
+/// State of a match
+struct Roshambo {
+    rounds: usize,
+    played: usize,
+    wins: [usize; 2],
+}
+
+impl Roshambo {
+    fn results(&self, retired: &[bool]) -> Vec<(PlayerResult, Option<f64>)> {
+        let wins = self.wins;
+        (0..2)
+            .map(|i| {
+                let result = if retired[i] {
+                    PlayerResult::Loss
+                } else if retired[1 - i] || wins[i] > wins[1 - i] {
+                    PlayerResult::Win
+                } else if wins[i] < wins[1 - i] {
+                    PlayerResult::Loss
+                } else {
+                    PlayerResult::Draw
+                };
+                (result, Some(wins[i] as f64))
+            })
+            .collect()
+    }
+}
+
+impl SimultaneousGame for Roshambo {
+    type Move = &'static str;
+
+    fn start(&mut self, names: &[String], out: &mut Output) {
+        for i in 0..2 {
+            out.spectators(&names[i]);
+            out.player(i, &names[i]);
+            out.player(i, &names[1 - i]);
+            out.player(i, format!("{}", self.rounds));
+        }
+        out.spectators(format!("{}", self.rounds));
+    }
+
+    fn outcome(&mut self, _out: &mut Output) -> Option<Ending> {
+        if self.played < self.rounds {
+            return None;
+        }
+        Some(Ending {
+            results: self.results(&[false, false]),
+            details: None,
+        })
+    }
+
+    fn parse(&self, _player: usize, line: &str) -> Result<&'static str, String> {
+        match line {
+            "ROCK" => Ok("ROCK"),
+            "PAPER" => Ok("PAPER"),
+            "SCISSORS" => Ok("SCISSORS"),
+            _ => Err(format!("Invalid move \"{}\"", line)),
+        }
+    }
+
+    fn resolve(&mut self, moves: Vec<Option<&'static str>>, out: &mut Output) {
+        // When somebody retires, the opponent has nothing to answer to
+        let retired = moves.contains(&None);
+        for (i, m) in moves.iter().enumerate() {
+            match m {
+                Some(x) if retired => out.spectators(x),
+                Some(x) => out.others(i, x),
+                None => out.others(i, "RETIRE"),
+            }
+        }
+        if let [Some(x), Some(y)] = moves[..] {
+            if beats(x, y) {
+                self.wins[0] += 1;
+            } else if beats(y, x) {
+                self.wins[1] += 1;
+            }
+        }
+        self.played += 1;
+    }
+
+    fn forfeit(&mut self, failed: &[bool]) -> Ending {
+        Ending {
+            results: self.results(failed),
+            details: None,
         }
-    }};
+    }
 }
 
 #[async_trait]
 impl game::Instance for Instance {
     async fn start(
         &mut self,
-        players: HashMap<String, DuplexStream>,
-        mut spectators: WriteHalf<DuplexStream>,
-    ) {
-        let mut p = Player::from(players, &mut self.rng);
+        players: Vec<(String, DuplexStream)>,
+        spectators: WriteHalf<DuplexStream>,
+    ) -> MatchOutcome {
+        let p = Player::from(players);
         assert_eq!(p.len(), 2);
-        for i in 0..2 {
-            lnout2!(spectators, &p[i].name);
-            lnout2!(p[i].output, &p[i].name);
-            lnout2!(p[i].output, &p[1 - i].name);
-            lnout2!(p[i].output, format!("{}", self.rounds));
-        }
-        lnout2!(spectators, format!("{}", self.rounds));
+        let roshambo = Roshambo {
+            rounds: self.rounds,
+            played: 0,
+            wins: [0; 2],
+        };
         let tout = Duration::from_secs_f64(self.timeout);
         let pace = Duration::from_secs_f64(self.pace);
-        let mut p1 = p.pop().expect("Cannot fail");
-        let mut p0 = p.pop().expect("Cannot fail");
-        for _ in 0..self.rounds {
-            let start = Instant::now();
-            let mut l0 = String::new();
-            let mut l1 = String::new();
-            let (r0, r1) = join!(
-                timeout(tout, p0.input.read_line(&mut l0)),
-                timeout(tout, p1.input.read_line(&mut l1))
-            );
-            let r0 = process!(r0, l0);
-            let r1 = process!(r1, l1);
-            sleep_until(start + pace).await;
-            match (r0, r1) {
-                (Some(x), Some(y)) => {
-                    lnout2!(p1.output, x);
-                    lnout2!(spectators, x);
-                    lnout2!(p0.output, y);
-                    lnout2!(spectators, y);
-                }
-                (None, Some(y)) => {
-                    lnout2!(p1.output, "RETIRE");
-                    lnout2!(spectators, "RETIRE");
-                    lnout2!(spectators, y);
-                    break;
-                }
-                (Some(x), None) => {
-                    lnout2!(p0.output, "RETIRE");
-                    lnout2!(spectators, x);
-                    lnout2!(spectators, "RETIRE");
-                    break;
-                }
-                (None, None) => {
-                    lnout2!(spectators, "RETIRE");
-                    lnout2!(spectators, "RETIRE");
-                    break;
-                }
-            };
-        }
-    }
-
-    async fn args(&self) -> HashMap<String, String> {
-        HashMap::from([
-            ("pace".to_owned(), format!("{:?}", self.pace)),
-            ("rounds".to_owned(), format!("{}", self.rounds)),
-        ])
+        util::play_rounds(roshambo, p, spectators, tout, pace, Policy::Forfeit).await
     }
 }
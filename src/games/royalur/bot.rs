@@ -1,22 +1,44 @@
+use super::super::util::LineReader;
 use super::logic::Board;
 use crate::game;
+use crate::tuning::MAX_LINE_LENGTH;
 use async_trait::async_trait;
-use rand::rngs::{OsRng, StdRng};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use rand::SeedableRng;
-use tokio::io::{split, AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::io::{split, AsyncWriteExt, DuplexStream};
 use tracing::error;
 
 #[derive(Debug)]
-pub(crate) struct Bot {}
+pub(crate) struct Bot {
+    pub(crate) seed: u64,
+    /// Pick the move that looks best right now, instead of a random one
+    pub(crate) greedy: bool,
+}
+
+/// How good moving `token` looks: bringing it home is better than capturing,
+/// which is better than landing on a rosette
+fn score(board: &Board, me: usize, token: usize, roll: usize) -> usize {
+    let mut after = board.clone();
+    let again = after.make_move(me, token, roll).expect("Cannot fail");
+    if after.completed(me) > board.completed(me) {
+        3
+    } else if after.waiting(1 - me) > board.waiting(1 - me) {
+        2
+    } else if again {
+        1
+    } else {
+        0
+    }
+}
 
 #[async_trait]
 impl game::Bot for Bot {
     async fn start(&mut self, stream: DuplexStream) {
         let mut board = Board::new();
-        let mut rng = StdRng::from_rng(OsRng).expect("Cannot initialize PRNG");
+        let mut rng = StdRng::seed_from_u64(self.seed);
         let (input, mut output) = split(stream);
-        let mut input = BufReader::new(input);
+        let mut input = LineReader::new(input, MAX_LINE_LENGTH);
         lnin!(input); // Read my name
         lnin!(input); // Read opponent name
         let me: usize = lnin!(input).parse().expect("Cannot parse player number");
@@ -28,7 +50,12 @@ impl game::Bot for Bot {
                 .map(|x| x.parse::<usize>().expect("Cannot parse die roll"))
                 .sum();
             if turn == me {
-                if let Some(x) = board.valid_moves(me, roll).choose(&mut rng) {
+                let mut moves = board.valid_moves(me, roll);
+                if self.greedy {
+                    let best = moves.iter().map(|&x| score(&board, me, x, roll)).max();
+                    moves.retain(|&x| Some(score(&board, me, x, roll)) == best);
+                }
+                if let Some(x) = moves.choose(&mut rng) {
                     if board.make_move(me, *x, roll).expect("Cannot fail") {
                         turn = 1 - turn;
                     }
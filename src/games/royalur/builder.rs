@@ -2,12 +2,11 @@ use super::bot::Bot;
 use super::instance::Instance;
 use crate::game;
 use crate::games;
-use crate::proto::GameArgInfo;
+use crate::proto::{BotInfo, GameArgInfo, GameArgKind};
 use async_trait::async_trait;
 use games::util::arg;
-use rand::rngs::{OsRng, StdRng};
-use rand::SeedableRng;
-use regex::Regex;
+use rand::rngs::StdRng;
+use rand::{random, SeedableRng};
 use std::collections::HashMap;
 use std::hash::Hash;
 use tokio::time::Duration;
@@ -37,8 +36,13 @@ impl game::Builder for Builder {
         HashMap::from([(
             "pace".to_owned(),
             GameArgInfo {
-                description: "How fast the game plays (0-30)".to_owned(),
-                regex: "^(30|([12][0-9]|[0-9])(.[0-9]*)?)$".to_owned(),
+                description: "Pause between moves, to let spectators follow".to_owned(),
+                kind: GameArgKind::Float {
+                    min: 0.0,
+                    max: 30.0,
+                },
+                default: DEFAULT_PACE.to_string(),
+                unit: Some("seconds".to_owned()),
             },
         )])
     }
@@ -55,30 +59,35 @@ impl game::Builder for Builder {
         };
 
         param.timeout = param.timeout.or(Some(DEFAULT_TIMEOUT));
-        let constraints = self.args().await;
-
-        let pace_reg = Regex::new(&constraints["pace"].regex).unwrap();
-        let pace = match arg(&args, "pace", DEFAULT_PACE) {
-            Ok(x) => {
-                if !pace_reg.is_match(&x.to_string()) {
-                    return Err(format!("Invalid pace"));
-                } else {
-                    x
-                }
-            }
-            Err(x) => return Err(format!("Invaid pace: {}", x)),
-        };
-        let rng = match StdRng::from_rng(OsRng) {
-            Ok(x) => x,
-            Err(x) => return Err(format!("Cannot initialize PRNG: {}", x)),
-        };
+        let pace = arg(&args, "pace");
+        let rng = StdRng::seed_from_u64(*param.seed.get_or_insert_with(random));
         Ok(Box::new(Instance {
             timeout: Duration::from_secs_f64(param.timeout.expect("Cannot fail")),
             pace: Duration::from_secs_f64(pace),
+            clock: param.clock,
             rng: rng,
         }))
     }
-    async fn gen_bot(&self) -> Box<dyn game::Bot> {
-        Box::new(Bot {})
+    async fn bots(&self) -> Vec<BotInfo> {
+        vec![
+            BotInfo {
+                name: "random".to_owned(),
+                description: "Moves a random token".to_owned(),
+            },
+            BotInfo {
+                name: "greedy".to_owned(),
+                description: "Prefers bringing tokens home, then captures, then rosettes"
+                    .to_owned(),
+            },
+        ]
+    }
+    async fn seats(&self) -> Vec<String> {
+        vec!["first".to_owned(), "second".to_owned()]
+    }
+    async fn gen_bot(&self, name: &str, seed: u64) -> Box<dyn game::Bot> {
+        Box::new(Bot {
+            seed,
+            greedy: name == "greedy",
+        })
     }
 }
@@ -1,109 +1,105 @@
-use super::super::util::Player;
+use super::super::util::{self, Ending, Output, Player, TurnBasedGame};
 use super::logic::Board;
-use crate::game;
+use crate::game::{self, MatchOutcome, PlayerResult, TimeControl};
 use async_trait::async_trait;
 use rand::rngs::StdRng;
 use rand::Rng;
-use std::collections::HashMap;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, DuplexStream, WriteHalf};
-use tokio::time::{sleep_until, timeout, Duration, Instant};
-use tracing::warn;
+use tokio::io::{DuplexStream, WriteHalf};
+use tokio::time::Duration;
 
 #[derive(Debug)]
 pub(crate) struct Instance {
     pub(crate) timeout: Duration,
     pub(crate) pace: Duration,
+    pub(crate) clock: Option<TimeControl>,
     pub(crate) rng: StdRng,
 }
 
-macro_rules! retired {
-    ($other:expr, $spectators:expr) => {{
-        lnout2!($other, "RETIRE");
-        lnout2!($spectators, "RETIRE");
-        break;
-    }};
+/// State of a match
+struct Ur {
+    board: Board,
+    rng: StdRng,
+    turn: usize,
+    roll: usize,
 }
 
-#[async_trait]
-impl game::Instance for Instance {
-    async fn start(
-        &mut self,
-        players: HashMap<String, DuplexStream>,
-        mut spectators: WriteHalf<DuplexStream>,
-    ) {
-        let mut board = Board::new();
-        let mut p = Player::from(players, &mut self.rng);
-        assert_eq!(p.len(), 2);
+impl TurnBasedGame for Ur {
+    type Move = usize;
+
+    fn start(&mut self, names: &[String], out: &mut Output) {
         // Send names in order
-        for i in 0..2 {
-            lnout2!(p[0].output, &p[i].name);
-            lnout2!(p[1].output, &p[i].name);
-            lnout2!(spectators, &p[i].name);
+        for name in names {
+            out.all(name);
         }
         // Send player index to players
-        lnout2!(p[0].output, "0");
-        lnout2!(p[1].output, "1");
-        let mut turn = 0;
-        while !board.finished() {
-            let start = Instant::now();
-            // Generate dice roll
-            let d: Vec<_> = (0..4).map(|_| self.rng.gen::<bool>() as usize).collect();
-            let roll = d.iter().sum::<usize>();
-            let d: Vec<_> = d.into_iter().map(|x| format!("{}", x)).collect();
-            let d = d.join(" ");
-            // Send dice roll
-            lnout2!(p[0].output, &d);
-            lnout2!(p[1].output, &d);
-            lnout2!(spectators, &d);
-            if board.valid_moves(turn, roll).len() == 0 {
-                // The current player has no valid moves
-                turn = 1 - turn;
-                // Keep the pace
-                sleep_until(start + self.pace).await;
-                continue;
-            }
-            // Read move
-            let mut buffer = String::new();
-            let token = match timeout(self.timeout, p[turn].input.read_line(&mut buffer)).await {
-                // Timed out or closed connection
-                Err(_) | Ok(Err(_)) => retired!(p[1 - turn].output, spectators),
-                // Parse response
-                Ok(Ok(_)) => match buffer.trim().parse::<usize>() {
-                    // Token value too high
-                    Ok(x) if x >= 7 => retired!(p[1 - turn].output, spectators),
-                    // Valid token value
-                    Ok(x) => x,
-                    // Other garbage
-                    Err(_) => retired!(p[1 - turn].output, spectators),
-                },
-            };
-            // Keep the pace
-            sleep_until(start + self.pace).await;
-            let start = Instant::now();
-            match board.make_move(turn, token, roll) {
-                // Normal move
-                Ok(again) => {
-                    let m = format!("{}", token);
-                    lnout2!(p[1 - turn].output, &m);
-                    lnout2!(spectators, &m);
-                    if again {
-                        turn = 1 - turn;
-                    }
-                }
-                // Wrong move
-                Err(x) => {
-                    warn!("Wrong move: {}", x);
-                    retired!(p[1 - turn].output, spectators);
-                }
-            }
-            // Give turn to other player
-            turn = 1 - turn;
-            // Keep the pace
-            sleep_until(start + self.pace).await;
+        out.player(0, "0");
+        out.player(1, "1");
+    }
+
+    fn outcome(&mut self, _out: &mut Output) -> Option<Ending> {
+        let winner = self.board.winner()?;
+        let results = (0..2)
+            .map(|i| match i == winner {
+                true => (PlayerResult::Win, Some(self.board.completed(i) as f64)),
+                false => (PlayerResult::Loss, Some(self.board.completed(i) as f64)),
+            })
+            .collect();
+        Some(Ending {
+            results,
+            details: None,
+        })
+    }
+
+    fn turn(&mut self, out: &mut Output) -> Option<usize> {
+        // Generate and send dice roll
+        let d: Vec<_> = (0..4).map(|_| self.rng.gen::<bool>() as usize).collect();
+        self.roll = d.iter().sum::<usize>();
+        let d: Vec<_> = d.into_iter().map(|x| format!("{}", x)).collect();
+        out.all(d.join(" "));
+        if self.board.valid_moves(self.turn, self.roll).is_empty() {
+            // The current player has no valid moves
+            self.turn = 1 - self.turn;
+            return None;
+        }
+        Some(self.turn)
+    }
+
+    fn parse(&self, player: usize, line: &str) -> Result<usize, String> {
+        match line.parse::<usize>() {
+            Ok(x) if self.board.valid_moves(player, self.roll).contains(&x) => Ok(x),
+            Ok(x) => Err(format!("Token {} cannot move by {}", x, self.roll)),
+            Err(_) => Err(format!("\"{}\" is not a token", line)),
+        }
+    }
+
+    fn apply(&mut self, player: usize, token: usize, out: &mut Output) {
+        let again = self
+            .board
+            .make_move(player, token, self.roll)
+            .expect("Cannot fail");
+        out.others(player, format!("{}", token));
+        // Give turn to other player, unless the move grants another one
+        if !again {
+            self.turn = 1 - self.turn;
         }
     }
+}
 
-    async fn args(&self) -> HashMap<String, String> {
-        HashMap::from([("pace".to_owned(), format!("{:?}", self.pace))])
+#[async_trait]
+impl game::Instance for Instance {
+    async fn start(
+        &mut self,
+        players: Vec<(String, DuplexStream)>,
+        spectators: WriteHalf<DuplexStream>,
+    ) -> MatchOutcome {
+        let p = Player::from(players);
+        assert_eq!(p.len(), 2);
+        let ur = Ur {
+            board: Board::new(),
+            rng: self.rng.clone(),
+            turn: 0,
+            roll: 0,
+        };
+        util::play_turns(ur, p, spectators, self.timeout, self.pace, self.clock).await
     }
 }
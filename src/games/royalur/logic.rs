@@ -11,7 +11,7 @@ enum Position {
     End(Player),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Board {
     board: [[Option<Token>; 8]; 3],
     position: [[Position; 7]; 2],
@@ -138,14 +138,26 @@ impl Board {
     }
 
     pub(crate) fn finished(&self) -> bool {
-        self.position.iter().any(|x| {
-            x.iter().all(|&y| {
-                if let Position::End(_) = y {
-                    true
-                } else {
-                    false
-                }
-            })
-        })
+        self.winner().is_some()
+    }
+
+    /// Number of tokens that `player` has already brought to the end
+    pub(crate) fn completed(&self, player: usize) -> usize {
+        self.position[player]
+            .iter()
+            .filter(|x| matches!(x, Position::End(_)))
+            .count()
+    }
+
+    /// Number of tokens of `player` that are still waiting to enter the board
+    pub(crate) fn waiting(&self, player: usize) -> usize {
+        self.position[player]
+            .iter()
+            .filter(|x| matches!(x, Position::Start(_)))
+            .count()
+    }
+
+    pub(crate) fn winner(&self) -> Option<usize> {
+        (0..2).find(|&x| self.completed(x) == 7)
     }
 }
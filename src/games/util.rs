@@ -1,39 +1,627 @@
-use rand::seq::SliceRandom;
-use rand::Rng;
-use std::collections::HashMap;
+use crate::game::{MatchOutcome, PlayerOutcome, PlayerResult, Termination, TimeControl};
+use crate::tuning::MAX_LINE_LENGTH;
+use futures_util::future::join_all;
+use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
-use tokio::io::{split, BufReader, DuplexStream, ReadHalf, WriteHalf};
+use tokio::io::{
+    split, AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader, DuplexStream, ReadHalf, WriteHalf,
+};
+use tokio::time::{sleep_until, timeout_at, Duration, Instant};
+use tracing::warn;
+
+/// Why a line could not be read
+#[derive(Debug)]
+pub(crate) enum LineError {
+    /// The stream was closed or broke down
+    Closed,
+    /// The other end does not respect the protocol, sending lines
+    /// that are too long or not valid UTF-8
+    Violation(String),
+}
+
+/// Reads lines of bounded length, so that nobody can fill the memory
+/// of the server by never ending a line
+#[derive(Debug)]
+pub(crate) struct LineReader<R> {
+    input: BufReader<R>,
+    line: Vec<u8>,
+    max: usize,
+}
+
+impl<R: AsyncRead + Unpin> LineReader<R> {
+    /// Read from `input` lines of at most `max` bytes, newline excluded
+    pub(crate) fn new(input: R, max: usize) -> LineReader<R> {
+        LineReader {
+            input: BufReader::new(input),
+            line: Vec::new(),
+            max,
+        }
+    }
+
+    /// Read the next line, without the newline. Data read so far is kept
+    /// when the future is dropped, so a line can be awaited within a timeout.
+    pub(crate) async fn next(&mut self) -> Result<String, LineError> {
+        loop {
+            let buffer = match self.input.fill_buf().await {
+                Ok([]) => return Err(LineError::Closed),
+                Ok(x) => x,
+                Err(_) => return Err(LineError::Closed),
+            };
+            let (chunk, end) = match buffer.iter().position(|x| *x == b'\n') {
+                Some(i) => (&buffer[..i], true),
+                None => (buffer, false),
+            };
+            let size = chunk.len();
+            if self.line.len() + size > self.max {
+                return Err(LineError::Violation(format!(
+                    "Line longer than {} bytes",
+                    self.max
+                )));
+            }
+            self.line.extend_from_slice(chunk);
+            self.input.consume(size + end as usize);
+            if end {
+                let line = std::mem::take(&mut self.line);
+                return String::from_utf8(line)
+                    .map_err(|_| LineError::Violation("Line is not valid UTF-8".to_string()));
+            }
+        }
+    }
+}
 
 pub(crate) struct Player {
     pub(crate) name: String,
-    pub(crate) input: BufReader<ReadHalf<DuplexStream>>,
+    pub(crate) input: LineReader<ReadHalf<DuplexStream>>,
     pub(crate) output: WriteHalf<DuplexStream>,
 }
 
 impl Player {
-    pub(crate) fn from<T: Rng>(players: HashMap<String, DuplexStream>, rng: &mut T) -> Vec<Player> {
-        let mut p = Vec::new();
-        for (name, stream) in players.into_iter() {
-            let (r, w) = split(stream);
-            p.push(Player {
-                name: name,
-                input: BufReader::new(r),
-                output: w,
+    /// Wrap the streams of the players, which are given in seat order
+    pub(crate) fn from(players: Vec<(String, DuplexStream)>) -> Vec<Player> {
+        players
+            .into_iter()
+            .map(|(name, stream)| {
+                let (r, w) = split(stream);
+                Player {
+                    name,
+                    input: LineReader::new(r, MAX_LINE_LENGTH),
+                    output: w,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Build an outcome assigning `results[i]` to `players[i]`
+pub(crate) fn outcome(
+    players: &[Player],
+    results: Vec<(PlayerResult, Option<f64>)>,
+    termination: Termination,
+) -> MatchOutcome {
+    let players: BTreeMap<_, _> = players
+        .iter()
+        .zip(results)
+        .map(|(p, (result, score))| {
+            (
+                p.name.clone(),
+                PlayerOutcome {
+                    result,
+                    score,
+                    time_left: None,
+                },
+            )
+        })
+        .collect();
+    MatchOutcome {
+        players,
+        termination,
+        details: None,
+    }
+}
+
+/// Text for the players and spectators of a match, written by the rules
+/// of a game and delivered by the driver playing it
+#[derive(Debug)]
+pub(crate) struct Output {
+    players: Vec<String>,
+    spectators: String,
+}
+
+impl Output {
+    pub(crate) fn new(players: usize) -> Output {
+        Output {
+            players: vec![String::new(); players],
+            spectators: String::new(),
+        }
+    }
+
+    /// Send a line to a single player
+    pub(crate) fn player(&mut self, i: usize, line: impl AsRef<str>) {
+        self.players[i] += line.as_ref();
+        self.players[i].push('\n');
+    }
+
+    /// Send a line to the spectators
+    pub(crate) fn spectators(&mut self, line: impl AsRef<str>) {
+        self.spectators += line.as_ref();
+        self.spectators.push('\n');
+    }
+
+    /// Send a line to every player and the spectators
+    pub(crate) fn all(&mut self, line: impl AsRef<str>) {
+        self.others(usize::MAX, line);
+    }
+
+    /// Send a line to the spectators and every player but `i`
+    pub(crate) fn others(&mut self, i: usize, line: impl AsRef<str>) {
+        for j in 0..self.players.len() {
+            if j != i {
+                self.player(j, &line);
+            }
+        }
+        self.spectators(line);
+    }
+
+    /// Deliver everything written so far. Players that cannot be reached
+    /// are ignored here, they are going to fail when asked to move.
+    async fn flush(&mut self, players: &mut [Player], spectators: &mut WriteHalf<DuplexStream>) {
+        for (player, text) in players.iter_mut().zip(self.players.iter_mut()) {
+            if !text.is_empty() {
+                if let Err(x) = player.output.write_all(text.as_bytes()).await {
+                    warn!("Cannot write to \"{}\": {}", player.name, x);
+                }
+                text.clear();
+            }
+        }
+        if !self.spectators.is_empty() {
+            if let Err(x) = spectators.write_all(self.spectators.as_bytes()).await {
+                warn!("Cannot write to spectators: {}", x);
+            }
+            self.spectators.clear();
+        }
+    }
+}
+
+/// How a match ended for each player, in seat order
+#[derive(Debug)]
+pub(crate) struct Ending {
+    pub(crate) results: Vec<(PlayerResult, Option<f64>)>,
+    pub(crate) details: Option<String>,
+}
+
+impl Ending {
+    /// Player `winner` wins and everybody else loses
+    pub(crate) fn winner(players: usize, winner: usize) -> Ending {
+        Ending {
+            results: (0..players)
+                .map(|i| match i == winner {
+                    true => (PlayerResult::Win, None),
+                    false => (PlayerResult::Loss, None),
+                })
+                .collect(),
+            details: None,
+        }
+    }
+
+    /// Player `loser` loses and everybody else wins
+    pub(crate) fn loser(players: usize, loser: usize) -> Ending {
+        Ending {
+            results: (0..players)
+                .map(|i| match i == loser {
+                    true => (PlayerResult::Loss, None),
+                    false => (PlayerResult::Win, None),
+                })
+                .collect(),
+            details: None,
+        }
+    }
+
+    /// Every player draws
+    pub(crate) fn draw(players: usize) -> Ending {
+        Ending {
+            results: vec![(PlayerResult::Draw, None); players],
+            details: None,
+        }
+    }
+
+    fn outcome(self, players: &[Player], termination: Termination) -> MatchOutcome {
+        MatchOutcome {
+            details: self.details,
+            ..outcome(players, self.results, termination)
+        }
+    }
+}
+
+/// The rules of a game where players move one at a time, played by `play_turns`.
+/// Players are identified by their seat, and everything they should see
+/// is written to an `Output`.
+pub(crate) trait TurnBasedGame: Send {
+    type Move: Send;
+
+    /// Introduce the match, given the names of the players in seat order
+    fn start(&mut self, names: &[String], out: &mut Output);
+
+    /// Check before every turn whether the match is over, announcing how it ended
+    fn outcome(&mut self, out: &mut Output) -> Option<Ending>;
+
+    /// Begin a turn, returning who has to move or `None` to skip it
+    fn turn(&mut self, out: &mut Output) -> Option<usize>;
+
+    /// Parse a line sent by `player`, checking that it is a valid move
+    fn parse(&self, player: usize, line: &str) -> Result<Self::Move, String>;
+
+    /// Play a valid move of `player`
+    fn apply(&mut self, player: usize, m: Self::Move, out: &mut Output);
+
+    /// Handle an invalid move, returning whether `player` can try again
+    /// within the same turn instead of retiring
+    fn rejected(&mut self, _player: usize, _reason: &str, _out: &mut Output) -> bool {
+        false
+    }
+
+    /// Announce that `player` retired, because it did not make a valid move in time
+    fn retire(&mut self, player: usize, _termination: &Termination, out: &mut Output) {
+        out.others(player, "RETIRE");
+    }
+
+    /// Take `player` out of the match after it failed to make a valid move
+    /// in time, returning whether the match goes on without it. Otherwise
+    /// `player` retires, losing the match while everybody else wins.
+    fn eliminate(&mut self, _player: usize, _out: &mut Output) -> bool {
+        false
+    }
+}
+
+/// The time left to each player of a match played with a time control
+struct Clock {
+    increment: Duration,
+    delay: Duration,
+    left: Vec<Duration>,
+}
+
+/// Convert seconds to a duration, taking invalid values as zero
+fn seconds(x: f64) -> Duration {
+    Duration::try_from_secs_f64(x).unwrap_or_default()
+}
+
+impl Clock {
+    fn new(control: TimeControl, players: usize) -> Clock {
+        Clock {
+            increment: seconds(control.increment),
+            delay: seconds(control.delay),
+            left: vec![seconds(control.bank); players],
+        }
+    }
+
+    /// Time `player` has to make its move
+    fn allowed(&self, player: usize) -> Duration {
+        self.delay + self.left[player]
+    }
+
+    /// Line telling the time left to every player, in seat order
+    fn line(&self) -> String {
+        let left: Vec<_> = self
+            .left
+            .iter()
+            .map(|x| format!("{:.1}", x.as_secs_f64()))
+            .collect();
+        format!("CLOCK {}", left.join(" "))
+    }
+
+    /// Stop the clock of `player` after `elapsed`, not counting the delay
+    /// and adding the increment when it made its move
+    fn stop(&mut self, player: usize, elapsed: Duration, moved: bool) {
+        let elapsed = elapsed.saturating_sub(self.delay);
+        self.left[player] = self.left[player].saturating_sub(elapsed);
+        if moved {
+            self.left[player] += self.increment;
+        }
+    }
+
+    /// Record the time left to each player in `outcome`
+    fn record(&self, names: &[String], outcome: &mut MatchOutcome) {
+        for (name, left) in names.iter().zip(self.left.iter()) {
+            if let Some(x) = outcome.players.get_mut(name) {
+                x.time_left = Some(left.as_secs_f64());
+            }
+        }
+    }
+}
+
+/// Play a match of a turn based game. Every turn starts at least `pace`
+/// after the previous one, and players retire if they do not make a valid
+/// move within `timeout`, losing the match, unless the game eliminates them.
+/// With a `clock` the time of each move, after its delay, is instead taken
+/// from the bank of the player, which is sent to it and to the spectators
+/// before the move as `CLOCK` followed by the seconds left to every player.
+pub(crate) async fn play_turns<G: TurnBasedGame>(
+    mut game: G,
+    mut players: Vec<Player>,
+    mut spectators: WriteHalf<DuplexStream>,
+    timeout: Duration,
+    pace: Duration,
+    clock: Option<TimeControl>,
+) -> MatchOutcome {
+    let mut out = Output::new(players.len());
+    let names: Vec<_> = players.iter().map(|x| x.name.clone()).collect();
+    let mut clock = clock.map(|x| Clock::new(x, players.len()));
+    let mut eliminated = Vec::new();
+    game.start(&names, &mut out);
+    out.flush(&mut players, &mut spectators).await;
+    loop {
+        if let Some(mut ending) = game.outcome(&mut out) {
+            out.flush(&mut players, &mut spectators).await;
+            let termination = match eliminated.is_empty() {
+                true => Termination::Normal,
+                false => {
+                    let (termination, details) = blame(&eliminated, &names);
+                    ending.details = ending.details.or(details);
+                    termination
+                }
+            };
+            let mut outcome = ending.outcome(&players, termination);
+            if let Some(ref x) = clock {
+                x.record(&names, &mut outcome);
+            }
+            return outcome;
+        }
+        let start = Instant::now();
+        let turn = game.turn(&mut out);
+        if let (Some(i), Some(ref x)) = (turn, &clock) {
+            let line = x.line();
+            out.player(i, &line);
+            out.spectators(line);
+        }
+        out.flush(&mut players, &mut spectators).await;
+        let i = match turn {
+            Some(x) => x,
+            None => {
+                sleep_until(start + pace).await;
+                continue;
+            }
+        };
+        let deadline = match clock {
+            Some(ref x) => start + x.allowed(i),
+            None => start + timeout,
+        };
+        let result = loop {
+            match timeout_at(deadline, players[i].input.next()).await {
+                Err(_) => break Err((Failure::Timeout, None)),
+                Ok(Err(LineError::Closed)) => break Err((Failure::Disconnect, None)),
+                Ok(Err(LineError::Violation(x))) => {
+                    warn!("Protocol violation of \"{}\": {}", names[i], x);
+                    break Err((Failure::Violation, Some(x)));
+                }
+                Ok(Ok(line)) => match game.parse(i, line.trim()) {
+                    Ok(m) => break Ok(m),
+                    Err(x) => {
+                        let retry = game.rejected(i, &x, &mut out);
+                        out.flush(&mut players, &mut spectators).await;
+                        if !retry {
+                            warn!("Wrong move of \"{}\": {}", names[i], x);
+                            break Err((Failure::Invalid, Some(x)));
+                        }
+                    }
+                },
+            }
+        };
+        if let Some(ref mut x) = clock {
+            x.stop(i, start.elapsed(), result.is_ok());
+        }
+        match result {
+            Ok(m) => {
+                sleep_until(start + pace).await;
+                game.apply(i, m, &mut out);
+                out.flush(&mut players, &mut spectators).await;
+            }
+            Err((failure, details)) => {
+                if game.eliminate(i, &mut out) {
+                    out.flush(&mut players, &mut spectators).await;
+                    eliminated.push((i, failure, details));
+                    continue;
+                }
+                let termination = failure.termination(vec![names[i].clone()]);
+                game.retire(i, &termination, &mut out);
+                out.flush(&mut players, &mut spectators).await;
+                let mut ending = Ending::loser(players.len(), i);
+                ending.details = details;
+                let mut outcome = ending.outcome(&players, termination);
+                if let Some(ref x) = clock {
+                    x.record(&names, &mut outcome);
+                }
+                return outcome;
+            }
+        }
+    }
+}
+
+/// Why a player did not make a valid move
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Failure {
+    Timeout,
+    Disconnect,
+    Invalid,
+    Violation,
+}
+
+impl Failure {
+    fn termination(self, players: Vec<String>) -> Termination {
+        match self {
+            Failure::Timeout => Termination::Timeout(players),
+            Failure::Disconnect => Termination::Disconnect(players),
+            Failure::Invalid => Termination::IllegalMove(players),
+            Failure::Violation => Termination::ProtocolViolation(players),
+        }
+    }
+}
+
+/// How a match ended after some players failed to move, blaming the ones
+/// that failed in the same way as the first one, with its details
+fn blame(
+    failures: &[(usize, Failure, Option<String>)],
+    names: &[String],
+) -> (Termination, Option<String>) {
+    let (_, kind, ref details) = failures[0];
+    let culprits = failures
+        .iter()
+        .filter(|(_, failure, _)| *failure == kind)
+        .map(|(i, _, _)| names[*i].clone())
+        .collect();
+    (kind.termination(culprits), details.clone())
+}
+
+/// What happens to a player of a simultaneous game
+/// that does not make a valid move in time
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub(crate) enum Policy<M> {
+    /// The player retires and loses, ending the match
+    Forfeit,
+    /// The move is played in place of the player
+    Default(M),
+    /// The player is out, while the match goes on without it
+    Eliminate,
+}
+
+/// The rules of a game where every player moves at the same time in rounds,
+/// played by `play_rounds`
+pub(crate) trait SimultaneousGame: Send {
+    type Move: Send + Clone;
+
+    /// Introduce the match, given the names of the players in seat order
+    fn start(&mut self, names: &[String], out: &mut Output);
+
+    /// Check before every round whether the match is over, announcing how it ended
+    fn outcome(&mut self, out: &mut Output) -> Option<Ending>;
+
+    /// Parse a line sent by `player`, checking that it is a valid move
+    fn parse(&self, player: usize, line: &str) -> Result<Self::Move, String>;
+
+    /// Play a round given the move of every player, which is `None`
+    /// for the ones that failed to move or are out of the match
+    fn resolve(&mut self, moves: Vec<Option<Self::Move>>, out: &mut Output);
+
+    /// End the match after the `failed` players forfeited it
+    fn forfeit(&mut self, failed: &[bool]) -> Ending {
+        Ending {
+            results: failed
+                .iter()
+                .map(|&x| match x {
+                    true => (PlayerResult::Loss, None),
+                    false => (PlayerResult::Win, None),
+                })
+                .collect(),
+            details: None,
+        }
+    }
+}
+
+/// Play a match of a simultaneous game. Moves are gathered from all players
+/// at once, each one within `timeout`, and every round lasts at least `pace`.
+/// Players failing to move are handled according to `policy`: the match ends
+/// when they forfeit or when every player is eliminated.
+pub(crate) async fn play_rounds<G: SimultaneousGame>(
+    mut game: G,
+    mut players: Vec<Player>,
+    mut spectators: WriteHalf<DuplexStream>,
+    timeout: Duration,
+    pace: Duration,
+    policy: Policy<G::Move>,
+) -> MatchOutcome {
+    let mut out = Output::new(players.len());
+    let names: Vec<_> = players.iter().map(|x| x.name.clone()).collect();
+    let mut active = vec![true; players.len()];
+    let mut eliminated = Vec::new();
+    game.start(&names, &mut out);
+    out.flush(&mut players, &mut spectators).await;
+    loop {
+        if let Some(mut ending) = game.outcome(&mut out) {
+            out.flush(&mut players, &mut spectators).await;
+            let termination = match eliminated.is_empty() {
+                true => Termination::Normal,
+                false => {
+                    let (termination, details) = blame(&eliminated, &names);
+                    ending.details = ending.details.or(details);
+                    termination
+                }
+            };
+            return ending.outcome(&players, termination);
+        }
+        let start = Instant::now();
+        let game_ref = &game;
+        let reads = players.iter_mut().zip(active.iter()).enumerate().map(
+            |(i, (player, &active))| async move {
+                if !active {
+                    return None;
+                }
+                Some(
+                    match timeout_at(start + timeout, player.input.next()).await {
+                        Err(_) => Err((Failure::Timeout, None)),
+                        Ok(Err(LineError::Closed)) => Err((Failure::Disconnect, None)),
+                        Ok(Err(LineError::Violation(x))) => Err((Failure::Violation, Some(x))),
+                        Ok(Ok(line)) => game_ref
+                            .parse(i, line.trim())
+                            .map_err(|x| (Failure::Invalid, Some(x))),
+                    },
+                )
+            },
+        );
+        let results = join_all(reads).await;
+        sleep_until(start + pace).await;
+
+        let mut moves = Vec::new();
+        let mut failures = Vec::new();
+        for (i, result) in results.into_iter().enumerate() {
+            moves.push(match result {
+                Some(Ok(m)) => Some(m),
+                Some(Err((failure, details))) => {
+                    warn!("\"{}\" failed to move: {:?}", names[i], failure);
+                    failures.push((i, failure, details));
+                    match policy {
+                        Policy::Default(ref m) => Some(m.clone()),
+                        Policy::Forfeit | Policy::Eliminate => None,
+                    }
+                }
+                None => None,
             });
         }
-        p.shuffle(rng);
-        p
+        game.resolve(moves, &mut out);
+        out.flush(&mut players, &mut spectators).await;
+
+        match policy {
+            Policy::Forfeit => {}
+            Policy::Default(_) => continue,
+            Policy::Eliminate => {
+                for (i, _, _) in failures.iter() {
+                    active[*i] = false;
+                }
+                eliminated.append(&mut failures);
+                if active.contains(&true) {
+                    continue;
+                }
+                // Nobody is left, so they all forfeit
+                failures = std::mem::take(&mut eliminated);
+            }
+        }
+        if !failures.is_empty() {
+            let mut failed = vec![false; players.len()];
+            for (i, _, _) in failures.iter() {
+                failed[*i] = true;
+            }
+            let (termination, details) = blame(&failures, &names);
+            let mut ending = game.forfeit(&failed);
+            ending.details = ending.details.or(details);
+            return ending.outcome(&players, termination);
+        }
     }
 }
 
-pub(crate) fn arg<T: FromStr>(
-    m: &HashMap<String, String>,
-    a: &str,
-    d: T,
-) -> Result<T, <T as FromStr>::Err> {
-    match m.get(a) {
-        Some(x) => x.parse(),
-        None => Ok(d),
+/// Read an argument checked by `game::check_args`, which makes sure that it is
+/// given, within range and of the kind the game declared
+pub(crate) fn arg<T: FromStr>(m: &HashMap<String, String>, a: &str) -> T {
+    match m.get(a).map(|x| x.parse()) {
+        Some(Ok(x)) => x,
+        _ => panic!("Argument \"{}\" was not checked", a),
     }
 }
 
@@ -50,28 +638,453 @@ macro_rules! lnout {
     }};
 }
 
+/// Read a line for a server bot, skipping the ones sent by the clock
 macro_rules! lnin {
     ($stream:expr) => {{
-        let mut s = String::new();
-        match $stream.read_line(&mut s).await {
-            Ok(_) => s.trim().to_string(),
-            Err(x) => {
-                error!("Cannot read from stream: {}", x);
-                return;
+        loop {
+            match $stream.next().await {
+                Ok(x) if x.starts_with("CLOCK ") => continue,
+                Ok(x) => break x.trim().to_string(),
+                Err($crate::games::util::LineError::Closed) => return,
+                Err($crate::games::util::LineError::Violation(x)) => {
+                    error!("Cannot read from stream: {}", x);
+                    return;
+                }
             }
         }
     }};
 }
 
-macro_rules! lnout2 {
-    ($stream:expr, $msg:expr) => {{
-        let msg = String::from($msg) + "\n";
-        match $stream.write_all(msg.as_bytes()).await {
-            Ok(_) => true,
-            Err(x) => {
-                warn!("Cannot write to stream: {}", x);
-                false
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{duplex, AsyncReadExt};
+
+    async fn reader(data: &[u8], max: usize) -> LineReader<DuplexStream> {
+        let (mut w, r) = duplex(1024);
+        w.write_all(data).await.unwrap();
+        LineReader::new(r, max)
+    }
+
+    fn violation(x: Result<String, LineError>) -> String {
+        match x {
+            Err(LineError::Violation(x)) => x,
+            x => panic!("not a violation: {:?}", x),
+        }
+    }
+
+    #[tokio::test]
+    async fn lines() {
+        let mut r = reader(b"a\n\nbc d\n", 4).await;
+        assert_eq!(r.next().await.unwrap(), "a");
+        assert_eq!(r.next().await.unwrap(), "");
+        assert_eq!(r.next().await.unwrap(), "bc d");
+        assert!(matches!(r.next().await, Err(LineError::Closed)));
+    }
+
+    #[tokio::test]
+    async fn unterminated_line() {
+        let mut r = reader(b"a\nbc", 4).await;
+        assert_eq!(r.next().await.unwrap(), "a");
+        assert!(matches!(r.next().await, Err(LineError::Closed)));
+    }
+
+    #[tokio::test]
+    async fn long_line() {
+        let mut r = reader(b"abcd\nabcde\n", 4).await;
+        assert_eq!(r.next().await.unwrap(), "abcd");
+        assert_eq!(violation(r.next().await), "Line longer than 4 bytes");
+    }
+
+    #[tokio::test]
+    async fn long_line_in_pieces() {
+        let (mut w, r) = duplex(1024);
+        let mut r = LineReader::new(r, 4);
+        w.write_all(b"abc").await.unwrap();
+        let short = Duration::from_millis(10);
+        assert!(tokio::time::timeout(short, r.next()).await.is_err());
+        w.write_all(b"de\n").await.unwrap();
+        assert_eq!(violation(r.next().await), "Line longer than 4 bytes");
+    }
+
+    #[tokio::test]
+    async fn invalid_utf8() {
+        let mut r = reader(b"\xff\xfe\nok\n", 4).await;
+        assert_eq!(violation(r.next().await), "Line is not valid UTF-8");
+        assert_eq!(r.next().await.unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn line_survives_timeout() {
+        let (mut w, r) = duplex(1024);
+        let mut r = LineReader::new(r, 16);
+        w.write_all(b"ab").await.unwrap();
+        let short = Duration::from_millis(10);
+        assert!(tokio::time::timeout(short, r.next()).await.is_err());
+        w.write_all(b"c\n").await.unwrap();
+        assert_eq!(r.next().await.unwrap(), "abc");
+    }
+
+    fn control(bank: f64, increment: f64, delay: f64) -> TimeControl {
+        TimeControl {
+            bank,
+            increment,
+            delay,
+        }
+    }
+
+    #[test]
+    fn clock_increment() {
+        let mut c = Clock::new(control(10.0, 2.0, 0.0), 2);
+        assert_eq!(c.allowed(0), Duration::from_secs(10));
+        c.stop(0, Duration::from_secs(3), true);
+        c.stop(1, Duration::from_secs(3), false);
+        assert_eq!(c.left, vec![Duration::from_secs(9), Duration::from_secs(7)]);
+        c.stop(1, Duration::from_secs(30), false);
+        assert_eq!(c.left[1], Duration::ZERO);
+        assert_eq!(c.line(), "CLOCK 9.0 0.0");
+    }
+
+    #[test]
+    fn clock_delay() {
+        let mut c = Clock::new(control(10.0, 0.0, 2.0), 2);
+        assert_eq!(c.allowed(0), Duration::from_secs(12));
+        c.stop(0, Duration::from_secs(1), true);
+        c.stop(1, Duration::from_secs(5), true);
+        assert_eq!(
+            c.left,
+            vec![Duration::from_secs(10), Duration::from_secs(7)]
+        );
+    }
+
+    #[test]
+    fn clock_invalid_values() {
+        let c = Clock::new(control(f64::NAN, -1.0, f64::INFINITY), 1);
+        assert_eq!(c.allowed(0), Duration::ZERO);
+        assert_eq!(c.increment, Duration::ZERO);
+    }
+
+    /// Players take turns adding 1 or 2 to a total, whoever reaches
+    /// `target` wins. Players that fail are eliminated if `elimination`.
+    struct Race {
+        target: u32,
+        total: u32,
+        next: usize,
+        last: usize,
+        out: Vec<bool>,
+        retries: usize,
+        elimination: bool,
+    }
+
+    impl Race {
+        fn new(players: usize, target: u32) -> Race {
+            Race {
+                target,
+                total: 0,
+                next: 0,
+                last: 0,
+                out: vec![false; players],
+                retries: 0,
+                elimination: false,
             }
         }
-    }};
+    }
+
+    impl TurnBasedGame for Race {
+        type Move = u32;
+
+        fn start(&mut self, names: &[String], out: &mut Output) {
+            out.all(names.join(" "));
+        }
+
+        fn outcome(&mut self, out: &mut Output) -> Option<Ending> {
+            let left: Vec<_> = (0..self.out.len()).filter(|&i| !self.out[i]).collect();
+            let winner = match (self.total >= self.target, &left[..]) {
+                (true, _) => self.last,
+                (false, &[x]) => x,
+                _ => return None,
+            };
+            out.all(format!("WIN {}", winner));
+            Some(Ending::winner(self.out.len(), winner))
+        }
+
+        fn turn(&mut self, _out: &mut Output) -> Option<usize> {
+            let i = self.next;
+            self.next = (i + 1) % self.out.len();
+            match self.out[i] {
+                true => None,
+                false => Some(i),
+            }
+        }
+
+        fn parse(&self, _player: usize, line: &str) -> Result<u32, String> {
+            match line.parse() {
+                Ok(x @ 1..=2) => Ok(x),
+                _ => Err(format!("Invalid move \"{}\"", line)),
+            }
+        }
+
+        fn apply(&mut self, player: usize, m: u32, out: &mut Output) {
+            self.total += m;
+            self.last = player;
+            out.others(player, m.to_string());
+        }
+
+        fn rejected(&mut self, player: usize, reason: &str, out: &mut Output) -> bool {
+            out.player(player, reason);
+            self.retries > 0 && {
+                self.retries -= 1;
+                true
+            }
+        }
+
+        fn eliminate(&mut self, player: usize, out: &mut Output) -> bool {
+            if self.elimination {
+                self.out[player] = true;
+                out.others(player, format!("OUT {}", player));
+            }
+            self.elimination
+        }
+    }
+
+    /// Play `game` with players sending the given lines and closing their
+    /// streams, returning the outcome and the text sent to the spectators
+    async fn play(
+        game: Race,
+        moves: &[&str],
+        clock: Option<TimeControl>,
+    ) -> (MatchOutcome, String) {
+        let mut streams = Vec::new();
+        for (i, lines) in moves.iter().enumerate() {
+            let (mut a, b) = duplex(1024);
+            a.write_all(lines.as_bytes()).await.unwrap();
+            streams.push((format!("p{}", i), b));
+        }
+        let (mut s, spectators) = duplex(1024);
+        let (_, spectators) = split(spectators);
+        let second = Duration::from_secs(1);
+        let outcome = play_turns(
+            game,
+            Player::from(streams),
+            spectators,
+            second,
+            Duration::ZERO,
+            clock,
+        )
+        .await;
+        let mut text = String::new();
+        s.read_to_string(&mut text).await.unwrap();
+        (outcome, text)
+    }
+
+    fn results(outcome: &MatchOutcome) -> Vec<PlayerResult> {
+        outcome.players.values().map(|x| x.result).collect()
+    }
+
+    #[tokio::test]
+    async fn turns() {
+        let (outcome, text) = play(Race::new(2, 5), &["2\n2\n", "1\n"], None).await;
+        assert_eq!(outcome.termination, Termination::Normal);
+        assert_eq!(
+            results(&outcome),
+            vec![PlayerResult::Win, PlayerResult::Loss]
+        );
+        assert_eq!(text, "p0 p1\n2\n1\n2\nWIN 0\n");
+    }
+
+    #[tokio::test]
+    async fn invalid_move() {
+        let (outcome, text) = play(Race::new(2, 5), &["3\n", ""], None).await;
+        assert_eq!(
+            outcome.termination,
+            Termination::IllegalMove(vec!["p0".to_owned()])
+        );
+        assert_eq!(outcome.details.as_deref(), Some("Invalid move \"3\""));
+        assert_eq!(
+            results(&outcome),
+            vec![PlayerResult::Loss, PlayerResult::Win]
+        );
+        assert_eq!(text, "p0 p1\nRETIRE\n");
+    }
+
+    #[tokio::test]
+    async fn retry() {
+        let mut game = Race::new(2, 3);
+        game.retries = 1;
+        let (outcome, _) = play(game, &["0\n2\n", "x\n"], None).await;
+        assert_eq!(
+            outcome.termination,
+            Termination::IllegalMove(vec!["p1".to_owned()])
+        );
+    }
+
+    #[tokio::test]
+    async fn disconnect() {
+        let (outcome, _) = play(Race::new(2, 5), &["1\n", ""], None).await;
+        assert_eq!(
+            outcome.termination,
+            Termination::Disconnect(vec!["p1".to_owned()])
+        );
+    }
+
+    #[tokio::test]
+    async fn elimination() {
+        let mut game = Race::new(3, 5);
+        game.elimination = true;
+        let (outcome, text) = play(game, &["1\n1\n", "", "1\n9\n"], None).await;
+        assert_eq!(
+            outcome.termination,
+            Termination::Disconnect(vec!["p1".to_owned()])
+        );
+        let expected = vec![PlayerResult::Win, PlayerResult::Loss, PlayerResult::Loss];
+        assert_eq!(results(&outcome), expected);
+        assert_eq!(text, "p0 p1 p2\n1\nOUT 1\n1\n1\nOUT 2\nWIN 0\n");
+    }
+
+    #[tokio::test]
+    async fn clock() {
+        let clock = Some(control(1.0, 0.5, 0.0));
+        let (outcome, text) = play(Race::new(2, 3), &["1\n2\n", "1\n"], clock).await;
+        assert_eq!(outcome.termination, Termination::Normal);
+        assert!(text.starts_with("p0 p1\nCLOCK 1.0 1.0\n1\nCLOCK 1.5 1.0\n"));
+        let left = outcome.players["p1"].time_left.unwrap();
+        assert!(left > 1.0 && left <= 1.5, "{}", left);
+    }
+
+    /// Every round each player adds 1 or 2 to its score, and after the last
+    /// round the highest score wins
+    struct Sum {
+        rounds: usize,
+        scores: Vec<u32>,
+    }
+
+    impl SimultaneousGame for Sum {
+        type Move = u32;
+
+        fn start(&mut self, names: &[String], out: &mut Output) {
+            out.all(names.join(" "));
+        }
+
+        fn outcome(&mut self, _out: &mut Output) -> Option<Ending> {
+            if self.rounds > 0 {
+                return None;
+            }
+            let best = self.scores.iter().max().copied();
+            let results = self
+                .scores
+                .iter()
+                .map(|&x| match Some(x) == best {
+                    true => (PlayerResult::Win, Some(x as f64)),
+                    false => (PlayerResult::Loss, Some(x as f64)),
+                })
+                .collect();
+            Some(Ending {
+                results,
+                details: None,
+            })
+        }
+
+        fn parse(&self, _player: usize, line: &str) -> Result<u32, String> {
+            match line.parse() {
+                Ok(x @ 1..=2) => Ok(x),
+                _ => Err(format!("Invalid move \"{}\"", line)),
+            }
+        }
+
+        fn resolve(&mut self, moves: Vec<Option<u32>>, out: &mut Output) {
+            self.rounds -= 1;
+            let line: Vec<_> = moves
+                .iter()
+                .map(|x| match x {
+                    Some(x) => x.to_string(),
+                    None => "-".to_owned(),
+                })
+                .collect();
+            for (score, m) in self.scores.iter_mut().zip(moves) {
+                *score += m.unwrap_or(0);
+            }
+            out.all(line.join(" "));
+        }
+    }
+
+    /// Play `rounds` of `Sum` with players sending the given lines and closing
+    /// their streams, returning the outcome and the text sent to the spectators
+    async fn play_sum(
+        rounds: usize,
+        moves: &[&str],
+        policy: Policy<u32>,
+    ) -> (MatchOutcome, String) {
+        let mut streams = Vec::new();
+        for (i, lines) in moves.iter().enumerate() {
+            let (mut a, b) = duplex(1024);
+            a.write_all(lines.as_bytes()).await.unwrap();
+            streams.push((format!("p{}", i), b));
+        }
+        let game = Sum {
+            rounds,
+            scores: vec![0; moves.len()],
+        };
+        let (mut s, spectators) = duplex(1024);
+        let (_, spectators) = split(spectators);
+        let second = Duration::from_secs(1);
+        let players = Player::from(streams);
+        let outcome = play_rounds(game, players, spectators, second, Duration::ZERO, policy).await;
+        let mut text = String::new();
+        s.read_to_string(&mut text).await.unwrap();
+        (outcome, text)
+    }
+
+    #[tokio::test]
+    async fn rounds_forfeit() {
+        let (outcome, text) = play_sum(2, &["1\n2\n", "2\n"], Policy::Forfeit).await;
+        assert_eq!(
+            outcome.termination,
+            Termination::Disconnect(vec!["p1".to_owned()])
+        );
+        assert_eq!(
+            results(&outcome),
+            vec![PlayerResult::Win, PlayerResult::Loss]
+        );
+        assert_eq!(text, "p0 p1\n1 2\n2 -\n");
+    }
+
+    #[tokio::test]
+    async fn rounds_default() {
+        let (outcome, text) = play_sum(2, &["2\n2\n", "1\n3\n"], Policy::Default(1)).await;
+        assert_eq!(outcome.termination, Termination::Normal);
+        assert_eq!(
+            results(&outcome),
+            vec![PlayerResult::Win, PlayerResult::Loss]
+        );
+        assert_eq!(text, "p0 p1\n2 1\n2 1\n");
+    }
+
+    #[tokio::test]
+    async fn rounds_eliminate() {
+        let moves = ["1\n1\n", "", "2\n1\n"];
+        let (outcome, text) = play_sum(2, &moves, Policy::Eliminate).await;
+        assert_eq!(
+            outcome.termination,
+            Termination::Disconnect(vec!["p1".to_owned()])
+        );
+        let expected = vec![PlayerResult::Loss, PlayerResult::Loss, PlayerResult::Win];
+        assert_eq!(results(&outcome), expected);
+        assert_eq!(text, "p0 p1 p2\n1 - 2\n1 - 1\n");
+    }
+
+    #[test]
+    fn blame_first_failure() {
+        let names: Vec<_> = ["a", "b", "c"].iter().map(|x| x.to_string()).collect();
+        let failures = [
+            (2, Failure::Timeout, None),
+            (0, Failure::Invalid, Some("x".to_owned())),
+            (1, Failure::Timeout, Some("y".to_owned())),
+        ];
+        let (termination, details) = blame(&failures, &names);
+        assert_eq!(
+            termination,
+            Termination::Timeout(vec!["c".to_owned(), "b".to_owned()])
+        );
+        assert_eq!(details, None);
+    }
 }
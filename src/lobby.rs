@@ -1,14 +1,15 @@
-use crate::db::DatabaseHandle;
+use crate::db::{self, DatabaseHandle};
 use crate::game;
 use crate::play;
 pub(crate) use crate::play::MatchEvent;
-use crate::proto::MatchInfo;
+use crate::proto::{MatchInfo, MatchOutcome, PlayerResult, QueueInfo, SeatPolicy, SeriesInfo};
 use crate::tuning::*;
 use data_encoding::BASE32_DNSSEC;
 use rand::Rng;
 use regex::Regex;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::time::UNIX_EPOCH;
+use tokio::io::DuplexStream;
 use tokio::spawn;
 use tokio::sync::{broadcast, mpsc, oneshot};
 use tokio::time::{timeout_at, Duration, Instant};
@@ -92,8 +93,27 @@ pub(crate) enum Command {
         >,
         String,
     ),
+    QueueJoin(
+        oneshot::Sender<Result<QueueInfo, String>>,
+        String,
+        String,
+        HashMap<String, String>,
+        mpsc::Sender<MatchEvent>,
+    ),
+    QueueLeave(
+        oneshot::Sender<Result<(), String>>,
+        String,
+        HashMap<String, String>,
+        String,
+    ),
+    ResumeMatch(
+        oneshot::Sender<Result<(MatchInfo, String, DuplexStream), String>>,
+        String,
+        u64,
+        mpsc::Sender<MatchEvent>,
+    ),
     RefreshGame(String),
-    DeleteGame(String),
+    EndGame(String, MatchOutcome),
 }
 
 #[derive(Debug, Clone)]
@@ -110,8 +130,175 @@ struct Match {
     password: Option<String>,
     expiration: Instant,
     players: BTreeMap<String, mpsc::Sender<MatchEvent>>,
+    /// Names of the players in the order they joined
+    joined: Vec<String>,
     spectators: broadcast::Sender<MatchEvent>,
     play: Option<mpsc::Sender<play::Command>>,
+    seed: u64,
+    /// Parameters the match was created with, to start the next games of a series
+    params: game::Params,
+}
+
+impl Match {
+    fn new(
+        info: MatchInfo,
+        instance: Box<dyn game::Instance>,
+        password: Option<String>,
+        expiration: Instant,
+        seed: u64,
+        params: game::Params,
+    ) -> Match {
+        Match {
+            info,
+            instance: Some(instance),
+            password,
+            expiration,
+            seed,
+            params,
+            players: BTreeMap::new(),
+            joined: Vec::new(),
+            spectators: broadcast::channel(BROADCAST_BUFFER).0,
+            play: None,
+        }
+    }
+}
+
+impl SeriesInfo {
+    /// Add the points of a game that ended
+    fn record(&mut self, outcome: &MatchOutcome) {
+        for (name, player) in outcome.players.iter() {
+            let points = match player.result {
+                PlayerResult::Win => 1.0,
+                PlayerResult::Draw => 0.5,
+                PlayerResult::Loss => 0.0,
+            };
+            *self.scores.entry(name.clone()).or_default() += points;
+        }
+    }
+
+    /// Whether the remaining games cannot change who leads the series
+    fn decided(&self) -> bool {
+        let left = self.length.saturating_sub(self.games.len()) as f64;
+        let mut scores: Vec<f64> = self.scores.values().cloned().collect();
+        scores.sort_by(|x, y| y.partial_cmp(x).expect("Scores are never NaN"));
+        match scores[..] {
+            [first, second, ..] => left == 0.0 || first > second + left,
+            _ => left == 0.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Queued {
+    name: String,
+    rating: f64,
+    since: Instant,
+    tx: mpsc::Sender<MatchEvent>,
+}
+
+#[derive(Debug)]
+struct Queue {
+    info: QueueInfo,
+    entries: Vec<Queued>,
+}
+
+type QueueKey = (String, BTreeMap<String, String>);
+
+/// Pick the players for the next match of a queue, either in order of arrival or
+/// by rating closeness, with a tolerance growing as players wait
+fn pick(queue: &Queue, by_rating: bool, now: Instant) -> Option<Vec<usize>> {
+    let n = queue.info.players;
+    if queue.entries.len() < n {
+        return None;
+    }
+    if !by_rating {
+        return Some((0..n).collect());
+    }
+    let e = &queue.entries;
+    let mut order: Vec<usize> = (0..e.len()).collect();
+    order.sort_by(|&a, &b| e[a].rating.total_cmp(&e[b].rating));
+    let spread = |w: &[usize]| e[w[n - 1]].rating - e[w[0]].rating;
+    order
+        .windows(n)
+        .filter(|w| {
+            let waited = w
+                .iter()
+                .map(|&i| now - e[i].since)
+                .max()
+                .unwrap_or_default();
+            spread(w) <= QUEUE_RATING_WINDOW + QUEUE_RATING_GROWTH * waited.as_secs_f64()
+        })
+        .min_by(|a, b| spread(a).total_cmp(&spread(b)))
+        .map(|w| w.to_vec())
+}
+
+/// Generate the server bots and hand the match over to a new play task
+async fn start_match(
+    m: &mut Match,
+    id: String,
+    game: &mpsc::Sender<game::Command>,
+    db: &DatabaseHandle,
+    lobby: &mpsc::Sender<Command>,
+    rated: bool,
+    reconnect_grace: f64,
+) -> Result<(), String> {
+    let instance = match m.instance.take() {
+        Some(x) => x,
+        None => return Err("Option consistency error".to_string()),
+    };
+    let (otx, orx) = oneshot::channel();
+    if game
+        .send(game::Command::GenBots(
+            otx,
+            m.info.game.clone(),
+            m.info.bots.clone(),
+            m.seed,
+        ))
+        .await
+        .is_err()
+    {
+        return Err("Cannot send request to game::Command::GenBots".to_string());
+    }
+    let bots = match orx.await {
+        Ok(Ok(x)) => x,
+        Ok(Err(x)) => return Err(format!("Wrong reply from game::Command::GenBots: {}", x)),
+        Err(_) => return Err("Cannot get reply from game::Command::GenBots".to_string()),
+    };
+    let mut game_id = None;
+    if let Some(ref mut x) = m.info.series {
+        // Stored games need alphanumeric IDs, and x is not a base32 digit
+        game_id = Some(format!("{}x{}", id, x.games.len() + 1));
+        x.games.extend(game_id.clone());
+    }
+    if !m.info.seated.is_empty() {
+        // The players of a series move one seat forward after each game
+        m.info.seated.rotate_left(1);
+    } else {
+        let mut joined = m.joined.clone();
+        joined.extend(bots.iter().map(|(name, _)| name.clone()));
+        m.info.seated = game::seat(joined, &m.params.seating, m.seed);
+    }
+    m.info.running = true;
+    m.play = Some(
+        play::start(
+            instance,
+            db.clone(),
+            bots,
+            m.info.seated.clone(),
+            m.players.clone(),
+            m.spectators.clone(),
+            lobby.clone(),
+            m.info.game.clone(),
+            m.info.args.clone(),
+            id,
+            game_id,
+            rated,
+            reconnect_grace,
+            m.seed,
+        )
+        .await,
+    );
+    Ok(())
 }
 
 macro_rules! send {
@@ -167,6 +354,7 @@ macro_rules! match_update {
                 changed = true;
                 for id in to_remove {
                     $m.players.remove(&id);
+                    $m.joined.retain(|x| *x != id);
                     $m.info.connected.remove(&id);
                 }
             }
@@ -185,6 +373,26 @@ macro_rules! match_expired {
     }};
 }
 
+macro_rules! queue_update {
+    ($q:expr) => {{
+        let mut changed = true;
+        while changed {
+            changed = false;
+            $q.info.waiting = $q.entries.len();
+            let mut to_remove = Vec::new();
+            for x in $q.entries.iter() {
+                if let Err(_) = x.tx.send(MatchEvent::Queued($q.info.clone())).await {
+                    to_remove.push(x.name.clone());
+                }
+            }
+            if to_remove.len() > 0 {
+                changed = true;
+                $q.entries.retain(|x| !to_remove.contains(&x.name));
+            }
+        }
+    }};
+}
+
 macro_rules! matches_info {
     ($matches:expr) => {{
         $matches.values().map(|x| x.info.clone()).collect()
@@ -197,6 +405,9 @@ pub(crate) async fn start<T: 'static + Rng + Send>(
     gamename_regex: Regex,
     password_regex: Regex,
     verification_pw: String,
+    only_verified_ratings: bool,
+    queue_by_rating: bool,
+    reconnect_grace: f64,
     game: mpsc::Sender<game::Command>,
     db: DatabaseHandle,
 ) -> mpsc::Sender<Command> {
@@ -215,9 +426,137 @@ pub(crate) async fn start<T: 'static + Rng + Send>(
         let mut matches: BTreeMap<u64, Match> = BTreeMap::new();
         let (event_tx, _) = broadcast::channel(BROADCAST_BUFFER);
         let mut reaper: BTreeSet<(Instant, u64)> = BTreeSet::new();
+        let mut queues: BTreeMap<QueueKey, Queue> = BTreeMap::new();
         loop {
-            let cmd = if let Some((t, _)) = reaper.iter().nth(0) {
-                timeout_at(*t, rx.recv()).await.ok()
+            let now = Instant::now();
+            let mut groups = Vec::new();
+            for (key, q) in queues.iter_mut() {
+                while let Some(mut picked) = pick(q, queue_by_rating, now) {
+                    picked.sort_unstable();
+                    let entries: Vec<Queued> = picked
+                        .into_iter()
+                        .rev()
+                        .map(|i| q.entries.remove(i))
+                        .collect();
+                    groups.push((key.clone(), entries));
+                }
+                if groups.iter().any(|(k, _)| k == key) {
+                    queue_update!(q);
+                }
+            }
+            queues.retain(|_, q| !q.entries.is_empty());
+            for ((gamename, args), entries) in groups {
+                let args: HashMap<String, String> = args.into_iter().collect();
+                let seed = rng.gen();
+                let params = game::Params {
+                    players: Some(entries.len()),
+                    bots: Vec::new(),
+                    timeout: None,
+                    seed: Some(seed),
+                    clock: None,
+                    seating: SeatPolicy::Random,
+                    series: None,
+                };
+                let (otx, orx) = oneshot::channel();
+                let cmd =
+                    game::Command::NewGame(otx, gamename.clone(), params.clone(), args.clone());
+                let created = match game.send(cmd).await {
+                    Ok(()) => orx
+                        .await
+                        .unwrap_or(Err("Internal server error".to_string())),
+                    Err(_) => Err("Internal server error".to_string()),
+                };
+                let created = match created {
+                    Ok(_) if matches.len() >= MAX_GAME_INSTANCES => {
+                        Err("Server is at maximum capacity".to_string())
+                    }
+                    Ok((
+                        x,
+                        game::Params {
+                            timeout: Some(y), ..
+                        },
+                        seats,
+                        args,
+                    )) => Ok((x, y, seats, args)),
+                    Ok((_, params, _, _)) => Err(format!("Empty parameters: {:?}", params)),
+                    Err(x) => Err(x),
+                };
+                let (instance, timeout, seats, args) = match created {
+                    Ok(x) => x,
+                    Err(x) => {
+                        error!("Cannot create match for \"{}\" queue: {}", gamename, x);
+                        for e in entries {
+                            drop(e.tx.send(MatchEvent::Expired).await);
+                        }
+                        continue;
+                    }
+                };
+                let id = gen_unique_id!(rng, matches);
+                let eid = encode(id);
+                let expiry_time = Instant::now() + Duration::from_secs_f64(INSTANCE_LIFETIME);
+                let info = MatchInfo {
+                    players: entries.len(),
+                    bots: Vec::new(),
+                    timeout,
+                    clock: None,
+                    seats,
+                    seated: Vec::new(),
+                    series: None,
+                    args,
+                    id: eid.clone(),
+                    name: format!("Queue {}", gamename).chars().take(24).collect(),
+                    game: gamename,
+                    running: false,
+                    time: get_unix_time(expiry_time),
+                    connected: entries.iter().map(|x| x.name.clone()).collect(),
+                    spectators: 0,
+                    password: true,
+                    verified: false,
+                };
+                let password = Some(encode(rng.gen()));
+                let mut m = Match::new(info.clone(), instance, password, expiry_time, seed, params);
+                for e in entries {
+                    m.joined.push(e.name.clone());
+                    m.players.insert(e.name, e.tx);
+                }
+                info!("Game of \"{}\" created from queue: {}", m.info.game, eid);
+                send_event!(event_tx, Event::New(info));
+                match_update!(m);
+                if m.players.len() < m.info.players {
+                    // Someone left in the meantime, the others will have to leave too
+                    match_expired!(m);
+                    send_event!(event_tx, Event::Delete(eid));
+                    continue;
+                }
+                let rated = !only_verified_ratings;
+                if let Err(x) = start_match(
+                    &mut m,
+                    eid.clone(),
+                    &game,
+                    &db,
+                    &mtx,
+                    rated,
+                    reconnect_grace,
+                )
+                .await
+                {
+                    error!("Cannot start match: {}", x);
+                    match_expired!(m);
+                    send_event!(event_tx, Event::Delete(eid));
+                    continue;
+                }
+                m.info.time = get_unix_time(Instant::now());
+                match_update!(m);
+                send_event!(event_tx, Event::Update(m.info.clone()));
+                matches.insert(id, m);
+            }
+            let mut deadline = reaper.iter().next().map(|(t, _)| *t);
+            if queue_by_rating && !queues.is_empty() {
+                let t = Instant::now() + Duration::from_secs_f64(QUEUE_CHECK_INTERVAL);
+                deadline = Some(deadline.map_or(t, |x| x.min(t)));
+            }
+            let cmd = if let Some(t) = deadline {
+                timeout_at(t, rx.recv()).await.ok()
             } else {
                 Some(rx.recv().await)
             };
@@ -261,13 +600,80 @@ pub(crate) async fn start<T: 'static + Rng + Send>(
                 Command::Subscribe(tx) => {
                     send!(tx, (event_tx.subscribe(), matches_info!(matches)));
                 }
-                Command::DeleteGame(id) => {
+                Command::EndGame(id, outcome) => {
                     let eid = decode!(&id);
-                    if let Some(_) = matches.remove(&eid) {
-                        send_event!(event_tx, Event::Delete(id));
-                    } else {
-                        error!("Trying to delete non-existent game \"{}\"", id);
+                    let m = match matches.get_mut(&eid) {
+                        Some(x) => x,
+                        None => {
+                            error!("Trying to end non-existent game \"{}\"", id);
+                            continue;
+                        }
+                    };
+                    m.play = None;
+                    m.players.retain(|_, tx| !tx.is_closed());
+                    let players = &m.players;
+                    m.info.connected.retain(|x| players.contains_key(x));
+                    let present = m.players.len() + m.info.bots.len() == m.info.players;
+                    let next = match m.info.series {
+                        Some(ref mut x) => {
+                            x.record(&outcome);
+                            present && !x.decided()
+                        }
+                        None => false,
+                    };
+                    if next {
+                        let seed = rng.gen();
+                        m.seed = seed;
+                        m.params.seed = Some(seed);
+                        let (otx, orx) = oneshot::channel();
+                        let cmd = game::Command::NewGame(
+                            otx,
+                            m.info.game.clone(),
+                            m.params.clone(),
+                            m.info.args.clone(),
+                        );
+                        let created = match game.send(cmd).await {
+                            Ok(()) => orx
+                                .await
+                                .unwrap_or(Err("Internal server error".to_string())),
+                            Err(_) => Err("Internal server error".to_string()),
+                        };
+                        let started = match created {
+                            Ok((instance, _, _, _)) => {
+                                m.instance = Some(instance);
+                                let rated = m.info.verified || !only_verified_ratings;
+                                start_match(m, id.clone(), &game, &db, &mtx, rated, reconnect_grace)
+                                    .await
+                            }
+                            Err(x) => Err(x),
+                        };
+                        match started {
+                            Ok(()) => {
+                                info!("Next game of series started: {}", id);
+                                match_update!(m);
+                                send_event!(event_tx, Event::Update(m.info.clone()));
+                                continue;
+                            }
+                            Err(x) => error!("Cannot continue series: {}", x),
+                        }
                     }
+                    if let Some(ref series) = m.info.series {
+                        for (name, tx) in m.players.iter() {
+                            if tx
+                                .send(MatchEvent::SeriesEnded(series.clone()))
+                                .await
+                                .is_err()
+                            {
+                                warn!(
+                                    "Player \"{}\" did not receive MatchEvent::SeriesEnded",
+                                    name
+                                );
+                            }
+                        }
+                        drop(m.spectators.send(MatchEvent::SeriesEnded(series.clone())));
+                    }
+                    matches.remove(&eid);
+                    send_event!(event_tx, Event::Delete(id));
                 }
                 Command::RefreshGame(id) => {
                     let eid = decode!(&id);
@@ -317,6 +723,40 @@ pub(crate) async fn start<T: 'static + Rng + Send>(
                     match_update!(m);
                     send_event!(event_tx, Event::Update(m.info.clone()));
                 }
+                Command::ResumeMatch(tx, token, received, events) => {
+                    let id = token.split('-').next().unwrap_or_default();
+                    let eid = decode2!(id, tx);
+                    let m = match matches.get_mut(&eid) {
+                        Some(x) if x.info.running => x,
+                        _ => {
+                            send!(tx, Err(format!("Game \"{}\" is not running", id)));
+                            continue;
+                        }
+                    };
+                    let p = match m.play {
+                        Some(ref x) => x,
+                        None => {
+                            error!("Option consistency error at Command::ResumeMatch");
+                            continue;
+                        }
+                    };
+                    let (otx, orx) = oneshot::channel();
+                    if p.send(play::Command::Resume(otx, token, received, events.clone()))
+                        .await
+                        .is_err()
+                    {
+                        error!("play::Command::Resume send failed");
+                        continue;
+                    }
+                    match orx.await {
+                        Ok(Ok((name, stream))) => {
+                            m.players.insert(name.clone(), events);
+                            send!(tx, Ok((m.info.clone(), name, stream)));
+                        }
+                        Ok(Err(x)) => send!(tx, Err(x)),
+                        Err(_) => error!("play::Command::Resume recv failed"),
+                    }
+                }
                 Command::LeaveMatch(tx, id, name) => {
                     let eid = decode2!(&id, tx);
                     let m = match matches.get_mut(&eid) {
@@ -338,6 +778,7 @@ pub(crate) async fn start<T: 'static + Rng + Send>(
                     let now = Instant::now();
                     m.info.connected.remove(&name);
                     m.players.remove(&name);
+                    m.joined.retain(|x| *x != name);
                     reaper.remove(&(m.expiration, eid));
                     m.expiration = now + Duration::from_secs_f64(INSTANCE_LIFETIME);
                     m.info.time = get_unix_time(m.expiration);
@@ -373,6 +814,7 @@ pub(crate) async fn start<T: 'static + Rng + Send>(
                     send!(tx, Ok(m.info.clone()));
                     let now = Instant::now();
                     m.info.connected.insert(name.clone());
+                    m.joined.push(name.clone());
                     m.players.insert(name, ctx);
                     reaper.remove(&(m.expiration, eid));
                     m.expiration = now + Duration::from_secs_f64(INSTANCE_LIFETIME);
@@ -380,57 +822,123 @@ pub(crate) async fn start<T: 'static + Rng + Send>(
                     reaper.insert((m.expiration, eid));
                     match_update!(m);
                     send_event!(event_tx, Event::Update(m.info.clone()));
-                    if m.players.len() + m.info.bots == m.info.players {
-                        let instance = match m.instance.take() {
-                            Some(x) => x,
-                            None => {
-                                error!("Option consistency error");
-                                continue;
-                            }
-                        };
-                        let (otx, orx) = oneshot::channel();
-                        if let Err(_) = game
-                            .send(game::Command::GenBots(
-                                otx,
-                                m.info.game.clone(),
-                                m.info.bots,
-                            ))
-                            .await
+                    if m.players.len() + m.info.bots.len() == m.info.players {
+                        let rated = m.info.verified || !only_verified_ratings;
+                        if let Err(x) =
+                            start_match(m, id, &game, &db, &mtx, rated, reconnect_grace).await
                         {
-                            error!("Cannot send request to game::Command::GenBots");
+                            error!("Cannot start match: {}", x);
                             continue;
                         }
-                        let bots = match orx.await {
-                            Ok(Ok(x)) => x,
-                            Ok(Err(x)) => {
-                                error!("Wrong reply from game::Command::GenBots: {}", x);
-                                continue;
-                            }
-                            Err(_) => {
-                                error!("Cannot get reply from game::Command::GenBots");
-                                continue;
-                            }
-                        };
-                        m.info.running = true;
                         m.info.time = get_unix_time(Instant::now());
-                        m.play = Some(
-                            play::start(
-                                instance,
-                                db.clone(),
-                                bots,
-                                m.players.clone(),
-                                m.spectators.clone(),
-                                mtx.clone(),
-                                m.info.game.clone(),
-                                id,
-                            )
-                            .await,
-                        );
                         match_update!(m);
                         send_event!(event_tx, Event::Update(m.info.clone()));
                     }
                 }
-                Command::NewGame(tx, name, gamename, params, args, password, verification) => {
+                Command::QueueJoin(tx, gamename, name, args, ctx) => {
+                    if !username_regex.is_match(&name) {
+                        send!(tx, Err(format!("\"{}\" is not a valid username", name)));
+                        continue;
+                    }
+                    let key = (gamename.clone(), args.clone().into_iter().collect());
+                    let players = match queues.get(&key) {
+                        Some(q) if q.entries.iter().any(|x| x.name == name) => {
+                            send!(tx, Err(format!("Username \"{}\" already queued", name)));
+                            continue;
+                        }
+                        Some(q) => q.info.players,
+                        None => {
+                            let params = game::Params {
+                                players: None,
+                                bots: Vec::new(),
+                                timeout: None,
+                                seed: None,
+                                clock: None,
+                                seating: SeatPolicy::Random,
+                                series: None,
+                            };
+                            match recv!(
+                                game,
+                                tx,
+                                game::Command::NewGame,
+                                gamename.clone(),
+                                params,
+                                args.clone()
+                            ) {
+                                Ok((
+                                    _,
+                                    game::Params {
+                                        players: Some(x), ..
+                                    },
+                                    _,
+                                    _,
+                                )) => x,
+                                Ok((_, params, _, _)) => {
+                                    error!(
+                                        "Game \"{}\" gave empty parameters: {:?}",
+                                        gamename, params
+                                    );
+                                    send!(tx, Err("Internal server error".to_string()));
+                                    continue;
+                                }
+                                Err(x) => {
+                                    send!(tx, Err(x));
+                                    continue;
+                                }
+                            }
+                        }
+                    };
+                    let mut rating = RATING_INITIAL;
+                    if queue_by_rating {
+                        let (otx, orx) = oneshot::channel();
+                        let cmd = db::Command::PlayerStats {
+                            response: otx,
+                            name: name.clone(),
+                        };
+                        if db.send(cmd).await.is_err() {
+                            error!("Cannot forward request to database");
+                        } else if let Ok(ratings) = orx.await {
+                            if let Some(x) = ratings.iter().find(|x| x.game == gamename) {
+                                rating = x.rating;
+                            }
+                        }
+                    }
+                    let q = queues.entry(key).or_insert_with(|| Queue {
+                        info: QueueInfo {
+                            game: gamename,
+                            args,
+                            players,
+                            waiting: 0,
+                        },
+                        entries: Vec::new(),
+                    });
+                    q.entries.push(Queued {
+                        name,
+                        rating,
+                        since: Instant::now(),
+                        tx: ctx,
+                    });
+                    q.info.waiting = q.entries.len();
+                    send!(tx, Ok(q.info.clone()));
+                    queue_update!(q);
+                }
+                Command::QueueLeave(tx, gamename, args, name) => {
+                    let key = (gamename, args.into_iter().collect());
+                    let q = match queues.get_mut(&key) {
+                        Some(q) if q.entries.iter().any(|x| x.name == name) => q,
+                        _ => {
+                            send!(tx, Err(format!("\"{}\" is not queued", name)));
+                            continue;
+                        }
+                    };
+                    q.entries.retain(|x| x.name != name);
+                    send!(tx, Ok(()));
+                    queue_update!(q);
+                    if q.entries.is_empty() {
+                        queues.remove(&key);
+                    }
+                }
+                Command::NewGame(tx, name, gamename, mut params, args, password, verification) => {
                     if matches.len() >= MAX_GAME_INSTANCES {
                         send!(tx, Err(format!("Server is at maximum capacity")));
                         continue;
@@ -456,7 +964,8 @@ pub(crate) async fn start<T: 'static + Rng + Send>(
                         }
                         None => false,
                     };
-                    let (instance, params) = match recv!(
+                    let seed = *params.seed.get_or_insert_with(|| rng.gen());
+                    let (instance, params, seats, args) = match recv!(
                         game,
                         tx,
                         game::Command::NewGame,
@@ -478,7 +987,7 @@ pub(crate) async fn start<T: 'static + Rng + Send>(
                             continue;
                         }
                     };
-                    if params.bots >= players {
+                    if params.bots.len() >= players {
                         send!(tx, Err(format!("Cannot have all server bots")));
                         continue;
                     }
@@ -489,22 +998,35 @@ pub(crate) async fn start<T: 'static + Rng + Send>(
                         );
                         continue;
                     }
-                    if !(MIN_TIMEOUT <= timeout && timeout <= MAX_TIMEOUT) {
-                        send!(
-                            tx,
-                            Err(format!(
-                                "Timeout {} out of allowed range [{}; {}]",
-                                timeout, MIN_TIMEOUT, MAX_TIMEOUT
-                            ))
-                        );
+                    if let Err(x) = game::check_timing(&params) {
+                        send!(tx, Err(x));
                         continue;
                     }
+                    if let Some(n) = params.series {
+                        if !(1..=MAX_SERIES_LENGTH).contains(&n) {
+                            send!(
+                                tx,
+                                Err(format!(
+                                    "Series length {} out of allowed range [1; {}]",
+                                    n, MAX_SERIES_LENGTH
+                                ))
+                            );
+                            continue;
+                        }
+                    }
                     let id = gen_unique_id!(rng, matches);
                     let expiry_time = Instant::now() + Duration::from_secs_f64(INSTANCE_LIFETIME);
                     let info = MatchInfo {
                         players: players,
-                        bots: params.bots,
+                        bots: params.bots.clone(),
                         timeout: timeout,
+                        clock: params.clock,
+                        seats,
+                        seated: Vec::new(),
+                        series: params.series.map(|length| SeriesInfo {
+                            length,
+                            ..Default::default()
+                        }),
                         args: args,
                         id: encode(id),
                         name: name,
@@ -517,15 +1039,8 @@ pub(crate) async fn start<T: 'static + Rng + Send>(
                         verified: verified,
                     };
                     reaper.insert((expiry_time, id));
-                    let data = Match {
-                        info: info.clone(),
-                        instance: Some(instance),
-                        expiration: expiry_time,
-                        players: BTreeMap::new(),
-                        password: password,
-                        spectators: broadcast::channel(BROADCAST_BUFFER).0,
-                        play: None,
-                    };
+                    let data =
+                        Match::new(info.clone(), instance, password, expiry_time, seed, params);
                     info!("Game of \"{}\" created: {}", data.info.game, encode(id));
                     matches.insert(id, data);
                     send_event!(event_tx, Event::New(info));
@@ -0,0 +1,245 @@
+use crate::game::{self, MatchOutcome, Params};
+use crate::games;
+use crate::tuning::PIPE_BUFFER;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::{copy, duplex, split, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::time::{timeout, Duration, Instant};
+use tokio::{join, select, spawn};
+use tracing::warn;
+
+/// How long programs are given to exit on their own after the match ended
+const EXIT_GRACE_PERIOD: f64 = 1.0;
+
+/// Response times of a program, from when it receives some data
+/// to when it writes back
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Latency {
+    pub(crate) total: Duration,
+    pub(crate) moves: u32,
+    waiting: Option<Instant>,
+}
+
+impl Latency {
+    pub(crate) fn received(&mut self) {
+        self.waiting.get_or_insert_with(Instant::now);
+    }
+
+    pub(crate) fn answered(&mut self) {
+        if let Some(x) = self.waiting.take() {
+            self.total += x.elapsed();
+            self.moves += 1;
+        }
+    }
+
+    pub(crate) fn add(&mut self, other: &Latency) {
+        self.total += other.total;
+        self.moves += other.moves;
+    }
+
+    pub(crate) fn average(&self) -> Option<Duration> {
+        match self.moves {
+            0 => None,
+            x => Some(self.total / x),
+        }
+    }
+}
+
+/// Find one of the games compiled into this binary
+pub(crate) fn builder(name: &str) -> Result<Box<dyn game::Builder>, String> {
+    games::get()
+        .into_iter()
+        .find(|x| x.name() == name)
+        .ok_or_else(|| format!("Game \"{}\" does not exist", name))
+}
+
+/// Name a player after the file invoked last in its command line,
+/// so that `python bot.py` plays as `bot`
+pub(crate) fn player_name(program: &[String], taken: &HashSet<String>) -> String {
+    let stem = program
+        .last()
+        .and_then(|x| Path::new(x).file_stem())
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut name: String = stem
+        .chars()
+        .filter(|x| x.is_ascii_graphic() && *x != '$')
+        .take(12)
+        .collect();
+    if name.is_empty() {
+        name = String::from("player");
+    }
+    let mut unique = name.clone();
+    let mut i = 2;
+    while taken.contains(&unique) {
+        unique = format!("{}#{}", name, i);
+        i += 1;
+    }
+    unique
+}
+
+/// Forward data between the game and a program until the program
+/// closes its output, timing its answers along the way
+async fn bridge(game: DuplexStream, stdin: ChildStdin, mut stdout: ChildStdout) -> Latency {
+    let (mut gin, mut gout) = split(game);
+    let mut stdin = Some(stdin);
+    let mut latency = Latency::default();
+    let mut gbuf = [0; PIPE_BUFFER];
+    let mut pbuf = [0; PIPE_BUFFER];
+    loop {
+        select! {
+            result = gin.read(&mut gbuf), if stdin.is_some() => {
+                let size = match result {
+                    Ok(0) | Err(_) => {
+                        // The game is over, let the program know
+                        stdin = None;
+                        continue;
+                    }
+                    Ok(x) => x,
+                };
+                latency.received();
+                if let Some(ref mut x) = stdin {
+                    if x.write_all(&gbuf[..size]).await.is_err() {
+                        stdin = None;
+                    }
+                }
+            }
+            result = stdout.read(&mut pbuf) => {
+                let size = match result {
+                    Ok(0) | Err(_) => break,
+                    Ok(x) => x,
+                };
+                latency.answered();
+                if gout.write_all(&pbuf[..size]).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    latency
+}
+
+/// Play a match between local programs and server bots without a server.
+/// Every program talks to the game through its stdin and stdout, while the
+/// spectator stream is copied to `spectators`. When `fill` is set the seats
+/// left empty by the programs are taken by default server bots. Along with the outcome
+/// the name and response times of each program are returned, in order.
+pub(crate) async fn run<W: AsyncWrite + Unpin>(
+    game: &str,
+    mut params: Params,
+    args: HashMap<String, String>,
+    programs: Vec<Vec<String>>,
+    fill: bool,
+    mut spectators: W,
+) -> Result<(MatchOutcome, Vec<(String, Latency)>), String> {
+    let builder = builder(game)?;
+    if params.players.is_none() && !fill {
+        params.players = Some(programs.len() + params.bots.len());
+    }
+    let args = game::check_args(&builder.args().await, args)?;
+    let mut instance = builder.gen_instance(&mut params, args).await?;
+    let players = params.players.unwrap_or_default();
+    if programs.len() + params.bots.len() > players {
+        return Err(format!(
+            "Too many participants for {} seats: {} programs and {} bots",
+            players,
+            programs.len(),
+            params.bots.len()
+        ));
+    }
+    if fill {
+        params.bots.resize(players - programs.len(), String::new());
+    } else if programs.len() + params.bots.len() < players {
+        return Err(format!(
+            "{} seats are empty, add programs or fill them with bots",
+            players - programs.len() - params.bots.len()
+        ));
+    }
+    game::check_bots(&builder.bots().await, &mut params.bots)?;
+    game::check_seating(&params.seating, players)?;
+    game::check_timing(&params)?;
+
+    let mut streams = HashMap::new();
+    let mut joined = Vec::new();
+    let mut taken = HashSet::new();
+    let mut children = Vec::new();
+    for program in programs {
+        if program.is_empty() {
+            return Err("Empty command line".to_string());
+        }
+        let name = player_name(&program, &taken);
+        let mut child = match Command::new(&program[0])
+            .args(&program[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(x) => x,
+            Err(x) => return Err(format!("Cannot spawn \"{}\": {}", program[0], x)),
+        };
+        let (ph, gh) = duplex(PIPE_BUFFER);
+        let stdin = child.stdin.take().expect("Cannot fail");
+        let stdout = child.stdout.take().expect("Cannot fail");
+        let bridge = spawn(bridge(ph, stdin, stdout));
+        streams.insert(name.clone(), gh);
+        joined.push(name.clone());
+        taken.insert(name.clone());
+        children.push((name, child, bridge));
+    }
+    let mut hbot = Vec::new();
+    for (i, name) in params.bots.iter().enumerate() {
+        let seed = game::bot_seed(params.seed.unwrap_or_default(), i);
+        let mut bot = builder.gen_bot(name, seed).await;
+        let (bh, gh) = duplex(PIPE_BUFFER);
+        streams.insert(game::bot_name(name, i), gh);
+        joined.push(game::bot_name(name, i));
+        hbot.push(spawn(async move {
+            bot.start(bh).await;
+        }));
+    }
+
+    let seed = params.seed.unwrap_or_default();
+    let streams = game::seat(joined, &params.seating, seed)
+        .into_iter()
+        .map(|name| {
+            let stream = streams.remove(&name).expect("Cannot fail");
+            (name, stream)
+        })
+        .collect();
+
+    let (msh, gsh) = duplex(PIPE_BUFFER);
+    let mut spectate = split(msh).0;
+    let instance = spawn(async move { instance.start(streams, split(gsh).1).await });
+    let (copied, outcome) = join!(copy(&mut spectate, &mut spectators), instance);
+    if let Err(x) = copied {
+        warn!("Cannot write spectator stream: {}", x);
+    }
+    for bot in hbot {
+        bot.abort();
+    }
+    let mut latencies = Vec::new();
+    for (name, child, bridge) in children {
+        wait(&name, child).await;
+        latencies.push((name, bridge.await.unwrap_or_default()));
+    }
+    match outcome {
+        Ok(x) => Ok((x, latencies)),
+        Err(x) => Err(format!("Game exited with a panic: {}", x)),
+    }
+}
+
+/// Give a program some time to exit after the match, then kill it
+pub(crate) async fn wait(name: &str, mut child: Child) {
+    match timeout(Duration::from_secs_f64(EXIT_GRACE_PERIOD), child.wait()).await {
+        Ok(Ok(x)) if x.success() => {}
+        Ok(Ok(x)) => warn!("Program of \"{}\" exited with non-zero code: {}", name, x),
+        Ok(Err(x)) => warn!("Program of \"{}\" exited abruptly: {}", name, x),
+        Err(_) => {
+            warn!("Program of \"{}\" did not exit, killing it", name);
+            drop(child.kill().await);
+        }
+    }
+}
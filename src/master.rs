@@ -2,7 +2,7 @@ use crate::connection::Client;
 use crate::db::filesystem::{FileSystem, FileSystemArgs};
 use crate::db::{self, DatabaseHandle};
 use crate::tuning::{GAMENAME_REGEX, PASSWORD_REGEX, USERNAME_REGEX};
-use crate::{game, lobby};
+use crate::{account, game, games, lobby, tournament};
 use rand::rngs::{OsRng, StdRng};
 use rand::{Rng, SeedableRng};
 use regex::Regex;
@@ -28,7 +28,10 @@ use {
 pub(crate) struct Services {
     pub(crate) game: mpsc::Sender<game::Command>,
     pub(crate) lobby: mpsc::Sender<lobby::Command>,
+    pub(crate) tournament: mpsc::Sender<tournament::Command>,
     pub(crate) db: DatabaseHandle,
+    pub(crate) accounts: account::Policy,
+    pub(crate) verification_pw: String,
 }
 
 async fn handle_raw_socket<T: AsyncRead + AsyncWrite + Unpin>(
@@ -125,7 +128,11 @@ pub(crate) async fn start(args: crate::CliArgs) {
     let gamename_regex = init!(Regex::new(GAMENAME_REGEX));
     let password_regex = init!(Regex::new(PASSWORD_REGEX));
 
-    let srv_game = game::start().await;
+    let external = match args.games {
+        Some(ref path) => init!(games::external::load(path)),
+        None => Vec::new(),
+    };
+    let srv_game = game::start(external).await;
 
     let root_dir = args.database_dir.clone();
     if let Err(e) = tokio::fs::create_dir_all(&root_dir).await {
@@ -133,19 +140,36 @@ pub(crate) async fn start(args: crate::CliArgs) {
     }
 
     let db = db::start::<FileSystem>(FileSystemArgs { root_dir });
+    let tournament_rng = init!(StdRng::from_rng(&mut rng));
+    let srv_lobby = lobby::start(
+        rng,
+        username_regex.clone(),
+        gamename_regex.clone(),
+        password_regex,
+        verification_pw.clone(),
+        args.only_verified_ratings,
+        args.queue_by_rating,
+        args.reconnect_grace,
+        srv_game.clone(),
+        db.clone(),
+    )
+    .await;
     let services = Services {
         game: srv_game.clone(),
-        lobby: lobby::start(
-            rng,
+        lobby: srv_lobby.clone(),
+        tournament: tournament::start(
+            tournament_rng,
             username_regex,
             gamename_regex,
-            password_regex,
-            verification_pw,
+            verification_pw.clone(),
             srv_game,
+            srv_lobby,
             db.clone(),
         )
         .await,
         db: db.clone(),
+        accounts: args.accounts,
+        verification_pw,
     };
 
     #[cfg(unix)]
@@ -3,22 +3,29 @@ use crate::db::DatabaseHandle;
 use crate::db::MatchData;
 use crate::game;
 use crate::lobby;
-use crate::proto::MatchInfo;
-use crate::tuning::{END_GRACE_PERIOD, PIPE_BUFFER, QUEUE_BUFFER};
-use std::collections::{BTreeMap, HashMap};
-use tokio::io::{duplex, split, AsyncReadExt, DuplexStream};
-use tokio::sync::{broadcast, mpsc, oneshot};
-use tokio::time::{sleep, Duration};
+use crate::proto::{MatchInfo, MatchOutcome, QueueInfo, SeriesInfo, Termination, TranscriptEntry};
+use crate::tuning::{
+    END_GRACE_PERIOD, MAX_RESUME_BUFFER, MAX_TRANSCRIPT_LENGTH, PIPE_BUFFER, QUEUE_BUFFER,
+};
+use rand::random;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::pending;
+use std::io;
+use tokio::io::{duplex, split, AsyncReadExt, AsyncWriteExt, DuplexStream, WriteHalf};
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tokio::time::{sleep, sleep_until, Duration, Instant};
 use tokio::{select, spawn};
 use tracing::{error, info, info_span, warn, Instrument};
 
 #[derive(Debug)]
 pub(crate) enum MatchEvent {
     Update(MatchInfo),
-    Started(Option<DuplexStream>),
+    Started(Option<Seat>),
     SpectatorData(Vec<u8>),
     Expired,
-    Ended,
+    Ended(MatchOutcome),
+    SeriesEnded(SeriesInfo),
+    Queued(QueueInfo),
 }
 
 impl Clone for MatchEvent {
@@ -27,26 +34,199 @@ impl Clone for MatchEvent {
             MatchEvent::Update(x) => MatchEvent::Update(x.clone()),
             MatchEvent::Started(_) => MatchEvent::Started(None),
             MatchEvent::SpectatorData(x) => MatchEvent::SpectatorData(x.clone()),
-            MatchEvent::Ended => MatchEvent::Ended,
+            MatchEvent::Ended(x) => MatchEvent::Ended(x.clone()),
+            MatchEvent::SeriesEnded(x) => MatchEvent::SeriesEnded(x.clone()),
             MatchEvent::Expired => MatchEvent::Expired,
+            MatchEvent::Queued(x) => MatchEvent::Queued(x.clone()),
         }
     }
 }
 
+/// The pipe of a player together with the token to resume it
+#[derive(Debug)]
+pub(crate) struct Seat {
+    pub(crate) stream: DuplexStream,
+    pub(crate) token: Option<String>,
+}
+
 #[derive(Debug)]
 pub(crate) enum Command {
     Subscribe(oneshot::Sender<(broadcast::Receiver<MatchEvent>, Vec<u8>)>),
+    Resume(
+        oneshot::Sender<Result<(String, DuplexStream), String>>,
+        String,
+        u64,
+        mpsc::Sender<MatchEvent>,
+    ),
 }
 
-pub(crate) async fn start(
+async fn read_attached(stream: &mut Option<DuplexStream>, buffer: &mut [u8]) -> io::Result<usize> {
+    match stream {
+        Some(x) => x.read(buffer).await,
+        None => pending().await,
+    }
+}
+
+/// Append a chunk of data to a transcript, which holds `*recorded` bytes,
+/// cutting it at `MAX_TRANSCRIPT_LENGTH`
+fn record(
+    transcript: &mut Vec<TranscriptEntry>,
+    recorded: &mut usize,
+    sent: bool,
+    data: &[u8],
+    time: f64,
+) {
+    let room = MAX_TRANSCRIPT_LENGTH - *recorded;
+    if room == 0 {
+        if let Some(x) = transcript.last_mut() {
+            x.truncated = true;
+        }
+        return;
+    }
+    let size = data.len().min(room);
+    *recorded += size;
+    transcript.push(TranscriptEntry {
+        time,
+        sent,
+        data: data[..size].to_vec(),
+        truncated: size < data.len(),
+    });
+}
+
+/// Sit between the game and the connection of a player, recording a transcript
+/// of everything that goes through, up to a length. The player can drop and reattach within
+/// `grace`, telling how many bytes of the game output it received: the rest is
+/// replayed from the latest output, whose bounds are published on `window`.
+async fn relay(
+    mut game: DuplexStream,
+    player: DuplexStream,
+    mut attach: mpsc::Receiver<(DuplexStream, u64)>,
+    window: watch::Sender<(u64, u64)>,
+    grace: Duration,
+    name: String,
+    start: Instant,
+) -> Vec<TranscriptEntry> {
+    let mut player = Some(player);
+    let mut transcript = Vec::new();
+    let mut recorded = 0;
+    let mut recent = VecDeque::new();
+    let mut sent: u64 = 0;
+    let mut away = 0;
+    let mut deadline = None;
+    let mut gbuf = [0; PIPE_BUFFER];
+    let mut pbuf = [0; PIPE_BUFFER];
+    macro_rules! disconnected {
+        () => {
+            player = None;
+            if grace.is_zero() {
+                break;
+            }
+            deadline = Some(Instant::now() + grace);
+            warn!("Player \"{}\" disconnected, waiting for reconnection", name);
+        };
+    }
+    loop {
+        select! {
+            result = game.read(&mut gbuf) => {
+                let size = match result {
+                    Ok(0) | Err(_) => break,
+                    Ok(x) => x,
+                };
+                let time = start.elapsed().as_secs_f64();
+                record(&mut transcript, &mut recorded, false, &gbuf[..size], time);
+                sent += size as u64;
+                recent.extend(&gbuf[..size]);
+                if recent.len() > MAX_RESUME_BUFFER {
+                    recent.drain(..recent.len() - MAX_RESUME_BUFFER);
+                }
+                window.send_replace((sent - recent.len() as u64, sent));
+                if let Some(ref mut x) = player {
+                    if let Ok(()) = x.write_all(&gbuf[..size]).await {
+                        continue;
+                    }
+                    disconnected!();
+                }
+                away += size;
+                if away > MAX_RESUME_BUFFER {
+                    warn!("Player \"{}\" is away for too long", name);
+                    break;
+                }
+            }
+            result = read_attached(&mut player, &mut pbuf) => {
+                let size = match result {
+                    Ok(0) | Err(_) => {
+                        disconnected!();
+                        continue;
+                    }
+                    Ok(x) => x,
+                };
+                let time = start.elapsed().as_secs_f64();
+                record(&mut transcript, &mut recorded, true, &pbuf[..size], time);
+                if game.write_all(&pbuf[..size]).await.is_err() {
+                    break;
+                }
+            }
+            stream = attach.recv(), if !grace.is_zero() => {
+                let (mut stream, received) = match stream {
+                    Some(x) => x,
+                    None => break,
+                };
+                // The window may have moved on since the resume was accepted
+                let first = sent - recent.len() as u64;
+                if received < first || received > sent {
+                    continue;
+                }
+                let missed: Vec<u8> = recent.iter().skip((received - first) as usize).copied().collect();
+                if stream.write_all(&missed).await.is_err() {
+                    continue;
+                }
+                info!("Player \"{}\" reconnected", name);
+                away = 0;
+                player = Some(stream);
+                deadline = None;
+            }
+            _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                warn!("Player \"{}\" did not reconnect in time", name);
+                break;
+            }
+        }
+    }
+    transcript
+}
+
+/// Play a match on its own task, so that a panic of the game ends it as aborted
+async fn guarded(
     mut instance: Box<dyn game::Instance>,
+    players: Vec<(String, DuplexStream)>,
+    spectators: WriteHalf<DuplexStream>,
+) -> MatchOutcome {
+    let names: Vec<_> = players.iter().map(|(name, _)| name.clone()).collect();
+    match spawn(async move { instance.start(players, spectators).await }).await {
+        Ok(outcome) => outcome,
+        Err(x) => {
+            error!("Game exited with a panic: {}", x);
+            game::aborted(&names)
+        }
+    }
+}
+
+/// Play the match `id`. A game of a series is stored as `game_id`,
+/// linked to the series, which is the match itself.
+pub(crate) async fn start(
+    instance: Box<dyn game::Instance>,
     db: DatabaseHandle,
-    bots: Vec<Box<dyn game::Bot>>,
-    players: BTreeMap<String, mpsc::Sender<MatchEvent>>,
+    bots: game::Bots,
+    seated: Vec<String>,
+    mut players: BTreeMap<String, mpsc::Sender<MatchEvent>>,
     spectators: broadcast::Sender<MatchEvent>,
     lobby: mpsc::Sender<lobby::Command>,
     game: String,
+    args: HashMap<String, String>,
     id: String,
+    game_id: Option<String>,
+    rated: bool,
+    reconnect_grace: f64,
+    seed: u64,
 ) -> mpsc::Sender<Command> {
     let span = info_span!("game", id = id.as_str(), game = game.as_str());
     let (tx, mut rx) = mpsc::channel(QUEUE_BUFFER);
@@ -54,16 +234,34 @@ pub(crate) async fn start(
         info!("Game started");
         let mut streams = HashMap::new();
         let mut hbot = Vec::new();
+        let mut resumable = HashMap::new();
+        let mut relays = Vec::new();
+        let start = Instant::now();
+        let grace = Duration::from_secs_f64(reconnect_grace);
         for (name, tx) in players.iter() {
             let (ph, gh) = duplex(PIPE_BUFFER);
+            let (ch, rh) = duplex(PIPE_BUFFER);
+            let (atx, arx) = mpsc::channel(1);
+            let (wtx, wrx) = watch::channel((0, 0));
             streams.insert(name.clone(), gh);
-            if let Err(_) = tx.send(MatchEvent::Started(Some(ph))).await {
+            let relay = relay(ph, rh, arx, wtx, grace, name.clone(), start);
+            relays.push((name.clone(), spawn(relay.in_current_span())));
+            let token = if grace.is_zero() {
+                None
+            } else {
+                let token = format!("{}-{}", id, lobby::encode(random()));
+                resumable.insert(token.clone(), (name.clone(), atx, wrx));
+                Some(token)
+            };
+            let seat = Seat { stream: ch, token };
+            if tx.send(MatchEvent::Started(Some(seat))).await.is_err() {
                 warn!("Player \"{}\" left before start", name);
             }
         }
-        for (i, mut bot) in bots.into_iter().enumerate() {
+        let bot_names: Vec<String> = bots.iter().map(|(name, _)| name.clone()).collect();
+        for (name, mut bot) in bots {
             let (bh, gh) = duplex(PIPE_BUFFER);
-            streams.insert(format!("ServerBot${}", i), gh);
+            streams.insert(name, gh);
             hbot.push(spawn(async move {
                 bot.start(bh).await;
             }));
@@ -74,12 +272,14 @@ pub(crate) async fn start(
         let mut history: Vec<u8> = Vec::new();
         let mut buffer = [0; PIPE_BUFFER];
 
-        let instance_args = instance.args().await;
-        let mut instance = spawn(async move {
-            instance.start(streams, split(gsh).1).await;
-        });
+        let streams = seated
+            .into_iter()
+            .filter_map(|name| Some((name.clone(), streams.remove(&name)?)))
+            .collect();
+        let instance = guarded(instance, streams, split(gsh).1);
+        tokio::pin!(instance);
 
-        loop {
+        let outcome = loop {
             select! {
                 result = spectate.read(&mut buffer) => {
                     let size = match result {
@@ -99,26 +299,49 @@ pub(crate) async fn start(
                             error!("Subscription send failed");
                         }
                     }
+                    Some(Command::Resume(tx, token, received, events)) => {
+                        let (name, attach, window) = match resumable.get(&token) {
+                            Some(x) => x,
+                            None => {
+                                drop(tx.send(Err("Invalid resume token".to_string())));
+                                continue;
+                            }
+                        };
+                        let (first, sent) = *window.borrow();
+                        if received < first || received > sent {
+                            drop(tx.send(Err(format!("Cannot replay the game from byte {}", received))));
+                            continue;
+                        }
+                        let (ch, rh) = duplex(PIPE_BUFFER);
+                        if attach.send((rh, received)).await.is_err() {
+                            drop(tx.send(Err(format!("Player \"{}\" cannot be resumed anymore", name))));
+                            continue;
+                        }
+                        players.insert(name.clone(), events);
+                        drop(tx.send(Ok((name.clone(), ch))));
+                    }
                     None => error!("Command queue dropped prematurely"),
                 }}
-                result = &mut instance => {
-                    if let Err(x) = result {
-                        error!("Game exited with a panic: {}", x);
-                    }
-                    break;
-                }
+                outcome = &mut instance => break outcome,
             }
-        }
+        };
         while let Ok(size) = spectate.read(&mut buffer).await {
             if size == 0 {
                 break;
             }
             drop(spectators.send(MatchEvent::SpectatorData(Vec::from(&buffer[..size]))));
         }
+        let mut transcripts = BTreeMap::new();
+        for (name, relay) in relays {
+            match relay.await {
+                Ok(x) => drop(transcripts.insert(name, x)),
+                Err(x) => error!("Cannot record transcript of \"{}\": {}", name, x),
+            }
+        }
         sleep(Duration::from_secs_f64(END_GRACE_PERIOD)).await;
-        drop(spectators.send(MatchEvent::Ended));
+        drop(spectators.send(MatchEvent::Ended(outcome.clone())));
         for (name, tx) in players.iter() {
-            if let Err(_) = tx.send(MatchEvent::Ended).await {
+            if tx.send(MatchEvent::Ended(outcome.clone())).await.is_err() {
                 warn!("Player \"{}\" did not receive MatchEvent::Ended", name);
             }
         }
@@ -131,28 +354,133 @@ pub(crate) async fn start(
             }
         }
 
-        let id_clone = id.clone();
-        if let Err(_) = lobby.send(lobby::Command::DeleteGame(id)).await {
-            error!("Cannot send delete request lobby::Command::DeleteGame");
+        // Games of a series are stored under their own ID, linked to the series
+        let (record, series) = match game_id {
+            Some(x) => (x, Some(id.clone())),
+            None => (id.clone(), None),
+        };
+        if lobby.send(lobby::Command::EndGame(id, outcome.clone())).await.is_err() {
+            error!("Cannot send end request lobby::Command::EndGame");
         }
 
         info!("Game ended");
 
+        if rated && outcome.termination != Termination::Panic {
+            let rate = db::Command::Rate {
+                game: game.clone(),
+                outcome: outcome.clone(),
+            };
+            if let Err(e) = db.send(rate).await {
+                error!("Cannot update ratings: {}", e);
+            }
+        }
+
         // Collect all informations that will be stored
         let match_data = MatchData {
-            id: id_clone,
+            id: record.clone(),
             game_name: game,
-            args: instance_args,
+            args,
+            seed: Some(seed),
             bot_count,
+            bots: bot_names,
             players: players.keys().cloned().map(|x| x.to_string()).collect(),
-            history: history.clone()
+            history: history.clone(),
+            outcome: Some(outcome),
+            series,
         };
 
         if let Err(e) = db.send(db::Command::Store(match_data)).await {
             error!("Cannot save history of game {}", e);
         }
+        let transcripts = db::Command::StoreTranscripts {
+            id: record,
+            transcripts,
+        };
+        if let Err(e) = db.send(transcripts).await {
+            error!("Cannot save transcripts of game {}", e);
+        }
 
 
     }.instrument(span));
     tx
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::PlayerResult;
+    use async_trait::async_trait;
+
+    #[derive(Debug)]
+    struct Panicking {}
+
+    #[async_trait]
+    impl game::Instance for Panicking {
+        async fn start(
+            &mut self,
+            _players: Vec<(String, DuplexStream)>,
+            _spectators: WriteHalf<DuplexStream>,
+        ) -> MatchOutcome {
+            panic!("Something went wrong");
+        }
+    }
+
+    #[test]
+    fn truncate() {
+        let mut transcript = Vec::new();
+        let mut recorded = 0;
+        let data = vec![0; MAX_TRANSCRIPT_LENGTH - 5];
+        record(&mut transcript, &mut recorded, false, &data, 0.0);
+        record(&mut transcript, &mut recorded, true, &[1; 10], 1.0);
+        record(&mut transcript, &mut recorded, false, &[2; 10], 2.0);
+        assert_eq!(transcript.len(), 2);
+        assert!(!transcript[0].truncated);
+        assert_eq!(transcript[1].data, vec![1; 5]);
+        assert!(transcript[1].truncated);
+        assert_eq!(recorded, MAX_TRANSCRIPT_LENGTH);
+    }
+
+    #[tokio::test]
+    async fn replay() {
+        let (mut game, gh) = duplex(PIPE_BUFFER);
+        let (mut player, ph) = duplex(PIPE_BUFFER);
+        let (atx, arx) = mpsc::channel(1);
+        let (wtx, mut wrx) = watch::channel((0, 0));
+        let grace = Duration::from_secs(60);
+        let name = "a".to_owned();
+        let relay = spawn(relay(gh, ph, arx, wtx, grace, name, Instant::now()));
+        game.write_all(b"hello ").await.unwrap();
+        let mut buffer = [0; 6];
+        player.read_exact(&mut buffer).await.unwrap();
+        drop(player);
+        game.write_all(b"world").await.unwrap();
+        while wrx.borrow().1 < 11 {
+            wrx.changed().await.unwrap();
+        }
+        assert_eq!(*wrx.borrow(), (0, 11));
+        let (mut player, ph) = duplex(PIPE_BUFFER);
+        atx.send((ph, 3)).await.unwrap();
+        let mut buffer = [0; 8];
+        player.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(&buffer, b"lo world");
+        drop(game);
+        assert_eq!(relay.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn panic() {
+        let players = vec![
+            ("a".to_owned(), duplex(PIPE_BUFFER).0),
+            ("b".to_owned(), duplex(PIPE_BUFFER).0),
+        ];
+        let spectators = split(duplex(PIPE_BUFFER).0).1;
+        let outcome = guarded(Box::new(Panicking {}), players, spectators).await;
+        assert_eq!(outcome.termination, Termination::Panic);
+        let players: Vec<_> = outcome.players.iter().collect();
+        assert_eq!(players.len(), 2);
+        for (name, (expected, player)) in ["a", "b"].iter().zip(players) {
+            assert_eq!(name, expected);
+            assert_eq!(player.result, PlayerResult::Draw);
+        }
+    }
+}
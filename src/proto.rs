@@ -1,16 +1,19 @@
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 
 use crate::db::{DatabaseError, MatchData};
 
 pub(crate) const MAGIC: &str = "coco";
-pub(crate) const VERSION: u64 = 2;
+pub(crate) const VERSION: u64 = 16;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) enum Request {
     Handshake {
         magic: String,
         version: u64,
+        token: Option<String>,
     },
     GameList {},
     GameDescription {
@@ -33,6 +36,11 @@ pub(crate) enum Request {
         password: Option<String>,
     },
     LobbyLeaveMatch {},
+    MatchResume {
+        token: String,
+        /// Bytes of game data received before losing the connection
+        received: u64,
+    },
     SpectateJoin {
         id: String,
     },
@@ -41,6 +49,50 @@ pub(crate) enum Request {
     HistoryMatch {
         id: String,
     },
+    HistoryTranscript {
+        id: String,
+        name: String,
+        verification: Option<String>,
+    },
+    Leaderboard {
+        game: String,
+        limit: usize,
+    },
+    PlayerStats {
+        name: String,
+    },
+    TournamentNew {
+        name: String,
+        game: String,
+        format: TournamentFormat,
+        participants: Vec<String>,
+        timeout: Option<f64>,
+        args: HashMap<String, String>,
+        verification: String,
+    },
+    TournamentList {},
+    TournamentInfo {
+        id: String,
+    },
+    TournamentSubscribe {
+        id: String,
+        name: Option<String>,
+    },
+    TournamentUnsubscribe {},
+    QueueJoin {
+        game: String,
+        name: String,
+        args: HashMap<String, String>,
+    },
+    QueueLeave {},
+    AccountRegister {
+        name: String,
+        password: String,
+    },
+    AccountLogin {
+        name: String,
+        password: String,
+    },
 }
 
 /// All the informations available for a game
@@ -48,51 +100,221 @@ pub(crate) enum Request {
 pub(crate) struct GameUsage {
     pub name: String,
     pub args: HashMap<String, GameArgInfo>,
+    /// Server bots offered by the game, the first one is the default
+    pub bots: Vec<BotInfo>,
+    /// Names of the seats, the ones past them are called by their number
+    pub seats: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A kind of server bot offered by a game
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct BotInfo {
+    pub name: String,
+    pub description: String,
+}
+
+/// An argument accepted by a game
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub(crate) struct GameArgInfo {
     pub description: String,
-    pub regex: String,
+    pub kind: GameArgKind,
+    /// Value used when the argument is not given
+    pub default: String,
+    /// Unit of measure of numeric values, if any
+    pub unit: Option<String>,
+}
+
+/// The values a game argument can take
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) enum GameArgKind {
+    Integer { min: i64, max: i64 },
+    Float { min: f64, max: f64 },
+    Enum(Vec<String>),
+    Bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) enum Reply {
-    Handshake { magic: String, version: u64 },
-    GameList { games: Vec<GameUsage> },
-    GameDescription { description: Option<String> },
-    GameNew { id: Result<String, String> },
-    LobbyList { info: Vec<MatchInfo> },
-    LobbySubscribed { seed: Vec<MatchInfo> },
-    LobbyJoinedMatch { info: Result<MatchInfo, String> },
-    LobbyNew { info: MatchInfo },
-    LobbyUpdate { info: MatchInfo },
-    LobbyDelete { id: String },
+    Handshake {
+        magic: String,
+        version: u64,
+        user: Option<String>,
+    },
+    GameList {
+        games: Vec<GameUsage>,
+    },
+    GameDescription {
+        description: Option<String>,
+    },
+    GameNew {
+        id: Result<String, String>,
+    },
+    LobbyList {
+        info: Vec<MatchInfo>,
+    },
+    LobbySubscribed {
+        seed: Vec<MatchInfo>,
+    },
+    LobbyJoinedMatch {
+        info: Result<MatchInfo, String>,
+    },
+    LobbyNew {
+        info: MatchInfo,
+    },
+    LobbyUpdate {
+        info: MatchInfo,
+    },
+    LobbyDelete {
+        id: String,
+    },
     LobbyUnsubscribed {},
     LobbyLeavedMatch {},
-    MatchStarted {},
-    MatchEnded {},
-    SpectateJoined { info: Result<MatchInfo, String> },
+    MatchStarted {
+        token: Option<String>,
+    },
+    MatchResumed {
+        info: Result<MatchInfo, String>,
+    },
+    MatchEnded {
+        outcome: MatchOutcome,
+    },
+    SeriesEnded {
+        series: SeriesInfo,
+    },
+    SpectateJoined {
+        info: Result<MatchInfo, String>,
+    },
     SpectateStarted {},
     SpectateSynced {},
-    SpectateEnded {},
+    SpectateEnded {
+        outcome: MatchOutcome,
+    },
     SpectateLeaved {},
     HistoryMatchList(Vec<String>),
     HistoryMatch(Result<MatchData, DatabaseError>),
+    HistoryTranscript {
+        transcript: Result<Vec<TranscriptEntry>, String>,
+    },
+    Leaderboard {
+        ratings: Vec<Rating>,
+    },
+    PlayerStats {
+        ratings: Vec<Rating>,
+    },
+    TournamentNew {
+        id: Result<String, String>,
+    },
+    TournamentList {
+        ids: Vec<String>,
+    },
+    TournamentInfo {
+        info: Result<TournamentData, String>,
+    },
+    TournamentSubscribed {
+        info: Result<TournamentData, String>,
+    },
+    TournamentPairing {
+        id: String,
+        password: String,
+    },
+    TournamentUpdate {
+        info: TournamentData,
+    },
+    TournamentEnded {
+        info: TournamentData,
+    },
+    TournamentUnsubscribed {},
+    QueueJoined {
+        info: Result<QueueInfo, String>,
+    },
+    QueueUpdate {
+        info: QueueInfo,
+    },
+    QueueMatched {
+        info: MatchInfo,
+    },
+    QueueLeaved {},
+    AccountRegister {
+        token: Result<String, String>,
+    },
+    AccountLogin {
+        token: Result<String, String>,
+    },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub(crate) struct GameParams {
     pub(crate) players: Option<usize>,
-    pub(crate) bots: usize,
+    /// Names of the server bots, empty ones are replaced by the default bot
+    pub(crate) bots: Vec<String>,
     pub(crate) timeout: Option<f64>,
+    pub(crate) seed: Option<u64>,
+    /// Time control replacing the timeout of each move
+    pub(crate) clock: Option<TimeControl>,
+    /// How players are assigned to the seats of the match
+    pub(crate) seating: SeatPolicy,
+    /// Number of games of a best-of series played by the same players
+    pub(crate) series: Option<usize>,
+}
+
+/// Progress of a series of games played by the same players, who
+/// rotate seats after each game
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct SeriesInfo {
+    /// Most games the series can last
+    pub(crate) length: usize,
+    /// Identifiers in the history of the games started so far
+    pub(crate) games: Vec<String>,
+    /// Points of each player, one for a win and half for a draw
+    pub(crate) scores: BTreeMap<String, f64>,
+}
+
+/// How the players of a match, server bots included, take their seats
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub(crate) enum SeatPolicy {
+    /// Shuffle the players
+    #[default]
+    Random,
+    /// Seat the players in the order they joined, server bots last
+    JoinOrder,
+    /// Put the named players in the given seats, the others take the
+    /// remaining ones at random
+    Explicit(BTreeMap<String, usize>),
+}
+
+/// A chess clock: every player has a bank of seconds for the whole
+/// match, which grows by the increment after each of its moves. With a
+/// delay, the first seconds of every move are not taken from the bank.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub(crate) struct TimeControl {
+    pub(crate) bank: f64,
+    pub(crate) increment: f64,
+    #[serde(default)]
+    pub(crate) delay: f64,
+}
+
+/// State of a matchmaking queue
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct QueueInfo {
+    pub(crate) game: String,
+    pub(crate) args: HashMap<String, String>,
+    pub(crate) players: usize,
+    pub(crate) waiting: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub(crate) struct MatchInfo {
     pub(crate) players: usize,
-    pub(crate) bots: usize,
+    /// Names of the server bots playing the match
+    pub(crate) bots: Vec<String>,
     pub(crate) timeout: f64,
+    pub(crate) clock: Option<TimeControl>,
+    /// Names of the seats of the match, in order
+    pub(crate) seats: Vec<String>,
+    /// Who sits in each seat, once the match started
+    pub(crate) seated: Vec<String>,
+    /// Progress of the series, for matches that are part of one
+    pub(crate) series: Option<SeriesInfo>,
     pub(crate) args: HashMap<String, String>,
     pub(crate) id: String,
     pub(crate) name: String,
@@ -105,6 +327,312 @@ pub(crate) struct MatchInfo {
     pub(crate) verified: bool,
 }
 
+/// How a match ended, as reported by its game manager
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct MatchOutcome {
+    pub(crate) players: BTreeMap<String, PlayerOutcome>,
+    pub(crate) termination: Termination,
+    pub(crate) details: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct PlayerOutcome {
+    pub(crate) result: PlayerResult,
+    pub(crate) score: Option<f64>,
+    /// Seconds left on the clock of the player, in matches with a time control
+    #[serde(default)]
+    pub(crate) time_left: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PlayerResult {
+    Win,
+    Loss,
+    Draw,
+}
+
+/// Why a match ended, with the names of the players at fault
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) enum Termination {
+    Normal,
+    Timeout(Vec<String>),
+    IllegalMove(Vec<String>),
+    Disconnect(Vec<String>),
+    /// Lines too long or not valid UTF-8
+    ProtocolViolation(Vec<String>),
+    Panic,
+}
+
+/// A chunk of data exchanged between a player and the game
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct TranscriptEntry {
+    /// Seconds since the start of the match
+    pub(crate) time: f64,
+    /// Whether the player sent the data or received it
+    pub(crate) sent: bool,
+    pub(crate) data: Vec<u8>,
+    /// Whether the transcript was cut here for being too long
+    #[serde(default)]
+    pub(crate) truncated: bool,
+}
+
+/// Rating of a player for a single game
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct Rating {
+    pub(crate) game: String,
+    pub(crate) name: String,
+    pub(crate) rating: f64,
+    pub(crate) matches: u64,
+    pub(crate) wins: u64,
+    pub(crate) losses: u64,
+    pub(crate) draws: u64,
+    /// Best score of the player, in single player problems
+    #[serde(default)]
+    pub(crate) best: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TournamentFormat {
+    RoundRobin,
+    /// Every pair plays twice, swapping sides the second time
+    DoubleRoundRobin,
+    Swiss {
+        rounds: usize,
+    },
+    SingleElimination,
+}
+
+/// A single game of a tournament, or a bye if it has only one player
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct Pairing {
+    pub(crate) round: usize,
+    pub(crate) players: Vec<String>,
+    pub(crate) id: Option<String>,
+    pub(crate) results: Option<Vec<PlayerResult>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct Standing {
+    pub(crate) name: String,
+    pub(crate) points: f64,
+    pub(crate) wins: u64,
+    pub(crate) losses: u64,
+    pub(crate) draws: u64,
+    pub(crate) byes: u64,
+    pub(crate) eliminated: bool,
+}
+
+/// Everything there is to know about a tournament
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct TournamentData {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) game: String,
+    pub(crate) format: TournamentFormat,
+    pub(crate) participants: Vec<String>,
+    pub(crate) timeout: Option<f64>,
+    pub(crate) args: HashMap<String, String>,
+    pub(crate) round: usize,
+    pub(crate) rounds: usize,
+    pub(crate) pairings: Vec<Pairing>,
+    pub(crate) standings: Vec<Standing>,
+    pub(crate) finished: bool,
+}
+
+impl fmt::Display for TournamentFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TournamentFormat::RoundRobin => write!(f, "round-robin"),
+            TournamentFormat::DoubleRoundRobin => write!(f, "double round-robin"),
+            TournamentFormat::Swiss { rounds } => write!(f, "swiss ({} rounds)", rounds),
+            TournamentFormat::SingleElimination => write!(f, "single elimination"),
+        }
+    }
+}
+
+impl fmt::Display for PlayerResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlayerResult::Win => write!(f, "win"),
+            PlayerResult::Loss => write!(f, "loss"),
+            PlayerResult::Draw => write!(f, "draw"),
+        }
+    }
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Termination::Normal => write!(f, "normal"),
+            Termination::Timeout(x) => write!(f, "timeout ({})", x.join(", ")),
+            Termination::IllegalMove(x) => write!(f, "illegal move ({})", x.join(", ")),
+            Termination::Disconnect(x) => write!(f, "disconnect ({})", x.join(", ")),
+            Termination::ProtocolViolation(x) => {
+                write!(f, "protocol violation ({})", x.join(", "))
+            }
+            Termination::Panic => write!(f, "game manager panic"),
+        }
+    }
+}
+
+impl fmt::Display for MatchOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "termination: {}", self.termination)?;
+        if let Some(ref details) = self.details {
+            writeln!(f, "details: {}", details)?;
+        }
+        writeln!(f, "results:")?;
+        for (name, outcome) in self.players.iter() {
+            write!(f, "- {}: {}", name, outcome.result)?;
+            if let Some(score) = outcome.score {
+                write!(f, " ({})", score)?;
+            }
+            match outcome.time_left {
+                Some(time) => writeln!(f, ", {:.1}s left", time)?,
+                None => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SeriesInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "games: {} of {}", self.games.len(), self.length)?;
+        writeln!(f, "scores:")?;
+        for (name, score) in self.scores.iter() {
+            writeln!(f, "- {}: {}", name, score)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.bank)?;
+        if self.delay == 0.0 || self.increment != 0.0 {
+            write!(f, "+{}", self.increment)?;
+        }
+        if self.delay != 0.0 {
+            write!(f, "d{}", self.delay)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    /// Parse a time control written as `bank+increment`, like `300+2`,
+    /// as `bank` and `delay` separated by a `d`, like `300d2`,
+    /// or as just `bank` for neither
+    fn from_str(s: &str) -> Result<TimeControl, String> {
+        let (bank, extra, delayed) = match (s.split_once('+'), s.split_once('d')) {
+            (Some((x, y)), None) => (x, y, false),
+            (None, Some((x, y))) => (x, y, true),
+            _ => (s, "0", false),
+        };
+        match (bank.trim().parse::<f64>(), extra.trim().parse::<f64>()) {
+            (Ok(bank), Ok(extra))
+                if bank.is_finite() && extra.is_finite() && bank > 0.0 && extra >= 0.0 =>
+            {
+                Ok(TimeControl {
+                    bank,
+                    increment: if delayed { 0.0 } else { extra },
+                    delay: if delayed { extra } else { 0.0 },
+                })
+            }
+            _ => Err(format!(
+                "\"{}\" is not a valid time control, write it like 300+2 or 300d2",
+                s
+            )),
+        }
+    }
+}
+
+impl GameArgInfo {
+    /// Check a value given for the argument `name`,
+    /// returning it in its canonical form
+    pub(crate) fn coerce(&self, name: &str, value: &str) -> Result<String, String> {
+        let value = value.trim();
+        let unit = match self.unit {
+            Some(ref x) => format!(" {}", x),
+            None => String::new(),
+        };
+        match self.kind {
+            GameArgKind::Integer { min, max } => match value.parse::<i64>() {
+                Ok(x) if x >= min && x <= max => Ok(x.to_string()),
+                Ok(_) => Err(format!(
+                    "\"{}\" must be between {} and {}{}, got {}",
+                    name, min, max, unit, value
+                )),
+                Err(_) => Err(format!(
+                    "\"{}\" must be an integer, got \"{}\"",
+                    name, value
+                )),
+            },
+            GameArgKind::Float { min, max } => match value.parse::<f64>() {
+                Ok(x) if x >= min && x <= max => Ok(x.to_string()),
+                Ok(_) => Err(format!(
+                    "\"{}\" must be between {} and {}{}, got {}",
+                    name, min, max, unit, value
+                )),
+                Err(_) => Err(format!("\"{}\" must be a number, got \"{}\"", name, value)),
+            },
+            GameArgKind::Enum(ref choices) => choices
+                .iter()
+                .find(|x| x.eq_ignore_ascii_case(value))
+                .cloned()
+                .ok_or_else(|| {
+                    format!(
+                        "\"{}\" must be one of {}, got \"{}\"",
+                        name,
+                        choices.join(", "),
+                        value
+                    )
+                }),
+            GameArgKind::Bool => match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Ok("true".to_string()),
+                "false" | "no" | "off" | "0" => Ok("false".to_string()),
+                _ => Err(format!(
+                    "\"{}\" must be true or false, got \"{}\"",
+                    name, value
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for GameArgInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (", self.description)?;
+        match self.kind {
+            GameArgKind::Integer { min, max } => write!(f, "integer from {} to {}", min, max)?,
+            GameArgKind::Float { min, max } => write!(f, "number from {} to {}", min, max)?,
+            GameArgKind::Enum(ref x) => write!(f, "one of {}", x.join(", "))?,
+            GameArgKind::Bool => write!(f, "true or false")?,
+        }
+        if let Some(ref unit) = self.unit {
+            write!(f, " {}", unit)?;
+        }
+        write!(f, ", default {})", self.default)
+    }
+}
+
+impl fmt::Display for TranscriptEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let arrow = if self.sent { '>' } else { '<' };
+        let data = String::from_utf8_lossy(&self.data);
+        for line in data.lines() {
+            writeln!(f, "{:9.3} {} {}", self.time, arrow, line)?;
+        }
+        if self.truncated {
+            writeln!(f, "{:9.3}   (transcript truncated)", self.time)?;
+        }
+        Ok(())
+    }
+}
+
 #[allow(dead_code)]
 impl Request {
     pub(crate) fn forge(&self) -> Result<String, String> {
@@ -136,3 +664,25 @@ impl Reply {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_control() {
+        let clock = |bank, increment, delay| TimeControl {
+            bank,
+            increment,
+            delay,
+        };
+        assert_eq!("300+2".parse(), Ok(clock(300.0, 2.0, 0.0)));
+        assert_eq!("300d2".parse(), Ok(clock(300.0, 0.0, 2.0)));
+        assert_eq!("60".parse(), Ok(clock(60.0, 0.0, 0.0)));
+        for x in [
+            "0+1", "-60", "60+-1", "inf", "60dinf", "NaN+1", "60+nan", "60+2d1",
+        ] {
+            assert!(x.parse::<TimeControl>().is_err(), "{}", x);
+        }
+    }
+}
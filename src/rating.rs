@@ -0,0 +1,238 @@
+use crate::proto::{MatchOutcome, PlayerResult, Rating};
+use crate::tuning::{RATING_INITIAL, RATING_K};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Server bots have a `$` in their name and are never rated
+pub(crate) fn rated(name: &str) -> bool {
+    !name.contains('$')
+}
+
+pub(crate) fn new(game: &str, name: &str) -> Rating {
+    Rating {
+        game: game.to_owned(),
+        name: name.to_owned(),
+        rating: RATING_INITIAL,
+        matches: 0,
+        wins: 0,
+        losses: 0,
+        draws: 0,
+        best: None,
+    }
+}
+
+/// Order of the leaderboards: by best score in single player problems,
+/// by rating otherwise
+pub(crate) fn rank(a: &Rating, b: &Rating) -> Ordering {
+    let best = |x: &Rating| x.best.unwrap_or(f64::NEG_INFINITY);
+    best(b)
+        .total_cmp(&best(a))
+        .then(b.rating.total_cmp(&a.rating))
+}
+
+fn count(entry: &mut Rating, result: PlayerResult) {
+    entry.matches += 1;
+    match result {
+        PlayerResult::Win => entry.wins += 1,
+        PlayerResult::Loss => entry.losses += 1,
+        PlayerResult::Draw => entry.draws += 1,
+    }
+}
+
+fn score(a: PlayerResult, b: PlayerResult) -> f64 {
+    match (a, b) {
+        (PlayerResult::Win, PlayerResult::Win)
+        | (PlayerResult::Loss, PlayerResult::Loss)
+        | (PlayerResult::Draw, PlayerResult::Draw) => 0.5,
+        (PlayerResult::Win, _) | (PlayerResult::Draw, PlayerResult::Loss) => 1.0,
+        _ => 0.0,
+    }
+}
+
+/// Update the ratings of a game with the outcome of a match.
+///
+/// Multiplayer matches are rated as a set of pairwise Elo games whose
+/// adjustments are averaged, so that each match weights the same regardless
+/// of the number of players. Single player problems have no opponents to be
+/// rated against, and keep the best score of the player instead.
+pub(crate) fn update(game: &str, ratings: &mut HashMap<String, Rating>, outcome: &MatchOutcome) {
+    if let [(name, result)] = outcome.players.iter().collect::<Vec<_>>()[..] {
+        if rated(name) {
+            let entry = ratings
+                .entry(name.to_string())
+                .or_insert_with(|| new(game, name));
+            count(entry, result.result);
+            if let Some(score) = result.score {
+                entry.best = Some(entry.best.map_or(score, |x| x.max(score)));
+            }
+        }
+        return;
+    }
+    let players: Vec<_> = outcome
+        .players
+        .iter()
+        .filter(|(name, _)| rated(name))
+        .collect();
+    if players.len() < 2 {
+        return;
+    }
+    let current: Vec<f64> = players
+        .iter()
+        .map(|(name, _)| match ratings.get(*name) {
+            Some(x) => x.rating,
+            None => RATING_INITIAL,
+        })
+        .collect();
+    for (i, (name, result)) in players.iter().enumerate() {
+        let mut delta = 0.0;
+        for (j, (_, other)) in players.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            let expected = 1.0 / (1.0 + 10f64.powf((current[j] - current[i]) / 400.0));
+            delta += score(result.result, other.result) - expected;
+        }
+        let entry = ratings
+            .entry(name.to_string())
+            .or_insert_with(|| new(game, name));
+        entry.rating += RATING_K * delta / (players.len() - 1) as f64;
+        count(entry, result.result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{PlayerOutcome, Termination};
+
+    fn outcome(players: &[(&str, PlayerResult, Option<f64>)]) -> MatchOutcome {
+        MatchOutcome {
+            players: players
+                .iter()
+                .map(|&(name, result, score)| {
+                    let outcome = PlayerOutcome {
+                        result,
+                        score,
+                        time_left: None,
+                    };
+                    (name.to_owned(), outcome)
+                })
+                .collect(),
+            termination: Termination::Normal,
+            details: None,
+        }
+    }
+
+    fn rating(ratings: &HashMap<String, Rating>, name: &str) -> f64 {
+        ratings[name].rating
+    }
+
+    #[test]
+    fn even_match() {
+        let mut ratings = HashMap::new();
+        let o = outcome(&[
+            ("a", PlayerResult::Win, None),
+            ("b", PlayerResult::Loss, None),
+        ]);
+        update("chess", &mut ratings, &o);
+        assert_eq!(rating(&ratings, "a"), RATING_INITIAL + RATING_K / 2.0);
+        assert_eq!(rating(&ratings, "b"), RATING_INITIAL - RATING_K / 2.0);
+        assert_eq!((ratings["a"].matches, ratings["a"].wins), (1, 1));
+        assert_eq!((ratings["b"].matches, ratings["b"].losses), (1, 1));
+    }
+
+    #[test]
+    fn upset() {
+        let mut ratings = HashMap::new();
+        ratings.insert(
+            "a".to_owned(),
+            Rating {
+                rating: 1900.0,
+                ..new("chess", "a")
+            },
+        );
+        let o = outcome(&[
+            ("a", PlayerResult::Loss, None),
+            ("b", PlayerResult::Win, None),
+        ]);
+        update("chess", &mut ratings, &o);
+        // 400 points apart, the favourite was expected to score 10/11
+        let delta = RATING_K * 10.0 / 11.0;
+        assert!((rating(&ratings, "a") - (1900.0 - delta)).abs() < 1e-9);
+        assert!((rating(&ratings, "b") - (RATING_INITIAL + delta)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn draw_keeps_even_ratings() {
+        let mut ratings = HashMap::new();
+        let o = outcome(&[
+            ("a", PlayerResult::Draw, None),
+            ("b", PlayerResult::Draw, None),
+        ]);
+        update("chess", &mut ratings, &o);
+        assert_eq!(rating(&ratings, "a"), RATING_INITIAL);
+        assert_eq!(rating(&ratings, "b"), RATING_INITIAL);
+        assert_eq!(ratings["a"].draws, 1);
+    }
+
+    #[test]
+    fn multiplayer_is_averaged() {
+        let mut ratings = HashMap::new();
+        let o = outcome(&[
+            ("a", PlayerResult::Win, None),
+            ("b", PlayerResult::Loss, None),
+            ("c", PlayerResult::Loss, None),
+        ]);
+        update("poker", &mut ratings, &o);
+        // The winner gains as much as in a two player match, not twice as much
+        assert_eq!(rating(&ratings, "a"), RATING_INITIAL + RATING_K / 2.0);
+        assert_eq!(rating(&ratings, "b"), RATING_INITIAL - RATING_K / 4.0);
+        assert_eq!(rating(&ratings, "c"), RATING_INITIAL - RATING_K / 4.0);
+        let total: f64 = ratings.values().map(|x| x.rating).sum();
+        assert!((total - 3.0 * RATING_INITIAL).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bots_are_not_rated() {
+        let mut ratings = HashMap::new();
+        let o = outcome(&[
+            ("a", PlayerResult::Win, None),
+            ("$bot", PlayerResult::Loss, None),
+        ]);
+        update("chess", &mut ratings, &o);
+        assert!(ratings.is_empty());
+        let o = outcome(&[
+            ("a", PlayerResult::Win, None),
+            ("b", PlayerResult::Loss, None),
+            ("$bot", PlayerResult::Loss, None),
+        ]);
+        update("poker", &mut ratings, &o);
+        assert_eq!(rating(&ratings, "a"), RATING_INITIAL + RATING_K / 2.0);
+        assert!(!ratings.contains_key("$bot"));
+    }
+
+    #[test]
+    fn single_player_keeps_best_score() {
+        let mut ratings = HashMap::new();
+        for score in [3.0, 7.0, 5.0] {
+            let o = outcome(&[("a", PlayerResult::Win, Some(score))]);
+            update("guess", &mut ratings, &o);
+        }
+        assert_eq!(ratings["a"].best, Some(7.0));
+        assert_eq!(ratings["a"].matches, 3);
+        assert_eq!(rating(&ratings, "a"), RATING_INITIAL);
+    }
+
+    #[test]
+    fn leaderboard_order() {
+        let mut a = new("guess", "a");
+        let mut b = new("guess", "b");
+        a.best = Some(2.0);
+        b.best = Some(5.0);
+        assert_eq!(rank(&a, &b), Ordering::Greater);
+        a.best = None;
+        b.best = None;
+        a.rating = 1600.0;
+        assert_eq!(rank(&a, &b), Ordering::Less);
+    }
+}
@@ -1,3 +1,4 @@
+mod account;
 mod connection;
 mod db;
 mod game;
@@ -6,6 +7,8 @@ mod lobby;
 mod master;
 mod play;
 mod proto;
+mod rating;
+mod tournament;
 mod tuning;
 
 use clap::Parser;
@@ -32,11 +35,42 @@ struct CliArgs {
         default_value = "matches"
     )]
     database_dir: String,
+    #[clap(long, help = "Update ratings only after verified matches")]
+    only_verified_ratings: bool,
+    #[clap(long, help = "Match queued players with similar ratings")]
+    queue_by_rating: bool,
+    #[clap(
+        arg_enum,
+        long,
+        help = "Whether usernames can be reserved with accounts",
+        default_value = "disabled"
+    )]
+    accounts: account::Policy,
+    #[clap(
+        long,
+        help = "Seconds a disconnected player can take to resume a match, 0 to disable",
+        default_value = "0",
+        parse(try_from_str = parse_grace)
+    )]
+    reconnect_grace: f64,
+    #[clap(short, long, help = "JSON file listing games run by external referees")]
+    games: Option<String>,
     #[cfg(unix)]
     #[clap(short, long, help = "Use bind address as a Unix Domain Socket")]
     unix_domain_socket: bool,
 }
 
+/// Parse the seconds given to disconnected players to resume their matches
+fn parse_grace(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(x) if (0.0..=tuning::MAX_RECONNECT_GRACE).contains(&x) => Ok(x),
+        _ => Err(format!(
+            "must be a number of seconds between 0 and {}",
+            tuning::MAX_RECONNECT_GRACE
+        )),
+    }
+}
+
 fn main() {
     let args = CliArgs::parse();
     if args.journald {
@@ -0,0 +1,1013 @@
+use crate::db::{self, DatabaseHandle};
+use crate::game::{self, PlayerResult, SeatPolicy};
+use crate::lobby::{self, MatchEvent};
+use crate::proto::{Pairing, Standing, TournamentData, TournamentFormat};
+use crate::tuning::*;
+use rand::Rng;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tokio::spawn;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tracing::{error, info, warn};
+
+/// Events of a tournament, its current state and the IDs and passwords
+/// of the pending matches of the subscriber
+pub(crate) type Subscription = (
+    broadcast::Receiver<Event>,
+    TournamentData,
+    Vec<(String, String)>,
+);
+
+#[derive(Debug)]
+pub(crate) enum Command {
+    New(
+        oneshot::Sender<Result<String, String>>,
+        String,
+        String,
+        TournamentFormat,
+        Vec<String>,
+        Option<f64>,
+        HashMap<String, String>,
+        String,
+    ),
+    Info(oneshot::Sender<Result<TournamentData, String>>, String),
+    Subscribe(
+        oneshot::Sender<Result<Subscription, String>>,
+        String,
+        Option<String>,
+    ),
+    MatchEnded(String, String, Vec<PlayerResult>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Event {
+    Update(TournamentData),
+    Pairing {
+        players: Vec<String>,
+        id: String,
+        password: String,
+    },
+    Ended(TournamentData),
+}
+
+#[derive(Debug)]
+struct Tournament {
+    data: TournamentData,
+    /// Passwords of the matches of the current round, indexed by match ID
+    passwords: HashMap<String, String>,
+    events: broadcast::Sender<Event>,
+    /// Players still in the bracket, in bracket order (knockout only)
+    slots: Vec<Option<String>>,
+    /// Pairing index for every couple of slots of the current round (knockout only)
+    bracket: Vec<Option<usize>>,
+}
+
+macro_rules! send {
+    ($channel:expr, $data:expr) => {
+        if let Err(_) = $channel.send($data) {
+            warn!("Channel closed prematurely");
+        }
+    };
+}
+
+fn points(result: PlayerResult) -> f64 {
+    match result {
+        PlayerResult::Win => 1.0,
+        PlayerResult::Draw => 0.5,
+        PlayerResult::Loss => 0.0,
+    }
+}
+
+/// Pairings of a round of the circle method, with byes for odd participants
+fn round_robin(participants: &[String], round: usize) -> Vec<Vec<String>> {
+    let mut p: Vec<Option<&String>> = participants.iter().map(Some).collect();
+    if p.len() % 2 == 1 {
+        p.push(None);
+    }
+    let n = p.len();
+    p[1..].rotate_right(round % (n - 1));
+    (0..n / 2)
+        .map(|i| {
+            let (a, b) = match i == 0 && round % 2 == 1 {
+                true => (p[n - 1], p[0]),
+                false => (p[i], p[n - 1 - i]),
+            };
+            [a, b].iter().flatten().map(|x| x.to_string()).collect()
+        })
+        .collect()
+}
+
+/// Pair players with similar scores who have not met yet, the lowest ranked
+/// player without a bye gets one if needed
+fn swiss(
+    ranking: &[String],
+    played: &HashSet<(String, String)>,
+    byes: &HashSet<String>,
+) -> Vec<Vec<String>> {
+    let mut remaining: Vec<&String> = ranking.iter().collect();
+    let mut result = Vec::new();
+    if remaining.len() % 2 == 1 {
+        let idx = (0..remaining.len())
+            .rev()
+            .find(|&i| !byes.contains(remaining[i]))
+            .unwrap_or(remaining.len() - 1);
+        result.push(vec![remaining.remove(idx).clone()]);
+    }
+    let mut budget = SWISS_SEARCH_BUDGET;
+    if let Some(pairs) = pair_unplayed(&remaining, played, &mut budget) {
+        result.extend(pairs);
+        return result;
+    }
+    while remaining.len() >= 2 {
+        let a = remaining.remove(0);
+        let idx = (0..remaining.len())
+            .find(|&i| !played.contains(&key(a, remaining[i])))
+            .unwrap_or(0);
+        let b = remaining.remove(idx);
+        result.push(vec![a.clone(), b.clone()]);
+    }
+    result
+}
+
+/// Backtracking search of a pairing without rematches, preferring opponents
+/// close in the ranking
+fn pair_unplayed(
+    remaining: &[&String],
+    played: &HashSet<(String, String)>,
+    budget: &mut usize,
+) -> Option<Vec<Vec<String>>> {
+    if remaining.is_empty() {
+        return Some(Vec::new());
+    }
+    let a = remaining[0];
+    for i in 1..remaining.len() {
+        if *budget == 0 {
+            return None;
+        }
+        *budget -= 1;
+        if played.contains(&key(a, remaining[i])) {
+            continue;
+        }
+        let rest: Vec<&String> = remaining[1..]
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j + 1 != i)
+            .map(|(_, x)| *x)
+            .collect();
+        if let Some(mut pairs) = pair_unplayed(&rest, played, budget) {
+            pairs.insert(0, vec![a.clone(), remaining[i].clone()]);
+            return Some(pairs);
+        }
+    }
+    None
+}
+
+/// Order of the seeds in the first round of a bracket of `size` players,
+/// such that the best seeds meet as late as possible
+fn seeding(size: usize) -> Vec<usize> {
+    let mut order = vec![0];
+    while order.len() < size {
+        let n = order.len() * 2;
+        order = order.iter().flat_map(|&s| [s, n - 1 - s]).collect();
+    }
+    order
+}
+
+fn key(a: &str, b: &str) -> (String, String) {
+    match a < b {
+        true => (a.to_owned(), b.to_owned()),
+        false => (b.to_owned(), a.to_owned()),
+    }
+}
+
+impl Tournament {
+    fn new(data: TournamentData) -> Tournament {
+        let mut t = Tournament {
+            data,
+            passwords: HashMap::new(),
+            events: broadcast::channel(BROADCAST_BUFFER).0,
+            slots: Vec::new(),
+            bracket: Vec::new(),
+        };
+        let n = t.data.participants.len();
+        t.data.rounds = match t.data.format {
+            TournamentFormat::RoundRobin => n + n % 2 - 1,
+            TournamentFormat::DoubleRoundRobin => 2 * (n + n % 2 - 1),
+            TournamentFormat::Swiss { rounds } => rounds,
+            TournamentFormat::SingleElimination => {
+                let size = n.next_power_of_two();
+                t.slots = seeding(size)
+                    .into_iter()
+                    .map(|x| t.data.participants.get(x).cloned())
+                    .collect();
+                size.trailing_zeros() as usize
+            }
+        };
+        t.data.standings = t.standings();
+        t
+    }
+
+    /// Rebuild a saved tournament, replaying its rounds to recover the bracket
+    fn restore(data: TournamentData) -> Tournament {
+        let mut t = Tournament::new(TournamentData {
+            round: 0,
+            pairings: Vec::new(),
+            ..data.clone()
+        });
+        while t.data.round < data.round {
+            if t.data.format == TournamentFormat::SingleElimination && t.data.round > 0 {
+                t.advance_bracket();
+            }
+            t.data.standings = t.standings();
+            t.next_round();
+            let n = t.data.pairings.len().min(data.pairings.len());
+            t.data.pairings[..n].clone_from_slice(&data.pairings[..n]);
+        }
+        t.data = data;
+        t
+    }
+
+    fn seed(&self, name: &str) -> usize {
+        self.data
+            .participants
+            .iter()
+            .position(|x| x == name)
+            .unwrap_or(usize::MAX)
+    }
+
+    fn standings(&self) -> Vec<Standing> {
+        let mut table: HashMap<&String, Standing> = HashMap::new();
+        for name in self.data.participants.iter() {
+            table.insert(
+                name,
+                Standing {
+                    name: name.clone(),
+                    eliminated: self.data.format == TournamentFormat::SingleElimination
+                        && self.data.round > 0
+                        && !self.slots.contains(&Some(name.clone())),
+                    ..Default::default()
+                },
+            );
+        }
+        for pairing in self.data.pairings.iter() {
+            let results = match pairing.results {
+                Some(ref x) => x,
+                None => continue,
+            };
+            for (name, &result) in pairing.players.iter().zip(results.iter()) {
+                let s = match table.get_mut(name) {
+                    Some(x) => x,
+                    None => continue,
+                };
+                s.points += points(result);
+                if pairing.players.len() == 1 {
+                    s.byes += 1;
+                    continue;
+                }
+                match result {
+                    PlayerResult::Win => s.wins += 1,
+                    PlayerResult::Loss => s.losses += 1,
+                    PlayerResult::Draw => s.draws += 1,
+                }
+            }
+        }
+        let mut standings: Vec<Standing> = table.into_values().collect();
+        standings.sort_by(|a, b| {
+            a.eliminated
+                .cmp(&b.eliminated)
+                .then(b.points.total_cmp(&a.points))
+                .then(b.wins.cmp(&a.wins))
+                .then(self.seed(&a.name).cmp(&self.seed(&b.name)))
+        });
+        standings
+    }
+
+    /// Generate the pairings of the next round, returning their indices
+    fn next_round(&mut self) -> Vec<usize> {
+        self.data.round += 1;
+        let round = self.data.round;
+        let n = self.data.participants.len();
+        let groups = match self.data.format {
+            TournamentFormat::RoundRobin => round_robin(&self.data.participants, round - 1),
+            TournamentFormat::DoubleRoundRobin => {
+                let cycle = n + n % 2 - 1;
+                let mut groups = round_robin(&self.data.participants, (round - 1) % cycle);
+                if round > cycle {
+                    groups.iter_mut().for_each(|x| x.reverse());
+                }
+                groups
+            }
+            TournamentFormat::Swiss { .. } => {
+                let ranking: Vec<String> =
+                    self.data.standings.iter().map(|x| x.name.clone()).collect();
+                let mut played = HashSet::new();
+                let mut byes = HashSet::new();
+                for p in self.data.pairings.iter() {
+                    match p.players.len() {
+                        1 => drop(byes.insert(p.players[0].clone())),
+                        _ => drop(played.insert(key(&p.players[0], &p.players[1]))),
+                    }
+                }
+                swiss(&ranking, &played, &byes)
+            }
+            TournamentFormat::SingleElimination => {
+                self.bracket.clear();
+                let mut groups = Vec::new();
+                for couple in self.slots.chunks(2) {
+                    let group: Vec<String> = couple.iter().flatten().cloned().collect();
+                    if group.is_empty() {
+                        self.bracket.push(None);
+                    } else {
+                        self.bracket
+                            .push(Some(self.data.pairings.len() + groups.len()));
+                        groups.push(group);
+                    }
+                }
+                groups
+            }
+        };
+        let mut indices = Vec::new();
+        for players in groups {
+            indices.push(self.data.pairings.len());
+            let results = match players.len() {
+                1 => Some(vec![PlayerResult::Win]),
+                _ => None,
+            };
+            self.data.pairings.push(Pairing {
+                round,
+                players,
+                id: None,
+                results,
+            });
+        }
+        indices
+    }
+
+    /// Move the winners of the current round to the next one (knockout only)
+    fn advance_bracket(&mut self) {
+        let mut slots = Vec::new();
+        for idx in self.bracket.iter() {
+            let pairing = match idx {
+                Some(x) => &self.data.pairings[*x],
+                None => {
+                    slots.push(None);
+                    continue;
+                }
+            };
+            let results = pairing.results.clone().unwrap_or_default();
+            let winner = pairing
+                .players
+                .iter()
+                .zip(results.iter())
+                .filter(|(_, &r)| r != PlayerResult::Loss)
+                .min_by_key(|(name, &r)| (r != PlayerResult::Win, self.seed(name)))
+                .map(|(name, _)| name.clone());
+            slots.push(winner);
+        }
+        self.slots = slots;
+    }
+
+    fn finished(&self) -> bool {
+        match self.data.format {
+            TournamentFormat::SingleElimination => {
+                self.data.round >= self.data.rounds || self.slots.iter().flatten().count() < 2
+            }
+            _ => self.data.round >= self.data.rounds,
+        }
+    }
+}
+
+/// Follow a tournament match as a spectator and report its result
+async fn watch(
+    tournament: mpsc::Sender<Command>,
+    lobby: mpsc::Sender<lobby::Command>,
+    tid: String,
+    mid: String,
+    players: Vec<String>,
+) {
+    let forfeit = |connected: &HashSet<String>| -> Vec<PlayerResult> {
+        players
+            .iter()
+            .map(|x| match connected.contains(x) {
+                true => PlayerResult::Win,
+                false => PlayerResult::Loss,
+            })
+            .collect()
+    };
+    let (tx, rx) = oneshot::channel();
+    let subscription = match lobby
+        .send(lobby::Command::SpectateMatch(tx, mid.clone()))
+        .await
+    {
+        Ok(()) => rx.await.ok(),
+        Err(_) => None,
+    };
+    let results = match subscription {
+        Some(Ok((mut rx, info, _))) => {
+            let mut connected = info.connected;
+            loop {
+                match rx.recv().await {
+                    Ok(MatchEvent::Update(info)) => connected = info.connected,
+                    Ok(MatchEvent::Ended(outcome)) => {
+                        break players
+                            .iter()
+                            .map(|x| match outcome.players.get(x) {
+                                Some(x) => x.result,
+                                None => PlayerResult::Draw,
+                            })
+                            .collect()
+                    }
+                    Ok(MatchEvent::Expired) | Err(RecvError::Closed) => break forfeit(&connected),
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                }
+            }
+        }
+        _ => {
+            error!("Cannot follow tournament match \"{}\"", mid);
+            forfeit(&HashSet::new())
+        }
+    };
+    if tournament
+        .send(Command::MatchEnded(tid, mid, results))
+        .await
+        .is_err()
+    {
+        error!("Cannot report result to tournament");
+    }
+}
+
+/// Create the match of a pairing of the current round and let its players
+/// know how to join it
+async fn pair<T: Rng>(
+    t: &mut Tournament,
+    idx: usize,
+    rng: &mut T,
+    verification_pw: &str,
+    lobby: &mpsc::Sender<lobby::Command>,
+    tournament: &mpsc::Sender<Command>,
+) {
+    let suffix = format!(" R{}", t.data.round);
+    let name: String = t
+        .data
+        .name
+        .chars()
+        .take(24 - suffix.len())
+        .chain(suffix.chars())
+        .collect();
+    let password = lobby::encode(rng.gen());
+    // Players sit in the order of the pairing, which the second
+    // half of a double round robin reverses to swap colors
+    let seats = t.data.pairings[idx]
+        .players
+        .iter()
+        .cloned()
+        .zip(0..)
+        .collect();
+    let params = game::Params {
+        players: Some(2),
+        bots: Vec::new(),
+        timeout: t.data.timeout,
+        seed: None,
+        clock: None,
+        seating: SeatPolicy::Explicit(seats),
+        series: None,
+    };
+    let (otx, orx) = oneshot::channel();
+    let cmd = lobby::Command::NewGame(
+        otx,
+        name,
+        t.data.game.clone(),
+        params,
+        t.data.args.clone(),
+        Some(password.clone()),
+        Some(verification_pw.to_owned()),
+    );
+    let result = match lobby.send(cmd).await {
+        Ok(()) => orx.await.unwrap_or(Err("Lobby is unreachable".to_string())),
+        Err(_) => Err("Lobby is unreachable".to_string()),
+    };
+    let pairing = &mut t.data.pairings[idx];
+    match result {
+        Ok(mid) => {
+            pairing.id = Some(mid.clone());
+            t.passwords.insert(mid.clone(), password.clone());
+            spawn(watch(
+                tournament.clone(),
+                lobby.clone(),
+                t.data.id.clone(),
+                mid.clone(),
+                pairing.players.clone(),
+            ));
+            drop(t.events.send(Event::Pairing {
+                players: pairing.players.clone(),
+                id: mid,
+                password,
+            }));
+        }
+        Err(x) => {
+            error!("Cannot create tournament match: {}", x);
+            pairing.results = Some(vec![PlayerResult::Loss; 2]);
+        }
+    }
+}
+
+/// Start new rounds until there is some match to wait for, then publish
+/// and save the state of the tournament
+async fn advance<T: Rng>(
+    t: &mut Tournament,
+    rng: &mut T,
+    verification_pw: &str,
+    lobby: &mpsc::Sender<lobby::Command>,
+    db: &DatabaseHandle,
+    tournament: &mpsc::Sender<Command>,
+) {
+    while !t.data.finished && t.data.pairings.iter().all(|p| p.results.is_some()) {
+        if t.data.format == TournamentFormat::SingleElimination && t.data.round > 0 {
+            t.advance_bracket();
+        }
+        if t.finished() {
+            t.data.finished = true;
+            break;
+        }
+        t.data.standings = t.standings();
+        for idx in t.next_round() {
+            if t.data.pairings[idx].players.len() == 2 {
+                pair(t, idx, rng, verification_pw, lobby, tournament).await;
+            }
+        }
+    }
+    t.data.standings = t.standings();
+    if t.data.finished {
+        info!("Tournament ended: {}", t.data.id);
+        drop(t.events.send(Event::Ended(t.data.clone())));
+    } else {
+        drop(t.events.send(Event::Update(t.data.clone())));
+    }
+    if db
+        .send(db::Command::StoreTournament(t.data.clone()))
+        .await
+        .is_err()
+    {
+        error!("Cannot save tournament {}", t.data.id);
+    }
+}
+
+/// Load the tournaments that were still running when the server stopped
+async fn unfinished(db: &DatabaseHandle) -> Vec<TournamentData> {
+    let (tx, rx) = oneshot::channel();
+    if db.send(db::Command::ListTournaments(tx)).await.is_err() {
+        error!("Cannot forward request to database");
+        return Vec::new();
+    }
+    let mut result = Vec::new();
+    for id in rx.await.unwrap_or_default() {
+        let (tx, rx) = oneshot::channel();
+        let retrieve = db::Command::RetrieveTournament { response: tx, id };
+        if db.send(retrieve).await.is_err() {
+            error!("Cannot forward request to database");
+            break;
+        }
+        match rx.await {
+            Ok(Ok(x)) if !x.finished => result.push(x),
+            _ => {}
+        }
+    }
+    result
+}
+
+pub(crate) async fn start<T: 'static + Rng + Send>(
+    mut rng: T,
+    username_regex: Regex,
+    gamename_regex: Regex,
+    verification_pw: String,
+    games: mpsc::Sender<game::Command>,
+    lobby: mpsc::Sender<lobby::Command>,
+    db: DatabaseHandle,
+) -> mpsc::Sender<Command> {
+    let (tx, mut rx) = mpsc::channel(QUEUE_BUFFER);
+    let mtx = tx.clone();
+    spawn(async move {
+        let mut tournaments: BTreeMap<String, Tournament> = BTreeMap::new();
+        for data in unfinished(&db).await {
+            let mut t = Tournament::restore(data);
+            info!("Tournament resumed: {}", t.data.id);
+            // The matches of the current round were lost with the previous run
+            let lost: Vec<_> = (0..t.data.pairings.len())
+                .filter(|&i| t.data.pairings[i].results.is_none())
+                .collect();
+            for idx in lost {
+                pair(&mut t, idx, &mut rng, &verification_pw, &lobby, &mtx).await;
+            }
+            advance(&mut t, &mut rng, &verification_pw, &lobby, &db, &mtx).await;
+            tournaments.insert(t.data.id.clone(), t);
+        }
+        while let Some(cmd) = rx.recv().await {
+            let id = match cmd {
+                Command::New(
+                    tx,
+                    name,
+                    gamename,
+                    format,
+                    participants,
+                    timeout,
+                    args,
+                    verification,
+                ) => {
+                    if verification != verification_pw {
+                        send!(
+                            tx,
+                            Err(format!(
+                                "\"{}\" is the wrong verification password",
+                                verification
+                            ))
+                        );
+                        continue;
+                    }
+                    if !gamename_regex.is_match(&name) {
+                        send!(
+                            tx,
+                            Err(format!("\"{}\" is not a valid tournament name", name))
+                        );
+                        continue;
+                    }
+                    if let Some(x) = participants.iter().find(|x| !username_regex.is_match(x)) {
+                        send!(tx, Err(format!("\"{}\" is not a valid username", x)));
+                        continue;
+                    }
+                    let unique: HashSet<_> = participants.iter().collect();
+                    if unique.len() != participants.len() {
+                        send!(tx, Err("Participants must be unique".to_string()));
+                        continue;
+                    }
+                    if participants.len() < 2 || participants.len() > MAX_TOURNAMENT_PARTICIPANTS {
+                        send!(
+                            tx,
+                            Err(format!(
+                                "Participants must be between 2 and {}",
+                                MAX_TOURNAMENT_PARTICIPANTS
+                            ))
+                        );
+                        continue;
+                    }
+                    if let TournamentFormat::Swiss { rounds } = format {
+                        if rounds < 1 || rounds >= participants.len() {
+                            send!(tx, Err(format!("Invalid number of rounds: {}", rounds)));
+                            continue;
+                        }
+                    }
+                    // Every match of a tournament is between two players
+                    let params = game::Params {
+                        players: Some(2),
+                        bots: Vec::new(),
+                        timeout,
+                        seed: None,
+                        clock: None,
+                        seating: SeatPolicy::Random,
+                        series: None,
+                    };
+                    let (otx, orx) = oneshot::channel();
+                    let cmd = game::Command::NewGame(otx, gamename.clone(), params, args);
+                    let created = match games.send(cmd).await {
+                        Ok(()) => orx
+                            .await
+                            .unwrap_or(Err("Internal server error".to_string())),
+                        Err(_) => Err("Internal server error".to_string()),
+                    };
+                    let args = match created {
+                        Ok((_, _, _, args)) => args,
+                        Err(x) => {
+                            send!(
+                                tx,
+                                Err(format!(
+                                    "Cannot play \"{}\" in a tournament: {}",
+                                    gamename, x
+                                ))
+                            );
+                            continue;
+                        }
+                    };
+                    let mut id = lobby::encode(rng.gen());
+                    while tournaments.contains_key(&id) {
+                        id = lobby::encode(rng.gen());
+                    }
+                    let data = TournamentData {
+                        id: id.clone(),
+                        name,
+                        game: gamename,
+                        format,
+                        participants,
+                        timeout,
+                        args,
+                        round: 0,
+                        rounds: 0,
+                        pairings: Vec::new(),
+                        standings: Vec::new(),
+                        finished: false,
+                    };
+                    info!("Tournament of \"{}\" created: {}", data.game, id);
+                    tournaments.insert(id.clone(), Tournament::new(data));
+                    send!(tx, Ok(id.clone()));
+                    id
+                }
+                Command::Info(tx, id) => {
+                    if let Some(t) = tournaments.get(&id) {
+                        send!(tx, Ok(t.data.clone()));
+                        continue;
+                    }
+                    let (otx, orx) = oneshot::channel();
+                    let retrieve = db::Command::RetrieveTournament { response: otx, id };
+                    if db.send(retrieve).await.is_err() {
+                        error!("Cannot forward request to database");
+                        send!(tx, Err("Internal server error".to_string()));
+                        continue;
+                    }
+                    match orx.await {
+                        Ok(x) => send!(tx, x.map_err(|e| format!("{:?}", e))),
+                        Err(_) => send!(tx, Err("Internal server error".to_string())),
+                    }
+                    continue;
+                }
+                Command::Subscribe(tx, id, name) => {
+                    let t = match tournaments.get(&id) {
+                        Some(x) if !x.data.finished => x,
+                        Some(_) => {
+                            send!(tx, Err(format!("Tournament \"{}\" is over", id)));
+                            continue;
+                        }
+                        None => {
+                            send!(tx, Err(format!("Tournament \"{}\" does not exists", id)));
+                            continue;
+                        }
+                    };
+                    let pending = match name {
+                        Some(ref name) if !t.data.participants.contains(name) => {
+                            send!(tx, Err(format!("\"{}\" is not a participant", name)));
+                            continue;
+                        }
+                        Some(ref name) => t
+                            .data
+                            .pairings
+                            .iter()
+                            .filter(|p| p.results.is_none() && p.players.contains(name))
+                            .filter_map(|p| p.id.clone())
+                            .filter_map(|x| t.passwords.get(&x).map(|pw| (x.clone(), pw.clone())))
+                            .collect(),
+                        None => Vec::new(),
+                    };
+                    send!(tx, Ok((t.events.subscribe(), t.data.clone(), pending)));
+                    continue;
+                }
+                Command::MatchEnded(id, mid, results) => {
+                    let t = match tournaments.get_mut(&id) {
+                        Some(x) => x,
+                        None => {
+                            error!("Result for non-existent tournament \"{}\"", id);
+                            continue;
+                        }
+                    };
+                    t.passwords.remove(&mid);
+                    match t
+                        .data
+                        .pairings
+                        .iter_mut()
+                        .find(|p| p.id.as_ref() == Some(&mid))
+                    {
+                        Some(p) => p.results = Some(results),
+                        None => error!("Result for unknown match \"{}\"", mid),
+                    }
+                    id
+                }
+            };
+            if let Some(t) = tournaments.get_mut(&id) {
+                advance(t, &mut rng, &verification_pw, &lobby, &db, &mtx).await;
+            }
+        }
+    });
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(n: usize) -> Vec<String> {
+        (0..n).map(|x| format!("p{}", x)).collect()
+    }
+
+    fn tournament(format: TournamentFormat, n: usize) -> Tournament {
+        Tournament::new(TournamentData {
+            id: String::new(),
+            name: String::new(),
+            game: String::from("chess"),
+            format,
+            participants: names(n),
+            timeout: None,
+            args: HashMap::new(),
+            round: 0,
+            rounds: 0,
+            pairings: Vec::new(),
+            standings: Vec::new(),
+            finished: false,
+        })
+    }
+
+    /// Play a round where the best seed of every pairing wins
+    fn play_round(t: &mut Tournament) -> Vec<usize> {
+        let indices = t.next_round();
+        for &i in indices.iter() {
+            let p = &mut t.data.pairings[i];
+            if p.results.is_none() {
+                let best = p
+                    .players
+                    .iter()
+                    .map(|x| x[1..].parse::<usize>().unwrap())
+                    .min();
+                let results = p
+                    .players
+                    .iter()
+                    .map(|x| match Some(x[1..].parse().unwrap()) == best {
+                        true => PlayerResult::Win,
+                        false => PlayerResult::Loss,
+                    })
+                    .collect();
+                p.results = Some(results);
+            }
+        }
+        t.data.standings = t.standings();
+        indices
+    }
+
+    fn check_round_robin(n: usize) {
+        let players = names(n);
+        let rounds = n + n % 2 - 1;
+        let mut met = HashSet::new();
+        for round in 0..rounds {
+            let groups = round_robin(&players, round);
+            let mut seen = HashSet::new();
+            for g in groups.iter() {
+                assert!(g.iter().all(|x| seen.insert(x.clone())), "{:?}", groups);
+                if g.len() == 2 {
+                    assert!(met.insert(key(&g[0], &g[1])), "rematch {:?}", g);
+                }
+            }
+            assert_eq!(seen.len(), n);
+            assert_eq!(groups.iter().filter(|x| x.len() == 1).count(), n % 2);
+        }
+        assert_eq!(met.len(), n * (n - 1) / 2);
+    }
+
+    #[test]
+    fn round_robin_pairs_everybody_once() {
+        for n in 2..=12 {
+            check_round_robin(n);
+        }
+    }
+
+    #[test]
+    fn round_robin_alternates_sides() {
+        let players = names(6);
+        let first: Vec<usize> = (0..5)
+            .map(|r| {
+                round_robin(&players, r)[0]
+                    .iter()
+                    .position(|x| x == "p0")
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(first, vec![0, 1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn double_round_robin_swaps_sides() {
+        let mut t = tournament(TournamentFormat::DoubleRoundRobin, 4);
+        assert_eq!(t.data.rounds, 6);
+        let mut games = HashMap::new();
+        while !t.finished() {
+            for i in play_round(&mut t) {
+                let p = &t.data.pairings[i].players;
+                *games.entry((p[0].clone(), p[1].clone())).or_insert(0) += 1;
+            }
+        }
+        assert_eq!(games.len(), 12);
+        assert!(games.values().all(|&x| x == 1));
+    }
+
+    #[test]
+    fn swiss_avoids_rematches() {
+        let ranking = names(4);
+        let mut played = HashSet::new();
+        played.insert(key("p0", "p1"));
+        played.insert(key("p2", "p3"));
+        let pairs = swiss(&ranking, &played, &HashSet::new());
+        assert_eq!(pairs, vec![vec!["p0", "p2"], vec!["p1", "p3"]]);
+        played.insert(key("p0", "p2"));
+        played.insert(key("p1", "p3"));
+        let pairs = swiss(&ranking, &played, &HashSet::new());
+        assert_eq!(pairs, vec![vec!["p0", "p3"], vec!["p1", "p2"]]);
+    }
+
+    #[test]
+    fn swiss_rematches_when_unavoidable() {
+        let ranking = names(2);
+        let mut played = HashSet::new();
+        played.insert(key("p0", "p1"));
+        let pairs = swiss(&ranking, &played, &HashSet::new());
+        assert_eq!(pairs, vec![vec!["p0", "p1"]]);
+    }
+
+    #[test]
+    fn swiss_bye_goes_to_lowest_without_one() {
+        let ranking = names(5);
+        let pairs = swiss(&ranking, &HashSet::new(), &HashSet::new());
+        assert_eq!(pairs[0], vec!["p4"]);
+        let byes = vec!["p4".to_owned(), "p3".to_owned()].into_iter().collect();
+        let pairs = swiss(&ranking, &HashSet::new(), &byes);
+        assert_eq!(pairs[0], vec!["p2"]);
+        assert_eq!(pairs.iter().map(|x| x.len()).sum::<usize>(), 5);
+    }
+
+    #[test]
+    fn swiss_tournament() {
+        let mut t = tournament(TournamentFormat::Swiss { rounds: 3 }, 7);
+        let mut byes = HashSet::new();
+        let mut met = HashSet::new();
+        while !t.finished() {
+            for i in play_round(&mut t) {
+                let p = &t.data.pairings[i].players;
+                match p.len() {
+                    1 => assert!(byes.insert(p[0].clone())),
+                    _ => assert!(met.insert(key(&p[0], &p[1]))),
+                }
+            }
+        }
+        assert_eq!(byes.len(), 3);
+        assert_eq!(t.data.standings[0].name, "p0");
+        assert_eq!(t.data.standings[0].points, 3.0);
+        let total: f64 = t.data.standings.iter().map(|x| x.points).sum();
+        assert_eq!(total, 3.0 * 4.0);
+    }
+
+    #[test]
+    fn seeds_meet_late() {
+        assert_eq!(seeding(1), vec![0]);
+        assert_eq!(seeding(2), vec![0, 1]);
+        assert_eq!(seeding(4), vec![0, 3, 1, 2]);
+        assert_eq!(seeding(8), vec![0, 7, 3, 4, 1, 6, 2, 5]);
+    }
+
+    #[test]
+    fn knockout_with_byes() {
+        let mut t = tournament(TournamentFormat::SingleElimination, 5);
+        assert_eq!(t.data.rounds, 3);
+        let first = play_round(&mut t);
+        let groups: Vec<_> = first
+            .iter()
+            .map(|&i| t.data.pairings[i].players.clone())
+            .collect();
+        assert_eq!(
+            groups,
+            vec![vec!["p0"], vec!["p3", "p4"], vec!["p1"], vec!["p2"]]
+        );
+        t.advance_bracket();
+        t.data.standings = t.standings();
+        assert!(t
+            .data
+            .standings
+            .iter()
+            .filter(|x| x.eliminated)
+            .all(|x| x.name == "p4"));
+        assert_eq!(t.data.standings.iter().filter(|x| x.eliminated).count(), 1);
+        while !t.finished() {
+            play_round(&mut t);
+            t.advance_bracket();
+        }
+        assert_eq!(t.data.round, 3);
+        assert_eq!(t.slots.iter().flatten().collect::<Vec<_>>(), vec!["p0"]);
+        t.data.standings = t.standings();
+        assert_eq!(t.data.standings[0].name, "p0");
+        assert!(t.data.standings[1..].iter().all(|x| x.eliminated));
+    }
+
+    #[test]
+    fn knockout_restores_bracket() {
+        let mut t = tournament(TournamentFormat::SingleElimination, 6);
+        play_round(&mut t);
+        t.advance_bracket();
+        t.next_round();
+        let restored = Tournament::restore(t.data.clone());
+        assert_eq!(restored.slots, t.slots);
+        assert_eq!(restored.bracket, t.bracket);
+        assert_eq!(restored.data.round, 2);
+    }
+
+    #[test]
+    fn knockout_draw_goes_to_best_seed() {
+        let mut t = tournament(TournamentFormat::SingleElimination, 2);
+        let i = t.next_round()[0];
+        t.data.pairings[i].results = Some(vec![PlayerResult::Draw, PlayerResult::Draw]);
+        t.advance_bracket();
+        assert_eq!(t.slots, vec![Some("p0".to_owned())]);
+        assert!(t.finished());
+    }
+}
@@ -8,7 +8,23 @@ pub(crate) const MAX_PLAYERS: usize = 100;
 pub(crate) const MAX_GAME_INSTANCES: usize = 1000;
 pub(crate) const MIN_TIMEOUT: f64 = 0.1;
 pub(crate) const MAX_TIMEOUT: f64 = 600.0;
+pub(crate) const MAX_CLOCK_BANK: f64 = 7200.0;
 pub(crate) const INSTANCE_LIFETIME: f64 = 600.0;
 pub(crate) const CHUNK_SIZE: usize = 1 << 20;
 pub(crate) const END_GRACE_PERIOD: f64 = 0.25;
 pub(crate) const PING_TIMEOUT: f64 = 25.0;
+pub(crate) const RATING_INITIAL: f64 = 1500.0;
+pub(crate) const RATING_K: f64 = 32.0;
+pub(crate) const MAX_LEADERBOARD: usize = 1000;
+pub(crate) const MAX_TOURNAMENT_PARTICIPANTS: usize = 256;
+pub(crate) const SWISS_SEARCH_BUDGET: usize = 100000;
+pub(crate) const QUEUE_RATING_WINDOW: f64 = 100.0;
+pub(crate) const QUEUE_RATING_GROWTH: f64 = 10.0;
+pub(crate) const QUEUE_CHECK_INTERVAL: f64 = 1.0;
+pub(crate) const ACCOUNT_PASSWORD_REGEX: &str = r"^[[:print:]]{8,64}$";
+pub(crate) const PBKDF2_ITERATIONS: u32 = 100_000;
+pub(crate) const MAX_RESUME_BUFFER: usize = 1 << 20;
+pub(crate) const MAX_TRANSCRIPT_LENGTH: usize = 1 << 22;
+pub(crate) const MAX_RECONNECT_GRACE: f64 = 600.0;
+pub(crate) const MAX_LINE_LENGTH: usize = 1 << 16;
+pub(crate) const MAX_SERIES_LENGTH: usize = 99;